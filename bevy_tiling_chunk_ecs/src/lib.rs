@@ -1,15 +1,23 @@
 use bevy::{
     math::IVec3,
-    prelude::{Commands, Component, Entity, Plugin, ResMut},
+    prelude::{
+        Commands, Component, Entity, EventWriter, Plugin, Query, Res, ResMut, Transform,
+        TransformBundle, With,
+    },
     utils::HashMap,
 };
-use bevy_tiling_core::{MapReader, TileMapReader, TilingCoreStage};
+use bevy_tiling_core::{grid::TileGrid, MapReader, TileMapReader, TilingCoreStage};
+
+#[cfg(feature = "testing")]
+pub mod testing;
 
 pub struct BevyTilingChunkEcs;
 
 impl Plugin for BevyTilingChunkEcs {
     fn build(&self, app: &mut bevy::prelude::App) {
         app.init_resource::<ChunkMap>()
+            .add_event::<ChunkSpawned>()
+            .add_event::<ChunkDespawned>()
             .add_system_to_stage(TilingCoreStage::Update, update_chunk_map);
     }
 }
@@ -17,22 +25,61 @@ impl Plugin for BevyTilingChunkEcs {
 fn update_chunk_map(
     mut commands: Commands,
     tile_map_reader: TileMapReader,
+    grid: Res<TileGrid>,
     mut chunk_map: ResMut<ChunkMap>,
+    mut chunks: Query<&mut Transform, With<ChunkMarker>>,
+    mut spawned: EventWriter<ChunkSpawned>,
+    mut despawned: EventWriter<ChunkDespawned>,
 ) {
+    if grid.is_changed() {
+        for (entity, chunk) in chunk_map.ent_to_int.iter() {
+            if let Ok(mut transform) = chunks.get_mut(*entity) {
+                transform.translation = grid.chunk_to_world(chunk);
+            }
+        }
+    }
+    for chunk in tile_map_reader.get_chunk_removals() {
+        // The chunk may have been created again after it was removed.
+        if tile_map_reader.get_chunk(chunk).is_some() {
+            continue;
+        }
+        if let Some(entity) = chunk_map.remove_chunk_by_key(chunk) {
+            commands.entity(entity).despawn();
+            despawned.send(ChunkDespawned(*chunk, entity));
+        }
+    }
     for chunk_update in tile_map_reader.get_chunk_updates() {
-        if chunk_map.get_chunk_entity(chunk_update).is_none() {
-            chunk_map.insert_chunk(chunk_update, &commands.spawn_bundle((ChunkMarker,)).id());
+        if chunk_map.get_chunk_entity(chunk_update).is_none()
+            && tile_map_reader.get_chunk(chunk_update).is_some()
+        {
+            let transform = Transform::from_translation(grid.chunk_to_world(chunk_update));
+            let entity = commands
+                .spawn_bundle(TransformBundle::from_transform(transform))
+                .insert(ChunkMarker)
+                .id();
+            chunk_map.insert_chunk(chunk_update, &entity);
+            spawned.send(ChunkSpawned(*chunk_update, entity));
         }
     }
 }
 
-/// Marks an entity as a Chunk.
+/// Marks an entity as a Chunk. Chunk entities are placed at the chunk origin
+/// according to the [`TileGrid`].
 #[derive(Component)]
 pub struct ChunkMarker;
 
+/// Sent when an entity was spawned for a new chunk of the tile map.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub struct ChunkSpawned(pub IVec3, pub Entity);
+
+/// Sent when a chunk was removed from the tile map and its entity despawned,
+/// e.g. to drop colliders or render data kept for the chunk.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub struct ChunkDespawned(pub IVec3, pub Entity);
+
 /// Contains mappings for tiling internal chunk representations
 /// to ecs entity chunk representations.
-#[derive(Default)]
+#[derive(Default, Debug)]
 pub struct ChunkMap {
     ent_to_int: HashMap<Entity, IVec3>,
     int_to_ent: HashMap<IVec3, Entity>,
@@ -0,0 +1,238 @@
+//! Helpers for integration tests of systems built on the tile map and its chunk entities,
+//! enabled by the `testing` feature. Add the crate with that feature to your dev-dependencies.
+
+use std::{
+    fmt,
+    ops::{Deref, DerefMut},
+};
+
+use bevy::{
+    math::IVec3,
+    prelude::{App, Entity, Plugin, ResMut, Transform, With},
+    transform::TransformPlugin,
+    utils::HashSet,
+};
+use bevy_tiling_core::{
+    grid::TileGrid, IntoTileCoord, Tile, TileMap, TileMapWriter, TilingCoreStage, TilingPlugin,
+};
+
+use crate::{BevyTilingChunkEcs, ChunkMap, ChunkMarker};
+
+type PendingWrite = Box<dyn FnOnce(&mut TileMapWriter) + Send + Sync>;
+
+/// Edits queued by [`TilingTestApp::write`], applied before `CoreStage::Update` of the next
+/// update so they cause updates like edits made by a game system.
+#[derive(Default)]
+struct PendingWrites(Vec<PendingWrite>);
+
+fn apply_pending_writes(mut pending: ResMut<PendingWrites>, mut writer: TileMapWriter) {
+    for write in pending.0.drain(..) {
+        write(&mut writer);
+    }
+}
+
+/// An [`App`] with [`TilingPlugin`], [`BevyTilingChunkEcs`] and transform propagation added,
+/// for testing systems against the tile map without a window or renderer.
+///
+/// Add the plugins under test with [`TilingTestApp::with_plugin`], queue edits with
+/// [`TilingTestApp::set_tile`] or [`TilingTestApp::write`] and step the app with
+/// [`TilingTestApp::run`]. The app is reachable through `Deref` for everything else.
+pub struct TilingTestApp {
+    app: App,
+}
+
+impl Default for TilingTestApp {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl TilingTestApp {
+    pub fn new() -> Self {
+        let mut app = App::new();
+        app.add_plugin(TransformPlugin)
+            .add_plugin(TilingPlugin)
+            .add_plugin(BevyTilingChunkEcs)
+            .init_resource::<PendingWrites>()
+            .add_system_to_stage(TilingCoreStage::Schedule, apply_pending_writes);
+        Self { app }
+    }
+
+    pub fn with_plugin(mut self, plugin: impl Plugin) -> Self {
+        self.app.add_plugin(plugin);
+        self
+    }
+
+    pub fn with_resource<R: Send + Sync + 'static>(mut self, resource: R) -> Self {
+        self.app.insert_resource(resource);
+        self
+    }
+
+    /// Queues an edit for the next update.
+    pub fn write(
+        &mut self,
+        f: impl FnOnce(&mut TileMapWriter) + Send + Sync + 'static,
+    ) -> &mut Self {
+        self.app
+            .world
+            .resource_mut::<PendingWrites>()
+            .0
+            .push(Box::new(f));
+        self
+    }
+
+    /// Queues setting or removing a tile for the next update.
+    pub fn set_tile(&mut self, coord: impl IntoTileCoord, tile: Option<Tile>) -> &mut Self {
+        let coord = coord.into_tile_coord();
+        self.write(move |writer| {
+            writer.set_tile(coord, tile);
+        })
+    }
+
+    /// Runs `updates` updates.
+    pub fn run(&mut self, updates: usize) -> &mut Self {
+        for _ in 0..updates {
+            self.app.update();
+        }
+        self
+    }
+
+    /// Runs `updates` updates, panicking as soon as the chunk entities don't match the map.
+    pub fn run_consistent(&mut self, updates: usize) -> &mut Self {
+        for update in 0..updates {
+            self.app.update();
+            let problems = chunk_consistency(&mut self.app);
+            if !problems.is_empty() {
+                panic!(
+                    "chunk entities don't match the map after update {}: {:?}",
+                    update + 1,
+                    problems
+                );
+            }
+        }
+        self
+    }
+
+    /// Panics unless the chunk entities match the map, see [`chunk_consistency`].
+    pub fn assert_chunks_consistent(&mut self) -> &mut Self {
+        let problems = chunk_consistency(&mut self.app);
+        assert!(
+            problems.is_empty(),
+            "chunk entities don't match the map: {:?}",
+            problems
+        );
+        self
+    }
+
+    /// The entity of a chunk, if it has one.
+    pub fn chunk_entity(&self, chunk: &IVec3) -> Option<Entity> {
+        self.app
+            .world
+            .resource::<ChunkMap>()
+            .get_chunk_entity(chunk)
+            .copied()
+    }
+
+    pub fn into_app(self) -> App {
+        self.app
+    }
+}
+
+impl Deref for TilingTestApp {
+    type Target = App;
+
+    fn deref(&self) -> &App {
+        &self.app
+    }
+}
+
+impl DerefMut for TilingTestApp {
+    fn deref_mut(&mut self) -> &mut App {
+        &mut self.app
+    }
+}
+
+/// A mismatch between the tile map and the chunk entities found by [`chunk_consistency`].
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum ChunkInconsistency {
+    /// The chunk exists in the map but has no entity.
+    MissingEntity(IVec3),
+    /// The [`ChunkMap`] keeps an entity for a chunk the map doesn't have.
+    StaleEntity(IVec3, Entity),
+    /// The [`ChunkMap`] points at an entity that doesn't exist or isn't a chunk.
+    DeadEntity(IVec3, Entity),
+    /// A chunk entity the [`ChunkMap`] doesn't know about.
+    UntrackedEntity(Entity),
+    /// The chunk entity isn't at the position the [`TileGrid`] gives its chunk.
+    Misplaced(IVec3, Entity),
+}
+
+impl fmt::Display for ChunkInconsistency {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ChunkInconsistency::MissingEntity(chunk) => write!(f, "chunk {} has no entity", chunk),
+            ChunkInconsistency::StaleEntity(chunk, entity) => {
+                write!(f, "entity {:?} is kept for removed chunk {}", entity, chunk)
+            }
+            ChunkInconsistency::DeadEntity(chunk, entity) => {
+                write!(f, "chunk {} points at missing entity {:?}", chunk, entity)
+            }
+            ChunkInconsistency::UntrackedEntity(entity) => {
+                write!(f, "chunk entity {:?} is not in the chunk map", entity)
+            }
+            ChunkInconsistency::Misplaced(chunk, entity) => {
+                write!(f, "entity {:?} of chunk {} is misplaced", entity, chunk)
+            }
+        }
+    }
+}
+
+/// Compares the chunks of the map with the chunk entities, returning every mismatch.
+/// Chunks only get entities once they are updated, so chunks placed without causing updates,
+/// e.g. by writing the [`TileMap`] resource directly, are reported as missing.
+pub fn chunk_consistency(app: &mut App) -> Vec<ChunkInconsistency> {
+    let world = &mut app.world;
+    let mut problems = Vec::new();
+    let mut tracked = HashSet::default();
+    {
+        let map = world.resource::<TileMap>();
+        let chunk_map = world.resource::<ChunkMap>();
+        let grid = world.resource::<TileGrid>();
+        let mut chunks: Vec<(&IVec3, &Entity)> = chunk_map.int_to_ent.iter().collect();
+        chunks.sort_by_key(|(chunk, _)| (chunk.z, chunk.y, chunk.x));
+        for (chunk, entity) in chunks {
+            tracked.insert(*entity);
+            if map.get_chunk(chunk).is_none() {
+                problems.push(ChunkInconsistency::StaleEntity(*chunk, *entity));
+            }
+            let entity_ref = match world.get_entity(*entity) {
+                Some(entity_ref) if entity_ref.contains::<ChunkMarker>() => entity_ref,
+                _ => {
+                    problems.push(ChunkInconsistency::DeadEntity(*chunk, *entity));
+                    continue;
+                }
+            };
+            let placed = entity_ref
+                .get::<Transform>()
+                .is_some_and(|transform| transform.translation == grid.chunk_to_world(chunk));
+            if !placed {
+                problems.push(ChunkInconsistency::Misplaced(*chunk, *entity));
+            }
+        }
+        let mut missing: Vec<IVec3> = map
+            .chunk_coords()
+            .filter(|chunk| chunk_map.get_chunk_entity(chunk).is_none())
+            .copied()
+            .collect();
+        missing.sort_by_key(|chunk| (chunk.z, chunk.y, chunk.x));
+        problems.extend(missing.into_iter().map(ChunkInconsistency::MissingEntity));
+    }
+    let mut chunk_entities = world.query_filtered::<Entity, With<ChunkMarker>>();
+    problems.extend(
+        chunk_entities
+            .iter(world)
+            .filter(|entity| !tracked.contains(entity))
+            .map(ChunkInconsistency::UntrackedEntity),
+    );
+    problems
+}
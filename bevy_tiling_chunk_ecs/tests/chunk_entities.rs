@@ -0,0 +1,151 @@
+use bevy::{
+    ecs::event::{Events, ManualEventReader},
+    math::{IVec3, Vec2, Vec3},
+    prelude::{App, Entity, ResMut, Transform},
+};
+use bevy_tiling_chunk_ecs::{
+    BevyTilingChunkEcs, ChunkDespawned, ChunkMap, ChunkMarker, ChunkSpawned,
+};
+use bevy_tiling_core::{grid::TileGrid, Tile, TileMapWriter, TilingCoreStage, TilingPlugin};
+
+type Edit = Box<dyn FnOnce(&mut TileMapWriter) + Send + Sync>;
+
+/// Edits applied during the next update, so they cause updates like edits of a game system.
+#[derive(Default)]
+struct Edits(Vec<Edit>);
+
+fn apply_edits(mut edits: ResMut<Edits>, mut writer: TileMapWriter) {
+    for edit in edits.0.drain(..) {
+        edit(&mut writer);
+    }
+}
+
+fn app() -> App {
+    let mut app = App::new();
+    app.add_plugin(TilingPlugin)
+        .add_plugin(BevyTilingChunkEcs)
+        .init_resource::<Edits>()
+        .add_system_to_stage(TilingCoreStage::Schedule, apply_edits);
+    app
+}
+
+/// Runs an update applying `edit`.
+fn update(app: &mut App, edit: impl FnOnce(&mut TileMapWriter) + Send + Sync + 'static) {
+    app.world.resource_mut::<Edits>().0.push(Box::new(edit));
+    app.update();
+}
+
+fn chunk_entity(app: &App, chunk: &IVec3) -> Option<Entity> {
+    app.world
+        .resource::<ChunkMap>()
+        .get_chunk_entity(chunk)
+        .copied()
+}
+
+#[test]
+fn updated_chunks_are_spawned_and_removed_ones_despawned() {
+    let mut app = app();
+    let chunk = IVec3::new(-1, 0, 0);
+    update(&mut app, |writer| {
+        writer.set_tile_xy(-3, 4, 0, Some(Tile::new(0, 1)));
+    });
+    let entity = chunk_entity(&app, &chunk).unwrap();
+    assert!(app.world.entity(entity).contains::<ChunkMarker>());
+    assert_eq!(
+        app.world.resource::<ChunkMap>().get_chunk_index(&entity),
+        Some(&chunk)
+    );
+
+    // Further edits keep the entity.
+    update(&mut app, |writer| {
+        writer.set_tile_xy(-4, 4, 0, Some(Tile::new(0, 2)));
+    });
+    assert_eq!(chunk_entity(&app, &chunk), Some(entity));
+
+    update(&mut app, move |writer| {
+        writer.remove_chunk(&chunk);
+    });
+    assert_eq!(chunk_entity(&app, &chunk), None);
+    assert!(app.world.get_entity(entity).is_none());
+}
+
+#[test]
+fn spawns_and_despawns_are_sent_as_events() {
+    let mut app = app();
+    let mut spawned = ManualEventReader::<ChunkSpawned>::default();
+    let mut despawned = ManualEventReader::<ChunkDespawned>::default();
+    update(&mut app, |writer| {
+        writer.set_tile_xy(0, 0, 0, Some(Tile::new(0, 1)));
+        writer.set_tile_xy(16, 0, 0, Some(Tile::new(0, 1)));
+    });
+    let mut events: Vec<ChunkSpawned> = spawned
+        .iter(app.world.resource::<Events<ChunkSpawned>>())
+        .copied()
+        .collect();
+    events.sort_by_key(|event| event.0.x);
+    assert_eq!(
+        events,
+        vec![
+            ChunkSpawned(IVec3::ZERO, chunk_entity(&app, &IVec3::ZERO).unwrap()),
+            ChunkSpawned(IVec3::X, chunk_entity(&app, &IVec3::X).unwrap()),
+        ]
+    );
+
+    let entity = chunk_entity(&app, &IVec3::X).unwrap();
+    update(&mut app, |writer| {
+        writer.set_tile_xy(1, 0, 0, Some(Tile::new(0, 1)));
+        writer.remove_chunk(&IVec3::X);
+    });
+    assert_eq!(
+        spawned
+            .iter(app.world.resource::<Events<ChunkSpawned>>())
+            .count(),
+        0
+    );
+    assert_eq!(
+        despawned
+            .iter(app.world.resource::<Events<ChunkDespawned>>())
+            .copied()
+            .collect::<Vec<_>>(),
+        vec![ChunkDespawned(IVec3::X, entity)]
+    );
+}
+
+#[test]
+fn chunk_entities_follow_the_grid() {
+    let mut app = app();
+    app.insert_resource(TileGrid::new(Vec2::splat(16.0)));
+    let chunk = IVec3::new(1, -1, 2);
+    update(&mut app, |writer| {
+        writer.set_tile_xy(20, -3, 2, Some(Tile::new(0, 1)));
+    });
+    let entity = chunk_entity(&app, &chunk).unwrap();
+    let translation = |app: &App| app.world.get::<Transform>(entity).unwrap().translation;
+    assert_eq!(translation(&app), Vec3::new(256.0, -256.0, 2.0));
+
+    {
+        let mut grid = app.world.resource_mut::<TileGrid>();
+        grid.tile_size = Vec2::new(8.0, 4.0);
+        grid.layer_height = 10.0;
+    }
+    app.update();
+    assert_eq!(translation(&app), Vec3::new(128.0, -64.0, 20.0));
+}
+
+#[test]
+fn chunk_map_keeps_both_directions_in_sync() {
+    let mut chunk_map = ChunkMap::default();
+    let (first, second) = (Entity::from_raw(1), Entity::from_raw(2));
+    assert_eq!(chunk_map.insert_chunk(&IVec3::ZERO, &first), None);
+    assert_eq!(chunk_map.insert_chunk(&IVec3::X, &second), None);
+    assert_eq!(
+        chunk_map.insert_chunk(&IVec3::Y, &first),
+        Some((IVec3::ZERO, first))
+    );
+    assert_eq!(chunk_map.get_chunk_index(&first), Some(&IVec3::Y));
+
+    assert_eq!(chunk_map.remove_chunk_by_entity(&second), Some(IVec3::X));
+    assert_eq!(chunk_map.get_chunk_entity(&IVec3::X), None);
+    assert_eq!(chunk_map.remove_chunk_by_key(&IVec3::Y), Some(first));
+    assert_eq!(chunk_map.get_chunk_index(&first), None);
+}
@@ -0,0 +1,54 @@
+use bevy::math::IVec3;
+use bevy_tiling_chunk_ecs::testing::{chunk_consistency, ChunkInconsistency, TilingTestApp};
+use bevy_tiling_core::{Tile, TileMap};
+
+#[test]
+fn chunks_are_spawned_despawned_and_spawned_again() {
+    let chunk = IVec3::new(1, -1, 0);
+    let mut app = TilingTestApp::new();
+    app.set_tile((20, -3), Some(Tile::new(0, 1)))
+        .run_consistent(2);
+    let first = app.chunk_entity(&chunk).unwrap();
+
+    app.write(move |writer| {
+        writer.remove_chunk(&chunk);
+    })
+    .run_consistent(2);
+    assert_eq!(app.chunk_entity(&chunk), None);
+    assert!(app.world.get_entity(first).is_none());
+
+    app.set_tile((20, -3), Some(Tile::new(0, 2)))
+        .run_consistent(2);
+    let second = app.chunk_entity(&chunk).unwrap();
+    assert_ne!(second, first);
+    assert!(app.world.get_entity(second).is_some());
+}
+
+#[test]
+fn a_chunk_created_again_in_the_same_update_keeps_its_entity() {
+    let chunk = IVec3::ZERO;
+    let mut app = TilingTestApp::new();
+    app.set_tile((3, 3), Some(Tile::new(0, 1)))
+        .run_consistent(1);
+    let entity = app.chunk_entity(&chunk).unwrap();
+
+    app.write(move |writer| {
+        writer.remove_chunk(&chunk);
+        writer.set_tile_xy(5, 5, 0, Some(Tile::new(0, 2)));
+    })
+    .run_consistent(3);
+    assert_eq!(app.chunk_entity(&chunk), Some(entity));
+}
+
+#[test]
+fn chunks_placed_without_updates_are_reported() {
+    let mut app = TilingTestApp::new();
+    app.run(1);
+    app.world
+        .resource_mut::<TileMap>()
+        .get_or_create_chunk(&IVec3::new(2, 0, 0));
+    assert_eq!(
+        chunk_consistency(&mut app),
+        vec![ChunkInconsistency::MissingEntity(IVec3::new(2, 0, 0))]
+    );
+}
@@ -0,0 +1,73 @@
+//! Run-length encoding of chunks that are mostly one tile, run with `cargo bench --bench rle`.
+
+use std::{
+    hint::black_box,
+    time::{Duration, Instant},
+};
+
+use bevy_tiling_core::{rle::RleChunk, Chunk, Tile};
+
+const ITERATIONS: u32 = 100_000;
+
+/// A grass chunk with a few scattered rocks and a short path, about 90% one tile.
+fn mostly_uniform_chunk() -> Chunk {
+    let mut chunk = Chunk::uniform(Some(Tile::new(0, 1)));
+    for index in (7..=u8::MAX).step_by(23) {
+        chunk.set_tile(index, Some(Tile::new(0, 2)));
+    }
+    for index in 128..140 {
+        chunk.set_tile(index, Some(Tile::new(0, 3)));
+    }
+    chunk
+}
+
+fn bench(name: &str, mut f: impl FnMut()) {
+    let start = Instant::now();
+    for _ in 0..ITERATIONS {
+        f();
+    }
+    let per_iteration = start.elapsed() / ITERATIONS;
+    println!(
+        "{:<32} {:>10?}",
+        name,
+        per_iteration.max(Duration::from_nanos(1))
+    );
+}
+
+fn main() {
+    let chunk = mostly_uniform_chunk();
+    let rle = RleChunk::from_chunk(&chunk);
+    let bytes = rle.to_bytes();
+    let compressed = rle.to_chunk();
+    println!(
+        "{} runs, {} bytes encoded, {} bytes dense",
+        rle.len(),
+        bytes.len(),
+        256 * (std::mem::size_of::<Tile>() + 1)
+    );
+
+    bench("RleChunk::from_chunk", || {
+        black_box(RleChunk::from_chunk(black_box(&chunk)));
+    });
+    bench("RleChunk::to_bytes", || {
+        black_box(black_box(&rle).to_bytes());
+    });
+    bench("RleChunk::from_bytes", || {
+        black_box(RleChunk::from_bytes(black_box(&bytes)));
+    });
+    bench("read 256 tiles, dense", || {
+        for index in 0..=u8::MAX {
+            black_box(black_box(&chunk).get_tile(index));
+        }
+    });
+    bench("read 256 tiles, compressed", || {
+        for index in 0..=u8::MAX {
+            black_box(black_box(&compressed).get_tile(index));
+        }
+    });
+    bench("first write to compressed chunk", || {
+        let mut chunk = compressed.clone();
+        chunk.set_tile(black_box(0), Some(Tile::new(1, 0)));
+        black_box(chunk);
+    });
+}
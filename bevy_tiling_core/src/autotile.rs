@@ -0,0 +1,323 @@
+use std::{cmp::Reverse, sync::Arc};
+
+use bevy::{
+    math::IVec3,
+    prelude::{Res, ResMut},
+    utils::{HashMap, HashSet},
+};
+
+use crate::{
+    priority::ChunkPriorities, rng::ChunkRng, Tile, TileCoord, TileMap, TileMapUpdates,
+    TileMapWriter,
+};
+
+type AutotileRule = Arc<dyn Fn(&TileMap, &TileCoord, &Tile) -> Option<Tile> + Send + Sync>;
+
+/// Picks the variant of tiles from their neighbours, e.g. the edge and corner pieces of walls,
+/// spread over several frames so painting thousands of tiles at once doesn't stall one.
+///
+/// Changed tiles and their four neighbours are queued and at most `budget_per_frame` of them
+/// are resolved each frame before `CoreStage::Update`, tiles in chunks with a higher
+/// [`ChunkPriorities`] hint first, so hint the visible chunks. Every queued tile is resolved
+/// eventually, and the tiles the rule changes queue their neighbours in turn.
+///
+/// Use [`TileAutotiler::set_terrains`] for the common case of picking tiles by bitmask.
+pub struct TileAutotiler {
+    rule: Option<AutotileRule>,
+    /// Most tiles resolved per frame.
+    pub budget_per_frame: usize,
+    /// Whether the diagonal neighbours of changed tiles are queued too, for rules looking at
+    /// the corners. Set by [`TileAutotiler::set_terrains`].
+    pub queue_diagonals: bool,
+    queue: HashSet<TileCoord>,
+}
+
+impl Default for TileAutotiler {
+    fn default() -> Self {
+        Self {
+            rule: None,
+            budget_per_frame: 1024,
+            queue_diagonals: false,
+            queue: HashSet::default(),
+        }
+    }
+}
+
+impl TileAutotiler {
+    /// Sets the rule resolving tiles, it returns the tile to use instead or None to keep the
+    /// tile as it is. Empty positions are never resolved.
+    pub fn set_rule(
+        &mut self,
+        rule: impl Fn(&TileMap, &TileCoord, &Tile) -> Option<Tile> + Send + Sync + 'static,
+    ) {
+        self.rule = Some(Arc::new(rule));
+    }
+
+    /// Resolves the tiles of the `terrains` by their neighbours, see [`TerrainRules`].
+    pub fn set_terrains(&mut self, terrains: TerrainRules) {
+        self.queue_diagonals = terrains
+            .terrains
+            .iter()
+            .any(|terrain| terrain.mode == BitmaskMode::Corners);
+        self.set_rule(move |map, coord, tile| terrains.resolve(map, coord, tile));
+    }
+
+    /// Removes the rule and drops the queued tiles.
+    pub fn clear_rule(&mut self) {
+        self.rule = None;
+        self.queue.clear();
+    }
+
+    /// Queues a tile, e.g. to resolve tiles that were in the map before the rule was set.
+    pub fn queue(&mut self, coord: TileCoord) {
+        self.queue.insert(coord);
+    }
+
+    /// Number of tiles waiting to be resolved.
+    pub fn pending(&self) -> usize {
+        self.queue.len()
+    }
+
+    /// Whether every queued tile was resolved.
+    pub fn is_settled(&self) -> bool {
+        self.queue.is_empty()
+    }
+}
+
+/// Which of the four neighbours on the same layer `matches` accepts, as bits: 1 up, 2 right,
+/// 4 down and 8 left. Missing neighbours never match. A common base for autotile rules, e.g.
+/// picking `index = base + mask`.
+pub fn neighbour_mask<L>(
+    map: &TileMap<L>,
+    coord: &TileCoord,
+    matches: impl Fn(&Tile) -> bool,
+) -> u8 {
+    let [right, left, up, down] = map.neighbours(coord);
+    [up, right, down, left]
+        .iter()
+        .enumerate()
+        .filter(|(_, neighbour)| map.get_tile(neighbour).is_some_and(&matches))
+        .fold(0, |mask, (bit, _)| mask | 1 << bit)
+}
+
+/// Like [`neighbour_mask`] with the diagonal neighbours added as bits 16 up right, 32 down right,
+/// 64 down left and 128 up left. A corner only counts when both edges next to it match, which
+/// leaves the 47 masks of a blob tileset.
+pub fn corner_mask<L>(map: &TileMap<L>, coord: &TileCoord, matches: impl Fn(&Tile) -> bool) -> u8 {
+    let edges = neighbour_mask(map, coord, &matches);
+    [(1, 1), (1, -1), (-1, -1), (-1, 1)]
+        .iter()
+        .enumerate()
+        .filter(|(bit, _)| {
+            // The corner between edge `bit` and the next one clockwise.
+            let both = 1 << bit | 1 << ((bit + 1) % 4);
+            edges & both == both
+        })
+        .filter(|(_, (x, y))| {
+            let corner = map.offset_coord(coord, IVec3::new(*x, *y, 0));
+            map.get_tile(&corner).is_some_and(&matches)
+        })
+        .fold(edges, |mask, (bit, _)| mask | 1 << (bit + 4))
+}
+
+/// Which neighbours a [`Terrain`] looks at.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum BitmaskMode {
+    /// The four edges, 16 masks, see [`neighbour_mask`].
+    Edges,
+    /// The edges and corners, 47 masks, see [`corner_mask`].
+    Corners,
+}
+
+/// A terrain drawn with the tiles of one sheet, picking the tile index from which neighbours
+/// belong to the same terrain. A mask can have several weighted variants, every position
+/// always gets the same one.
+#[derive(Clone, Debug)]
+pub struct Terrain {
+    pub sheet: u16,
+    pub mode: BitmaskMode,
+    /// Indices and weights of the variants of each mask.
+    indices: HashMap<u8, Vec<(u16, u32)>>,
+}
+
+impl Terrain {
+    pub fn new(sheet: u16, mode: BitmaskMode) -> Self {
+        Self {
+            sheet,
+            mode,
+            indices: HashMap::default(),
+        }
+    }
+
+    /// A 16 tile terrain, `indices` giving the index of each edge mask.
+    pub fn edges(sheet: u16, indices: [u16; 16]) -> Self {
+        let mut terrain = Self::new(sheet, BitmaskMode::Edges);
+        for (mask, index) in indices.into_iter().enumerate() {
+            terrain = terrain.with_index(mask as u8, index);
+        }
+        terrain
+    }
+
+    /// Sets the tile index used for a mask.
+    pub fn with_index(self, mask: u8, index: u16) -> Self {
+        self.with_variants(mask, [(index, 1)])
+    }
+
+    /// Sets the variants of a mask as pairs of index and weight.
+    pub fn with_variants(
+        mut self,
+        mask: u8,
+        variants: impl IntoIterator<Item = (u16, u32)>,
+    ) -> Self {
+        self.indices.insert(mask, variants.into_iter().collect());
+        self
+    }
+
+    /// The first index for a mask. Corner masks without an index fall back to the index of
+    /// their edges, so a blob terrain works with only the 16 edge tiles set.
+    pub fn index(&self, mask: u8) -> Option<u16> {
+        self.variants(mask)?.first().map(|(index, _)| *index)
+    }
+
+    /// The first index of the lowest mask with one, standing in for the whole terrain.
+    pub fn first_index(&self) -> Option<u16> {
+        let mask = self
+            .indices
+            .iter()
+            .filter(|(_, variants)| !variants.is_empty())
+            .map(|(mask, _)| *mask)
+            .min()?;
+        self.index(mask)
+    }
+
+    /// The index for a mask at a position, picking between the variants by weight.
+    pub fn pick(&self, mask: u8, coord: &TileCoord) -> Option<u16> {
+        let variants = self.variants(mask)?;
+        let total: u64 = variants.iter().map(|(_, weight)| *weight as u64).sum();
+        if total == 0 {
+            return variants.first().map(|(index, _)| *index);
+        }
+        let mut roll = ChunkRng::new(0, coord.tile_position()).below(total);
+        variants
+            .iter()
+            .find_map(|(index, weight)| match roll.checked_sub(*weight as u64) {
+                Some(rest) => {
+                    roll = rest;
+                    None
+                }
+                None => Some(*index),
+            })
+    }
+
+    fn variants(&self, mask: u8) -> Option<&[(u16, u32)]> {
+        self.indices
+            .get(&mask)
+            .or_else(|| self.indices.get(&(mask & 0b1111)))
+            .map(Vec::as_slice)
+            .filter(|variants| !variants.is_empty())
+    }
+}
+
+/// Identifies a terrain added to [`TerrainRules`].
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
+pub struct TerrainId(u16);
+
+/// The terrains resolved by [`TileAutotiler::set_terrains`]. A tile belongs to the terrain
+/// whose sheet and indices it uses, so painting any tile of a terrain places the terrain and
+/// the autotiler picks the tile fitting its neighbours. Flips and rotation are kept.
+#[derive(Clone, Default, Debug)]
+pub struct TerrainRules {
+    terrains: Vec<Terrain>,
+    by_tile: HashMap<(u16, u16), TerrainId>,
+}
+
+impl TerrainRules {
+    /// Adds a terrain, tiles used by an earlier terrain stay with that one.
+    pub fn add(&mut self, terrain: Terrain) -> TerrainId {
+        let id = TerrainId(self.terrains.len() as u16);
+        for (index, _) in terrain.indices.values().flatten() {
+            self.by_tile.entry((terrain.sheet, *index)).or_insert(id);
+        }
+        self.terrains.push(terrain);
+        id
+    }
+
+    pub fn get(&self, id: TerrainId) -> Option<&Terrain> {
+        self.terrains.get(id.0 as usize)
+    }
+
+    pub fn terrain_of(&self, tile: &Tile) -> Option<TerrainId> {
+        self.by_tile.get(&(tile.sheet(), tile.index())).copied()
+    }
+
+    /// The tile fitting the neighbours of a terrain tile, None for tiles of no terrain and
+    /// masks the terrain has no index for.
+    pub fn resolve<L>(&self, map: &TileMap<L>, coord: &TileCoord, tile: &Tile) -> Option<Tile> {
+        let id = self.terrain_of(tile)?;
+        let terrain = self.get(id)?;
+        let matches = |neighbour: &Tile| self.terrain_of(neighbour) == Some(id);
+        let mask = match terrain.mode {
+            BitmaskMode::Edges => neighbour_mask(map, coord, matches),
+            BitmaskMode::Corners => corner_mask(map, coord, matches),
+        };
+        Some(tile.with_index(terrain.pick(mask, coord)?))
+    }
+}
+
+/// Queues the tiles updated this frame and their neighbours.
+pub(crate) fn queue_autotile_updates(
+    mut autotiler: ResMut<TileAutotiler>,
+    map: Res<TileMap>,
+    updates: Res<TileMapUpdates>,
+) {
+    if autotiler.rule.is_none() {
+        return;
+    }
+    for coord in updates.get_tile_updates() {
+        autotiler.queue.insert(coord);
+        autotiler.queue.extend(map.neighbours(&coord));
+        if autotiler.queue_diagonals {
+            for (x, y) in [(1, 1), (1, -1), (-1, -1), (-1, 1)] {
+                let diagonal = map.offset_coord(&coord, IVec3::new(x, y, 0));
+                autotiler.queue.insert(diagonal);
+            }
+        }
+    }
+}
+
+/// Resolves up to the budget of queued tiles, most important chunks first.
+pub(crate) fn resolve_autotiles(
+    mut autotiler: ResMut<TileAutotiler>,
+    priorities: Res<ChunkPriorities>,
+    mut writer: TileMapWriter,
+) {
+    let autotiler = &mut *autotiler;
+    let rule = match &autotiler.rule {
+        Some(rule) if !autotiler.queue.is_empty() => rule.clone(),
+        _ => return,
+    };
+    let mut due: Vec<TileCoord> = autotiler.queue.iter().copied().collect();
+    if due.len() > autotiler.budget_per_frame {
+        due.select_nth_unstable_by_key(autotiler.budget_per_frame, |coord| {
+            Reverse(priorities.get(&coord.chunk))
+        });
+        due.truncate(autotiler.budget_per_frame);
+    }
+    let mut changed = Vec::new();
+    for coord in due {
+        // Locked tiles stay queued until they are unlocked.
+        if writer.is_locked(&coord) {
+            continue;
+        }
+        autotiler.queue.remove(&coord);
+        if let Some(tile) = writer.chunks.get_tile(&coord) {
+            if let Some(resolved) = rule(&writer.chunks, &coord, tile) {
+                if resolved != *tile {
+                    changed.push((coord, Some(resolved)));
+                }
+            }
+        }
+    }
+    writer.set_tiles(changed);
+}
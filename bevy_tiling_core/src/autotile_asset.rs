@@ -0,0 +1,179 @@
+//! Autotile rules loaded from RON files through the `AssetServer`, enabled by the
+//! `autotile_assets` feature. Files ending in `.autotile.ron` describe a list of terrains:
+//!
+//! ```ron
+//! (
+//!     terrains: [
+//!         (
+//!             sheet: 1,
+//!             mode: Corners,
+//!             // Mask to tile index, or to a list of (index, weight) variants.
+//!             tiles: {
+//!                 0: 40,
+//!                 15: 30,
+//!                 255: [(20, 3), (21, 1)],
+//!             },
+//!         ),
+//!     ],
+//! )
+//! ```
+//!
+//! Point [`ActiveAutotileRules`] at a loaded file and [`AutotileAssetPlugin`] hands its
+//! terrains to the [`TileAutotiler`]. With the asset server watching for changes, saving the
+//! file re-resolves every tile of the terrains.
+
+use bevy::{
+    asset::{
+        AddAsset, AssetEvent, AssetLoader, Assets, BoxedFuture, Handle, LoadContext, LoadedAsset,
+    },
+    prelude::{EventReader, Local, Plugin, Res, ResMut},
+    reflect::TypeUuid,
+    utils::HashMap,
+};
+use serde::Deserialize;
+
+use crate::{
+    autotile::{BitmaskMode, Terrain, TerrainRules, TileAutotiler},
+    internal, TileMapWriter, TilingCoreStage,
+};
+
+/// The terrains of an `.autotile.ron` file, see the [module docs](self).
+#[derive(Deserialize, TypeUuid, Clone, Debug)]
+#[uuid = "de6a0805-fb23-4a19-807b-30eb7d4a26cc"]
+pub struct AutotileRules {
+    pub terrains: Vec<TerrainDefinition>,
+}
+
+/// A [`Terrain`] as written in an [`AutotileRules`] file.
+#[derive(Deserialize, Clone, Debug)]
+pub struct TerrainDefinition {
+    pub sheet: u16,
+    pub mode: BitmaskMode,
+    pub tiles: HashMap<u8, TileVariants>,
+}
+
+/// The tile of a mask, a single index or weighted variants.
+#[derive(Deserialize, Clone, Debug)]
+#[serde(untagged)]
+pub enum TileVariants {
+    Index(u16),
+    Weighted(Vec<(u16, u32)>),
+}
+
+impl AutotileRules {
+    /// The rules for [`TileAutotiler::set_terrains`], terrain ids follow the order of the file.
+    pub fn terrain_rules(&self) -> TerrainRules {
+        let mut rules = TerrainRules::default();
+        for definition in self.terrains.iter() {
+            let terrain = definition.tiles.iter().fold(
+                Terrain::new(definition.sheet, definition.mode),
+                |terrain, (mask, variants)| match variants {
+                    TileVariants::Index(index) => terrain.with_index(*mask, *index),
+                    TileVariants::Weighted(variants) => {
+                        terrain.with_variants(*mask, variants.iter().copied())
+                    }
+                },
+            );
+            rules.add(terrain);
+        }
+        rules
+    }
+}
+
+#[derive(Default)]
+pub struct AutotileRulesLoader;
+
+impl AssetLoader for AutotileRulesLoader {
+    fn load<'a>(
+        &'a self,
+        bytes: &'a [u8],
+        load_context: &'a mut LoadContext,
+    ) -> BoxedFuture<'a, Result<(), anyhow::Error>> {
+        Box::pin(async move {
+            let rules: AutotileRules = ron::de::from_bytes(bytes)?;
+            load_context.set_default_asset(LoadedAsset::new(rules));
+            Ok(())
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["autotile.ron"]
+    }
+}
+
+/// The rules the [`TileAutotiler`] follows, applied once loaded and again whenever they change.
+#[derive(Default)]
+pub struct ActiveAutotileRules(pub Option<Handle<AutotileRules>>);
+
+/// Loads [`AutotileRules`] and keeps the [`TileAutotiler`] on the [`ActiveAutotileRules`].
+/// [`crate::TilingPlugin`] and the `AssetPlugin` must be added too.
+pub struct AutotileAssetPlugin;
+
+impl Plugin for AutotileAssetPlugin {
+    fn build(&self, app: &mut bevy::prelude::App) {
+        app.add_asset::<AutotileRules>()
+            .init_asset_loader::<AutotileRulesLoader>()
+            .init_resource::<ActiveAutotileRules>()
+            .add_system_to_stage(TilingCoreStage::Schedule, apply_autotile_rules);
+    }
+}
+
+fn apply_autotile_rules(
+    mut events: EventReader<AssetEvent<AutotileRules>>,
+    active: Res<ActiveAutotileRules>,
+    assets: Res<Assets<AutotileRules>>,
+    mut applied: Local<Option<TerrainRules>>,
+    mut autotiler: ResMut<TileAutotiler>,
+    mut writer: TileMapWriter,
+) {
+    let handle = match &active.0 {
+        Some(handle) => handle,
+        None => return,
+    };
+    let changed = events.iter().any(|event| match event {
+        AssetEvent::Created { handle: loaded } | AssetEvent::Modified { handle: loaded } => {
+            loaded == handle
+        }
+        AssetEvent::Removed { .. } => false,
+    });
+    if !changed && !active.is_changed() {
+        return;
+    }
+    let rules = match assets.get(handle) {
+        Some(rules) => rules.terrain_rules(),
+        None => return,
+    };
+    // Tiles placed under the old rules are resolved again under the new ones, tiles the new
+    // rules no longer know are moved to their terrain's first tile, which updates them.
+    let mut moved = Vec::new();
+    for chunk_coord in writer.chunks.chunk_coords() {
+        let chunk = match writer.chunks.get_chunk(chunk_coord) {
+            Some(chunk) => chunk,
+            None => continue,
+        };
+        for index in 0..=u8::MAX {
+            let tile = match chunk.get_tile(index) {
+                Some(tile) => tile,
+                None => continue,
+            };
+            let coord = internal::tile_coord(*chunk_coord, index);
+            if rules.terrain_of(tile).is_some() {
+                autotiler.queue(coord);
+            } else if let Some(terrain) = applied
+                .as_ref()
+                .and_then(|old| old.terrain_of(tile))
+                .and_then(|id| rules.get(id))
+            {
+                if let Some(first) = terrain.first_index() {
+                    moved.push((
+                        coord,
+                        Some(tile.with_sheet(terrain.sheet).with_index(first)),
+                    ));
+                }
+            }
+        }
+    }
+    writer.set_tiles(moved);
+    *applied = Some(rules.clone());
+    autotiler.set_terrains(rules);
+}
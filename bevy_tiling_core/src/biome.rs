@@ -0,0 +1,187 @@
+use bevy::{
+    math::{IVec2, IVec3, Vec2},
+    prelude::{Res, ResMut},
+    utils::HashMap,
+};
+
+use crate::{bounds::MapWrap, TileCoord, TileMap, TileMapUpdates, CHUNK_SIZE};
+
+pub type BiomeId = u16;
+
+/// Coarse biome grid stored per chunk, one biome value per `cell_size` x `cell_size` tiles.
+///
+/// The resource added by [`crate::TilingPlugin`] follows the default map: it takes over the
+/// map's wrapping at the end of every frame, and the biomes of chunks removed from the map are
+/// dropped then, so set them again when the chunk comes back, e.g. from its generator.
+pub struct BiomeMap {
+    cell_size: i32,
+    wrap: Option<MapWrap>,
+    pub(crate) chunks: HashMap<IVec3, Vec<Option<BiomeId>>>,
+}
+
+impl Default for BiomeMap {
+    fn default() -> Self {
+        Self::new(4)
+    }
+}
+
+impl BiomeMap {
+    /// Creates an empty biome map.
+    /// # Panics
+    /// Panics if `cell_size` does not evenly divide the chunk size.
+    pub fn new(cell_size: u8) -> Self {
+        let cell_size = cell_size as i32;
+        assert!(
+            cell_size > 0 && CHUNK_SIZE % cell_size == 0,
+            "biome cell size must divide the chunk size"
+        );
+        Self {
+            cell_size,
+            wrap: None,
+            chunks: HashMap::default(),
+        }
+    }
+
+    pub fn cell_size(&self) -> u8 {
+        self.cell_size as u8
+    }
+
+    pub fn wrap(&self) -> Option<&MapWrap> {
+        self.wrap.as_ref()
+    }
+
+    /// Makes lookups wrap around like a map with the same [`MapWrap`], or stop wrapping with
+    /// None. Biomes already stored outside the new period are no longer reachable.
+    pub fn set_wrap(&mut self, wrap: Option<MapWrap>) {
+        self.wrap = wrap;
+    }
+
+    /// Biome cells of a chunk in row-major order, if any have been set.
+    pub fn get_chunk_biomes(&self, chunk: &IVec3) -> Option<&[Option<BiomeId>]> {
+        self.chunks
+            .get(&self.normalize_chunk(*chunk))
+            .map(Vec::as_slice)
+    }
+
+    /// Drops the biomes of a chunk, returning its cells.
+    pub fn remove_chunk(&mut self, chunk: &IVec3) -> Option<Vec<Option<BiomeId>>> {
+        self.chunks.remove(&self.normalize_chunk(*chunk))
+    }
+
+    fn normalize_chunk(&self, chunk: IVec3) -> IVec3 {
+        match &self.wrap {
+            Some(wrap) => wrap.normalize_chunk(chunk),
+            None => chunk,
+        }
+    }
+
+    /// Sets the biome of the cell containing the given tile, returning the previous value.
+    pub fn set_biome(&mut self, coord: &TileCoord, biome: Option<BiomeId>) -> Option<BiomeId> {
+        let position = coord.tile_position();
+        let cell = IVec2::new(
+            position.x.div_euclid(self.cell_size),
+            position.y.div_euclid(self.cell_size),
+        );
+        let (chunk, index) = self.locate_cell(cell, position.z);
+        let cells_per_chunk = self.cells_per_chunk();
+        match self.chunks.get_mut(&chunk) {
+            Some(cells) => std::mem::replace(&mut cells[index], biome),
+            None => {
+                if biome.is_some() {
+                    let mut cells = vec![None; (cells_per_chunk * cells_per_chunk) as usize];
+                    cells[index] = biome;
+                    self.chunks.insert(chunk, cells);
+                }
+                None
+            }
+        }
+    }
+
+    /// The biome of the cell containing the given tile.
+    pub fn biome_at(&self, coord: &TileCoord) -> Option<BiomeId> {
+        let position = coord.tile_position();
+        self.get_cell(
+            IVec2::new(
+                position.x.div_euclid(self.cell_size),
+                position.y.div_euclid(self.cell_size),
+            ),
+            position.z,
+        )
+    }
+
+    /// Bilinear blend of the four cells surrounding the tile center.
+    /// Returns each biome with its weight, heaviest first. Weights sum to one,
+    /// cells without a biome are left out, and an empty result means no biome is set nearby.
+    pub fn blend_at(&self, coord: &TileCoord) -> Vec<(BiomeId, f32)> {
+        let position = coord.tile_position();
+        let cell_space =
+            (Vec2::new(position.x as f32, position.y as f32) + 0.5) / self.cell_size as f32 - 0.5;
+        let base = cell_space.floor();
+        let fraction = cell_space - base;
+        let base = IVec2::new(base.x as i32, base.y as i32);
+
+        let mut weights: Vec<(BiomeId, f32)> = Vec::with_capacity(4);
+        let mut total = 0.0;
+        for (offset, weight) in [
+            (IVec2::new(0, 0), (1.0 - fraction.x) * (1.0 - fraction.y)),
+            (IVec2::new(1, 0), fraction.x * (1.0 - fraction.y)),
+            (IVec2::new(0, 1), (1.0 - fraction.x) * fraction.y),
+            (IVec2::new(1, 1), fraction.x * fraction.y),
+        ] {
+            if weight <= 0.0 {
+                continue;
+            }
+            if let Some(biome) = self.get_cell(base + offset, position.z) {
+                total += weight;
+                match weights.iter_mut().find(|(id, _)| *id == biome) {
+                    Some((_, existing)) => *existing += weight,
+                    None => weights.push((biome, weight)),
+                }
+            }
+        }
+
+        for (_, weight) in weights.iter_mut() {
+            *weight /= total;
+        }
+        weights.sort_by(|a, b| b.1.total_cmp(&a.1));
+        weights
+    }
+
+    fn cells_per_chunk(&self) -> i32 {
+        CHUNK_SIZE / self.cell_size
+    }
+
+    fn locate_cell(&self, cell: IVec2, layer: i32) -> (IVec3, usize) {
+        let cells_per_chunk = self.cells_per_chunk();
+        let chunk = self.normalize_chunk(IVec3::new(
+            cell.x.div_euclid(cells_per_chunk),
+            cell.y.div_euclid(cells_per_chunk),
+            layer,
+        ));
+        let index = cell.y.rem_euclid(cells_per_chunk) * cells_per_chunk
+            + cell.x.rem_euclid(cells_per_chunk);
+        (chunk, index as usize)
+    }
+
+    fn get_cell(&self, cell: IVec2, layer: i32) -> Option<BiomeId> {
+        let (chunk, index) = self.locate_cell(cell, layer);
+        self.chunks.get(&chunk).and_then(|cells| cells[index])
+    }
+}
+
+/// Keeps the [`BiomeMap`] in line with the default map, see its docs.
+pub(crate) fn follow_tile_map(
+    mut biomes: ResMut<BiomeMap>,
+    updates: Res<TileMapUpdates>,
+    map: Res<TileMap>,
+) {
+    if biomes.wrap() != map.wrap() {
+        biomes.set_wrap(map.wrap().copied());
+    }
+    for chunk in updates.get_chunk_removals() {
+        // A chunk can be removed and created again in the same frame, e.g. when it is replaced.
+        if map.get_chunk(chunk).is_none() {
+            biomes.remove_chunk(chunk);
+        }
+    }
+}
@@ -0,0 +1,190 @@
+use bevy::{
+    math::IVec3,
+    prelude::ResMut,
+    utils::{HashMap, HashSet},
+};
+
+use crate::{preview::TilePreview, IntoTileCoord, Tile, TileCoord, TileMap, TileMapWriter};
+
+/// A copy of the set tiles of a region, positioned in tiles relative to the region's minimum
+/// corner. The z offset is the layer offset.
+#[derive(Clone, Default, PartialEq, Eq, Debug)]
+pub struct TilePatch {
+    tiles: Vec<(IVec3, Tile)>,
+}
+
+impl TilePatch {
+    /// Copies the set tiles of the box from `min` to `max` (inclusive, in tiles).
+    pub fn capture<L>(map: &TileMap<L>, min: IVec3, max: IVec3) -> Self {
+        let origin = min.min(max);
+        let mut tiles: Vec<(IVec3, Tile)> = map
+            .iter_region(min, max)
+            .map(|(coord, tile)| (coord.tile_position() - origin, *tile))
+            .collect();
+        tiles.sort_by_key(|(offset, _)| (offset.z, offset.y, offset.x));
+        Self { tiles }
+    }
+
+    /// Sets `tile` at `offset`, replacing the tile the patch had there.
+    pub fn insert(&mut self, offset: IVec3, tile: Tile) {
+        match self.tiles.iter_mut().find(|(other, _)| *other == offset) {
+            Some((_, old)) => *old = tile,
+            None => self.tiles.push((offset, tile)),
+        }
+    }
+
+    /// Every tile with its offset, lowest layer and row first for captured patches.
+    pub fn tiles(&self) -> impl Iterator<Item = (IVec3, &Tile)> {
+        self.tiles.iter().map(|(offset, tile)| (*offset, tile))
+    }
+
+    /// Every tile with the coordinate it lands on when the patch is placed at `origin`.
+    pub fn placed_at(&self, origin: IVec3) -> impl Iterator<Item = (TileCoord, &Tile)> {
+        self.tiles
+            .iter()
+            .map(move |(offset, tile)| (TileCoord::from_tile_position(origin + *offset), tile))
+    }
+
+    /// Writes the patch into the map right away, with its minimum corner at `origin`.
+    /// Tiles the patch doesn't have are left alone.
+    pub fn stamp(&self, writer: &mut TileMapWriter, origin: IVec3) {
+        writer.set_tiles(
+            self.placed_at(origin)
+                .map(|(coord, tile)| (coord, Some(*tile))),
+        );
+    }
+
+    pub fn len(&self) -> usize {
+        self.tiles.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.tiles.is_empty()
+    }
+}
+
+/// A [`TilePatch`] along with the resources needed to build it, e.g. the items a player must
+/// carry to construct a copied factory. Resources are named like markers and regions.
+#[derive(Clone, Default, PartialEq, Eq, Debug)]
+pub struct Blueprint {
+    pub patch: TilePatch,
+    pub requirements: HashMap<String, u32>,
+}
+
+impl Blueprint {
+    /// Captures the box from `min` to `max` (inclusive, in tiles), summing what `cost` returns
+    /// for every tile into the requirements.
+    pub fn capture<L>(
+        map: &TileMap<L>,
+        min: IVec3,
+        max: IVec3,
+        cost: impl Fn(&Tile) -> Vec<(String, u32)>,
+    ) -> Self {
+        let patch = TilePatch::capture(map, min, max);
+        let mut requirements: HashMap<String, u32> = HashMap::default();
+        for (_, tile) in patch.tiles() {
+            for (resource, amount) in cost(tile) {
+                *requirements.entry(resource).or_default() += amount;
+            }
+        }
+        Self {
+            patch,
+            requirements,
+        }
+    }
+}
+
+/// Tiles waiting to be built, shown as ghosts in the [`TilePreview`] until construction
+/// confirms them, e.g. once a worker reached the cell and spent the resources.
+///
+/// Confirmed cells are written through [`TileMapWriter`] before `CoreStage::Update` and leave
+/// the construction, cells locked with [`crate::locks::TileLocks`] once they are unlocked.
+/// Ghosts are checked against the placement rules every frame, so their validity follows the
+/// map as it changes.
+#[derive(Default, Debug)]
+pub struct TileConstruction {
+    cells: HashMap<TileCoord, Tile>,
+    confirmed: HashSet<TileCoord>,
+}
+
+impl TileConstruction {
+    /// Queues the patch with its minimum corner at `origin`, skipping tiles the map already has.
+    /// Cells queued before are replaced. Returns how many cells were queued.
+    pub fn place<L>(&mut self, patch: &TilePatch, origin: IVec3, map: &TileMap<L>) -> usize {
+        let mut queued = 0;
+        for (coord, tile) in patch.placed_at(origin) {
+            if map.get_tile(&coord) == Some(tile) {
+                continue;
+            }
+            self.cells.insert(coord, *tile);
+            self.confirmed.remove(&coord);
+            queued += 1;
+        }
+        queued
+    }
+
+    /// The tile waiting to be built at `coord`.
+    pub fn get(&self, coord: impl IntoTileCoord) -> Option<&Tile> {
+        self.cells.get(&coord.into_tile_coord())
+    }
+
+    /// Marks a cell as built, its tile is written on the next run. Returns whether the cell
+    /// was waiting.
+    pub fn confirm(&mut self, coord: impl IntoTileCoord) -> bool {
+        let coord = coord.into_tile_coord();
+        self.cells.contains_key(&coord) && self.confirmed.insert(coord)
+    }
+
+    /// Drops a cell, confirmed or not, returning its tile.
+    pub fn cancel(&mut self, coord: impl IntoTileCoord) -> Option<Tile> {
+        let coord = coord.into_tile_coord();
+        self.confirmed.remove(&coord);
+        self.cells.remove(&coord)
+    }
+
+    pub fn clear(&mut self) {
+        self.cells.clear();
+        self.confirmed.clear();
+    }
+
+    /// Every cell still waiting for confirmation, in no particular order.
+    pub fn pending(&self) -> impl Iterator<Item = (&TileCoord, &Tile)> {
+        self.cells
+            .iter()
+            .filter(|(coord, _)| !self.confirmed.contains(coord))
+    }
+
+    /// Number of cells waiting to be built or written.
+    pub fn len(&self) -> usize {
+        self.cells.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.cells.is_empty()
+    }
+}
+
+/// Writes the confirmed cells and shows the rest as ghosts.
+pub(crate) fn build_confirmed_tiles(
+    mut construction: ResMut<TileConstruction>,
+    mut preview: ResMut<TilePreview>,
+    mut writer: TileMapWriter,
+) {
+    let construction = &mut *construction;
+    if !construction.confirmed.is_empty() {
+        let mut built: Vec<(TileCoord, Option<Tile>)> = Vec::new();
+        construction.confirmed.retain(|coord| {
+            if writer.is_locked(coord) {
+                return true;
+            }
+            if let Some(tile) = construction.cells.remove(coord) {
+                built.push((*coord, Some(tile)));
+            }
+            false
+        });
+        writer.set_tiles(built);
+    }
+    for (coord, tile) in construction.cells.iter() {
+        preview.set_checked(*coord, *tile, &writer.placement, &writer.chunks);
+    }
+}
@@ -0,0 +1,100 @@
+use bevy::math::{IVec2, IVec3};
+
+use crate::TileCoord;
+
+/// What happens to writes outside of [`MapBounds`].
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum BoundsMode {
+    /// The write is dropped.
+    Reject,
+    /// The write lands on the nearest tile inside the bounds.
+    Clamp,
+}
+
+/// Finite extent of a map, in tiles with z as the layer. Both corners are inclusive.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct MapBounds {
+    pub min: IVec3,
+    pub max: IVec3,
+    pub mode: BoundsMode,
+}
+
+impl MapBounds {
+    pub fn new(min: IVec3, max: IVec3, mode: BoundsMode) -> Self {
+        Self {
+            min: min.min(max),
+            max: min.max(max),
+            mode,
+        }
+    }
+
+    pub fn contains(&self, coord: &TileCoord) -> bool {
+        let position = coord.tile_position();
+        position.cmpge(self.min).all() && position.cmple(self.max).all()
+    }
+
+    /// Whether any tile of the chunk lies inside the bounds.
+    pub fn contains_chunk(&self, chunk: &IVec3) -> bool {
+        let (min_chunk, max_chunk) = self.chunk_range();
+        chunk.cmpge(min_chunk).all() && chunk.cmple(max_chunk).all()
+    }
+
+    /// The first and last chunk touched by the bounds.
+    pub fn chunk_range(&self) -> (IVec3, IVec3) {
+        (
+            TileCoord::from_tile_position(self.min).chunk,
+            TileCoord::from_tile_position(self.max).chunk,
+        )
+    }
+
+    /// Where a write to `coord` should land, or None if it must be dropped.
+    pub fn resolve(&self, coord: &TileCoord) -> Option<TileCoord> {
+        if self.contains(coord) {
+            return Some(*coord);
+        }
+        match self.mode {
+            BoundsMode::Reject => None,
+            BoundsMode::Clamp => Some(TileCoord::from_tile_position(
+                coord.tile_position().clamp(self.min, self.max),
+            )),
+        }
+    }
+}
+
+/// Wrap-around topology where leaving the map on one side enters it on the opposite side.
+/// The period is counted in chunks so wrapping never splits a chunk, an axis with a period
+/// of zero does not wrap.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct MapWrap {
+    pub period: IVec2,
+}
+
+impl MapWrap {
+    pub fn new(period: IVec2) -> Self {
+        Self {
+            period: period.max(IVec2::ZERO),
+        }
+    }
+
+    /// Maps any chunk coordinate onto the canonical copy inside the period.
+    pub fn normalize_chunk(&self, chunk: IVec3) -> IVec3 {
+        let mut chunk = chunk;
+        if self.period.x > 0 {
+            chunk.x = chunk.x.rem_euclid(self.period.x);
+        }
+        if self.period.y > 0 {
+            chunk.y = chunk.y.rem_euclid(self.period.y);
+        }
+        chunk
+    }
+
+    pub fn normalize(&self, coord: &TileCoord) -> TileCoord {
+        TileCoord {
+            index: coord.index,
+            chunk: self.normalize_chunk(coord.chunk),
+        }
+    }
+}
@@ -0,0 +1,121 @@
+use std::{
+    any::{Any, TypeId},
+    marker::PhantomData,
+};
+
+use bevy::{math::IVec3, utils::HashMap};
+
+use crate::DefaultMap;
+
+/// Typed user data attached to chunks, keyed by the same coordinates as [`crate::TileMap`].
+/// The data of every type lives in the [`ChunkDataStore`] of its map, reach it through
+/// [`crate::MapReader::get_chunk_data`] and the `*_chunk_data` methods of
+/// [`crate::TileMapWriter`].
+pub struct ChunkData<T> {
+    chunks: HashMap<IVec3, T>,
+}
+
+impl<T> Default for ChunkData<T> {
+    fn default() -> Self {
+        Self {
+            chunks: HashMap::default(),
+        }
+    }
+}
+
+impl<T> ChunkData<T> {
+    pub fn get(&self, chunk: &IVec3) -> Option<&T> {
+        self.chunks.get(chunk)
+    }
+
+    pub fn get_mut(&mut self, chunk: &IVec3) -> Option<&mut T> {
+        self.chunks.get_mut(chunk)
+    }
+
+    /// Gets the data of a chunk, creating it with `f` if the chunk has none yet.
+    pub fn get_or_insert_with(&mut self, chunk: IVec3, f: impl FnOnce() -> T) -> &mut T {
+        self.chunks.entry(chunk).or_insert_with(f)
+    }
+
+    /// Attaches data to a chunk, returning the data it replaced.
+    pub fn insert(&mut self, chunk: IVec3, data: T) -> Option<T> {
+        self.chunks.insert(chunk, data)
+    }
+
+    pub fn remove(&mut self, chunk: &IVec3) -> Option<T> {
+        self.chunks.remove(chunk)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&IVec3, &T)> {
+        self.chunks.iter()
+    }
+}
+
+trait AnyChunkData: Send + Sync {
+    fn remove_chunk(&mut self, chunk: &IVec3);
+    fn as_any(&self) -> &dyn Any;
+    fn as_any_mut(&mut self) -> &mut dyn Any;
+}
+
+impl<T: Send + Sync + 'static> AnyChunkData for ChunkData<T> {
+    fn remove_chunk(&mut self, chunk: &IVec3) {
+        self.chunks.remove(chunk);
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+}
+
+/// The [`ChunkData`] of every type attached to the map labeled `L`.
+/// Removing a chunk with [`crate::TileMapWriter::remove_chunk`] drops its data of every type,
+/// so streamed out chunks don't leave stale data behind. Serialize the [`ChunkData`] of a type
+/// along with the map to keep it.
+pub struct ChunkDataStore<L = DefaultMap> {
+    data: HashMap<TypeId, Box<dyn AnyChunkData>>,
+    label: PhantomData<fn() -> L>,
+}
+
+impl Default for ChunkDataStore {
+    fn default() -> Self {
+        Self::empty()
+    }
+}
+
+impl<L> ChunkDataStore<L> {
+    pub(crate) fn empty() -> Self {
+        Self {
+            data: HashMap::default(),
+            label: PhantomData,
+        }
+    }
+
+    /// The data of type `T`, if any has been attached.
+    pub fn get<T: Send + Sync + 'static>(&self) -> Option<&ChunkData<T>> {
+        self.data
+            .get(&TypeId::of::<T>())
+            .and_then(|data| data.as_any().downcast_ref())
+    }
+
+    /// The data of type `T`, created empty if none has been attached yet.
+    /// Assign to it to replace all data of the type, e.g. after loading it.
+    pub fn get_mut<T: Send + Sync + 'static>(&mut self) -> &mut ChunkData<T> {
+        self.data
+            .entry(TypeId::of::<T>())
+            .or_insert_with(|| Box::new(ChunkData::<T>::default()))
+            .as_any_mut()
+            .downcast_mut()
+            .unwrap()
+    }
+
+    /// Drops the data of every type attached to a chunk.
+    pub fn remove_chunk(&mut self, chunk: &IVec3) {
+        for data in self.data.values_mut() {
+            data.remove_chunk(chunk);
+        }
+    }
+}
@@ -0,0 +1,118 @@
+use std::fmt;
+
+use bevy::math::IVec3;
+
+use crate::{Tile, TileCoord};
+
+/// The cell value of an empty cell in the grids of [`crate::TileMap::from_grid`].
+pub const EMPTY_CELL: u16 = u16::MAX;
+
+/// Errors returned by [`parse_csv`].
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum CsvError {
+    /// A cell isn't a tile index, -1 or empty. Lines and columns count from 1.
+    InvalidCell {
+        line: usize,
+        column: usize,
+        value: String,
+    },
+    /// A row has a different number of cells than the first row.
+    RaggedRow {
+        line: usize,
+        expected: usize,
+        found: usize,
+    },
+}
+
+impl fmt::Display for CsvError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CsvError::InvalidCell {
+                line,
+                column,
+                value,
+            } => write!(
+                f,
+                "invalid cell {:?} at line {}, column {}",
+                value, line, column
+            ),
+            CsvError::RaggedRow {
+                line,
+                expected,
+                found,
+            } => write!(
+                f,
+                "line {} has {} cells, expected {}",
+                line, found, expected
+            ),
+        }
+    }
+}
+
+impl std::error::Error for CsvError {}
+
+/// Reads a grid of tile indices, one row per line from the top, returning the cells and the
+/// width of the grid. Empty cells and -1 become [`EMPTY_CELL`], blank lines are skipped.
+pub fn parse_csv(text: &str) -> Result<(Vec<u16>, usize), CsvError> {
+    let mut cells = Vec::new();
+    let mut width = None;
+    for (line, row) in text.lines().enumerate() {
+        let row = row.trim();
+        if row.is_empty() {
+            continue;
+        }
+        let start = cells.len();
+        for (column, cell) in row.split(',').enumerate() {
+            let cell = cell.trim();
+            cells.push(match cell {
+                "" | "-1" => EMPTY_CELL,
+                _ => cell
+                    .parse()
+                    .ok()
+                    .filter(|index| *index != EMPTY_CELL)
+                    .ok_or_else(|| CsvError::InvalidCell {
+                        line: line + 1,
+                        column: column + 1,
+                        value: cell.to_string(),
+                    })?,
+            });
+        }
+        let found = cells.len() - start;
+        match width {
+            None => width = Some(found),
+            Some(expected) if expected != found => {
+                return Err(CsvError::RaggedRow {
+                    line: line + 1,
+                    expected,
+                    found,
+                })
+            }
+            Some(_) => {}
+        }
+    }
+    Ok((cells, width.unwrap_or(0)))
+}
+
+/// The tiles of a grid `width` cells wide in row order from the top, the top row landing on
+/// the highest y so the grid reads the same as it is written. `origin` is the tile position
+/// of the bottom left cell.
+pub(crate) fn grid_tiles(
+    grid: &[u16],
+    width: usize,
+    origin: IVec3,
+    sheet: u16,
+) -> impl Iterator<Item = (TileCoord, Option<Tile>)> + '_ {
+    let grid = if width == 0 { &[][..] } else { grid };
+    let width = width.max(1);
+    let height = grid.len().div_ceil(width);
+    grid.chunks(width)
+        .enumerate()
+        .flat_map(move |(row, cells)| {
+            let y = (height - 1 - row) as i32;
+            cells.iter().enumerate().map(move |(x, cell)| {
+                let position = origin + IVec3::new(x as i32, y, 0);
+                let tile = (*cell != EMPTY_CELL).then(|| Tile::new(sheet, *cell));
+                (TileCoord::from_tile_position(position), tile)
+            })
+        })
+}
@@ -0,0 +1,161 @@
+use std::marker::PhantomData;
+
+use bevy::{
+    math::IVec3,
+    prelude::{Plugin, Res, ResMut},
+    utils::{HashMap, HashSet},
+};
+
+use crate::{Tile, TileCoord, TileMap, TilingCoreStage};
+
+type Conductivity = Box<dyn Fn(Option<&Tile>) -> f32 + Send + Sync>;
+
+/// A scalar value per tile, such as temperature or gas concentration, that spreads to
+/// neighbouring tiles every step. `M` is a marker type so several layers can coexist.
+///
+/// Only chunks that differ from the ambient value are stored. Chunks that settle back to
+/// ambient are dropped again, so quiet areas of the map cost nothing to simulate. A chunk
+/// written to since the last step is never dropped, so small amounts added every frame add up.
+///
+/// Values flow across the seam of wrapping maps. Coordinates passed in are expected to be the
+/// canonical ones there, see [`TileMap::resolve_coord`].
+pub struct DiffusionLayer<M> {
+    chunks: HashMap<IVec3, Box<[f32; 256]>>,
+    /// Chunks written to since the last step.
+    written: HashSet<IVec3>,
+    ambient: f32,
+    rate: f32,
+    conductivity: Conductivity,
+    marker: PhantomData<fn() -> M>,
+}
+
+/// Values closer than this to ambient are considered settled.
+const SETTLED: f32 = 1e-3;
+
+impl<M> DiffusionLayer<M> {
+    /// Creates a layer where every tile starts at `ambient`.
+    /// `rate` is clamped to 0.25, above which the simulation becomes unstable.
+    /// `conductivity` maps the tile at a position to how readily it exchanges value with its
+    /// neighbours, from 0 (insulator) to 1. The exchange between two tiles uses the lower of the two.
+    pub fn new(
+        ambient: f32,
+        rate: f32,
+        conductivity: impl Fn(Option<&Tile>) -> f32 + Send + Sync + 'static,
+    ) -> Self {
+        Self {
+            chunks: HashMap::default(),
+            written: HashSet::default(),
+            ambient,
+            rate: rate.clamp(0.0, 0.25),
+            conductivity: Box::new(conductivity),
+            marker: PhantomData,
+        }
+    }
+
+    pub fn ambient(&self) -> f32 {
+        self.ambient
+    }
+
+    pub fn value_at(&self, coord: &TileCoord) -> f32 {
+        self.chunks
+            .get(&coord.chunk)
+            .map_or(self.ambient, |values| values[coord.index as usize])
+    }
+
+    pub fn set_value(&mut self, coord: &TileCoord, value: f32) {
+        let ambient = self.ambient;
+        self.written.insert(coord.chunk);
+        self.chunks
+            .entry(coord.chunk)
+            .or_insert_with(|| Box::new([ambient; 256]))[coord.index as usize] = value;
+    }
+
+    /// Adds to the value at a tile, e.g. a heat source emitting every frame.
+    pub fn add_value(&mut self, coord: &TileCoord, amount: f32) {
+        let value = self.value_at(coord);
+        self.set_value(coord, value + amount);
+    }
+
+    /// Chunks currently being simulated.
+    pub fn active_chunks(&self) -> impl Iterator<Item = &IVec3> {
+        self.chunks.keys()
+    }
+
+    /// Advances the simulation by one step, exchanging value between horizontally
+    /// and vertically adjacent tiles of the same layer.
+    pub fn step(&mut self, map: &TileMap) {
+        self.wake_neighbours(map);
+
+        let mut next: HashMap<IVec3, Box<[f32; 256]>> = HashMap::default();
+        for (chunk, values) in self.chunks.iter() {
+            let mut new_values = values.clone();
+            for index in 0..=u8::MAX {
+                let coord = TileCoord {
+                    index,
+                    chunk: *chunk,
+                };
+                let value = values[index as usize];
+                let own_conductivity = (self.conductivity)(map.get_tile(&coord));
+                let mut flux = 0.0;
+                for neighbour in map.neighbours(&coord) {
+                    let conductivity =
+                        own_conductivity.min((self.conductivity)(map.get_tile(&neighbour)));
+                    flux += conductivity.clamp(0.0, 1.0) * (self.value_at(&neighbour) - value);
+                }
+                new_values[index as usize] = value + self.rate * flux;
+            }
+            if self.written.contains(chunk)
+                || new_values
+                    .iter()
+                    .any(|value| (value - self.ambient).abs() > SETTLED)
+            {
+                next.insert(*chunk, new_values);
+            }
+        }
+        self.chunks = next;
+        self.written.clear();
+    }
+
+    /// Starts simulating neighbours of chunks whose border differs from ambient,
+    /// so values can flow into them.
+    fn wake_neighbours(&mut self, map: &TileMap) {
+        let mut woken = HashSet::default();
+        for (chunk, values) in self.chunks.iter() {
+            let unsettled = |index: usize| (values[index] - self.ambient).abs() > SETTLED;
+            for (offset, unsettled_border) in [
+                (IVec3::new(-1, 0, 0), (0..16).any(|y| unsettled(y * 16))),
+                (IVec3::new(1, 0, 0), (0..16).any(|y| unsettled(y * 16 + 15))),
+                (IVec3::new(0, -1, 0), (0..16).any(unsettled)),
+                (IVec3::new(0, 1, 0), (0..16).any(|x| unsettled(240 + x))),
+            ] {
+                let neighbour = map.normalize_chunk(&(*chunk + offset));
+                if unsettled_border && !self.chunks.contains_key(&neighbour) {
+                    woken.insert(neighbour);
+                }
+            }
+        }
+        for chunk in woken {
+            self.chunks.insert(chunk, Box::new([self.ambient; 256]));
+        }
+    }
+}
+
+/// Steps a [`DiffusionLayer<M>`] once per frame in [`TilingCoreStage::Update`].
+/// The layer resource itself must be inserted by the user.
+pub struct DiffusionPlugin<M>(PhantomData<fn() -> M>);
+
+impl<M> Default for DiffusionPlugin<M> {
+    fn default() -> Self {
+        Self(PhantomData)
+    }
+}
+
+impl<M: 'static> Plugin for DiffusionPlugin<M> {
+    fn build(&self, app: &mut bevy::prelude::App) {
+        app.add_system_to_stage(TilingCoreStage::Update, step_diffusion::<M>);
+    }
+}
+
+fn step_diffusion<M: 'static>(mut layer: ResMut<DiffusionLayer<M>>, map: Res<TileMap>) {
+    layer.step(&map);
+}
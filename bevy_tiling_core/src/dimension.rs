@@ -0,0 +1,181 @@
+//! Dimensions, like a cave layer or a nether-style alternate world, enabled by the `streaming`
+//! feature.
+//!
+//! A dimension is a map labeled `L` added with [`DimensionPlugin`]: a [`TileMapPlugin`] map with
+//! its own `ChunkStreaming<L>`, `TileMapGenerator<L>` and `ChunkPriorities<L>`, and with
+//! [`DimensionPersistPlugin`] its own `ChunkStore<L>`. [`StreamingAnchor`]s stream the map of
+//! their [`InDimension`], or the default map without one, so a map without anchors in it keeps
+//! its chunks like the default map does. [`crate::locks::TileLocks`] apply to every map.
+//!
+//! Each dimension lines up with the default map through a [`DimensionMapping`], which
+//! [`Dimensions`] uses to find corresponding positions and to move entities between dimensions.
+
+use std::{any::TypeId, marker::PhantomData};
+
+use bevy::{
+    math::{IVec3, Vec3},
+    prelude::{App, Component, Plugin},
+    utils::HashMap,
+};
+
+#[cfg(feature = "persist")]
+use crate::persist::add_persistence;
+#[cfg(doc)]
+use crate::streaming::StreamingAnchor;
+use crate::{
+    grid::TileGrid, streaming::add_streaming, DefaultMap, MapLabel, TileCoord, TileMapPlugin,
+};
+
+/// Puts a [`StreamingAnchor`] into the dimension of a labeled map, e.g.
+/// `InDimension::of::<Cave>()`, see the [module docs](self).
+#[derive(Component, Copy, Clone, PartialEq, Eq, Hash, Debug)]
+pub struct InDimension(TypeId);
+
+impl InDimension {
+    pub fn of<L: MapLabel>() -> Self {
+        Self(TypeId::of::<L>())
+    }
+
+    pub fn is<L: MapLabel>(&self) -> bool {
+        self.0 == TypeId::of::<L>()
+    }
+}
+
+/// How the tiles of a dimension line up with the default map: tile `p` covers `scale` by
+/// `scale` tiles of the default map starting at `p * scale + offset`, on layer `p.z + offset.z`.
+/// A nether where each tile spans 8 tiles of the default map has a scale of 8.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub struct DimensionMapping {
+    pub scale: i32,
+    pub offset: IVec3,
+}
+
+impl Default for DimensionMapping {
+    fn default() -> Self {
+        Self {
+            scale: 1,
+            offset: IVec3::ZERO,
+        }
+    }
+}
+
+impl DimensionMapping {
+    fn position_in_default_map(&self, position: IVec3) -> IVec3 {
+        (position.truncate() * self.scale).extend(position.z) + self.offset
+    }
+
+    fn position_from_default_map(&self, position: IVec3) -> IVec3 {
+        let position = position - self.offset;
+        let scale = self.scale.max(1);
+        IVec3::new(
+            position.x.div_euclid(scale),
+            position.y.div_euclid(scale),
+            position.z,
+        )
+    }
+}
+
+/// The [`DimensionMapping`] of every dimension, added by [`DimensionPlugin`]. The default map
+/// maps onto itself.
+#[derive(Default, Debug)]
+pub struct Dimensions {
+    mappings: HashMap<TypeId, DimensionMapping>,
+}
+
+impl Dimensions {
+    pub fn mapping<L: MapLabel>(&self) -> DimensionMapping {
+        self.mapping_of(TypeId::of::<L>())
+    }
+
+    pub fn set_mapping<L: MapLabel>(&mut self, mapping: DimensionMapping) {
+        self.mappings.insert(TypeId::of::<L>(), mapping);
+    }
+
+    fn mapping_of(&self, map: TypeId) -> DimensionMapping {
+        self.mappings.get(&map).copied().unwrap_or_default()
+    }
+
+    /// The tile position of the map labeled `To` corresponding to a tile position of the map
+    /// labeled `From`, going through the default map. Positions of a coarser dimension
+    /// correspond to the first tile they cover.
+    pub fn corresponding<From: MapLabel, To: MapLabel>(&self, position: IVec3) -> IVec3 {
+        self.corresponding_between(TypeId::of::<From>(), TypeId::of::<To>(), position)
+    }
+
+    fn corresponding_between(&self, from: TypeId, to: TypeId, position: IVec3) -> IVec3 {
+        self.mapping_of(to)
+            .position_from_default_map(self.mapping_of(from).position_in_default_map(position))
+    }
+
+    /// Moves an entity, like a player carrying a [`StreamingAnchor`], from the dimension it is
+    /// in (the default map without an [`InDimension`]) to the corresponding tile of the map
+    /// labeled `To`, keeping its offset inside the tile. Returns the dimension to insert on
+    /// the entity, so its anchor streams `To` from then on.
+    pub fn teleport<To: MapLabel>(
+        &self,
+        grid: &TileGrid,
+        dimension: Option<&InDimension>,
+        translation: &mut Vec3,
+    ) -> InDimension {
+        let from = dimension.map_or(TypeId::of::<DefaultMap>(), |dimension| dimension.0);
+        let tile = grid.world_to_tile(*translation);
+        let target = TileCoord::from_tile_position(self.corresponding_between(
+            from,
+            TypeId::of::<To>(),
+            tile.tile_position(),
+        ));
+        *translation += grid.tile_to_world(&target) - grid.tile_to_world(&tile);
+        InDimension::of::<To>()
+    }
+}
+
+/// Adds the map labeled `L` as a dimension streamed around the anchors in it, see the
+/// [module docs](self). [`crate::TilingPlugin`] must be added too.
+pub struct DimensionPlugin<L> {
+    mapping: DimensionMapping,
+    label: PhantomData<fn() -> L>,
+}
+
+impl<L> DimensionPlugin<L> {
+    pub fn new(mapping: DimensionMapping) -> Self {
+        Self {
+            mapping,
+            label: PhantomData,
+        }
+    }
+}
+
+impl<L> Default for DimensionPlugin<L> {
+    fn default() -> Self {
+        Self::new(DimensionMapping::default())
+    }
+}
+
+impl<L: MapLabel> Plugin for DimensionPlugin<L> {
+    fn build(&self, app: &mut App) {
+        app.add_plugin(TileMapPlugin::<L>::default());
+        add_streaming::<L>(app);
+        app.world
+            .get_resource_or_insert_with(Dimensions::default)
+            .set_mapping::<L>(self.mapping);
+    }
+}
+
+/// Saves the unloaded chunks of the dimension labeled `L` into the `ChunkStore<L>` resource and
+/// loads them back, like [`crate::persist::ChunkPersistPlugin`] does for the default map.
+#[cfg(feature = "persist")]
+pub struct DimensionPersistPlugin<L>(PhantomData<fn() -> L>);
+
+#[cfg(feature = "persist")]
+impl<L> Default for DimensionPersistPlugin<L> {
+    fn default() -> Self {
+        Self(PhantomData)
+    }
+}
+
+#[cfg(feature = "persist")]
+impl<L: MapLabel> Plugin for DimensionPersistPlugin<L> {
+    fn build(&self, app: &mut App) {
+        add_persistence::<L>(app);
+    }
+}
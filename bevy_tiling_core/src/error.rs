@@ -0,0 +1,27 @@
+use std::fmt;
+
+use bevy::math::IVec3;
+
+use crate::TileCoord;
+
+/// Errors returned by the fallible `try_` variants of the map methods.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum TilingError {
+    /// The write was rejected by the map bounds, see [`crate::TileMap::set_bounds`].
+    OutOfBounds(TileCoord),
+    /// No chunk exists at the coordinate.
+    MissingChunk(IVec3),
+}
+
+impl fmt::Display for TilingError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TilingError::OutOfBounds(coord) => {
+                write!(f, "tile {} is outside the map bounds", coord)
+            }
+            TilingError::MissingChunk(chunk) => write!(f, "no chunk at {}", chunk),
+        }
+    }
+}
+
+impl std::error::Error for TilingError {}
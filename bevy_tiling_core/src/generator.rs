@@ -0,0 +1,203 @@
+use bevy::math::IVec3;
+#[cfg(feature = "streaming")]
+use std::{
+    future::Future,
+    marker::PhantomData,
+    pin::Pin,
+    sync::Arc,
+    task::{Context, Poll, Wake, Waker},
+};
+
+#[cfg(feature = "streaming")]
+use bevy::{
+    prelude::{EventReader, Res, ResMut},
+    tasks::{AsyncComputeTaskPool, Task},
+    utils::{HashMap, HashSet},
+};
+
+#[cfg(feature = "persist")]
+use crate::persist::ChunkStore;
+use crate::Chunk;
+#[cfg(feature = "streaming")]
+use crate::{streaming::ChunkLoadRequest, DefaultMap, MapLabel, TileMapWriter};
+
+/// Creates the contents of chunks that don't exist yet, e.g. from a noise function for an
+/// infinite world. Register one with [`TileMapGenerator::set`].
+pub trait ChunkGenerator: Send + Sync + 'static {
+    fn generate(&self, chunk_coord: IVec3) -> Chunk;
+}
+
+impl<F> ChunkGenerator for F
+where
+    F: Fn(IVec3) -> Chunk + Send + Sync + 'static,
+{
+    fn generate(&self, chunk_coord: IVec3) -> Chunk {
+        self(chunk_coord)
+    }
+}
+
+/// The [`ChunkGenerator`] answering [`ChunkLoadRequest`]s, see
+/// [`crate::streaming::ChunkStreamingPlugin`]. Without a generator requests are left to other
+/// systems, e.g. ones loading chunks from disk.
+///
+/// Chunks are generated on the async compute task pool and inserted into the map in a later
+/// frame once they are done, so slow generators don't stall the frame. Without the pool they
+/// are generated right away. Chunks overlapping a box locked with [`crate::locks::TileLocks`]
+/// are held back until it is unlocked. The generator of the map labeled `L` is
+/// `TileMapGenerator<L>`.
+#[cfg(feature = "streaming")]
+pub struct TileMapGenerator<L: MapLabel = DefaultMap> {
+    generator: Option<Arc<dyn ChunkGenerator>>,
+    tasks: HashMap<IVec3, Task<Chunk>>,
+    ready: HashMap<IVec3, Chunk>,
+    label: PhantomData<fn() -> L>,
+}
+
+#[cfg(feature = "streaming")]
+impl<L: MapLabel> Default for TileMapGenerator<L> {
+    fn default() -> Self {
+        Self {
+            generator: None,
+            tasks: HashMap::default(),
+            ready: HashMap::default(),
+            label: PhantomData,
+        }
+    }
+}
+
+#[cfg(feature = "streaming")]
+impl<L: MapLabel> TileMapGenerator<L> {
+    /// Sets the generator, chunks already being generated still use the previous one.
+    pub fn set(&mut self, generator: impl ChunkGenerator) {
+        self.generator = Some(Arc::new(generator));
+    }
+
+    /// Removes the generator and cancels the chunks being generated.
+    pub fn clear(&mut self) {
+        self.generator = None;
+        self.tasks.clear();
+        self.ready.clear();
+    }
+
+    /// Whether the chunk is being generated in the background or waits for its lock.
+    pub fn is_pending(&self, chunk: &IVec3) -> bool {
+        self.tasks.contains_key(chunk) || self.ready.contains_key(chunk)
+    }
+
+    /// Cancels generating the chunks `cancel` returns true for, dropping their results.
+    pub fn cancel_where(&mut self, mut cancel: impl FnMut(&IVec3) -> bool) {
+        self.tasks.retain(|chunk, _| !cancel(chunk));
+        self.ready.retain(|chunk, _| !cancel(chunk));
+    }
+
+    /// Number of chunks being generated in the background or waiting for their lock.
+    pub fn pending_count(&self) -> usize {
+        self.tasks.len() + self.ready.len()
+    }
+
+    pub fn is_set(&self) -> bool {
+        self.generator.is_some()
+    }
+
+    /// Generates a chunk right away, None if no generator is set.
+    pub fn generate(&self, chunk_coord: IVec3) -> Option<Chunk> {
+        self.generator
+            .as_ref()
+            .map(|generator| generator.generate(chunk_coord))
+    }
+}
+
+/// Starts generating the requested chunks that are still missing and not stored on disk.
+#[cfg(feature = "streaming")]
+pub(crate) fn generate_requested_chunks<L: MapLabel>(
+    mut requests: EventReader<ChunkLoadRequest<L>>,
+    mut generator: ResMut<TileMapGenerator<L>>,
+    #[cfg(feature = "persist")] store: Option<Res<ChunkStore<L>>>,
+    async_pool: Option<Res<AsyncComputeTaskPool>>,
+    mut writer: TileMapWriter<L>,
+) {
+    #[cfg(feature = "persist")]
+    let stored = |chunk: &IVec3| store.as_ref().is_some_and(|store| store.has_chunk(chunk));
+    #[cfg(not(feature = "persist"))]
+    let stored = |_: &IVec3| false;
+    let generator = &mut *generator;
+    let active = match &generator.generator {
+        Some(active) => active,
+        None => return,
+    };
+    let chunks: HashSet<IVec3> = requests
+        .iter()
+        .map(|request| request.0)
+        .filter(|chunk| {
+            writer.chunks.get_chunk(chunk).is_none()
+                && !generator.tasks.contains_key(chunk)
+                && !generator.ready.contains_key(chunk)
+                && !stored(chunk)
+        })
+        .collect();
+    for chunk in chunks {
+        match &async_pool {
+            Some(pool) => {
+                let active = active.clone();
+                let task = pool.spawn(async move { active.generate(chunk) });
+                generator.tasks.insert(chunk, task);
+            }
+            None if writer.is_chunk_locked(&chunk) => {
+                generator.ready.insert(chunk, active.generate(chunk));
+            }
+            None => {
+                writer.insert_chunk(&chunk, Arc::new(active.generate(chunk)));
+            }
+        }
+    }
+}
+
+/// Inserts the chunks that finished generating once they are unlocked, unless the chunk was
+/// created or stored in the meantime.
+#[cfg(feature = "streaming")]
+pub(crate) fn insert_generated_chunks<L: MapLabel>(
+    mut generator: ResMut<TileMapGenerator<L>>,
+    #[cfg(feature = "persist")] store: Option<Res<ChunkStore<L>>>,
+    mut writer: TileMapWriter<L>,
+) {
+    #[cfg(feature = "persist")]
+    let stored = |chunk: &IVec3| store.as_ref().is_some_and(|store| store.has_chunk(chunk));
+    #[cfg(not(feature = "persist"))]
+    let stored = |_: &IVec3| false;
+    if generator.tasks.is_empty() && generator.ready.is_empty() {
+        return;
+    }
+    let generator = &mut *generator;
+    let waker = noop_waker();
+    let mut context = Context::from_waker(&waker);
+    generator
+        .tasks
+        .retain(|coord, task| match Pin::new(task).poll(&mut context) {
+            Poll::Ready(chunk) => {
+                generator.ready.insert(*coord, chunk);
+                false
+            }
+            Poll::Pending => true,
+        });
+    generator.ready.retain(|coord, chunk| {
+        if writer.is_chunk_locked(coord) {
+            return true;
+        }
+        if writer.chunks.get_chunk(coord).is_none() && !stored(coord) {
+            writer.insert_chunk(coord, Arc::new(std::mem::take(chunk)));
+        }
+        false
+    });
+}
+
+/// A waker that does nothing, for polling tasks once a frame without waiting on them.
+#[cfg(feature = "streaming")]
+pub(crate) fn noop_waker() -> Waker {
+    struct NoopWake;
+
+    impl Wake for NoopWake {
+        fn wake(self: Arc<Self>) {}
+    }
+
+    Waker::from(Arc::new(NoopWake))
+}
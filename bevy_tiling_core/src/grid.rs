@@ -0,0 +1,59 @@
+use bevy::math::{IVec3, Vec2, Vec3};
+
+use crate::{internal, TileCoord};
+
+/// Describes how tiles are laid out in world space, used to convert between world positions
+/// and [`TileCoord`]s. Tile (0, 0) on layer 0 starts at the world origin and extends
+/// towards positive x and y.
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct TileGrid {
+    /// Size of a single tile in world units.
+    pub tile_size: Vec2,
+    /// World distance along z between two layers.
+    pub layer_height: f32,
+}
+
+impl Default for TileGrid {
+    fn default() -> Self {
+        Self {
+            tile_size: Vec2::ONE,
+            layer_height: 1.0,
+        }
+    }
+}
+
+impl TileGrid {
+    pub fn new(tile_size: Vec2) -> Self {
+        Self {
+            tile_size,
+            ..Default::default()
+        }
+    }
+
+    /// World position of the corner of a chunk closest to negative infinity.
+    pub fn chunk_to_world(&self, chunk: &IVec3) -> Vec3 {
+        self.tile_to_world(&internal::tile_coord(*chunk, 0))
+    }
+
+    /// The tile containing a world position, the layer is taken from z.
+    pub fn world_to_tile(&self, position: Vec3) -> TileCoord {
+        let tile = (position / self.tile_size.extend(self.layer_height)).floor();
+        TileCoord::from_tile_position(IVec3::new(tile.x as i32, tile.y as i32, tile.z as i32))
+    }
+
+    /// The tile containing a 2d world position on the given layer.
+    pub fn world_to_tile_2d(&self, position: Vec2, layer: i32) -> TileCoord {
+        let tile = (position / self.tile_size).floor();
+        TileCoord::from_tile_position(IVec3::new(tile.x as i32, tile.y as i32, layer))
+    }
+
+    /// World position of the corner of a tile closest to negative infinity.
+    pub fn tile_to_world(&self, coord: &TileCoord) -> Vec3 {
+        coord.tile_position().as_vec3() * self.tile_size.extend(self.layer_height)
+    }
+
+    /// World position of the center of a tile, z is the layer position.
+    pub fn tile_center(&self, coord: &TileCoord) -> Vec3 {
+        self.tile_to_world(coord) + (self.tile_size * 0.5).extend(0.0)
+    }
+}
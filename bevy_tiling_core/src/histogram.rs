@@ -0,0 +1,65 @@
+use bevy::utils::HashMap;
+
+use crate::Tile;
+
+/// Counts of each distinct tile in a chunk, see [`crate::Chunk::histogram`].
+#[derive(Clone, Default, Debug)]
+pub struct TileHistogram {
+    /// Sorted by count, most common first. Ties are ordered by sheet, then index, then the
+    /// flip and rotation flags, so the order never depends on hashing.
+    counts: Vec<(Tile, u16)>,
+    empty: u16,
+}
+
+impl TileHistogram {
+    pub(crate) fn from_tiles<'a>(tiles: impl Iterator<Item = Option<&'a Tile>>) -> Self {
+        let mut counts: HashMap<Tile, u16> = HashMap::default();
+        let mut empty = 0;
+        for tile in tiles {
+            match tile {
+                Some(tile) => *counts.entry(*tile).or_default() += 1,
+                None => empty += 1,
+            }
+        }
+        let mut counts: Vec<(Tile, u16)> = counts.into_iter().collect();
+        counts.sort_by(|(a, a_count), (b, b_count)| {
+            b_count
+                .cmp(a_count)
+                .then(a.sheet.cmp(&b.sheet))
+                .then(a.index.cmp(&b.index))
+                .then(a.flags.cmp(&b.flags))
+        });
+        Self { counts, empty }
+    }
+
+    /// Multiplies every count, used to expand the histogram of a single representative tile.
+    pub(crate) fn scaled(mut self, factor: u16) -> Self {
+        for (_, count) in self.counts.iter_mut() {
+            *count *= factor;
+        }
+        self.empty *= factor;
+        self
+    }
+
+    /// Each distinct tile with the number of times it occurs, most common first.
+    pub fn iter(&self) -> impl Iterator<Item = &(Tile, u16)> {
+        self.counts.iter()
+    }
+
+    pub fn count(&self, tile: &Tile) -> u16 {
+        self.counts
+            .iter()
+            .find(|(other, _)| other == tile)
+            .map_or(0, |(_, count)| *count)
+    }
+
+    /// Number of positions without a tile.
+    pub fn empty(&self) -> u16 {
+        self.empty
+    }
+
+    /// The most common tile, ignoring empty positions.
+    pub fn dominant(&self) -> Option<(Tile, u16)> {
+        self.counts.first().copied()
+    }
+}
@@ -0,0 +1,208 @@
+use std::collections::VecDeque;
+
+use bevy::{math::IVec3, utils::HashMap};
+
+use crate::{IntoTileCoord, Tile, TileChanged, TileCoord, TileMap, TileMapUpdates};
+
+/// A single tile edit recorded by [`TileHistory`].
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub struct TileDelta {
+    pub coord: TileCoord,
+    pub old: Option<Tile>,
+    pub new: Option<Tile>,
+}
+
+impl<L> From<&TileChanged<L>> for TileDelta {
+    fn from(change: &TileChanged<L>) -> Self {
+        Self {
+            coord: change.coord,
+            old: change.old,
+            new: change.new,
+        }
+    }
+}
+
+/// Bounded history of tile edits for rollback netcode.
+///
+/// Edits reach the history either as [`TileChanged`] events passed to [`TileHistory::track`],
+/// or by writing through [`TileHistory::writer`]. Call [`TileHistory::record`] once per
+/// simulated frame to close the frame, and [`TileHistory::rollback`] to undo the most recent
+/// frames before resimulating them, or [`TileHistory::rollback_and_resimulate`] to do both in
+/// one go. Only the frames' deltas are kept, the history holds on to none of the map's chunks.
+pub struct TileHistory {
+    capacity: usize,
+    frames: VecDeque<Vec<TileDelta>>,
+    pending: Vec<TileDelta>,
+}
+
+impl TileHistory {
+    /// Creates a history keeping at most `capacity` frames.
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            frames: VecDeque::with_capacity(capacity),
+            pending: Vec::new(),
+        }
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Number of frames that can currently be rolled back.
+    pub fn len(&self) -> usize {
+        self.frames.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.frames.is_empty()
+    }
+
+    /// Forgets every recorded frame and the edits tracked since the last record,
+    /// e.g. after loading a level.
+    pub fn reset(&mut self) {
+        self.frames.clear();
+        self.pending.clear();
+    }
+
+    /// Adds edits made elsewhere to the current frame, e.g. the [`TileChanged`] events of a
+    /// frame converted with `TileDelta::from`.
+    pub fn track(&mut self, deltas: impl IntoIterator<Item = TileDelta>) {
+        self.pending.extend(deltas);
+    }
+
+    /// Writes to the map directly, tracking the edits in the current frame.
+    pub fn writer<'a, L>(
+        &'a mut self,
+        map: &'a mut TileMap<L>,
+        updates: &'a mut TileMapUpdates<L>,
+    ) -> HistoryWriter<'a, L> {
+        HistoryWriter {
+            map,
+            updates,
+            pending: &mut self.pending,
+        }
+    }
+
+    /// Closes the current frame, keeping its edits as one frame and dropping the oldest frame
+    /// once the history is full. Several edits of a tile in the frame are merged into one.
+    pub fn record(&mut self) {
+        let mut first: HashMap<TileCoord, usize> = HashMap::default();
+        let mut deltas: Vec<TileDelta> = Vec::with_capacity(self.pending.len());
+        for delta in self.pending.drain(..) {
+            match first.get(&delta.coord) {
+                Some(index) => deltas[*index].new = delta.new,
+                None => {
+                    first.insert(delta.coord, deltas.len());
+                    deltas.push(delta);
+                }
+            }
+        }
+        deltas.retain(|delta| delta.old != delta.new);
+
+        if self.capacity == 0 {
+            return;
+        }
+        if self.frames.len() == self.capacity {
+            self.frames.pop_front();
+        }
+        self.frames.push_back(deltas);
+    }
+
+    /// Undoes up to `frames` of the most recently recorded frames, newest first, and marks the
+    /// restored tiles as updated. Chunks left without tiles are removed. Returns how many
+    /// frames were undone.
+    /// Edits tracked since the last record are not undone, so record before rolling back.
+    pub fn rollback<L>(
+        &mut self,
+        map: &mut TileMap<L>,
+        updates: &mut TileMapUpdates<L>,
+        frames: usize,
+    ) -> usize {
+        let frames = frames.min(self.frames.len());
+        let mut touched: Vec<IVec3> = Vec::new();
+        for _ in 0..frames {
+            let deltas = self.frames.pop_back().unwrap_or_default();
+            for delta in deltas.iter().rev() {
+                let chunk = match map.get_chunk_mut(&delta.coord.chunk) {
+                    Some(chunk) => chunk,
+                    None if delta.old.is_none() => continue,
+                    None => map.get_or_create_chunk(&delta.coord.chunk),
+                };
+                chunk.set_tile(delta.coord.index, delta.old);
+                updates.set_update(&delta.coord);
+                touched.push(delta.coord.chunk);
+            }
+        }
+        touched.sort_unstable_by_key(|chunk| (chunk.z, chunk.y, chunk.x));
+        touched.dedup();
+        for chunk_coord in touched {
+            let empty = map
+                .get_chunk(&chunk_coord)
+                .is_some_and(|chunk| (0..=u8::MAX).all(|index| chunk.get_tile(index).is_none()));
+            if empty {
+                map.remove_chunk(&chunk_coord);
+                updates.set_chunk_removed(&chunk_coord);
+            }
+        }
+        frames
+    }
+
+    /// Rolls back like [`TileHistory::rollback`], then calls `resimulate` once for every undone
+    /// frame, oldest first, with how many frames ago it originally was. The edits each call
+    /// makes through the [`HistoryWriter`] are recorded as a frame again, so the history is as
+    /// long afterwards as before. Returns how many frames were resimulated.
+    pub fn rollback_and_resimulate<L>(
+        &mut self,
+        map: &mut TileMap<L>,
+        updates: &mut TileMapUpdates<L>,
+        frames: usize,
+        mut resimulate: impl FnMut(usize, &mut HistoryWriter<L>),
+    ) -> usize {
+        let frames = self.rollback(map, updates, frames);
+        for frames_ago in (0..frames).rev() {
+            resimulate(frames_ago, &mut self.writer(map, updates));
+            self.record();
+        }
+        frames
+    }
+
+    /// The edits of a recorded frame, 0 being the most recent.
+    pub fn frame(&self, frames_ago: usize) -> Option<&[TileDelta]> {
+        let index = self.frames.len().checked_sub(frames_ago + 1)?;
+        self.frames.get(index).map(Vec::as_slice)
+    }
+}
+
+/// Writes to a map while tracking the edits in a [`TileHistory`], see [`TileHistory::writer`].
+pub struct HistoryWriter<'a, L> {
+    map: &'a mut TileMap<L>,
+    updates: &'a mut TileMapUpdates<L>,
+    pending: &'a mut Vec<TileDelta>,
+}
+
+impl<'a, L> HistoryWriter<'a, L> {
+    pub fn map(&self) -> &TileMap<L> {
+        self.map
+    }
+
+    pub fn get_tile(&self, coord: impl IntoTileCoord) -> Option<&Tile> {
+        self.map.get_tile(&coord.into_tile_coord())
+    }
+
+    /// Sets or removes a tile like [`TileMap::set_tile`], marking it as updated if it changed.
+    /// Returns the previous tile.
+    pub fn set_tile(&mut self, coord: impl IntoTileCoord, tile: Option<Tile>) -> Option<Tile> {
+        let coord = self.map.resolve_coord(&coord.into_tile_coord())?;
+        let old = self.map.set_tile(&coord, tile);
+        if old != tile {
+            self.updates.set_update(&coord);
+            self.pending.push(TileDelta {
+                coord,
+                old,
+                new: tile,
+            });
+        }
+        old
+    }
+}
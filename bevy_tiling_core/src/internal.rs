@@ -0,0 +1,40 @@
+//! Items tied to how the map stores tiles, for tools like serializers and renderers that need
+//! the raw layout.
+//!
+//! # Stability
+//!
+//! Everything outside this module follows semver: breaking changes only come with a new minor
+//! version while the crate is below 1.0, and items are deprecated for one release before they
+//! are removed. Items in this module follow the storage instead and may change in any release,
+//! e.g. when the chunk size or the bits of the tile flags change. Prefer the high-level
+//! equivalents named on each item.
+
+use bevy::math::IVec3;
+
+use crate::{Tile, TileCoord};
+
+/// A coordinate from a chunk and the row-major index of the tile inside it.
+///
+/// Use [`TileCoord::from_tile_position`] or [`crate::IntoTileCoord`] instead, which don't
+/// depend on the chunk size.
+pub fn tile_coord(chunk: IVec3, index: u8) -> TileCoord {
+    TileCoord { index, chunk }
+}
+
+/// A tile from its raw flag bits, as returned by [`tile_flags`].
+///
+/// Use [`Tile::new`] with [`Tile::with_flip`] and [`Tile::with_rotation`] instead.
+pub fn tile_from_raw(sheet: u16, index: u16, flags: u8) -> Tile {
+    Tile {
+        sheet,
+        index,
+        flags,
+    }
+}
+
+/// The flip and rotation bits of a tile.
+///
+/// Use [`Tile::flip_x`], [`Tile::flip_y`] and [`Tile::rotation`] instead.
+pub fn tile_flags(tile: &Tile) -> u8 {
+    tile.flags
+}
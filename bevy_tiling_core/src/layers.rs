@@ -0,0 +1,61 @@
+use bevy::utils::HashMap;
+
+use crate::grid::TileGrid;
+
+/// Display settings of a layer, layers being the z coordinate of tile positions and chunks.
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct LayerSettings {
+    pub visible: bool,
+    /// Added to the world z of the layer, e.g. to sort a layer in front of the one above it.
+    pub z_offset: f32,
+}
+
+impl Default for LayerSettings {
+    fn default() -> Self {
+        Self {
+            visible: true,
+            z_offset: 0.0,
+        }
+    }
+}
+
+/// Settings of every layer, layers that were never configured use [`LayerSettings::default`].
+/// Renderers read these when drawing chunks, the tiles themselves are not affected.
+#[derive(Default)]
+pub struct TileLayers {
+    layers: HashMap<i32, LayerSettings>,
+}
+
+impl TileLayers {
+    pub fn get(&self, layer: i32) -> LayerSettings {
+        self.layers.get(&layer).copied().unwrap_or_default()
+    }
+
+    pub fn set(&mut self, layer: i32, settings: LayerSettings) {
+        self.layers.insert(layer, settings);
+    }
+
+    pub fn is_visible(&self, layer: i32) -> bool {
+        self.get(layer).visible
+    }
+
+    pub fn set_visible(&mut self, layer: i32, visible: bool) {
+        self.layers.entry(layer).or_default().visible = visible;
+    }
+
+    pub fn set_z_offset(&mut self, layer: i32, z_offset: f32) {
+        self.layers.entry(layer).or_default().z_offset = z_offset;
+    }
+
+    /// World z a layer is drawn at, used as its sort key.
+    pub fn world_z(&self, layer: i32, grid: &TileGrid) -> f32 {
+        layer as f32 * grid.layer_height + self.get(layer).z_offset
+    }
+
+    /// Every configured layer, in no particular order.
+    pub fn iter(&self) -> impl Iterator<Item = (i32, &LayerSettings)> {
+        self.layers
+            .iter()
+            .map(|(layer, settings)| (*layer, settings))
+    }
+}
@@ -0,0 +1,351 @@
+//! Import of [LDtk](https://ldtk.io) projects, enabled by the `ldtk` feature.
+//!
+//! The project types deserialize from the JSON of a `.ldtk` file with any serde JSON crate,
+//! e.g. `serde_json::from_slice::<LdtkProject>(&bytes)`, fields the import doesn't use are
+//! skipped. Queue the project in [`LdtkImports`] and [`LdtkImportPlugin`] writes it into the map:
+//!
+//! - Every level becomes a region of the [`TileMap`] named after the level, see [`TileRegions`].
+//! - Layers are stacked on the layers (z) of the map, the bottom LDtk layer on layer 0.
+//! - IntGrid values are kept per tile in a [`TileDataMap<IntGridValue>`].
+//! - Entities become [`MapObject`]s typed by their identifier and are spawned by the
+//!   [`ObjectSpawners`].
+//!
+//! LDtk's y axis points down, rows are placed at decreasing y so the project keeps its layout,
+//! with the top left cell of the world at (0, 0).
+
+use bevy::{
+    math::{IVec3, Vec2},
+    prelude::{Commands, Plugin, Res, ResMut},
+    utils::HashMap,
+};
+use serde::{de::IgnoredAny, Deserialize, Deserializer};
+
+use crate::{
+    grid::TileGrid,
+    objects::{MapObject, ObjectSpawners, PropertyValue},
+    regions::TileRegions,
+    tile_data::TileDataMap,
+    Tile, TileCoord, TileMapWriter, TilingCoreStage,
+};
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct LdtkProject {
+    pub levels: Vec<LdtkLevel>,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct LdtkLevel {
+    pub identifier: String,
+    /// Position of the level in the world, in pixels.
+    pub world_x: i32,
+    pub world_y: i32,
+    /// Size of the level in pixels.
+    pub px_wid: i32,
+    pub px_hei: i32,
+    /// Missing when the project saves levels in separate files.
+    #[serde(default)]
+    pub layer_instances: Option<Vec<LdtkLayer>>,
+}
+
+/// A layer of a level. LDtk lists the top layer first.
+#[derive(Deserialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct LdtkLayer {
+    #[serde(rename = "__identifier")]
+    pub identifier: String,
+    /// One of `IntGrid`, `Entities`, `Tiles` or `AutoLayer`.
+    #[serde(rename = "__type")]
+    pub layer_type: String,
+    /// Size of the layer in cells.
+    #[serde(rename = "__cWid")]
+    pub c_wid: i32,
+    #[serde(rename = "__cHei")]
+    pub c_hei: i32,
+    /// Size of a cell in pixels.
+    #[serde(rename = "__gridSize")]
+    pub grid_size: i32,
+    #[serde(rename = "__tilesetDefUid", default)]
+    pub tileset_def_uid: Option<i32>,
+    /// IntGrid values in row order from the top, 0 for empty cells.
+    #[serde(default)]
+    pub int_grid_csv: Vec<i32>,
+    #[serde(default)]
+    pub grid_tiles: Vec<LdtkTile>,
+    #[serde(default)]
+    pub auto_layer_tiles: Vec<LdtkTile>,
+    #[serde(default)]
+    pub entity_instances: Vec<LdtkEntity>,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct LdtkTile {
+    /// Position in the layer, in pixels.
+    pub px: [i32; 2],
+    /// Id of the tile in its tileset.
+    pub t: i32,
+    /// Bit 0 flips along x, bit 1 along y.
+    #[serde(default)]
+    pub f: u8,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct LdtkEntity {
+    #[serde(rename = "__identifier")]
+    pub identifier: String,
+    /// Cell of the entity in its layer.
+    #[serde(rename = "__grid")]
+    pub grid: [i32; 2],
+    /// Position in the layer, in pixels.
+    pub px: [i32; 2],
+    pub width: i32,
+    pub height: i32,
+    #[serde(default)]
+    pub iid: Option<String>,
+    /// Where `px` sits on the entity, from (0, 0) at the top left to (1, 1) at the bottom right.
+    #[serde(rename = "__pivot", default)]
+    pub pivot: [f32; 2],
+    #[serde(default)]
+    pub field_instances: Vec<LdtkField>,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct LdtkField {
+    #[serde(rename = "__identifier")]
+    pub identifier: String,
+    /// None for null values and for values without a matching [`PropertyValue`], like points
+    /// and arrays. Enums and colors are strings.
+    #[serde(rename = "__value", default, deserialize_with = "field_value")]
+    pub value: Option<PropertyValue>,
+}
+
+fn field_value<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Option<PropertyValue>, D::Error> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Value {
+        Bool(bool),
+        Int(i64),
+        Float(f64),
+        String(String),
+        Other(IgnoredAny),
+    }
+    Ok(match Value::deserialize(deserializer)? {
+        Value::Bool(value) => Some(PropertyValue::Bool(value)),
+        Value::Int(value) => Some(PropertyValue::Int(value)),
+        Value::Float(value) => Some(PropertyValue::Float(value)),
+        Value::String(value) => Some(PropertyValue::String(value)),
+        Value::Other(_) => None,
+    })
+}
+
+/// The value of an IntGrid cell, see [`LdtkImportPlugin`].
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub struct IntGridValue(pub i32);
+
+/// The tile sheet used for each LDtk tileset, keyed by the tileset's uid.
+/// Tilesets without a sheet use their uid as the sheet.
+#[derive(Clone, Default, Debug)]
+pub struct LdtkTilesets {
+    sheets: HashMap<i32, u16>,
+}
+
+impl LdtkTilesets {
+    pub fn add(&mut self, tileset_uid: i32, sheet: u16) -> &mut Self {
+        self.sheets.insert(tileset_uid, sheet);
+        self
+    }
+
+    pub fn sheet(&self, tileset_uid: i32) -> u16 {
+        self.sheets
+            .get(&tileset_uid)
+            .copied()
+            .unwrap_or(tileset_uid as u16)
+    }
+}
+
+impl LdtkLevel {
+    fn layers(&self) -> impl Iterator<Item = (i32, &LdtkLayer)> {
+        let layers = self.layer_instances.as_deref().unwrap_or_default();
+        let top = layers.len() as i32 - 1;
+        layers
+            .iter()
+            .enumerate()
+            .map(move |(index, layer)| (top - index as i32, layer))
+    }
+
+    /// Tile position of a cell of a layer, `z` being the map layer.
+    fn cell_position(&self, layer: &LdtkLayer, cell: [i32; 2], z: i32) -> IVec3 {
+        let grid_size = layer.grid_size.max(1);
+        IVec3::new(
+            self.world_x.div_euclid(grid_size) + cell[0],
+            -(self.world_y.div_euclid(grid_size) + cell[1]),
+            z,
+        )
+    }
+
+    /// The box of tiles the level covers on every layer, measured in cells of its first layer.
+    pub fn tile_bounds(&self) -> Option<(IVec3, IVec3)> {
+        let (top, layer) = self.layers().next()?;
+        let grid_size = layer.grid_size.max(1);
+        let cells = [
+            (self.px_wid + grid_size - 1) / grid_size,
+            (self.px_hei + grid_size - 1) / grid_size,
+        ];
+        let min = self.cell_position(layer, [0, cells[1] - 1], 0);
+        let max = self.cell_position(layer, [cells[0] - 1, 0], top);
+        Some((min, max))
+    }
+
+    /// Every tile of the Tiles, AutoLayer and IntGrid layers, lower layers first.
+    pub fn tiles(&self, tilesets: &LdtkTilesets) -> Vec<(TileCoord, Tile)> {
+        let mut tiles = Vec::new();
+        for (z, layer) in self.layers().collect::<Vec<_>>().into_iter().rev() {
+            let sheet = match layer.tileset_def_uid {
+                Some(uid) => tilesets.sheet(uid),
+                None => continue,
+            };
+            let grid_size = layer.grid_size.max(1);
+            for tile in layer.grid_tiles.iter().chain(layer.auto_layer_tiles.iter()) {
+                let index = match u16::try_from(tile.t) {
+                    Ok(index) => index,
+                    Err(_) => continue,
+                };
+                let cell = [tile.px[0] / grid_size, tile.px[1] / grid_size];
+                let position = self.cell_position(layer, cell, z);
+                tiles.push((
+                    TileCoord::from_tile_position(position),
+                    Tile::new(sheet, index).with_flip(tile.f & 1 != 0, tile.f & 2 != 0),
+                ));
+            }
+        }
+        tiles
+    }
+
+    /// Every non-empty IntGrid cell.
+    pub fn int_grid(&self) -> Vec<(TileCoord, IntGridValue)> {
+        let mut values = Vec::new();
+        for (z, layer) in self.layers() {
+            let width = layer.c_wid.max(1);
+            for (index, value) in layer.int_grid_csv.iter().enumerate() {
+                if *value == 0 {
+                    continue;
+                }
+                let cell = [index as i32 % width, index as i32 / width];
+                let position = self.cell_position(layer, cell, z);
+                values.push((
+                    TileCoord::from_tile_position(position),
+                    IntGridValue(*value),
+                ));
+            }
+        }
+        values
+    }
+
+    /// Every entity of the entity layers as a [`MapObject`] of the entity's identifier,
+    /// with its fields as properties.
+    pub fn objects(&self) -> Vec<MapObject> {
+        self.layers()
+            .flat_map(|(z, layer)| {
+                layer
+                    .entity_instances
+                    .iter()
+                    .map(move |entity| self.object(layer, entity, z))
+            })
+            .collect()
+    }
+
+    fn object(&self, layer: &LdtkLayer, entity: &LdtkEntity, z: i32) -> MapObject {
+        let grid_size = layer.grid_size.max(1) as f32;
+        let size = Vec2::new(entity.width as f32, entity.height as f32);
+        let left = (self.world_x + entity.px[0]) as f32 - entity.pivot[0] * size.x;
+        let bottom = (self.world_y + entity.px[1]) as f32 + (1.0 - entity.pivot[1]) * size.y;
+        MapObject {
+            name: entity.identifier.clone(),
+            object_type: entity.identifier.clone(),
+            // Row y spans y to y + 1 going up, see `cell_position`.
+            position: Vec2::new(left / grid_size, 1.0 - bottom / grid_size),
+            layer: z,
+            size: size / grid_size,
+            properties: entity
+                .field_instances
+                .iter()
+                .filter_map(|field| Some((field.identifier.clone(), field.value.clone()?)))
+                .collect(),
+        }
+    }
+}
+
+/// Projects waiting to be imported by [`LdtkImportPlugin`]. A project with tiles in a box
+/// locked with [`crate::locks::TileLocks`] waits until the box is unlocked.
+#[derive(Default)]
+pub struct LdtkImports {
+    projects: Vec<LdtkProject>,
+}
+
+impl LdtkImports {
+    /// Imports the project on the next update.
+    pub fn push(&mut self, project: LdtkProject) {
+        self.projects.push(project);
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.projects.is_empty()
+    }
+}
+
+/// Imports the projects queued in [`LdtkImports`], see the [module docs](self).
+/// [`crate::TilingPlugin`] must be added too.
+///
+/// Tiles are written through [`TileMapWriter`] before `CoreStage::Update`, so they cause
+/// updates like any other edit.
+pub struct LdtkImportPlugin;
+
+impl Plugin for LdtkImportPlugin {
+    fn build(&self, app: &mut bevy::prelude::App) {
+        app.init_resource::<LdtkImports>()
+            .init_resource::<LdtkTilesets>()
+            .init_resource::<TileDataMap<IntGridValue>>()
+            .add_system_to_stage(TilingCoreStage::Schedule, import_ldtk_projects);
+    }
+}
+
+fn import_ldtk_projects(
+    mut imports: ResMut<LdtkImports>,
+    tilesets: Res<LdtkTilesets>,
+    (spawners, grid): (Res<ObjectSpawners>, Res<TileGrid>),
+    mut regions: ResMut<TileRegions>,
+    mut int_grid: ResMut<TileDataMap<IntGridValue>>,
+    mut commands: Commands,
+    mut writer: TileMapWriter,
+) {
+    imports.projects.retain(|project| {
+        let tiles: Vec<Vec<(TileCoord, Tile)>> = project
+            .levels
+            .iter()
+            .map(|level| level.tiles(&tilesets))
+            .collect();
+        if tiles
+            .iter()
+            .flatten()
+            .any(|(coord, _)| writer.is_locked(coord))
+        {
+            return true;
+        }
+        for (level, tiles) in project.levels.iter().zip(tiles) {
+            if let Some((min, max)) = level.tile_bounds() {
+                regions.add_rect(level.identifier.clone(), min, max);
+            }
+            writer.set_tiles(tiles.into_iter().map(|(coord, tile)| (coord, Some(tile))));
+            for (coord, value) in level.int_grid() {
+                int_grid.set(&coord, Some(value));
+            }
+            for object in level.objects() {
+                spawners.spawn(&mut commands, &grid, &object);
+            }
+        }
+        false
+    });
+}
@@ -1,16 +1,132 @@
 use bevy::{
     ecs::system::SystemParam,
-    math::IVec3,
-    prelude::{CoreStage, Plugin, Res, ResMut, StageLabel, SystemStage},
+    math::{IVec2, IVec3, Vec2},
+    prelude::{CoreStage, EventWriter, Plugin, Res, ResMut, StageLabel, SystemStage},
+    tasks::ComputeTaskPool,
     utils::{hashbrown::hash_map::Keys, HashMap, HashSet},
 };
 
+#[cfg(feature = "autotile")]
+use autotile::{queue_autotile_updates, resolve_autotiles, TileAutotiler};
+use biome::{follow_tile_map, BiomeMap};
+use blueprint::{build_confirmed_tiles, TileConstruction};
+use bounds::{BoundsMode, MapBounds, MapWrap};
+use chunk_data::ChunkDataStore;
+use csv::CsvError;
+use error::TilingError;
+use grid::TileGrid;
+use histogram::TileHistogram;
+use layers::TileLayers;
+use locks::TileLocks;
+use markers::{update_tile_markers, TileMarkers};
+use objects::ObjectSpawners;
+use placement::{PlacementRules, PlacementViolation};
+use policy::TileWritePolicy;
+use prediction::TilePredictions;
+use preview::{expire_tile_previews, TilePreview};
+use priority::ChunkPriorities;
+use regions::TileRegions;
+use rle::{compress_idle_chunks, ChunkCompression, RleChunk};
+use schedule::{run_tile_schedule, TileSchedule};
+use std::{
+    fmt,
+    marker::PhantomData,
+    ops::{Bound, RangeBounds},
+    sync::{Arc, OnceLock},
+};
+use world_map::{update_world_map, WorldMap};
+
+#[cfg(feature = "autotile")]
+pub mod autotile;
+#[cfg(feature = "autotile_assets")]
+pub mod autotile_asset;
+pub mod biome;
+pub mod blueprint;
+pub mod bounds;
+pub mod chunk_data;
+pub mod csv;
+#[cfg(feature = "diffusion")]
+pub mod diffusion;
+#[cfg(feature = "streaming")]
+pub mod dimension;
+pub mod error;
+pub mod generator;
+pub mod grid;
+pub mod histogram;
+pub mod history;
+pub mod internal;
+pub mod layers;
+#[cfg(feature = "ldtk")]
+pub mod ldtk;
+pub mod locks;
+pub mod markers;
+pub mod objects;
+#[cfg(feature = "passes")]
+pub mod passes;
+#[cfg(feature = "persist")]
+pub mod persist;
+pub mod placement;
+pub mod policy;
+pub mod prediction;
+pub mod preview;
+pub mod priority;
+pub mod quads;
+pub mod raster;
+pub mod regions;
+pub mod rle;
+mod rng;
+pub mod scatter;
+pub mod schedule;
+#[cfg(feature = "serde")]
+mod serialization;
+#[cfg(feature = "signal")]
+pub mod signal;
+#[cfg(feature = "stitch")]
+pub mod stitch;
+#[cfg(feature = "streaming")]
+pub mod streaming;
+#[cfg(feature = "structures")]
+pub mod structures;
+pub mod tile_data;
+#[cfg(feature = "tiled")]
+pub mod tiled;
+#[cfg(feature = "tiled")]
+pub mod tiled_asset;
+#[cfg(feature = "web_storage")]
+pub mod web_storage;
+#[cfg(feature = "wfc")]
+pub mod wfc;
+pub mod world_map;
+
 pub struct TilingPlugin;
 
 impl Plugin for TilingPlugin {
     fn build(&self, app: &mut bevy::prelude::App) {
         app.init_resource::<TileMap>()
             .init_resource::<TileMapUpdates>()
+            .init_resource::<ChunkDataStore>()
+            .init_resource::<TileGrid>()
+            .init_resource::<TileLayers>()
+            .init_resource::<BiomeMap>()
+            .init_resource::<TileMarkers>()
+            .init_resource::<TileRegions>()
+            .init_resource::<TilePredictions>()
+            .init_resource::<TileWritePolicy>()
+            .init_resource::<TileLocks>()
+            .init_resource::<ChunkPriorities>()
+            .init_resource::<TileSchedule>()
+            .init_resource::<PlacementRules>()
+            .init_resource::<TilePreview>()
+            .init_resource::<TileConstruction>()
+            .init_resource::<WorldMap>()
+            .init_resource::<ChunkCompression>()
+            .init_resource::<ObjectSpawners>()
+            .add_event::<TileChanged>()
+            .add_stage_before(
+                CoreStage::Update,
+                TilingCoreStage::Schedule,
+                SystemStage::parallel(),
+            )
             .add_stage_after(
                 CoreStage::Update,
                 TilingCoreStage::Update,
@@ -21,160 +137,1100 @@ impl Plugin for TilingPlugin {
                 TilingCoreStage::Clear,
                 SystemStage::parallel(),
             )
-            .add_system_to_stage(CoreStage::PreUpdate, clear_tile_updates);
+            .add_system_to_stage(CoreStage::PreUpdate, clear_tile_updates::<DefaultMap>)
+            .add_system_to_stage(TilingCoreStage::Schedule, run_tile_schedule)
+            .add_system_to_stage(TilingCoreStage::Schedule, build_confirmed_tiles)
+            .add_system_to_stage(TilingCoreStage::Update, update_tile_markers)
+            .add_system_to_stage(TilingCoreStage::Update, update_world_map)
+            .add_system_to_stage(TilingCoreStage::Clear, expire_tile_previews)
+            .add_system_to_stage(TilingCoreStage::Clear, compress_idle_chunks)
+            .add_system_to_stage(TilingCoreStage::Clear, follow_tile_map);
+        #[cfg(feature = "autotile")]
+        app.init_resource::<TileAutotiler>()
+            .add_system_to_stage(TilingCoreStage::Schedule, resolve_autotiles)
+            .add_system_to_stage(TilingCoreStage::Update, queue_autotile_updates);
+        #[cfg(feature = "stitch")]
+        {
+            use bevy::prelude::ParallelSystemDescriptorCoercion;
+            let stitch = stitch::stitch_inserted_chunks.label(stitch::StitchSystem);
+            #[cfg(feature = "streaming")]
+            let stitch = stitch.after(streaming::StreamingSystem::Insert);
+            app.init_resource::<stitch::ChunkStitching>()
+                .add_system_to_stage(TilingCoreStage::Schedule, stitch)
+                .add_system_to_stage(TilingCoreStage::Clear, stitch::defer_late_insertions);
+        }
+    }
+}
+
+/// Adds an additional, independent tile map labeled `L`, e.g. a wall map next to the ground map.
+/// Access it with `TileMapReader<L>` and `TileMapWriter<L>`, [`TilingPlugin`] must be added too.
+/// Features like markers and regions only follow the default map.
+pub struct TileMapPlugin<L>(PhantomData<fn() -> L>);
+
+impl<L> Default for TileMapPlugin<L> {
+    fn default() -> Self {
+        Self(PhantomData)
+    }
+}
+
+impl<L: MapLabel> Plugin for TileMapPlugin<L> {
+    fn build(&self, app: &mut bevy::prelude::App) {
+        app.insert_resource(TileMap::<L>::empty())
+            .insert_resource(TileMapUpdates::<L>::empty())
+            .insert_resource(ChunkDataStore::<L>::empty())
+            .insert_resource(TilePredictions::<L>::empty())
+            .add_event::<TileChanged<L>>()
+            .add_system_to_stage(CoreStage::PreUpdate, clear_tile_updates::<L>);
     }
 }
 
-fn clear_tile_updates(mut updates: ResMut<TileMapUpdates>) {
+/// Whether `value` or one of its copies every `period` lies within `min..=max`.
+/// A period of zero means the axis doesn't wrap.
+fn wraps_into(value: i32, min: i32, max: i32, period: i32) -> bool {
+    if period <= 0 {
+        return (min..=max).contains(&value);
+    }
+    let span = max as i64 - min as i64;
+    span >= period as i64 - 1 || (value as i64 - min as i64).rem_euclid(period as i64) <= span
+}
+
+fn clear_tile_updates<L: MapLabel>(mut updates: ResMut<TileMapUpdates<L>>) {
     updates.chunks.clear();
+    updates.removed.clear();
+    updates.inserted.clear();
 }
 
+/// Distinguishes tile maps when a world has more than one, see [`TileMapPlugin`].
+pub trait MapLabel: Send + Sync + 'static {}
+
+/// Label of the map added by [`TilingPlugin`], used when no label is given.
+pub struct DefaultMap;
+
+impl MapLabel for DefaultMap {}
+
 #[derive(StageLabel, PartialEq, Eq, Clone, Hash, Debug)]
 pub enum TilingCoreStage {
+    /// Runs before `CoreStage::Update`, map edits made by the crate itself happen here,
+    /// like the due changes of [`schedule::TileSchedule`] and chunk streaming.
+    Schedule,
     Update,
     Clear,
 }
 
 #[repr(C)]
-#[derive(Copy, Clone, PartialEq, Eq)]
+#[derive(Copy, Clone, Hash, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Tile {
     sheet: u16,
     index: u16,
+    /// Bit 0 flips along x, bit 1 along y, bits 2 and 3 hold the quarter turns.
+    flags: u8,
+}
+
+const FLIP_X: u8 = 1;
+const FLIP_Y: u8 = 1 << 1;
+const ROTATION_SHIFT: u8 = 2;
+const ROTATION: u8 = 0b11 << ROTATION_SHIFT;
+
+impl Tile {
+    /// Creates an unflipped, unrotated tile from a sheet id and an index into that sheet.
+    pub fn new(sheet: u16, index: u16) -> Self {
+        Self {
+            sheet,
+            index,
+            flags: 0,
+        }
+    }
+
+    pub fn sheet(&self) -> u16 {
+        self.sheet
+    }
+
+    pub fn index(&self) -> u16 {
+        self.index
+    }
+
+    pub fn with_sheet(self, sheet: u16) -> Self {
+        Self { sheet, ..self }
+    }
+
+    pub fn with_index(self, index: u16) -> Self {
+        Self { index, ..self }
+    }
+
+    pub fn flip_x(&self) -> bool {
+        self.flags & FLIP_X != 0
+    }
+
+    pub fn flip_y(&self) -> bool {
+        self.flags & FLIP_Y != 0
+    }
+
+    /// Counter-clockwise quarter turns, from 0 to 3, applied before flipping.
+    pub fn rotation(&self) -> u8 {
+        (self.flags & ROTATION) >> ROTATION_SHIFT
+    }
+
+    pub fn with_flip(self, flip_x: bool, flip_y: bool) -> Self {
+        let mut flags = self.flags & !(FLIP_X | FLIP_Y);
+        if flip_x {
+            flags |= FLIP_X;
+        }
+        if flip_y {
+            flags |= FLIP_Y;
+        }
+        Self { flags, ..self }
+    }
+
+    /// Sets the counter-clockwise quarter turns, values above 3 wrap around.
+    pub fn with_rotation(self, quarter_turns: u8) -> Self {
+        Self {
+            flags: (self.flags & !ROTATION) | ((quarter_turns % 4) << ROTATION_SHIFT),
+            ..self
+        }
+    }
+}
+
+/// Formats as `sheet:index`.
+impl fmt::Display for Tile {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}", self.sheet, self.index)
+    }
 }
 
-#[derive(Copy, Clone, Hash, PartialEq, Eq)]
+/// Width and height of a chunk in tiles.
+pub const CHUNK_SIZE: i32 = 16;
+
+#[derive(Copy, Clone, Hash, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct TileCoord {
     index: u8,
     chunk: IVec3,
 }
 
+/// Formats as the tile position, `(x, y, layer)`.
+impl fmt::Display for TileCoord {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let position = self.tile_position();
+        write!(f, "({}, {}, {})", position.x, position.y, position.z)
+    }
+}
+
+/// Anything that can address a tile, accepted by the reader and writer methods.
+/// 2d positions are in tiles on layer 0, [`Vec2`] positions are floored to the tile containing
+/// them. World positions should go through [`TileGrid`] instead.
+pub trait IntoTileCoord {
+    fn into_tile_coord(self) -> TileCoord;
+}
+
+impl IntoTileCoord for TileCoord {
+    #[inline]
+    fn into_tile_coord(self) -> TileCoord {
+        self
+    }
+}
+
+impl IntoTileCoord for &TileCoord {
+    #[inline]
+    fn into_tile_coord(self) -> TileCoord {
+        *self
+    }
+}
+
+impl IntoTileCoord for IVec3 {
+    #[inline]
+    fn into_tile_coord(self) -> TileCoord {
+        TileCoord::from_tile_position(self)
+    }
+}
+
+impl IntoTileCoord for IVec2 {
+    #[inline]
+    fn into_tile_coord(self) -> TileCoord {
+        TileCoord::from_tile_position(self.extend(0))
+    }
+}
+
+impl IntoTileCoord for (i32, i32) {
+    #[inline]
+    fn into_tile_coord(self) -> TileCoord {
+        IVec2::new(self.0, self.1).into_tile_coord()
+    }
+}
+
+impl IntoTileCoord for (i32, i32, i32) {
+    #[inline]
+    fn into_tile_coord(self) -> TileCoord {
+        IVec3::new(self.0, self.1, self.2).into_tile_coord()
+    }
+}
+
+impl IntoTileCoord for Vec2 {
+    #[inline]
+    fn into_tile_coord(self) -> TileCoord {
+        let tile = self.floor();
+        IVec2::new(tile.x as i32, tile.y as i32).into_tile_coord()
+    }
+}
+
+impl TileCoord {
+    /// Creates a coordinate from a chunk and the row-major index of the tile inside it.
+    #[deprecated(
+        note = "depends on the chunk size, use `TileCoord::from_tile_position` or `internal::tile_coord`"
+    )]
+    pub fn new(chunk: IVec3, index: u8) -> Self {
+        Self { index, chunk }
+    }
+
+    pub fn chunk(&self) -> IVec3 {
+        self.chunk
+    }
+
+    pub fn index(&self) -> u8 {
+        self.index
+    }
+
+    /// The layer of the tile, which is the z coordinate of its chunk.
+    pub fn layer(&self) -> i32 {
+        self.chunk.z
+    }
+
+    /// Position of the tile in tile units, with the chunk z passed through as the layer.
+    pub fn tile_position(&self) -> IVec3 {
+        IVec3::new(
+            self.chunk.x * CHUNK_SIZE + (self.index as i32 % CHUNK_SIZE),
+            self.chunk.y * CHUNK_SIZE + (self.index as i32 / CHUNK_SIZE),
+            self.chunk.z,
+        )
+    }
+
+    /// Inverse of [`TileCoord::tile_position`], negative positions are handled with euclidean
+    /// division so tile -1 is the last tile of chunk -1.
+    pub fn from_tile_position(position: IVec3) -> Self {
+        let local_x = position.x.rem_euclid(CHUNK_SIZE);
+        let local_y = position.y.rem_euclid(CHUNK_SIZE);
+        Self {
+            index: (local_y * CHUNK_SIZE + local_x) as u8,
+            chunk: IVec3::new(
+                position.x.div_euclid(CHUNK_SIZE),
+                position.y.div_euclid(CHUNK_SIZE),
+                position.z,
+            ),
+        }
+    }
+}
+
+#[derive(Clone)]
 pub struct Chunk {
+    storage: ChunkStorage,
+    histogram: OnceLock<TileHistogram>,
+}
+
+#[derive(Clone)]
+enum ChunkStorage {
+    /// Every position holds the same tile, or every position is empty.
+    Uniform(Option<Tile>),
+    Dense(Box<DenseTiles>),
+    /// Runs of equal tiles, expanded to [`ChunkStorage::Dense`] on the first write.
+    Compressed(RleChunk),
+}
+
+#[derive(Clone)]
+struct DenseTiles {
     tiles: [Tile; 256],
     valid: [bool; 256],
 }
 
 impl Default for Chunk {
     fn default() -> Self {
-        Self {
-            tiles: [Tile { sheet: 0, index: 0 }; 256],
-            valid: [false; 256],
+        Self::uniform(None)
+    }
+}
+
+/// Uniform chunks print their single tile. Other chunks print a grid of tile indices with the
+/// top row (y = 15) first and `.` for empty positions.
+impl fmt::Debug for Chunk {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.storage {
+            ChunkStorage::Uniform(tile) => f.debug_tuple("Chunk::Uniform").field(tile).finish(),
+            ChunkStorage::Dense(_) | ChunkStorage::Compressed(_) => {
+                let sheets: HashSet<u16> = (0..=u8::MAX)
+                    .filter_map(|index| self.get_tile(index).map(|tile| tile.sheet))
+                    .collect();
+                writeln!(f, "Chunk::Dense(sheets {:?}) [", sheets)?;
+                for y in (0..CHUNK_SIZE).rev() {
+                    write!(f, "   ")?;
+                    for x in 0..CHUNK_SIZE {
+                        match self.get_tile((y * CHUNK_SIZE + x) as u8) {
+                            Some(tile) => write!(f, " {:>4}", tile.index)?,
+                            None => write!(f, "    .")?,
+                        }
+                    }
+                    writeln!(f)?;
+                }
+                write!(f, "]")
+            }
         }
     }
 }
 
 impl Chunk {
+    /// Creates a chunk where every position holds `tile`, stored as a single value until edited.
+    pub fn uniform(tile: Option<Tile>) -> Self {
+        Self {
+            storage: ChunkStorage::Uniform(tile),
+            histogram: OnceLock::new(),
+        }
+    }
+
+    /// The tile filling the whole chunk if it is stored as a single value.
+    /// `Some(None)` is an empty chunk.
+    pub fn as_uniform(&self) -> Option<Option<Tile>> {
+        match &self.storage {
+            ChunkStorage::Uniform(tile) => Some(*tile),
+            ChunkStorage::Dense(_) | ChunkStorage::Compressed(_) => None,
+        }
+    }
+
+    /// Whether the chunk is stored as runs of equal tiles, see [`Chunk::compress`].
+    pub fn is_compressed(&self) -> bool {
+        matches!(self.storage, ChunkStorage::Compressed(_))
+    }
+
+    /// Stores the tiles as runs of equal tiles, which takes far less memory for chunks that
+    /// are mostly one tile. Reads stay cheap, the first write expands the chunk again.
+    /// Uniform chunks are already as small as they get and stay as they are.
+    pub fn compress(&mut self) {
+        if let ChunkStorage::Dense(_) = &self.storage {
+            if !self.compact() {
+                self.storage = ChunkStorage::Compressed(RleChunk::from_chunk(self));
+            }
+        }
+    }
+
+    /// Switches back to the single value representation if every position holds the same tile.
+    /// Returns whether the chunk is uniform afterwards.
+    pub fn compact(&mut self) -> bool {
+        let first = match &self.storage {
+            ChunkStorage::Uniform(_) => return true,
+            ChunkStorage::Dense(dense) => {
+                let first = dense.valid[0].then_some(dense.tiles[0]);
+                let uniform = (1..256).all(|i| dense.valid[i].then_some(dense.tiles[i]) == first);
+                if !uniform {
+                    return false;
+                }
+                first
+            }
+            ChunkStorage::Compressed(rle) => match rle.runs().next() {
+                Some(run) if rle.len() == 1 => run.tile,
+                _ => return false,
+            },
+        };
+        self.storage = ChunkStorage::Uniform(first);
+        true
+    }
+
     pub fn get_tile(&self, coord: u8) -> Option<&Tile> {
-        if self.valid[coord as usize] {
-            return Some(&self.tiles[coord as usize]);
+        match &self.storage {
+            ChunkStorage::Uniform(tile) => tile.as_ref(),
+            ChunkStorage::Dense(dense) => {
+                if dense.valid[coord as usize] {
+                    return Some(&dense.tiles[coord as usize]);
+                }
+                None
+            }
+            ChunkStorage::Compressed(rle) => rle.get_tile(coord),
         }
-        None
     }
 
     pub fn get_tile_mut(&mut self, coord: u8) -> Option<&mut Tile> {
-        if self.valid[coord as usize] {
-            return Some(&mut self.tiles[coord as usize]);
-        }
-        None
+        self.get_tile(coord)?;
+        self.histogram.take();
+        let dense = self.dense_mut();
+        Some(&mut dense.tiles[coord as usize])
     }
 
     pub fn set_tile(&mut self, coord: u8, tile: Option<Tile>) -> Option<Tile> {
-        let mut res = None;
-        if self.valid[coord as usize] {
-            res = Some(self.tiles[coord as usize]);
+        let res = self.get_tile(coord).copied();
+        if res == tile {
+            return res;
         }
+        self.histogram.take();
+        let dense = self.dense_mut();
         match tile {
             Some(tile) => {
-                self.tiles[coord as usize] = tile;
-                self.valid[coord as usize] = true;
+                dense.tiles[coord as usize] = tile;
+                dense.valid[coord as usize] = true;
             }
-            None => self.valid[coord as usize] = false,
+            None => dense.valid[coord as usize] = false,
         };
         res
     }
+
+    /// Sets every position to `tile`, switching to the single value representation.
+    pub fn fill(&mut self, tile: Option<Tile>) {
+        if self.as_uniform() != Some(tile) {
+            self.histogram.take();
+            self.storage = ChunkStorage::Uniform(tile);
+        }
+    }
+
+    /// Removes every tile.
+    pub fn clear(&mut self) {
+        self.fill(None);
+    }
+
+    /// Sets every position of the given rows to `tile`, rows being y inside the chunk.
+    /// Rows past the end of the chunk are ignored.
+    pub fn fill_rows(&mut self, rows: impl RangeBounds<usize>, tile: Option<Tile>) {
+        let size = CHUNK_SIZE as usize;
+        let start = match rows.start_bound() {
+            Bound::Included(start) => *start,
+            Bound::Excluded(start) => start + 1,
+            Bound::Unbounded => 0,
+        };
+        let end = match rows.end_bound() {
+            Bound::Included(end) => end + 1,
+            Bound::Excluded(end) => *end,
+            Bound::Unbounded => size,
+        }
+        .min(size);
+        if start >= end {
+            return;
+        }
+        if start == 0 && end == size {
+            return self.fill(tile);
+        }
+        if self.as_uniform() == Some(tile) {
+            return;
+        }
+        self.histogram.take();
+        let dense = self.dense_mut();
+        let positions = start * size..end * size;
+        if let Some(tile) = tile {
+            dense.tiles[positions.clone()].fill(tile);
+        }
+        dense.valid[positions].fill(tile.is_some());
+    }
+
+    /// Expands a uniform or compressed chunk into one value per position.
+    fn dense_mut(&mut self) -> &mut DenseTiles {
+        match &self.storage {
+            ChunkStorage::Uniform(tile) => {
+                self.storage = ChunkStorage::Dense(Box::new(DenseTiles {
+                    tiles: [tile.unwrap_or(Tile::new(0, 0)); 256],
+                    valid: [tile.is_some(); 256],
+                }));
+            }
+            ChunkStorage::Compressed(rle) => {
+                let mut dense = Box::new(DenseTiles {
+                    tiles: [Tile::new(0, 0); 256],
+                    valid: [false; 256],
+                });
+                for index in 0..=u8::MAX {
+                    if let Some(tile) = rle.get_tile(index) {
+                        dense.tiles[index as usize] = *tile;
+                        dense.valid[index as usize] = true;
+                    }
+                }
+                self.storage = ChunkStorage::Dense(dense);
+            }
+            ChunkStorage::Dense(_) => {}
+        }
+        match &mut self.storage {
+            ChunkStorage::Dense(dense) => dense,
+            _ => unreachable!(),
+        }
+    }
+
+    /// Counts of each distinct tile in the chunk.
+    /// Computed on first use and cached until the chunk is modified.
+    /// Edits made through the unchecked accessors of [`TileMapWriter`] are not noticed.
+    pub fn histogram(&self) -> &TileHistogram {
+        self.histogram.get_or_init(|| match &self.storage {
+            ChunkStorage::Uniform(tile) => {
+                TileHistogram::from_tiles(std::iter::once(tile.as_ref())).scaled(256)
+            }
+            ChunkStorage::Dense(_) | ChunkStorage::Compressed(_) => {
+                TileHistogram::from_tiles((0..=u8::MAX).map(|i| self.get_tile(i)))
+            }
+        })
+    }
+
+    /// The most common tile in the chunk, or None if the chunk is empty.
+    pub fn dominant_tile(&self) -> Option<Tile> {
+        self.histogram().dominant().map(|(tile, _)| tile)
+    }
+}
+
+pub struct TileMap<L = DefaultMap> {
+    chunks: HashMap<IVec3, Arc<Chunk>>,
+    bounds: Option<MapBounds>,
+    wrap: Option<MapWrap>,
+    label: PhantomData<fn() -> L>,
+}
+
+impl Default for TileMap {
+    fn default() -> Self {
+        Self::empty()
+    }
 }
 
-#[derive(Default)]
-pub struct TileMap {
-    chunks: HashMap<IVec3, Chunk>,
+/// Prints a one line summary per chunk rather than every tile, use [`Chunk`]'s Debug for those.
+impl<L> fmt::Debug for TileMap<L> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        struct Summary<'a>(&'a Arc<Chunk>);
+
+        impl fmt::Debug for Summary<'_> {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                match self.0.as_uniform() {
+                    Some(tile) => write!(f, "uniform {:?}", tile)?,
+                    None => write!(f, "dense, {} set", 256 - self.0.histogram().empty() as u32)?,
+                }
+                if Arc::strong_count(self.0) > 1 {
+                    write!(f, ", shared")?;
+                }
+                Ok(())
+            }
+        }
+
+        f.debug_struct("TileMap")
+            .field("bounds", &self.bounds)
+            .field("wrap", &self.wrap)
+            .field(
+                "chunks",
+                &self
+                    .chunks
+                    .iter()
+                    .map(|(coord, chunk)| (coord, Summary(chunk)))
+                    .collect::<HashMap<_, _>>(),
+            )
+            .finish()
+    }
 }
 
 impl TileMap {
+    /// Builds a map from a grid of tile indices of `sheet`, `width` cells wide in row order from
+    /// the top, on `layer`. The bottom left cell lands on (0, 0) and the top row on the highest
+    /// y, so the map reads like the grid. [`csv::EMPTY_CELL`] leaves a cell empty.
+    ///
+    /// The map causes no updates when inserted as a resource, write the grid with
+    /// [`TileMapWriter::set_grid`] for that.
+    pub fn from_grid(grid: &[u16], width: usize, layer: i32, sheet: u16) -> Self {
+        let mut map = Self::empty();
+        map.set_tiles(csv::grid_tiles(grid, width, IVec3::Z * layer, sheet));
+        map
+    }
+
+    /// Builds a map from CSV text, see [`csv::parse_csv`] and [`TileMap::from_grid`].
+    pub fn from_csv(text: &str, layer: i32, sheet: u16) -> Result<Self, CsvError> {
+        let (grid, width) = csv::parse_csv(text)?;
+        Ok(Self::from_grid(&grid, width, layer, sheet))
+    }
+}
+
+impl<L> TileMap<L> {
+    fn empty() -> Self {
+        Self {
+            chunks: HashMap::default(),
+            bounds: None,
+            wrap: None,
+            label: PhantomData,
+        }
+    }
+
     pub fn get_chunk(&self, coord: &IVec3) -> Option<&Chunk> {
-        self.chunks.get(coord)
+        self.chunks
+            .get(&self.normalize_chunk(coord))
+            .map(|chunk| chunk.as_ref())
     }
 
+    pub fn try_get_chunk(&self, coord: &IVec3) -> Result<&Chunk, TilingError> {
+        self.get_chunk(coord)
+            .ok_or(TilingError::MissingChunk(*coord))
+    }
+
+    /// Mutable access to a chunk. A chunk shared with other coordinates is copied first,
+    /// so the edit only affects this coordinate.
     pub fn get_chunk_mut(&mut self, coord: &IVec3) -> Option<&mut Chunk> {
-        self.chunks.get_mut(coord)
+        let coord = self.normalize_chunk(coord);
+        self.chunks.get_mut(&coord).map(Arc::make_mut)
+    }
+
+    /// Places a chunk that may also be used at other coordinates, e.g. a filler ocean chunk.
+    /// Shared chunks are stored once and copied on the first edit through any coordinate.
+    pub fn insert_shared_chunk(&mut self, coord: IVec3, chunk: Arc<Chunk>) -> Option<Arc<Chunk>> {
+        let coord = self.normalize_chunk(&coord);
+        self.chunks.insert(coord, chunk)
+    }
+
+    /// Makes `to` reference the same chunk data as `from`, returning false if `from` has no chunk.
+    pub fn share_chunk(&mut self, from: &IVec3, to: IVec3) -> bool {
+        let from = self.normalize_chunk(from);
+        match self.chunks.get(&from).cloned() {
+            Some(chunk) => {
+                self.insert_shared_chunk(to, chunk);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Removes the chunk at `coord`, e.g. when it scrolls out of view, returning its data.
+    pub fn remove_chunk(&mut self, coord: &IVec3) -> Option<Arc<Chunk>> {
+        let coord = self.normalize_chunk(coord);
+        self.chunks.remove(&coord)
+    }
+
+    /// Whether the chunk at `coord` currently shares its data with another coordinate.
+    pub fn is_chunk_shared(&self, coord: &IVec3) -> bool {
+        self.chunks
+            .get(&self.normalize_chunk(coord))
+            .is_some_and(|chunk| Arc::strong_count(chunk) > 1)
+    }
+
+    /// Coordinates of every chunk in the map, in no particular order.
+    pub fn chunk_coords(&self) -> impl Iterator<Item = &IVec3> {
+        self.chunks.keys()
+    }
+
+    pub fn get_tile(&self, coord: &TileCoord) -> Option<&Tile> {
+        self.get_chunk(&coord.chunk)
+            .and_then(|chunk| chunk.get_tile(coord.index))
+    }
+
+    pub fn bounds(&self) -> Option<&MapBounds> {
+        self.bounds.as_ref()
     }
 
+    /// Limits the map to a finite area, or makes it infinite again with None.
+    /// Tiles already outside the new bounds are kept, only later writes are affected.
+    pub fn set_bounds(&mut self, bounds: Option<MapBounds>) {
+        self.bounds = bounds;
+    }
+
+    pub fn wrap(&self) -> Option<&MapWrap> {
+        self.wrap.as_ref()
+    }
+
+    /// Makes the map wrap around, or removes wrapping with None.
+    /// Chunks already stored outside the new period are no longer reachable.
+    pub fn set_wrap(&mut self, wrap: Option<MapWrap>) {
+        self.wrap = wrap;
+    }
+
+    /// The canonical coordinate of a chunk, which only differs from `coord` on wrapping maps.
+    pub fn normalize_chunk(&self, coord: &IVec3) -> IVec3 {
+        match &self.wrap {
+            Some(wrap) => wrap.normalize_chunk(*coord),
+            None => *coord,
+        }
+    }
+
+    /// The tile `offset` tiles away from `coord`, following the map wrapping.
+    pub fn offset_coord(&self, coord: &TileCoord, offset: IVec3) -> TileCoord {
+        let coord = TileCoord::from_tile_position(coord.tile_position() + offset);
+        TileCoord {
+            index: coord.index,
+            chunk: self.normalize_chunk(&coord.chunk),
+        }
+    }
+
+    /// The four tiles sharing an edge with `coord` on the same layer, following the map wrapping.
+    pub fn neighbours(&self, coord: &TileCoord) -> [TileCoord; 4] {
+        [
+            IVec3::new(1, 0, 0),
+            IVec3::new(-1, 0, 0),
+            IVec3::new(0, 1, 0),
+            IVec3::new(0, -1, 0),
+        ]
+        .map(|offset| self.offset_coord(coord, offset))
+    }
+
+    /// Where a write to `coord` lands under the map wrapping and bounds, or None if it is rejected.
+    pub fn resolve_coord(&self, coord: &TileCoord) -> Option<TileCoord> {
+        let coord = TileCoord {
+            index: coord.index,
+            chunk: self.normalize_chunk(&coord.chunk),
+        };
+        match &self.bounds {
+            Some(bounds) => bounds.resolve(&coord),
+            None => Some(coord),
+        }
+    }
+
+    /// Writes outside the map bounds are rejected or clamped, see [`TileMap::set_bounds`].
     pub fn set_tile(&mut self, coord: &TileCoord, tile: Option<Tile>) -> Option<Tile> {
+        let coord = self.resolve_coord(coord)?;
         match self.chunks.get_mut(&coord.chunk) {
-            Some(chunk) => chunk.set_tile(coord.index, tile),
-            None => {
-                if tile.is_none() {
-                    None
-                } else {
-                    self.chunks.insert(coord.chunk, Chunk::default());
-                    None
+            Some(chunk) => {
+                if chunk.get_tile(coord.index) == tile.as_ref() {
+                    return tile;
                 }
+                Arc::make_mut(chunk).set_tile(coord.index, tile)
             }
+            // Clearing a tile never needs a new chunk.
+            None => match tile {
+                Some(tile) => self
+                    .get_or_create_chunk(&coord.chunk)
+                    .set_tile(coord.index, Some(tile)),
+                None => None,
+            },
         }
     }
+
+    /// Like [`TileMap::set_tile`], but reports writes rejected by the map bounds as an error.
+    pub fn try_set_tile(
+        &mut self,
+        coord: &TileCoord,
+        tile: Option<Tile>,
+    ) -> Result<Option<Tile>, TilingError> {
+        if self.resolve_coord(coord).is_none() {
+            return Err(TilingError::OutOfBounds(*coord));
+        }
+        Ok(self.set_tile(coord, tile))
+    }
+
+    /// Sets many tiles at once, looking each chunk up only once.
+    /// Returns the indices that changed, grouped by chunk.
+    pub fn set_tiles(
+        &mut self,
+        tiles: impl IntoIterator<Item = (TileCoord, Option<Tile>)>,
+    ) -> HashMap<IVec3, Vec<u8>> {
+        let mut by_chunk: HashMap<IVec3, Vec<(u8, Option<Tile>)>> = HashMap::default();
+        for (coord, tile) in tiles {
+            if let Some(coord) = self.resolve_coord(&coord) {
+                by_chunk
+                    .entry(coord.chunk)
+                    .or_default()
+                    .push((coord.index, tile));
+            }
+        }
+
+        let mut changed: HashMap<IVec3, Vec<u8>> = HashMap::default();
+        for (chunk_coord, tiles) in by_chunk {
+            let needs_chunk = tiles.iter().any(|(_, tile)| tile.is_some());
+            if !needs_chunk && !self.chunks.contains_key(&chunk_coord) {
+                continue;
+            }
+            let chunk = self.get_or_create_chunk(&chunk_coord);
+            let indices: Vec<u8> = tiles
+                .into_iter()
+                .filter(|(index, tile)| chunk.set_tile(*index, *tile) != *tile)
+                .map(|(index, _)| index)
+                .collect();
+            if !indices.is_empty() {
+                changed.insert(chunk_coord, indices);
+            }
+        }
+        changed
+    }
+
+    /// Fills the box from `min` to `max` (inclusive, in tiles) with `tile`, or clears it with None.
+    /// Chunks are created as needed and chunks covered completely are replaced by a single
+    /// uniform value. Returns the indices that changed, grouped by chunk.
+    pub fn fill_rect(
+        &mut self,
+        min: IVec3,
+        max: IVec3,
+        tile: Option<Tile>,
+    ) -> HashMap<IVec3, Vec<u8>> {
+        self.fill_rect_tiles(min, max, tile)
+            .into_iter()
+            .map(|(chunk, old)| (chunk, old.into_iter().map(|(index, _)| index).collect()))
+            .collect()
+    }
+
+    /// [`TileMap::fill_rect`], returning the tile each changed index held before instead.
+    fn fill_rect_tiles(
+        &mut self,
+        min: IVec3,
+        max: IVec3,
+        tile: Option<Tile>,
+    ) -> HashMap<IVec3, Vec<(u8, Option<Tile>)>> {
+        let (mut min, mut max) = (min.min(max), min.max(max));
+        if let Some(bounds) = &self.bounds {
+            match bounds.mode {
+                BoundsMode::Reject => {
+                    min = min.max(bounds.min);
+                    max = max.min(bounds.max);
+                }
+                // Clamping every write of a box lands on the clamped box.
+                BoundsMode::Clamp => {
+                    min = min.clamp(bounds.min, bounds.max);
+                    max = max.clamp(bounds.min, bounds.max);
+                }
+            }
+            if min.cmpgt(max).any() {
+                return HashMap::default();
+            }
+        }
+
+        let min_chunk = TileCoord::from_tile_position(min).chunk;
+        let max_chunk = TileCoord::from_tile_position(max).chunk;
+        let mut changed: HashMap<IVec3, Vec<(u8, Option<Tile>)>> = HashMap::default();
+        for z in min_chunk.z..=max_chunk.z {
+            for y in min_chunk.y..=max_chunk.y {
+                for x in min_chunk.x..=max_chunk.x {
+                    let chunk_coord = self.normalize_chunk(&IVec3::new(x, y, z));
+                    let origin_x = x * CHUNK_SIZE;
+                    let origin_y = y * CHUNK_SIZE;
+                    let (min_x, max_x) = (
+                        (min.x - origin_x).max(0),
+                        (max.x - origin_x).min(CHUNK_SIZE - 1),
+                    );
+                    let (min_y, max_y) = (
+                        (min.y - origin_y).max(0),
+                        (max.y - origin_y).min(CHUNK_SIZE - 1),
+                    );
+                    let indices = (min_y..=max_y)
+                        .flat_map(|y| (min_x..=max_x).map(move |x| (y * CHUNK_SIZE + x) as u8));
+
+                    let chunk = match self.chunks.get_mut(&chunk_coord) {
+                        Some(chunk) => chunk,
+                        None if tile.is_none() => continue,
+                        None => self.chunks.entry(chunk_coord).or_default(),
+                    };
+                    let covers_rows = min_x == 0 && max_x == CHUNK_SIZE - 1;
+                    let covers_chunk = covers_rows && min_y == 0 && max_y == CHUNK_SIZE - 1;
+                    let old: Vec<(u8, Option<Tile>)> = indices
+                        .map(|index| (index, chunk.get_tile(index).copied()))
+                        .filter(|(_, old)| *old != tile)
+                        .collect();
+                    if old.is_empty() {
+                        continue;
+                    }
+                    if covers_chunk {
+                        *chunk = Arc::new(Chunk::uniform(tile));
+                    } else if covers_rows {
+                        Arc::make_mut(chunk).fill_rows(min_y as usize..=max_y as usize, tile);
+                    } else {
+                        let chunk = Arc::make_mut(chunk);
+                        for (index, _) in old.iter() {
+                            chunk.set_tile(*index, tile);
+                        }
+                    }
+                    changed.entry(chunk_coord).or_default().extend(old);
+                }
+            }
+        }
+        changed
+    }
+
+    /// Every set tile inside the box from `min` to `max` (inclusive, in tiles), crossing chunk
+    /// boundaries as needed. Coordinates are reported as seen from the box, even on wrapping maps.
+    pub fn iter_region(&self, min: IVec3, max: IVec3) -> impl Iterator<Item = (TileCoord, &Tile)> {
+        let (min, max) = (min.min(max), min.max(max));
+        let min_chunk = TileCoord::from_tile_position(min).chunk;
+        let max_chunk = TileCoord::from_tile_position(max).chunk;
+        (min_chunk.z..=max_chunk.z)
+            .flat_map(move |z| {
+                (min_chunk.y..=max_chunk.y).flat_map(move |y| {
+                    (min_chunk.x..=max_chunk.x).map(move |x| IVec3::new(x, y, z))
+                })
+            })
+            .filter_map(move |chunk_coord| {
+                self.get_chunk(&chunk_coord)
+                    .filter(|chunk| chunk.as_uniform() != Some(None))
+                    .map(|chunk| (chunk_coord, chunk))
+            })
+            .flat_map(move |(chunk_coord, chunk)| {
+                let origin_x = chunk_coord.x * CHUNK_SIZE;
+                let origin_y = chunk_coord.y * CHUNK_SIZE;
+                let (min_x, max_x) = (
+                    (min.x - origin_x).max(0),
+                    (max.x - origin_x).min(CHUNK_SIZE - 1),
+                );
+                let (min_y, max_y) = (
+                    (min.y - origin_y).max(0),
+                    (max.y - origin_y).min(CHUNK_SIZE - 1),
+                );
+                (min_y..=max_y)
+                    .flat_map(move |y| (min_x..=max_x).map(move |x| (y * CHUNK_SIZE + x) as u8))
+                    .filter_map(move |index| {
+                        chunk.get_tile(index).map(|tile| {
+                            (
+                                TileCoord {
+                                    index,
+                                    chunk: chunk_coord,
+                                },
+                                tile,
+                            )
+                        })
+                    })
+            })
+    }
+
+    /// Gets the chunk at `coord`, creating an empty one if there is none.
+    pub fn get_or_create_chunk(&mut self, coord: &IVec3) -> &mut Chunk {
+        let coord = self.normalize_chunk(coord);
+        Arc::make_mut(self.chunks.entry(coord).or_default())
+    }
 }
 
-#[derive(Default)]
-pub struct TileMapUpdates {
+pub struct TileMapUpdates<L = DefaultMap> {
     chunks: HashMap<IVec3, HashSet<u8>>,
+    removed: HashSet<IVec3>,
+    inserted: HashSet<IVec3>,
+    label: PhantomData<fn() -> L>,
+}
+
+impl Default for TileMapUpdates {
+    fn default() -> Self {
+        Self::empty()
+    }
 }
 
-impl TileMapUpdates {
+impl<L> TileMapUpdates<L> {
+    fn empty() -> Self {
+        Self {
+            chunks: HashMap::default(),
+            removed: HashSet::default(),
+            inserted: HashSet::default(),
+            label: PhantomData,
+        }
+    }
+
     pub fn set_update(&mut self, coord: &TileCoord) {
-        let chunk = match self.chunks.get_mut(&coord.chunk) {
-            Some(chunk) => chunk,
-            None => {
-                self.chunks.insert(coord.chunk, HashSet::default());
-                self.chunks.get_mut(&coord.chunk).unwrap()
-            }
-        };
-        chunk.insert(coord.index);
+        self.chunks
+            .entry(coord.chunk)
+            .or_default()
+            .insert(coord.index);
     }
 
-    pub fn get_chunk_updates(&self) -> Keys<IVec3, HashSet<u8>> {
+    /// Marks several tiles of the same chunk as updated at once.
+    pub fn set_updates(&mut self, chunk: &IVec3, indices: impl IntoIterator<Item = u8>) {
+        self.chunks.entry(*chunk).or_default().extend(indices);
+    }
+
+    pub fn get_chunk_updates(&self) -> Keys<'_, IVec3, HashSet<u8>> {
         self.chunks.keys()
     }
+
+    /// Indices of the tiles updated in a chunk, empty if the chunk has no updates.
+    pub fn get_chunk_tile_updates(&self, chunk: &IVec3) -> impl Iterator<Item = u8> + '_ {
+        self.chunks.get(chunk).into_iter().flatten().copied()
+    }
+
+    /// Every updated tile, in no particular order.
+    pub fn get_tile_updates(&self) -> impl Iterator<Item = TileCoord> + '_ {
+        self.chunks.iter().flat_map(|(chunk, indices)| {
+            indices.iter().map(move |index| TileCoord {
+                index: *index,
+                chunk: *chunk,
+            })
+        })
+    }
+
+    /// Marks a chunk as removed from the map.
+    pub fn set_chunk_removed(&mut self, chunk: &IVec3) {
+        self.removed.insert(*chunk);
+    }
+
+    /// Chunks removed from the map, a chunk may have been created again since.
+    pub fn get_chunk_removals(&self) -> impl Iterator<Item = &IVec3> + '_ {
+        self.removed.iter()
+    }
+
+    /// Marks a chunk as placed into the map as a whole, e.g. after loading or generating it.
+    pub fn set_chunk_inserted(&mut self, chunk: &IVec3) {
+        self.inserted.insert(*chunk);
+    }
+
+    /// Chunks placed with [`TileMapWriter::insert_chunk`], a chunk may have been removed since.
+    pub fn get_chunk_insertions(&self) -> impl Iterator<Item = &IVec3> + '_ {
+        self.inserted.iter()
+    }
+}
+
+/// Sent for every tile changed through [`TileMapWriter`], except by the `no_update` and
+/// `get_*_mut` methods. Unlike [`TileMapUpdates`] this keeps the tile that was replaced.
+pub struct TileChanged<L = DefaultMap> {
+    pub coord: TileCoord,
+    pub old: Option<Tile>,
+    pub new: Option<Tile>,
+    label: PhantomData<fn() -> L>,
+}
+
+impl<L> TileChanged<L> {
+    fn new(coord: TileCoord, old: Option<Tile>, new: Option<Tile>) -> Self {
+        Self {
+            coord,
+            old,
+            new,
+            label: PhantomData,
+        }
+    }
+}
+
+impl<L> fmt::Debug for TileChanged<L> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TileChanged")
+            .field("coord", &self.coord)
+            .field("old", &self.old)
+            .field("new", &self.new)
+            .finish()
+    }
 }
 
 #[derive(SystemParam)]
-pub struct TileMapReader<'w, 's> {
-    chunks: Res<'w, TileMap>,
-    updates: Res<'w, TileMapUpdates>,
+pub struct TileMapReader<'w, 's, L: MapLabel = DefaultMap> {
+    chunks: Res<'w, TileMap<L>>,
+    updates: Res<'w, TileMapUpdates<L>>,
+    data: Res<'w, ChunkDataStore<L>>,
     #[system_param(ignore)]
     marker: std::marker::PhantomData<&'s Tile>,
 }
 
 #[derive(SystemParam)]
-pub struct TileMapWriter<'w, 's> {
-    chunks: ResMut<'w, TileMap>,
-    updates: ResMut<'w, TileMapUpdates>,
+pub struct TileMapWriter<'w, 's, L: MapLabel = DefaultMap> {
+    chunks: ResMut<'w, TileMap<L>>,
+    updates: ResMut<'w, TileMapUpdates<L>>,
+    data: ResMut<'w, ChunkDataStore<L>>,
+    changes: EventWriter<'w, 's, TileChanged<L>>,
+    placement: Res<'w, PlacementRules>,
+    locks: Res<'w, TileLocks>,
+    compute_pool: Option<Res<'w, ComputeTaskPool>>,
     #[system_param(ignore)]
     marker: std::marker::PhantomData<&'s Tile>,
 }
 
 pub trait MapReader {
-    fn get_tile(&self, coord: &TileCoord) -> Option<&Tile>;
+    fn get_tile(&self, coord: impl IntoTileCoord) -> Option<&Tile>;
+
+    /// Gets the tile at a position in tiles, doing the chunk math internally.
+    #[inline]
+    fn get_tile_xy(&self, x: i32, y: i32, layer: i32) -> Option<&Tile> {
+        self.get_tile(IVec3::new(x, y, layer))
+    }
 
     fn get_chunk(&self, coord: &IVec3) -> Option<&Chunk>;
 
-    fn get_chunk_updates(&self) -> Keys<IVec3, HashSet<u8>>;
+    #[inline]
+    fn try_get_chunk(&self, coord: &IVec3) -> Result<&Chunk, TilingError> {
+        self.get_chunk(coord)
+            .ok_or(TilingError::MissingChunk(*coord))
+    }
+
+    fn get_chunk_updates(&self) -> Keys<'_, IVec3, HashSet<u8>>;
+
+    /// Chunks removed this frame, see [`TileMapWriter::remove_chunk`].
+    fn get_chunk_removals(&self) -> impl Iterator<Item = &IVec3>;
+
+    /// Every updated tile along with its current value, None if the update removed it.
+    fn get_tile_updates(&self) -> impl Iterator<Item = (TileCoord, Option<&Tile>)>;
+
+    /// Every set tile inside the box from `min` to `max`, see [`TileMap::iter_region`].
+    fn iter_region(&self, min: IVec3, max: IVec3) -> impl Iterator<Item = (TileCoord, &Tile)>;
+
+    /// The data of type `T` attached to a chunk, see [`chunk_data::ChunkData`].
+    fn get_chunk_data<T: Send + Sync + 'static>(&self, chunk: &IVec3) -> Option<&T>;
 }
 
-impl<'w, 's> MapReader for TileMapReader<'w, 's> {
+impl<'w, 's, L: MapLabel> MapReader for TileMapReader<'w, 's, L> {
     #[inline]
-    fn get_tile(&self, coord: &TileCoord) -> Option<&Tile> {
-        if let Some(chunk) = self.chunks.get_chunk(&coord.chunk) {
-            return chunk.get_tile(coord.index);
-        }
-        None
+    fn get_tile(&self, coord: impl IntoTileCoord) -> Option<&Tile> {
+        self.chunks.get_tile(&coord.into_tile_coord())
     }
 
     #[inline]
@@ -183,18 +1239,39 @@ impl<'w, 's> MapReader for TileMapReader<'w, 's> {
     }
 
     #[inline]
-    fn get_chunk_updates(&self) -> Keys<IVec3, HashSet<u8>> {
+    fn get_chunk_updates(&self) -> Keys<'_, IVec3, HashSet<u8>> {
         self.updates.get_chunk_updates()
     }
+
+    #[inline]
+    fn get_chunk_removals(&self) -> impl Iterator<Item = &IVec3> {
+        self.updates.get_chunk_removals()
+    }
+
+    #[inline]
+    fn get_tile_updates(&self) -> impl Iterator<Item = (TileCoord, Option<&Tile>)> {
+        self.updates
+            .get_tile_updates()
+            .map(|coord| (coord, self.chunks.get_tile(&coord)))
+    }
+
+    #[inline]
+    fn iter_region(&self, min: IVec3, max: IVec3) -> impl Iterator<Item = (TileCoord, &Tile)> {
+        self.chunks.iter_region(min, max)
+    }
+
+    #[inline]
+    fn get_chunk_data<T: Send + Sync + 'static>(&self, chunk: &IVec3) -> Option<&T> {
+        self.data
+            .get::<T>()?
+            .get(&self.chunks.normalize_chunk(chunk))
+    }
 }
 
-impl<'w, 's> MapReader for TileMapWriter<'w, 's> {
+impl<'w, 's, L: MapLabel> MapReader for TileMapWriter<'w, 's, L> {
     #[inline]
-    fn get_tile(&self, coord: &TileCoord) -> Option<&Tile> {
-        if let Some(chunk) = self.chunks.get_chunk(&coord.chunk) {
-            return chunk.get_tile(coord.index);
-        }
-        None
+    fn get_tile(&self, coord: impl IntoTileCoord) -> Option<&Tile> {
+        self.chunks.get_tile(&coord.into_tile_coord())
     }
 
     #[inline]
@@ -203,33 +1280,396 @@ impl<'w, 's> MapReader for TileMapWriter<'w, 's> {
     }
 
     #[inline]
-    fn get_chunk_updates(&self) -> Keys<IVec3, HashSet<u8>> {
+    fn get_chunk_updates(&self) -> Keys<'_, IVec3, HashSet<u8>> {
         self.updates.get_chunk_updates()
     }
+
+    #[inline]
+    fn get_chunk_removals(&self) -> impl Iterator<Item = &IVec3> {
+        self.updates.get_chunk_removals()
+    }
+
+    #[inline]
+    fn get_tile_updates(&self) -> impl Iterator<Item = (TileCoord, Option<&Tile>)> {
+        self.updates
+            .get_tile_updates()
+            .map(|coord| (coord, self.chunks.get_tile(&coord)))
+    }
+
+    #[inline]
+    fn iter_region(&self, min: IVec3, max: IVec3) -> impl Iterator<Item = (TileCoord, &Tile)> {
+        self.chunks.iter_region(min, max)
+    }
+
+    #[inline]
+    fn get_chunk_data<T: Send + Sync + 'static>(&self, chunk: &IVec3) -> Option<&T> {
+        self.data
+            .get::<T>()?
+            .get(&self.chunks.normalize_chunk(chunk))
+    }
 }
 
-impl<'w, 's> TileMapWriter<'w, 's> {
+impl<'w, 's, L: MapLabel> TileMapWriter<'w, 's, L> {
     /// Sets the tile at a given coordinate to a new tile, or removes it if None is given.
     /// This method causes updates.
     #[inline]
-    pub fn set_tile(&mut self, coord: &TileCoord, tile: Option<Tile>) -> Option<Tile> {
-        let old = self.chunks.set_tile(coord, tile);
+    pub fn set_tile(&mut self, coord: impl IntoTileCoord, tile: Option<Tile>) -> Option<Tile> {
+        let coord = self.chunks.resolve_coord(&coord.into_tile_coord())?;
+        let old = self.chunks.set_tile(&coord, tile);
         if old != tile {
-            self.updates.set_update(coord);
+            self.updates.set_update(&coord);
+            self.changes.send(TileChanged::new(coord, old, tile));
         }
         old
     }
 
+    /// Like [`TileMapWriter::set_tile`], but reports writes rejected by the map bounds as an error.
+    /// This method causes updates.
+    pub fn try_set_tile(
+        &mut self,
+        coord: impl IntoTileCoord,
+        tile: Option<Tile>,
+    ) -> Result<Option<Tile>, TilingError> {
+        let coord = coord.into_tile_coord();
+        if self.chunks.resolve_coord(&coord).is_none() {
+            return Err(TilingError::OutOfBounds(coord));
+        }
+        Ok(self.set_tile(coord, tile))
+    }
+
+    /// Places a tile if it satisfies the [`PlacementRules`], returning the previous tile or
+    /// every rule it breaks. This method causes updates.
+    pub fn try_place(
+        &mut self,
+        coord: impl IntoTileCoord,
+        tile: Tile,
+    ) -> Result<Option<Tile>, Vec<PlacementViolation>> {
+        let coord = coord.into_tile_coord();
+        let violations = self.check_placement(&coord, &tile);
+        if !violations.is_empty() {
+            return Err(violations);
+        }
+        Ok(self.set_tile(coord, Some(tile)))
+    }
+
+    /// Every [`PlacementRules`] rule placing `tile` at `coord` would break, without placing it.
+    pub fn check_placement(&self, coord: &TileCoord, tile: &Tile) -> Vec<PlacementViolation> {
+        self.placement.check(&self.chunks, coord, tile)
+    }
+
+    /// Whether the tile is in a box locked with [`TileLocks`]. Writes aren't checked against
+    /// the locks, see [`policy::GuardedTileMapWriter`] for a writer that is.
+    pub fn is_locked(&self, coord: &TileCoord) -> bool {
+        self.locks.is_locked(coord)
+    }
+
+    /// Whether any tile of the chunk is in a box locked with [`TileLocks`].
+    pub fn is_chunk_locked(&self, chunk: &IVec3) -> bool {
+        self.locks.is_chunk_locked(chunk)
+    }
+
+    /// Sets the tile at a position in tiles, doing the chunk math internally.
+    /// This method causes updates.
+    #[inline]
+    pub fn set_tile_xy(&mut self, x: i32, y: i32, layer: i32, tile: Option<Tile>) -> Option<Tile> {
+        self.set_tile(IVec3::new(x, y, layer), tile)
+    }
+
+    /// Sets the tile at a 2d position in tiles on the given layer, see [`TileMapWriter::set_tile`].
+    #[inline]
+    pub fn set_tile_on_layer(
+        &mut self,
+        position: IVec2,
+        layer: i32,
+        tile: Option<Tile>,
+    ) -> Option<Tile> {
+        self.set_tile(position.extend(layer), tile)
+    }
+
+    /// Sets many tiles at once, grouping the work and the update tracking by chunk.
+    /// This method causes updates for the tiles that changed.
+    pub fn set_tiles(&mut self, tiles: impl IntoIterator<Item = (TileCoord, Option<Tile>)>) {
+        self.write_tiles(tiles);
+    }
+
+    /// [`TileMapWriter::set_tiles`], returning how many tiles changed.
+    fn write_tiles(&mut self, tiles: impl IntoIterator<Item = (TileCoord, Option<Tile>)>) -> usize {
+        let tiles: Vec<(TileCoord, Option<Tile>)> = tiles.into_iter().collect();
+        // The first write to a tile sees the value it had before the whole batch.
+        let mut before: HashMap<TileCoord, Option<Tile>> = HashMap::default();
+        for (coord, _) in tiles.iter() {
+            if let Some(coord) = self.chunks.resolve_coord(coord) {
+                before
+                    .entry(coord)
+                    .or_insert_with(|| self.chunks.get_tile(&coord).copied());
+            }
+        }
+        let mut count = 0;
+        for (chunk, indices) in self.chunks.set_tiles(tiles) {
+            count += indices.len();
+            self.send_changes(&chunk, &indices, |coord| before[coord]);
+            self.updates.set_updates(&chunk, indices);
+        }
+        count
+    }
+
+    /// Writes a grid of tile indices of `sheet` with its bottom left cell at `origin`, see
+    /// [`TileMap::from_grid`]. Empty cells clear their tile.
+    /// This method causes updates for the tiles that changed.
+    pub fn set_grid(&mut self, grid: &[u16], width: usize, origin: IVec3, sheet: u16) {
+        self.set_tiles(csv::grid_tiles(grid, width, origin, sheet));
+    }
+
+    /// Fills or clears the box from `min` to `max` (inclusive, in tiles), see [`TileMap::fill_rect`].
+    /// This method causes updates for the tiles that changed.
+    pub fn fill_rect(&mut self, min: IVec3, max: IVec3, tile: Option<Tile>) {
+        for (chunk, old) in self.chunks.fill_rect_tiles(min, max, tile) {
+            let mut before = [None; (CHUNK_SIZE * CHUNK_SIZE) as usize];
+            for (index, tile) in old.iter() {
+                before[*index as usize] = *tile;
+            }
+            let indices: Vec<u8> = old.iter().map(|(index, _)| *index).collect();
+            self.send_changes(&chunk, &indices, |coord| before[coord.index as usize]);
+            self.updates.set_updates(&chunk, indices);
+        }
+    }
+
+    /// Sends a [`TileChanged`] for each changed index of a chunk, `old` gives the previous tiles.
+    fn send_changes(
+        &mut self,
+        chunk: &IVec3,
+        indices: &[u8],
+        old: impl Fn(&TileCoord) -> Option<Tile>,
+    ) {
+        let mut indices = indices.to_vec();
+        indices.sort_unstable();
+        indices.dedup();
+        for index in indices {
+            let coord = TileCoord {
+                index,
+                chunk: *chunk,
+            };
+            let old = old(&coord);
+            let new = self.chunks.get_tile(&coord).copied();
+            if old != new {
+                self.changes.send(TileChanged::new(coord, old, new));
+            }
+        }
+    }
+
+    /// Places a chunk at `coord`, replacing any chunk there, e.g. a chunk that finished loading.
+    /// This method causes updates for the tiles that differ from the replaced chunk and marks
+    /// the chunk as inserted.
+    pub fn insert_chunk(&mut self, coord: &IVec3, chunk: Arc<Chunk>) -> Option<Arc<Chunk>> {
+        let coord = self.chunks.normalize_chunk(coord);
+        let old = self.chunks.insert_shared_chunk(coord, chunk.clone());
+        let mut indices = Vec::new();
+        for index in 0..=u8::MAX {
+            let old = old.as_ref().and_then(|old| old.get_tile(index).copied());
+            let new = chunk.get_tile(index).copied();
+            if old != new {
+                let coord = TileCoord {
+                    index,
+                    chunk: coord,
+                };
+                self.changes.send(TileChanged::new(coord, old, new));
+                indices.push(index);
+            }
+        }
+        if !indices.is_empty() {
+            self.updates.set_updates(&coord, indices);
+        }
+        self.updates.set_chunk_inserted(&coord);
+        old
+    }
+
+    /// Removes the chunk at `coord` along with its tiles and its [`chunk_data::ChunkData`],
+    /// returning the chunk. This method causes updates for the removed tiles and marks the
+    /// chunk as removed.
+    pub fn remove_chunk(&mut self, coord: &IVec3) -> Option<Arc<Chunk>> {
+        let chunk = self.chunks.remove_chunk(coord)?;
+        let coord = self.chunks.normalize_chunk(coord);
+        self.data.remove_chunk(&coord);
+        let indices: Vec<u8> = (0..=u8::MAX)
+            .filter(|index| chunk.get_tile(*index).is_some())
+            .collect();
+        self.changes.send_batch(indices.iter().map(|index| {
+            let coord = TileCoord {
+                index: *index,
+                chunk: coord,
+            };
+            TileChanged::new(coord, chunk.get_tile(*index).copied(), None)
+        }));
+        if !indices.is_empty() {
+            self.updates.set_updates(&coord, indices);
+        }
+        self.updates.set_chunk_removed(&coord);
+        Some(chunk)
+    }
+
+    /// The data of type `T` attached to a chunk, for changing it in place.
+    pub fn get_chunk_data_mut<T: Send + Sync + 'static>(
+        &mut self,
+        chunk: &IVec3,
+    ) -> Option<&mut T> {
+        let chunk = self.chunks.normalize_chunk(chunk);
+        self.data.get_mut::<T>().get_mut(&chunk)
+    }
+
+    /// Attaches data of type `T` to a chunk, returning the data it replaced.
+    /// The data is dropped when the chunk is removed.
+    pub fn insert_chunk_data<T: Send + Sync + 'static>(
+        &mut self,
+        chunk: &IVec3,
+        data: T,
+    ) -> Option<T> {
+        let chunk = self.chunks.normalize_chunk(chunk);
+        self.data.get_mut().insert(chunk, data)
+    }
+
+    pub fn remove_chunk_data<T: Send + Sync + 'static>(&mut self, chunk: &IVec3) -> Option<T> {
+        let chunk = self.chunks.normalize_chunk(chunk);
+        self.data.get_mut::<T>().remove(&chunk)
+    }
+
+    /// All data of type `T`, e.g. to serialize it along with the map.
+    pub fn chunk_data_mut<T: Send + Sync + 'static>(&mut self) -> &mut chunk_data::ChunkData<T> {
+        self.data.get_mut()
+    }
+
     /// Sets the tile at a given coordinate to a new tile, or removes it if None is given.
     /// This method does not cause updates.
     #[inline]
-    pub fn set_tile_no_update(&mut self, coord: &TileCoord, tile: Option<Tile>) -> Option<Tile> {
-        self.chunks.set_tile(coord, tile)
+    pub fn set_tile_no_update(
+        &mut self,
+        coord: impl IntoTileCoord,
+        tile: Option<Tile>,
+    ) -> Option<Tile> {
+        self.chunks.set_tile(&coord.into_tile_coord(), tile)
+    }
+
+    /// Replaces every set tile inside the box from `min` to `max` (inclusive, in tiles) that
+    /// matches `predicate` with the result of `replace`, `None` removes the tile.
+    /// On a wrapping map the box covers every tile with a wrapped copy inside it, each once.
+    /// Chunks are processed in parallel on the compute task pool when one is available.
+    /// Only tiles that actually changed cause updates.
+    pub fn replace_where<P, R>(&mut self, min: IVec3, max: IVec3, predicate: P, replace: R)
+    where
+        P: Fn(&Tile) -> bool + Sync,
+        R: Fn(&Tile) -> Option<Tile> + Sync,
+    {
+        let (min, max) = (min.min(max), min.max(max));
+        let min_chunk = TileCoord::from_tile_position(min).chunk;
+        let max_chunk = TileCoord::from_tile_position(max).chunk;
+        // Stored chunks are canonical, so they are in the box if any of their wrapped copies is.
+        let period = self
+            .chunks
+            .wrap
+            .map_or(IVec3::ZERO, |wrap| wrap.period.extend(0));
+        let in_box = |position: IVec3, min: IVec3, max: IVec3, period: IVec3| {
+            (0..3).all(|axis| wraps_into(position[axis], min[axis], max[axis], period[axis]))
+        };
+        let chunks: Vec<(IVec3, &mut Arc<Chunk>)> = self
+            .chunks
+            .chunks
+            .iter_mut()
+            .filter(|(coord, _)| in_box(**coord, min_chunk, max_chunk, period))
+            .map(|(coord, chunk)| (*coord, chunk))
+            .collect();
+
+        let replace_in_chunk = |coord: IVec3, chunk: &mut Arc<Chunk>| {
+            let mut changed = Vec::new();
+            for index in 0..=u8::MAX {
+                let position = TileCoord {
+                    index,
+                    chunk: coord,
+                }
+                .tile_position();
+                if !in_box(position, min, max, period * CHUNK_SIZE) {
+                    continue;
+                }
+                if let Some(&tile) = chunk.get_tile(index) {
+                    if predicate(&tile) {
+                        let new = replace(&tile);
+                        if new != Some(tile) {
+                            // Only copies a shared chunk once something in it actually changes.
+                            Arc::make_mut(chunk).set_tile(index, new);
+                            changed.push((index, tile, new));
+                        }
+                    }
+                }
+            }
+            (coord, changed)
+        };
+
+        let changed = match &self.compute_pool {
+            Some(pool) => pool.scope(|scope| {
+                let replace_in_chunk = &replace_in_chunk;
+                for (coord, chunk) in chunks {
+                    scope.spawn(async move { replace_in_chunk(coord, chunk) });
+                }
+            }),
+            None => chunks
+                .into_iter()
+                .map(|(coord, chunk)| replace_in_chunk(coord, chunk))
+                .collect(),
+        };
+
+        for (chunk, tiles) in changed {
+            if tiles.is_empty() {
+                continue;
+            }
+            self.changes
+                .send_batch(tiles.iter().map(|(index, old, new)| {
+                    let coord = TileCoord {
+                        index: *index,
+                        chunk,
+                    };
+                    TileChanged::new(coord, Some(*old), *new)
+                }));
+            self.updates
+                .set_updates(&chunk, tiles.into_iter().map(|(index, _, _)| index));
+        }
+    }
+
+    /// Visits every position within `radius` tiles of `center` on its layer, e.g. for explosions
+    /// or terraforming brushes. `falloff` turns the distance, divided by the radius, into the
+    /// intensity passed to `effect`, which returns the tile the position should hold.
+    /// Changes are written per chunk once every position was visited and only tiles that
+    /// actually changed cause updates. Returns how many tiles changed, writes rejected by the
+    /// map bounds are not counted.
+    pub fn apply_area_effect(
+        &mut self,
+        center: IVec3,
+        radius: f32,
+        falloff: impl Fn(f32) -> f32,
+        mut effect: impl FnMut(&TileCoord, Option<&Tile>, f32) -> Option<Tile>,
+    ) -> usize {
+        let radius = radius.max(0.0);
+        let reach = radius.floor() as i32;
+        let mut changed = Vec::new();
+        for y in -reach..=reach {
+            for x in -reach..=reach {
+                let distance = Vec2::new(x as f32, y as f32).length();
+                if distance > radius {
+                    continue;
+                }
+                let coord = TileCoord::from_tile_position(center + IVec3::new(x, y, 0));
+                let intensity = falloff(if radius > 0.0 { distance / radius } else { 0.0 });
+                let old = self.chunks.get_tile(&coord);
+                let new = effect(&coord, old, intensity);
+                if new.as_ref() != old {
+                    changed.push((coord, new));
+                }
+            }
+        }
+        self.write_tiles(changed)
     }
 
     /// Accessing a tile via this method does not cause updates.
     #[inline]
-    pub fn get_tile_mut(&mut self, coord: &TileCoord) -> Option<&mut Tile> {
+    pub fn get_tile_mut(&mut self, coord: impl IntoTileCoord) -> Option<&mut Tile> {
+        let coord = coord.into_tile_coord();
         if let Some(chunk) = self.chunks.get_chunk_mut(&coord.chunk) {
             return chunk.get_tile_mut(coord.index);
         }
@@ -241,24 +1681,4 @@ impl<'w, 's> TileMapWriter<'w, 's> {
     pub fn get_chunk_mut(&mut self, coord: &IVec3) -> Option<&mut Chunk> {
         self.chunks.get_chunk_mut(coord)
     }
-
-    /// Get mutable access to a tile from a shared reference.
-    /// # Safety
-    /// This function breaks basic borrowing rules, it should be used not at all or very carefully.
-    /// This is mainly included to make a particular implementation of autotiling possible.
-    #[inline]
-    pub unsafe fn get_tile_mut_unchecked(&self, coord: &TileCoord) -> Option<&mut Tile> {
-        self.get_tile(coord)
-            .map(|tile| unsafe { (tile as *const Tile as *mut Tile).as_mut().unwrap() })
-    }
-
-    /// Get mutable access to a tile from a shared reference.
-    /// # Safety
-    /// This function breaks basic borrowing rules, it should be used not at all or very carefully.
-    /// This is mainly included to make a particular implementation of autotiling possible.
-    #[inline]
-    pub unsafe fn get_chunk_mut_unchecked(&self, coord: &IVec3) -> Option<&mut Chunk> {
-        self.get_chunk(coord)
-            .map(|chunk| unsafe { (chunk as *const Chunk as *mut Chunk).as_mut().unwrap() })
-    }
 }
@@ -0,0 +1,115 @@
+use std::sync::{Arc, Mutex, MutexGuard};
+
+use bevy::math::IVec3;
+
+use crate::{TileCoord, CHUNK_SIZE};
+
+#[derive(Default)]
+struct LockedRegions {
+    regions: Vec<(u64, IVec3, IVec3)>,
+    next_id: u64,
+}
+
+/// Advisory locks on boxes of tiles, e.g. so a background generation or save task can claim
+/// the area it works on. Writes through [`crate::policy::GuardedTileMapWriter`] into a locked
+/// box are denied until the [`RegionGuard`] is dropped.
+///
+/// The crate's own writers hold their writes into locked boxes back until the box is unlocked:
+/// due [`crate::schedule::TileSchedule`] changes, confirmed construction, autotiling, chunk
+/// streaming, generation and loading, and the importers. Plain [`crate::TileMapWriter`] writes
+/// are not checked, ask [`crate::TileMapWriter::is_locked`] first.
+///
+/// Cloning gives another handle to the same locks, guards can be moved into async tasks.
+#[derive(Clone, Default)]
+pub struct TileLocks {
+    inner: Arc<Mutex<LockedRegions>>,
+}
+
+impl TileLocks {
+    fn regions(&self) -> MutexGuard<'_, LockedRegions> {
+        // The lock is never held while calling user code, so it can't be poisoned in practice.
+        self.inner
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    /// Locks the box from `min` to `max` (inclusive, in tiles), returning None if it overlaps
+    /// a box that is already locked.
+    pub fn lock_region(&self, min: IVec3, max: IVec3) -> Option<RegionGuard> {
+        let (min, max) = (min.min(max), min.max(max));
+        let mut regions = self.regions();
+        let overlaps = regions.regions.iter().any(|(_, locked_min, locked_max)| {
+            min.cmple(*locked_max).all() && max.cmpge(*locked_min).all()
+        });
+        if overlaps {
+            return None;
+        }
+        let id = regions.next_id;
+        regions.next_id += 1;
+        regions.regions.push((id, min, max));
+        Some(RegionGuard {
+            locks: self.clone(),
+            id,
+            min,
+            max,
+        })
+    }
+
+    pub fn is_locked(&self, coord: &TileCoord) -> bool {
+        let position = coord.tile_position();
+        self.regions()
+            .regions
+            .iter()
+            .any(|(_, min, max)| position.cmpge(*min).all() && position.cmple(*max).all())
+    }
+
+    /// Whether any tile of the box from `min` to `max` (inclusive, in tiles) is locked.
+    pub fn is_region_locked(&self, min: IVec3, max: IVec3) -> bool {
+        let (min, max) = (min.min(max), min.max(max));
+        self.regions()
+            .regions
+            .iter()
+            .any(|(_, locked_min, locked_max)| {
+                min.cmple(*locked_max).all() && max.cmpge(*locked_min).all()
+            })
+    }
+
+    /// Whether any tile of a chunk is locked.
+    pub fn is_chunk_locked(&self, chunk: &IVec3) -> bool {
+        let min = IVec3::new(chunk.x * CHUNK_SIZE, chunk.y * CHUNK_SIZE, chunk.z);
+        self.is_region_locked(min, min + IVec3::new(CHUNK_SIZE - 1, CHUNK_SIZE - 1, 0))
+    }
+
+    /// Number of boxes currently locked.
+    pub fn len(&self) -> usize {
+        self.regions().regions.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.regions().regions.is_empty()
+    }
+}
+
+/// Keeps a box locked until dropped, see [`TileLocks::lock_region`].
+pub struct RegionGuard {
+    locks: TileLocks,
+    id: u64,
+    min: IVec3,
+    max: IVec3,
+}
+
+impl RegionGuard {
+    /// The locked box as `(min, max)`, inclusive.
+    pub fn region(&self) -> (IVec3, IVec3) {
+        (self.min, self.max)
+    }
+}
+
+impl Drop for RegionGuard {
+    fn drop(&mut self) {
+        self.locks
+            .regions()
+            .regions
+            .retain(|(id, _, _)| *id != self.id);
+    }
+}
@@ -0,0 +1,109 @@
+use bevy::{
+    prelude::{Res, ResMut},
+    utils::{HashMap, HashSet},
+};
+
+use crate::{Tile, TileCoord, TileMap, TileMapUpdates};
+
+type MarkerRule = Box<dyn Fn(&Tile) -> bool + Send + Sync>;
+
+/// Named sets of tile coordinates, like `"player_spawn"`, so game code can look up special
+/// locations without scanning the map.
+///
+/// Coordinates end up in a set either by being tagged directly, e.g. from imported map objects,
+/// or by holding a tile that matches a rule. Rule matches are kept up to date from tile updates.
+#[derive(Default)]
+pub struct TileMarkers {
+    rules: Vec<(String, Vec<MarkerRule>)>,
+    matched: HashMap<String, HashSet<TileCoord>>,
+    tagged: HashMap<String, HashSet<TileCoord>>,
+}
+
+impl TileMarkers {
+    /// Marks every tile matching `predicate` with `name`, a name with several rules marks the
+    /// tiles matching any of them. Only tiles that change afterwards are checked, call
+    /// [`TileMarkers::rescan`] to pick up tiles already in the map.
+    pub fn add_rule(
+        &mut self,
+        name: impl Into<String>,
+        predicate: impl Fn(&Tile) -> bool + Send + Sync + 'static,
+    ) {
+        let name = name.into();
+        match self.rules.iter_mut().find(|(other, _)| *other == name) {
+            Some((_, rules)) => rules.push(Box::new(predicate)),
+            None => self.rules.push((name, vec![Box::new(predicate)])),
+        }
+    }
+
+    /// Tags a coordinate with `name` regardless of the tile there.
+    pub fn tag(&mut self, name: impl Into<String>, coord: TileCoord) {
+        self.tagged.entry(name.into()).or_default().insert(coord);
+    }
+
+    /// Removes a tag added with [`TileMarkers::tag`], returning whether it existed.
+    pub fn untag(&mut self, name: &str, coord: &TileCoord) -> bool {
+        self.tagged
+            .get_mut(name)
+            .is_some_and(|coords| coords.remove(coord))
+    }
+
+    pub fn is_marked(&self, name: &str, coord: &TileCoord) -> bool {
+        [&self.matched, &self.tagged]
+            .into_iter()
+            .any(|sets| sets.get(name).is_some_and(|coords| coords.contains(coord)))
+    }
+
+    /// Every coordinate marked with `name`, in no particular order.
+    pub fn markers(&self, name: &str) -> Vec<TileCoord> {
+        let mut coords: HashSet<TileCoord> = HashSet::default();
+        for sets in [&self.matched, &self.tagged] {
+            if let Some(set) = sets.get(name) {
+                coords.extend(set.iter().copied());
+            }
+        }
+        coords.into_iter().collect()
+    }
+
+    /// Re-evaluates the rules against every tile in the map.
+    pub fn rescan(&mut self, map: &TileMap) {
+        self.matched.clear();
+        for (chunk_coord, chunk) in map.chunks.iter() {
+            for index in 0..=u8::MAX {
+                let coord = TileCoord {
+                    index,
+                    chunk: *chunk_coord,
+                };
+                self.refresh(&coord, chunk.get_tile(index));
+            }
+        }
+    }
+
+    fn refresh(&mut self, coord: &TileCoord, tile: Option<&Tile>) {
+        for (name, rules) in self.rules.iter() {
+            if tile.is_some_and(|tile| rules.iter().any(|rule| rule(tile))) {
+                self.matched.entry(name.clone()).or_default().insert(*coord);
+            } else if let Some(coords) = self.matched.get_mut(name) {
+                coords.remove(coord);
+            }
+        }
+    }
+}
+
+pub(crate) fn update_tile_markers(
+    mut markers: ResMut<TileMarkers>,
+    updates: Res<TileMapUpdates>,
+    map: Res<TileMap>,
+) {
+    if markers.rules.is_empty() {
+        return;
+    }
+    for (chunk, indices) in updates.chunks.iter() {
+        for index in indices.iter() {
+            let coord = TileCoord {
+                index: *index,
+                chunk: *chunk,
+            };
+            markers.refresh(&coord, map.get_tile(&coord));
+        }
+    }
+}
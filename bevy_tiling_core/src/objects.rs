@@ -0,0 +1,134 @@
+use bevy::{
+    ecs::system::EntityCommands,
+    math::{IVec3, Vec2},
+    prelude::{Commands, Component, Entity, Transform, TransformBundle},
+    utils::HashMap,
+};
+
+use crate::{grid::TileGrid, TileCoord};
+
+/// A custom property of a [`MapObject`].
+#[derive(Clone, PartialEq, Debug)]
+pub enum PropertyValue {
+    Bool(bool),
+    Int(i64),
+    Float(f64),
+    String(String),
+}
+
+impl PropertyValue {
+    pub fn as_bool(&self) -> Option<bool> {
+        match self {
+            PropertyValue::Bool(value) => Some(*value),
+            _ => None,
+        }
+    }
+
+    /// Floats are not converted.
+    pub fn as_int(&self) -> Option<i64> {
+        match self {
+            PropertyValue::Int(value) => Some(*value),
+            _ => None,
+        }
+    }
+
+    /// Ints are converted too, since editors often store whole numbers in float fields as ints.
+    pub fn as_float(&self) -> Option<f64> {
+        match self {
+            PropertyValue::Float(value) => Some(*value),
+            PropertyValue::Int(value) => Some(*value as f64),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            PropertyValue::String(value) => Some(value),
+            _ => None,
+        }
+    }
+}
+
+/// An object placed in a map editor, like a spawn point or a chest, converted from an object
+/// layer by an importer. Spawned entities keep it as a component, so the properties stay
+/// readable by game systems.
+#[derive(Component, Clone, PartialEq, Debug, Default)]
+pub struct MapObject {
+    pub name: String,
+    /// The type [`ObjectSpawners`] picks the spawner by.
+    pub object_type: String,
+    /// Position of the object's corner closest to negative infinity, in tiles.
+    pub position: Vec2,
+    pub layer: i32,
+    /// Size in tiles, zero for point objects.
+    pub size: Vec2,
+    pub properties: HashMap<String, PropertyValue>,
+}
+
+impl MapObject {
+    /// The tile containing the object's position.
+    pub fn tile(&self) -> TileCoord {
+        let tile = self.position.floor();
+        TileCoord::from_tile_position(IVec3::new(tile.x as i32, tile.y as i32, self.layer))
+    }
+
+    pub fn property(&self, name: &str) -> Option<&PropertyValue> {
+        self.properties.get(name)
+    }
+}
+
+/// Turns [`MapObject`]s of one type into game entities, register one per type in
+/// [`ObjectSpawners`]. The entity already holds the object and a transform at its position.
+pub trait ObjectSpawner: Send + Sync + 'static {
+    fn spawn(&self, entity: &mut EntityCommands, object: &MapObject);
+}
+
+impl<F> ObjectSpawner for F
+where
+    F: Fn(&mut EntityCommands, &MapObject) + Send + Sync + 'static,
+{
+    fn spawn(&self, entity: &mut EntityCommands, object: &MapObject) {
+        self(entity, object)
+    }
+}
+
+/// The [`ObjectSpawner`]s of each object type, used by the map importers.
+#[derive(Default)]
+pub struct ObjectSpawners {
+    spawners: HashMap<String, Box<dyn ObjectSpawner>>,
+}
+
+impl ObjectSpawners {
+    /// Registers the spawner of an object type, replacing the previous one.
+    pub fn register(
+        &mut self,
+        object_type: impl Into<String>,
+        spawner: impl ObjectSpawner,
+    ) -> &mut Self {
+        self.spawners.insert(object_type.into(), Box::new(spawner));
+        self
+    }
+
+    pub fn is_registered(&self, object_type: &str) -> bool {
+        self.spawners.contains_key(object_type)
+    }
+
+    /// Spawns an entity for the object, placed by the `grid`, and hands it to the spawner of
+    /// the object's type. Objects without a spawner are skipped and return None.
+    pub fn spawn(
+        &self,
+        commands: &mut Commands,
+        grid: &TileGrid,
+        object: &MapObject,
+    ) -> Option<Entity> {
+        let spawner = self.spawners.get(&object.object_type)?;
+        let translation =
+            (object.position * grid.tile_size).extend(object.layer as f32 * grid.layer_height);
+        let mut entity = commands.spawn_bundle(TransformBundle::from_transform(
+            Transform::from_translation(translation),
+        ));
+        entity.insert(object.clone());
+        spawner.spawn(&mut entity, object);
+        Some(entity.id())
+    }
+}
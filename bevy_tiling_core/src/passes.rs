@@ -0,0 +1,411 @@
+//! Composable worldgen steps like smoothing, decoration scattering or ore veins, see [`MapPass`].
+//!
+//! Passes are collected in [`MapPasses`], which runs them in order over a box of a map, e.g.
+//! after loading a level, or over every chunk a [`PassGenerator`] generates. Every pass gets
+//! its own seed and [`PassContext::rng`] gives each chunk its own stream, so running the same
+//! passes over the same box gives the same result. Passes that only look at the chunk they
+//! write, like [`Scatter`], also give every chunk the same result whichever box it is processed
+//! in. Passes looking past the chunk don't: [`Smooth`] reads the tiles around the box and
+//! [`OreVeins`] only continues veins into chunks inside the box.
+
+use std::sync::Arc;
+
+use bevy::{
+    math::IVec3,
+    utils::{HashMap, HashSet},
+};
+
+use crate::{
+    generator::ChunkGenerator,
+    rng::ChunkRng,
+    scatter::{scatter_chunk, ScatterSettings},
+    Chunk, DefaultMap, MapLabel, Tile, TileCoord, TileMap, TileMapWriter, CHUNK_SIZE,
+};
+
+type TileFilter = Box<dyn Fn(&Tile) -> bool + Send + Sync>;
+
+/// A reusable worldgen step run over a box of tiles, add it to [`MapPasses`].
+pub trait MapPass<L = DefaultMap>: Send + Sync + 'static {
+    fn run(&self, context: &mut PassContext<L>);
+}
+
+impl<L, F> MapPass<L> for F
+where
+    F: Fn(&mut PassContext<L>) + Send + Sync + 'static,
+{
+    fn run(&self, context: &mut PassContext<L>) {
+        self(context)
+    }
+}
+
+/// The map and the box a [`MapPass`] runs over. Tiles can be read anywhere, but only written
+/// inside the box.
+pub struct PassContext<'a, L = DefaultMap> {
+    map: &'a mut TileMap<L>,
+    min: IVec3,
+    max: IVec3,
+    seed: u64,
+    /// The tiles before their first write, when running through [`MapPasses::apply`].
+    written: Option<&'a mut HashMap<TileCoord, Option<Tile>>>,
+}
+
+impl<'a, L> PassContext<'a, L> {
+    /// The box the pass runs over as `(min, max)`, inclusive and in tiles.
+    pub fn region(&self) -> (IVec3, IVec3) {
+        (self.min, self.max)
+    }
+
+    pub fn contains(&self, position: IVec3) -> bool {
+        position.cmpge(self.min).all() && position.cmple(self.max).all()
+    }
+
+    /// Seed of the running pass, derived from the [`MapPasses`] seed and the pass's place in
+    /// the order.
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    /// A random number stream for a chunk, the same every time for the same pass and chunk.
+    pub fn rng(&self, chunk: IVec3) -> PassRng {
+        PassRng(ChunkRng::new(self.seed, chunk))
+    }
+
+    pub fn map(&self) -> &TileMap<L> {
+        self.map
+    }
+
+    pub fn get_tile(&self, position: IVec3) -> Option<&Tile> {
+        self.map.get_tile(&TileCoord::from_tile_position(position))
+    }
+
+    /// Sets or removes a tile, returning false without writing if `position` is outside the box.
+    pub fn set_tile(&mut self, position: IVec3, tile: Option<Tile>) -> bool {
+        if !self.contains(position) {
+            return false;
+        }
+        let coord = TileCoord::from_tile_position(position);
+        if let Some(written) = &mut self.written {
+            if let Some(coord) = self.map.resolve_coord(&coord) {
+                let old = self.map.get_tile(&coord).copied();
+                written.entry(coord).or_insert(old);
+            }
+        }
+        self.map.set_tile(&coord, tile);
+        true
+    }
+
+    /// Every position of the box, layer by layer and row by row.
+    pub fn positions(&self) -> impl Iterator<Item = IVec3> {
+        let (min, max) = (self.min, self.max);
+        (min.z..=max.z).flat_map(move |z| {
+            (min.y..=max.y).flat_map(move |y| (min.x..=max.x).map(move |x| IVec3::new(x, y, z)))
+        })
+    }
+
+    /// Coordinates of every chunk the box touches.
+    pub fn chunks(&self) -> impl Iterator<Item = IVec3> {
+        let min = TileCoord::from_tile_position(self.min).chunk;
+        let max = TileCoord::from_tile_position(self.max).chunk;
+        (min.z..=max.z).flat_map(move |z| {
+            (min.y..=max.y).flat_map(move |y| (min.x..=max.x).map(move |x| IVec3::new(x, y, z)))
+        })
+    }
+}
+
+/// Deterministic random numbers for passes, see [`PassContext::rng`].
+#[derive(Clone, Debug)]
+pub struct PassRng(ChunkRng);
+
+impl PassRng {
+    pub fn next_u64(&mut self) -> u64 {
+        self.0.next_u64()
+    }
+
+    /// A value in `0..bound`, always 0 if `bound` is 0.
+    pub fn below(&mut self, bound: u64) -> u64 {
+        if bound == 0 {
+            return 0;
+        }
+        self.0.below(bound)
+    }
+
+    /// True with the given probability.
+    pub fn chance(&mut self, probability: f32) -> bool {
+        ((self.next_u64() >> 40) as f32) < probability * (1u64 << 24) as f32
+    }
+}
+
+/// An ordered list of [`MapPass`]es sharing a seed.
+pub struct MapPasses<L = DefaultMap> {
+    seed: u64,
+    passes: Vec<(i32, Box<dyn MapPass<L>>)>,
+}
+
+impl<L: 'static> MapPasses<L> {
+    pub fn new(seed: u64) -> Self {
+        Self {
+            seed,
+            passes: Vec::new(),
+        }
+    }
+
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    /// Adds a pass. Passes run by ascending `order`, passes with the same order in the order
+    /// they were added.
+    pub fn add(&mut self, order: i32, pass: impl MapPass<L>) -> &mut Self {
+        let index = self.passes.partition_point(|(other, _)| *other <= order);
+        self.passes.insert(index, (order, Box::new(pass)));
+        self
+    }
+
+    pub fn len(&self) -> usize {
+        self.passes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.passes.is_empty()
+    }
+
+    /// Runs every pass over the box from `min` to `max` (inclusive, in tiles), e.g. right after
+    /// loading a level. The writes don't cause updates, see [`MapPasses::apply`].
+    pub fn run(&self, map: &mut TileMap<L>, min: IVec3, max: IVec3) {
+        self.run_tracked(map, min, max, None);
+    }
+
+    fn run_tracked(
+        &self,
+        map: &mut TileMap<L>,
+        min: IVec3,
+        max: IVec3,
+        mut written: Option<&mut HashMap<TileCoord, Option<Tile>>>,
+    ) {
+        let (min, max) = (min.min(max), min.max(max));
+        for (position, (_, pass)) in self.passes.iter().enumerate() {
+            let seed = self.seed ^ (position as u64 + 1).wrapping_mul(0x9E37_79B9_7F4A_7C15);
+            pass.run(&mut PassContext {
+                map,
+                min,
+                max,
+                seed,
+                written: written.as_deref_mut(),
+            });
+        }
+    }
+}
+
+impl<L: MapLabel> MapPasses<L> {
+    /// Runs every pass over the box like [`MapPasses::run`], on a map in use.
+    /// This method causes updates for the tiles that changed.
+    pub fn apply(&self, writer: &mut TileMapWriter<L>, min: IVec3, max: IVec3) {
+        let mut written: HashMap<TileCoord, Option<Tile>> = HashMap::default();
+        self.run_tracked(&mut writer.chunks, min, max, Some(&mut written));
+
+        let mut by_chunk: HashMap<IVec3, HashMap<u8, Option<Tile>>> = HashMap::default();
+        for (coord, old) in written {
+            if writer.chunks.get_tile(&coord).copied() != old {
+                by_chunk
+                    .entry(coord.chunk)
+                    .or_default()
+                    .insert(coord.index, old);
+            }
+        }
+        for (chunk, old) in by_chunk {
+            let indices: Vec<u8> = old.keys().copied().collect();
+            writer.send_changes(&chunk, &indices, |coord| old[&coord.index]);
+            writer.updates.set_updates(&chunk, indices);
+        }
+    }
+}
+
+/// A [`ChunkGenerator`] running [`MapPasses`] over every chunk another generator produces.
+/// The passes only see the chunk being generated, positions outside of it read as empty.
+pub struct PassGenerator<G, L = DefaultMap> {
+    generator: G,
+    passes: MapPasses<L>,
+}
+
+impl<G, L> PassGenerator<G, L> {
+    pub fn new(generator: G, passes: MapPasses<L>) -> Self {
+        Self { generator, passes }
+    }
+}
+
+impl<G: ChunkGenerator, L: 'static> ChunkGenerator for PassGenerator<G, L> {
+    fn generate(&self, chunk_coord: IVec3) -> Chunk {
+        let mut map = TileMap::<L>::empty();
+        map.insert_shared_chunk(chunk_coord, Arc::new(self.generator.generate(chunk_coord)));
+        let min = IVec3::new(
+            chunk_coord.x * CHUNK_SIZE,
+            chunk_coord.y * CHUNK_SIZE,
+            chunk_coord.z,
+        );
+        self.passes.run(
+            &mut map,
+            min,
+            min + IVec3::new(CHUNK_SIZE - 1, CHUNK_SIZE - 1, 0),
+        );
+        map.remove_chunk(&chunk_coord)
+            .map(|chunk| Arc::try_unwrap(chunk).unwrap_or_else(|chunk| (*chunk).clone()))
+            .unwrap_or_default()
+    }
+}
+
+const NEIGHBOURS: [(i32, i32); 8] = [
+    (-1, -1),
+    (0, -1),
+    (1, -1),
+    (-1, 0),
+    (1, 0),
+    (-1, 1),
+    (0, 1),
+    (1, 1),
+];
+
+/// Cellular smoothing: a tile takes the value at least `threshold` of its eight neighbours on
+/// the same layer share, where an empty position counts as a value too. Each of the
+/// `iterations` reads the result of the previous one. A threshold above four avoids ties.
+/// Tiles around the box are read but not smoothed, so the edges of the box follow them.
+pub struct Smooth {
+    pub iterations: usize,
+    pub threshold: usize,
+}
+
+impl Default for Smooth {
+    fn default() -> Self {
+        Self {
+            iterations: 1,
+            threshold: 5,
+        }
+    }
+}
+
+impl<L: 'static> MapPass<L> for Smooth {
+    fn run(&self, context: &mut PassContext<L>) {
+        for _ in 0..self.iterations {
+            let changes: Vec<(IVec3, Option<Tile>)> = context
+                .positions()
+                .filter_map(|position| {
+                    let mut counts: Vec<(Option<Tile>, usize)> = Vec::with_capacity(8);
+                    for (x, y) in NEIGHBOURS {
+                        let tile = context.get_tile(position + IVec3::new(x, y, 0)).copied();
+                        match counts.iter_mut().find(|(value, _)| *value == tile) {
+                            Some((_, count)) => *count += 1,
+                            None => counts.push((tile, 1)),
+                        }
+                    }
+                    let (value, count) = counts.into_iter().max_by_key(|(_, count)| *count)?;
+                    (count >= self.threshold && value.as_ref() != context.get_tile(position))
+                        .then_some((position, value))
+                })
+                .collect();
+            if changes.is_empty() {
+                break;
+            }
+            for (position, tile) in changes {
+                context.set_tile(position, tile);
+            }
+        }
+    }
+}
+
+/// Replaces tiles matching `on` with `tile`, spaced like [`scatter_chunk`], e.g. flowers on
+/// grass. The scatter seed is mixed with the pass seed.
+pub struct Scatter {
+    pub tile: Tile,
+    pub on: TileFilter,
+    pub settings: ScatterSettings,
+}
+
+impl Scatter {
+    pub fn new(tile: Tile, on: impl Fn(&Tile) -> bool + Send + Sync + 'static) -> Self {
+        Self {
+            tile,
+            on: Box::new(on),
+            settings: ScatterSettings::default(),
+        }
+    }
+
+    pub fn with_settings(mut self, settings: ScatterSettings) -> Self {
+        self.settings = settings;
+        self
+    }
+}
+
+impl<L: 'static> MapPass<L> for Scatter {
+    fn run(&self, context: &mut PassContext<L>) {
+        let settings = ScatterSettings {
+            seed: self.settings.seed ^ context.seed(),
+            ..self.settings
+        };
+        let picked: Vec<IVec3> = context
+            .chunks()
+            .filter_map(|chunk| {
+                context
+                    .map()
+                    .get_chunk(&chunk)
+                    .map(|data| scatter_chunk(data, chunk, &settings, &self.on))
+            })
+            .flatten()
+            .map(|coord| coord.tile_position())
+            .collect();
+        for position in picked {
+            context.set_tile(position, Some(self.tile));
+        }
+    }
+}
+
+/// Grows `veins_per_chunk` veins from random positions of every chunk, each a random walk of
+/// `length` steps on the layer that turns the tiles matching `host` it crosses into `ore`.
+/// Veins may continue into neighbouring chunks inside the box, a vein leaving the box is cut
+/// off, so a chunk's ore depends on the box it is processed in.
+pub struct OreVeins {
+    pub ore: Tile,
+    pub host: TileFilter,
+    pub veins_per_chunk: u32,
+    pub length: u32,
+}
+
+impl OreVeins {
+    pub fn new(ore: Tile, host: impl Fn(&Tile) -> bool + Send + Sync + 'static) -> Self {
+        Self {
+            ore,
+            host: Box::new(host),
+            veins_per_chunk: 1,
+            length: 8,
+        }
+    }
+}
+
+impl<L: 'static> MapPass<L> for OreVeins {
+    fn run(&self, context: &mut PassContext<L>) {
+        const STEPS: [(i32, i32); 4] = [(1, 0), (-1, 0), (0, 1), (0, -1)];
+        let chunks: Vec<IVec3> = context.chunks().collect();
+        for chunk in chunks {
+            let mut rng = context.rng(chunk);
+            let origin = IVec3::new(chunk.x * CHUNK_SIZE, chunk.y * CHUNK_SIZE, chunk.z);
+            let mut visited: HashSet<IVec3> = HashSet::default();
+            for _ in 0..self.veins_per_chunk {
+                let mut position = origin
+                    + IVec3::new(
+                        rng.below(CHUNK_SIZE as u64) as i32,
+                        rng.below(CHUNK_SIZE as u64) as i32,
+                        0,
+                    );
+                for _ in 0..self.length {
+                    if visited.insert(position)
+                        && context
+                            .get_tile(position)
+                            .is_some_and(|tile| (self.host)(tile))
+                    {
+                        context.set_tile(position, Some(self.ore));
+                    }
+                    let (x, y) = STEPS[rng.below(4) as usize];
+                    position += IVec3::new(x, y, 0);
+                }
+            }
+        }
+    }
+}
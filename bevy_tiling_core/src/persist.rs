@@ -0,0 +1,997 @@
+//! Binary save files storing every chunk on its own, so single chunks can be loaded and saved
+//! without reading or writing the whole map.
+//!
+//! A file starts with a header holding the magic bytes `BTCF`, the format version, and the
+//! position and length of the chunk index. The index lists the coordinate, position and length
+//! of every chunk. Saving never overwrites anything the header points at: chunks and a new
+//! index go into space freed by earlier saves or past the end of the file, and the header is
+//! pointed at the new index last, so a save that fails halfway leaves the previous contents.
+//! Space freed by replaced chunks is reused when new chunks fit, [`ChunkFile::compact`]
+//! rewrites the file without any free space.
+
+use std::{
+    collections::BTreeMap,
+    ffi::OsString,
+    fs::{self, File},
+    future::Future,
+    io::{self, Read, Seek, SeekFrom, Write},
+    marker::PhantomData,
+    path::{Path, PathBuf},
+    pin::Pin,
+    sync::{Arc, Mutex, OnceLock},
+    task::{Context, Poll},
+};
+
+use bevy::{
+    math::IVec3,
+    prelude::{EventReader, EventWriter, ParallelSystemDescriptorCoercion, Plugin, Res, ResMut},
+    tasks::{IoTaskPool, Task},
+    utils::{HashMap, HashSet},
+};
+
+use crate::{
+    generator::noop_waker,
+    rle::RleChunk,
+    streaming::{ChunkLoadRequest, ChunkStreaming, StreamingSystem},
+    Chunk, ChunkStorage, DefaultMap, DenseTiles, MapLabel, Tile, TileCoord, TileMap, TileMapWriter,
+    TilingCoreStage,
+};
+
+const MAGIC: [u8; 4] = *b"BTCF";
+/// Version of the file format written by [`ChunkFile`].
+pub const FORMAT_VERSION: u16 = 1;
+const HEADER_LEN: u64 = 4 + 2 + 8 + 4;
+const INDEX_ENTRY_LEN: usize = 4 * 3 + 8 + 4;
+
+const EMPTY: u8 = 0;
+const UNIFORM: u8 = 1;
+const SPARSE: u8 = 2;
+const RUNS: u8 = 3;
+
+/// A save file of chunks, see the [module docs](self) for the layout.
+pub struct ChunkFile {
+    file: File,
+    path: PathBuf,
+    index: HashMap<IVec3, (u64, u32)>,
+    /// Position and length of the index the header points at.
+    index_extent: (u64, u64),
+    /// Unused byte ranges before `len`, by position.
+    free: BTreeMap<u64, u64>,
+    len: u64,
+}
+
+impl ChunkFile {
+    /// Creates an empty file, replacing any file at `path`.
+    pub fn create(path: impl AsRef<Path>) -> io::Result<Self> {
+        let file = File::options()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(true)
+            .open(path.as_ref())?;
+        let mut chunk_file = Self {
+            file,
+            path: path.as_ref().to_path_buf(),
+            index: HashMap::default(),
+            index_extent: (HEADER_LEN, 0),
+            free: BTreeMap::new(),
+            len: HEADER_LEN,
+        };
+        chunk_file.write_header(HEADER_LEN, 0)?;
+        chunk_file.file.sync_data()?;
+        Ok(chunk_file)
+    }
+
+    /// Opens an existing file, reading only its index.
+    pub fn open(path: impl AsRef<Path>) -> io::Result<Self> {
+        let mut file = File::options().read(true).write(true).open(path.as_ref())?;
+        let len = file.metadata()?.len();
+        if len < HEADER_LEN {
+            return Err(invalid_data("not a chunk file"));
+        }
+        let mut header = [0; HEADER_LEN as usize];
+        file.read_exact(&mut header)?;
+        if header[0..4] != MAGIC {
+            return Err(invalid_data("not a chunk file"));
+        }
+        let version = u16::from_le_bytes([header[4], header[5]]);
+        if version > FORMAT_VERSION {
+            return Err(invalid_data(format!(
+                "chunk file version {} is newer than the supported version {}",
+                version, FORMAT_VERSION
+            )));
+        }
+        let index_offset = u64::from_le_bytes(header[6..14].try_into().unwrap());
+        let index_len = u32::from_le_bytes(header[14..18].try_into().unwrap()) as u64;
+        let index_bytes = index_len * INDEX_ENTRY_LEN as u64;
+        if index_offset < HEADER_LEN
+            || index_offset
+                .checked_add(index_bytes)
+                .map_or(true, |end| end > len)
+        {
+            return Err(invalid_data("chunk index lies outside the file"));
+        }
+
+        let mut entries = vec![0; index_bytes as usize];
+        file.seek(SeekFrom::Start(index_offset))?;
+        file.read_exact(&mut entries)?;
+        let mut index = HashMap::default();
+        for entry in entries.chunks_exact(INDEX_ENTRY_LEN) {
+            let int = |at: usize| i32::from_le_bytes(entry[at..at + 4].try_into().unwrap());
+            let coord = IVec3::new(int(0), int(4), int(8));
+            let offset = u64::from_le_bytes(entry[12..20].try_into().unwrap());
+            let chunk_len = u32::from_le_bytes(entry[20..24].try_into().unwrap());
+            if offset < HEADER_LEN
+                || offset
+                    .checked_add(chunk_len as u64)
+                    .map_or(true, |end| end > len)
+            {
+                return Err(invalid_data("chunk lies outside the file"));
+            }
+            index.insert(coord, (offset, chunk_len));
+        }
+
+        // Whatever neither the index nor a chunk covers is free.
+        let mut used: Vec<(u64, u64)> = index
+            .values()
+            .map(|(offset, len)| (*offset, *len as u64))
+            .chain([(index_offset, index_bytes)])
+            .collect();
+        used.sort_unstable();
+        let mut free = BTreeMap::new();
+        let mut end = HEADER_LEN;
+        for (offset, used_len) in used {
+            if offset > end {
+                free.insert(end, offset - end);
+            }
+            end = end.max(offset + used_len);
+        }
+        if len > end {
+            free.insert(end, len - end);
+        }
+        Ok(Self {
+            file,
+            path: path.as_ref().to_path_buf(),
+            index,
+            index_extent: (index_offset, index_bytes),
+            free,
+            len,
+        })
+    }
+
+    pub fn contains(&self, coord: &IVec3) -> bool {
+        self.index.contains_key(coord)
+    }
+
+    /// Every stored chunk coordinate, in no particular order.
+    pub fn chunks(&self) -> impl Iterator<Item = &IVec3> {
+        self.index.keys()
+    }
+
+    pub fn len(&self) -> usize {
+        self.index.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.index.is_empty()
+    }
+
+    /// Bytes of the file no chunk or index uses, which [`ChunkFile::compact`] would reclaim.
+    pub fn free_bytes(&self) -> u64 {
+        self.free.values().sum()
+    }
+
+    /// Reads a single chunk, None if the file doesn't store it.
+    pub fn load_chunk(&mut self, coord: &IVec3) -> io::Result<Option<Chunk>> {
+        match self.load_bytes(coord)? {
+            Some(bytes) => decode_chunk(&bytes).map(Some),
+            None => Ok(None),
+        }
+    }
+
+    fn load_bytes(&mut self, coord: &IVec3) -> io::Result<Option<Vec<u8>>> {
+        let (offset, len) = match self.index.get(coord) {
+            Some(entry) => *entry,
+            None => return Ok(None),
+        };
+        let mut bytes = vec![0; len as usize];
+        self.file.seek(SeekFrom::Start(offset))?;
+        self.file.read_exact(&mut bytes)?;
+        Ok(Some(bytes))
+    }
+
+    pub fn save_chunk(&mut self, coord: IVec3, chunk: &Chunk) -> io::Result<()> {
+        self.save_chunks([(coord, chunk)]).map(|_| ())
+    }
+
+    /// Writes several chunks, updating the index once. Returns how many were written.
+    /// The file keeps its previous contents if this fails.
+    pub fn save_chunks<'a>(
+        &mut self,
+        chunks: impl IntoIterator<Item = (IVec3, &'a Chunk)>,
+    ) -> io::Result<usize> {
+        let encoded: Vec<(IVec3, Vec<u8>)> = chunks
+            .into_iter()
+            .map(|(coord, chunk)| {
+                let mut bytes = Vec::new();
+                encode_chunk(chunk, &mut bytes);
+                (coord, bytes)
+            })
+            .collect();
+        let saved = encoded.len();
+        self.write_encoded(encoded)?;
+        Ok(saved)
+    }
+
+    /// Writes every chunk of the map between the chunk coordinates `min` and `max`, inclusive.
+    /// Returns how many were written.
+    pub fn save_region<L>(
+        &mut self,
+        map: &TileMap<L>,
+        min: IVec3,
+        max: IVec3,
+    ) -> io::Result<usize> {
+        self.save_chunks(region_chunks(map, min, max))
+    }
+
+    /// Rewrites the file with its chunks back to back, dropping the space of replaced chunks.
+    /// The new file is written next to this one and renamed over it once complete, holding
+    /// every stored chunk in memory meanwhile. Returns how many bytes the file shrank by.
+    pub fn compact(&mut self) -> io::Result<u64> {
+        let mut coords: Vec<IVec3> = self.index.keys().copied().collect();
+        coords.sort_by_key(|coord| (coord.z, coord.y, coord.x));
+        let mut encoded = Vec::with_capacity(coords.len());
+        for coord in coords {
+            if let Some(bytes) = self.load_bytes(&coord)? {
+                encoded.push((coord, bytes));
+            }
+        }
+        let mut temporary = OsString::from(self.path.as_os_str());
+        temporary.push(".compact");
+        let temporary = PathBuf::from(temporary);
+        let mut compacted = Self::create(&temporary)?;
+        compacted.write_encoded(encoded)?;
+        fs::rename(&temporary, &self.path)?;
+        compacted.path = self.path.clone();
+        let reclaimed = self.len.saturating_sub(compacted.len);
+        *self = compacted;
+        Ok(reclaimed)
+    }
+
+    /// Writes encoded chunks and a new index into unused space, then points the header at
+    /// the new index. The in-memory state only changes once the header is written.
+    fn write_encoded(&mut self, chunks: Vec<(IVec3, Vec<u8>)>) -> io::Result<()> {
+        let mut free = self.free.clone();
+        let mut len = self.len;
+        let mut index = self.index.clone();
+        let mut freed = vec![self.index_extent];
+        for (coord, bytes) in chunks {
+            let offset = allocate(&mut free, &mut len, bytes.len() as u64);
+            self.file.seek(SeekFrom::Start(offset))?;
+            self.file.write_all(&bytes)?;
+            if let Some((old, old_len)) = index.insert(coord, (offset, bytes.len() as u32)) {
+                freed.push((old, old_len as u64));
+            }
+        }
+
+        let mut bytes = Vec::with_capacity(index.len() * INDEX_ENTRY_LEN);
+        for (coord, (offset, len)) in index.iter() {
+            for value in coord.to_array() {
+                bytes.extend_from_slice(&value.to_le_bytes());
+            }
+            bytes.extend_from_slice(&offset.to_le_bytes());
+            bytes.extend_from_slice(&len.to_le_bytes());
+        }
+        let index_offset = allocate(&mut free, &mut len, bytes.len() as u64);
+        self.file.seek(SeekFrom::Start(index_offset))?;
+        self.file.write_all(&bytes)?;
+        // Everything the new header points at must be on disk before the header is.
+        self.file.sync_data()?;
+        self.write_header(index_offset, index.len() as u32)?;
+        self.file.sync_data()?;
+
+        for (offset, freed_len) in freed {
+            release(&mut free, offset, freed_len);
+        }
+        self.index = index;
+        self.index_extent = (index_offset, bytes.len() as u64);
+        self.free = free;
+        self.len = len;
+        Ok(())
+    }
+
+    fn write_header(&mut self, index_offset: u64, index_len: u32) -> io::Result<()> {
+        let mut header = Vec::with_capacity(HEADER_LEN as usize);
+        header.extend_from_slice(&MAGIC);
+        header.extend_from_slice(&FORMAT_VERSION.to_le_bytes());
+        header.extend_from_slice(&index_offset.to_le_bytes());
+        header.extend_from_slice(&index_len.to_le_bytes());
+        self.file.seek(SeekFrom::Start(0))?;
+        self.file.write_all(&header)?;
+        self.file.flush()
+    }
+}
+
+/// Where a [`ChunkStore`] keeps its chunks, a [`ChunkFile`] unless the store is created with
+/// [`ChunkStore::with_backend`]. Backends are used from background IO tasks, one at a time.
+pub trait ChunkBackend: Send + 'static {
+    /// Every stored chunk coordinate, in no particular order.
+    fn chunks(&self) -> Vec<IVec3>;
+
+    /// Reads a single chunk, None if the backend doesn't store it.
+    fn load_chunk(&mut self, coord: &IVec3) -> io::Result<Option<Chunk>>;
+
+    /// Writes several chunks and returns how many were written. If this fails, every chunk
+    /// still reads back as either its previous or its new data.
+    fn save_chunks(&mut self, chunks: &[(IVec3, &Chunk)]) -> io::Result<usize>;
+
+    /// Reclaims the space of replaced chunks and returns how many bytes were freed, nothing
+    /// by default.
+    fn compact(&mut self) -> io::Result<u64> {
+        Ok(0)
+    }
+}
+
+impl ChunkBackend for ChunkFile {
+    fn chunks(&self) -> Vec<IVec3> {
+        ChunkFile::chunks(self).copied().collect()
+    }
+
+    fn load_chunk(&mut self, coord: &IVec3) -> io::Result<Option<Chunk>> {
+        ChunkFile::load_chunk(self, coord)
+    }
+
+    fn save_chunks(&mut self, chunks: &[(IVec3, &Chunk)]) -> io::Result<usize> {
+        ChunkFile::save_chunks(self, chunks.iter().copied())
+    }
+
+    fn compact(&mut self) -> io::Result<u64> {
+        ChunkFile::compact(self)
+    }
+}
+
+fn region_chunks<L>(map: &TileMap<L>, min: IVec3, max: IVec3) -> Vec<(IVec3, &Chunk)> {
+    let (min, max) = (min.min(max), min.max(max));
+    let mut chunks: Vec<(IVec3, &Chunk)> = map
+        .chunks
+        .iter()
+        .filter(|(coord, _)| coord.cmpge(min).all() && coord.cmple(max).all())
+        .map(|(coord, chunk)| (*coord, chunk.as_ref()))
+        .collect();
+    chunks.sort_by_key(|(coord, _)| (coord.z, coord.y, coord.x));
+    chunks
+}
+
+/// Takes `len` bytes from the smallest free range they fit in, or from the end of the file.
+fn allocate(free: &mut BTreeMap<u64, u64>, end: &mut u64, len: u64) -> u64 {
+    let slot = free
+        .iter()
+        .filter(|(_, free_len)| **free_len >= len)
+        .min_by_key(|(_, free_len)| **free_len)
+        .map(|(offset, free_len)| (*offset, *free_len));
+    match slot {
+        Some((offset, free_len)) if len > 0 => {
+            free.remove(&offset);
+            if free_len > len {
+                free.insert(offset + len, free_len - len);
+            }
+            offset
+        }
+        _ => {
+            let offset = *end;
+            *end += len;
+            offset
+        }
+    }
+}
+
+/// Marks a range as free, merging it with the free ranges it touches.
+fn release(free: &mut BTreeMap<u64, u64>, offset: u64, len: u64) {
+    if len == 0 {
+        return;
+    }
+    let (mut offset, mut len) = (offset, len);
+    if let Some((before, before_len)) = free.range(..offset).next_back() {
+        if before + before_len == offset {
+            (offset, len) = (*before, before_len + len);
+            free.remove(&offset);
+        }
+    }
+    if let Some(after_len) = free.remove(&(offset + len)) {
+        len += after_len;
+    }
+    free.insert(offset, len);
+}
+
+fn invalid_data(message: impl Into<String>) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message.into())
+}
+
+fn encode_tile(tile: &Tile, bytes: &mut Vec<u8>) {
+    bytes.extend_from_slice(&tile.sheet.to_le_bytes());
+    bytes.extend_from_slice(&tile.index.to_le_bytes());
+    bytes.push(tile.flags);
+}
+
+fn decode_tile(bytes: &[u8]) -> Tile {
+    Tile {
+        sheet: u16::from_le_bytes([bytes[0], bytes[1]]),
+        index: u16::from_le_bytes([bytes[2], bytes[3]]),
+        flags: bytes[4],
+    }
+}
+
+/// Empty and uniform chunks take a tag and at most one tile, other chunks either a bit per
+/// position followed by the set tiles or their [`RleChunk`] bytes, whichever is smaller.
+pub(crate) fn encode_chunk(chunk: &Chunk, bytes: &mut Vec<u8>) {
+    if let Some(tile) = chunk.as_uniform() {
+        match tile {
+            Some(tile) => {
+                bytes.push(UNIFORM);
+                encode_tile(&tile, bytes);
+            }
+            None => bytes.push(EMPTY),
+        }
+        return;
+    }
+    let runs = RleChunk::from_chunk(chunk);
+    let set = (0..=u8::MAX)
+        .filter(|index| chunk.get_tile(*index).is_some())
+        .count();
+    let runs_len: usize = runs
+        .runs()
+        .map(|run| if run.tile.is_some() { 7 } else { 2 })
+        .sum();
+    if runs_len < 32 + set * 5 {
+        bytes.push(RUNS);
+        runs.encode(bytes);
+        return;
+    }
+    bytes.push(SPARSE);
+    let mut mask = [0u8; 32];
+    for index in 0..=u8::MAX {
+        if chunk.get_tile(index).is_some() {
+            mask[index as usize / 8] |= 1 << (index % 8);
+        }
+    }
+    bytes.extend_from_slice(&mask);
+    for index in 0..=u8::MAX {
+        if let Some(tile) = chunk.get_tile(index) {
+            encode_tile(tile, bytes);
+        }
+    }
+}
+
+pub(crate) fn decode_chunk(bytes: &[u8]) -> io::Result<Chunk> {
+    match bytes.first() {
+        Some(&EMPTY) if bytes.len() == 1 => Ok(Chunk::uniform(None)),
+        Some(&UNIFORM) if bytes.len() == 6 => Ok(Chunk::uniform(Some(decode_tile(&bytes[1..])))),
+        Some(&SPARSE) if bytes.len() >= 33 => {
+            let mask = &bytes[1..33];
+            let set: u32 = mask.iter().map(|bits| bits.count_ones()).sum();
+            let tiles = &bytes[33..];
+            if tiles.len() != set as usize * 5 {
+                return Err(invalid_data("chunk tiles don't match its mask"));
+            }
+            let mut dense = Box::new(DenseTiles {
+                tiles: [Tile::new(0, 0); 256],
+                valid: [false; 256],
+            });
+            let mut tiles = tiles.chunks_exact(5);
+            for index in 0..256 {
+                if mask[index / 8] & (1 << (index % 8)) != 0 {
+                    dense.valid[index] = true;
+                    dense.tiles[index] = decode_tile(tiles.next().unwrap());
+                }
+            }
+            Ok(Chunk {
+                storage: ChunkStorage::Dense(dense),
+                histogram: OnceLock::new(),
+            })
+        }
+        Some(&RUNS) => RleChunk::from_bytes(&bytes[1..])
+            .map(|runs| runs.to_chunk())
+            .ok_or_else(|| invalid_data("chunk runs don't cover the chunk")),
+        _ => Err(invalid_data("unknown chunk encoding")),
+    }
+}
+
+/// Loads requested chunks from a [`ChunkStore`] and saves the chunks streaming unloads into it,
+/// both on the IO task pool when one is available. Needs
+/// [`crate::streaming::ChunkStreamingPlugin`] and a [`ChunkStore`] resource.
+///
+/// Chunks found in the store are never generated, see [`crate::generator::TileMapGenerator`].
+/// Loads of chunks that are no longer requested, e.g. because they left the unload radius, are
+/// cancelled. [`MapLoadProgress`] and [`MapSaveProgress`] report the background work.
+///
+/// This persists the default map, [`crate::dimension::DimensionPersistPlugin`] persists other
+/// maps, which report no progress.
+pub struct ChunkPersistPlugin;
+
+impl Plugin for ChunkPersistPlugin {
+    fn build(&self, app: &mut bevy::prelude::App) {
+        add_persistence::<DefaultMap>(app);
+        app.add_event::<MapLoadProgress>()
+            .add_event::<MapSaveProgress>()
+            .add_system_to_stage(
+                TilingCoreStage::Schedule,
+                report_chunk_io_progress.after(StreamingSystem::Insert),
+            );
+    }
+}
+
+/// Persists the map labeled `L` through the `ChunkStore<L>` resource.
+pub(crate) fn add_persistence<L: MapLabel>(app: &mut bevy::prelude::App) {
+    app.world
+        .get_resource_or_insert_with(ChunkStreaming::<L>::default)
+        .persist = true;
+    app.add_system_to_stage(
+        TilingCoreStage::Schedule,
+        load_stored_chunks::<L>
+            .after(StreamingSystem::Stream)
+            .before(StreamingSystem::Generate),
+    )
+    .add_system_to_stage(
+        TilingCoreStage::Schedule,
+        save_unloaded_chunks::<L>
+            .after(StreamingSystem::Stream)
+            .before(StreamingSystem::Generate),
+    )
+    .add_system_to_stage(
+        TilingCoreStage::Schedule,
+        finish_chunk_io::<L>
+            .label(StreamingSystem::Insert)
+            .after(StreamingSystem::Generate),
+    );
+}
+
+/// Sent by [`ChunkPersistPlugin`] in frames where loads from the [`ChunkStore`] start or finish,
+/// e.g. to drive a loading screen. `total` counts the loads since the store was last done
+/// loading, minus cancelled ones, so the last event of a batch has `loaded == total`.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub struct MapLoadProgress {
+    pub loaded: usize,
+    pub total: usize,
+}
+
+/// Sent by [`ChunkPersistPlugin`] in frames where chunk saves start or finish, counted in
+/// chunks like [`MapLoadProgress`]. Failed saves count as finished, see
+/// [`ChunkStore::take_errors`].
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub struct MapSaveProgress {
+    pub saved: usize,
+    pub total: usize,
+}
+
+/// Counts background operations for the progress events.
+#[derive(Default)]
+struct Progress {
+    done: usize,
+    total: usize,
+    reported: Option<(usize, usize)>,
+}
+
+impl Progress {
+    fn start(&mut self, count: usize) {
+        self.total += count;
+    }
+
+    fn finish(&mut self, count: usize) {
+        self.done += count;
+    }
+
+    fn cancel(&mut self, count: usize) {
+        self.total -= count;
+    }
+
+    /// `(done, total)` if it changed since the last report, starting over once all is done.
+    fn report(&mut self) -> Option<(usize, usize)> {
+        let current = (self.done, self.total);
+        if self.reported == Some(current) || (self.total == 0 && self.reported.is_none()) {
+            return None;
+        }
+        if self.done >= self.total {
+            *self = Progress::default();
+        } else {
+            self.reported = Some(current);
+        }
+        Some(current)
+    }
+}
+
+/// The chunks a save was given with their generations, and whether it succeeded.
+type SaveTask = Task<(Vec<(IVec3, u64)>, io::Result<()>)>;
+
+/// The backend of a [`ChunkStore`] with the generation of the data last written for each
+/// chunk, so a save finishing after a newer save of the same chunk doesn't overwrite it.
+struct SharedBackend {
+    backend: Box<dyn ChunkBackend>,
+    written: HashMap<IVec3, u64>,
+}
+
+impl SharedBackend {
+    /// Writes the chunks newer than the data the file holds for them.
+    fn save<'a>(
+        &mut self,
+        chunks: impl IntoIterator<Item = (IVec3, u64, &'a Chunk)>,
+    ) -> io::Result<usize> {
+        let newer: Vec<(IVec3, u64, &Chunk)> = chunks
+            .into_iter()
+            .filter(|(coord, generation, _)| {
+                self.written
+                    .get(coord)
+                    .map_or(true, |written| written < generation)
+            })
+            .collect();
+        let newer_chunks: Vec<(IVec3, &Chunk)> = newer
+            .iter()
+            .map(|(coord, _, chunk)| (*coord, *chunk))
+            .collect();
+        let saved = self.backend.save_chunks(&newer_chunks)?;
+        for (coord, generation, _) in newer {
+            self.written.insert(coord, generation);
+        }
+        Ok(saved)
+    }
+}
+
+/// A [`ChunkBackend`] shared with background IO tasks, see [`ChunkPersistPlugin`].
+///
+/// Unloaded chunks stay in memory until their save succeeds. Every unload gives the chunk a
+/// new generation and saves only write generations newer than what the file holds, so saves
+/// of the same chunk finishing out of order keep the latest data.
+///
+/// The store of the map labeled `L` is `ChunkStore<L>`, created with [`ChunkStore::for_map`].
+pub struct ChunkStore<L: MapLabel = DefaultMap> {
+    backend: Arc<Mutex<SharedBackend>>,
+    known: HashSet<IVec3>,
+    /// Unloaded chunks whose latest data isn't saved yet, with its generation.
+    unsaved: HashMap<IVec3, (u64, Arc<Chunk>)>,
+    /// Unsaved chunks whose last save failed, saved again along with the next unloaded chunks.
+    failed: HashSet<IVec3>,
+    generation: u64,
+    loads: HashMap<IVec3, Task<io::Result<Option<Chunk>>>>,
+    /// Loaded chunks waiting for their lock, see [`crate::locks::TileLocks`].
+    ready: HashMap<IVec3, Arc<Chunk>>,
+    saves: Vec<SaveTask>,
+    errors: Vec<io::Error>,
+    load_progress: Progress,
+    save_progress: Progress,
+    label: PhantomData<fn() -> L>,
+}
+
+impl ChunkStore {
+    pub fn new(file: ChunkFile) -> Self {
+        Self::with_backend(file)
+    }
+
+    /// A store keeping its chunks somewhere other than a [`ChunkFile`].
+    pub fn with_backend(backend: impl ChunkBackend) -> Self {
+        Self::for_map(backend)
+    }
+}
+
+impl<L: MapLabel> ChunkStore<L> {
+    /// A store for the map labeled `L`, e.g. `ChunkStore::<Cave>::for_map(file)`.
+    pub fn for_map(backend: impl ChunkBackend) -> Self {
+        Self {
+            known: backend.chunks().into_iter().collect(),
+            backend: Arc::new(Mutex::new(SharedBackend {
+                backend: Box::new(backend),
+                written: HashMap::default(),
+            })),
+            unsaved: HashMap::default(),
+            failed: HashSet::default(),
+            generation: 0,
+            loads: HashMap::default(),
+            ready: HashMap::default(),
+            saves: Vec::new(),
+            errors: Vec::new(),
+            load_progress: Progress::default(),
+            save_progress: Progress::default(),
+            label: PhantomData,
+        }
+    }
+
+    /// Whether the chunk is stored or being saved.
+    pub fn has_chunk(&self, coord: &IVec3) -> bool {
+        self.known.contains(coord)
+    }
+
+    /// Whether chunks are still being loaded or saved in the background, or loaded chunks wait
+    /// for their lock.
+    pub fn is_busy(&self) -> bool {
+        !self.loads.is_empty() || !self.ready.is_empty() || !self.saves.is_empty()
+    }
+
+    /// Cancels the background loads of the chunks `cancel` returns true for, dropping their
+    /// results, and returns how many were cancelled. The chunks stay missing until streaming
+    /// requests them again, after they left the unload radius.
+    pub fn cancel_loads_where(&mut self, mut cancel: impl FnMut(&IVec3) -> bool) -> usize {
+        let len = self.loads.len();
+        self.loads.retain(|coord, _| !cancel(coord));
+        self.ready.retain(|coord, _| !cancel(coord));
+        let cancelled = len - self.loads.len();
+        self.load_progress.cancel(cancelled);
+        cancelled
+    }
+
+    /// Cancels every background load, see [`ChunkStore::cancel_loads_where`].
+    pub fn cancel_loads(&mut self) -> usize {
+        self.cancel_loads_where(|_| true)
+    }
+
+    /// Number of chunks being loaded in the background or waiting for their lock.
+    pub fn pending_loads(&self) -> usize {
+        self.loads.len() + self.ready.len()
+    }
+
+    /// Number of unloaded chunks kept in memory because their save hasn't succeeded yet.
+    pub fn unsaved_count(&self) -> usize {
+        self.unsaved.len()
+    }
+
+    /// IO errors of background loads and saves since the last call. Chunks that failed to
+    /// save are kept in memory, still loaded from there and saved again with the next
+    /// unloaded chunks.
+    pub fn take_errors(&mut self) -> Vec<io::Error> {
+        std::mem::take(&mut self.errors)
+    }
+
+    /// Saves chunks of the map between the chunk coordinates `min` and `max` right away,
+    /// e.g. when the game exits. Returns how many were written.
+    pub fn save_region(&mut self, map: &TileMap<L>, min: IVec3, max: IVec3) -> io::Result<usize> {
+        self.generation += 1;
+        let generation = self.generation;
+        let mut backend = lock(&self.backend);
+        let saved = backend.save(
+            region_chunks(map, min, max)
+                .into_iter()
+                .map(|(coord, chunk)| (coord, generation, chunk)),
+        )?;
+        self.known.extend(backend.backend.chunks());
+        Ok(saved)
+    }
+
+    /// Reclaims the space of replaced chunks, see [`ChunkFile::compact`]. Waits for background
+    /// loads and saves using the backend.
+    pub fn compact(&mut self) -> io::Result<u64> {
+        lock(&self.backend).backend.compact()
+    }
+
+    /// Keeps unloaded chunks in memory under a new generation and returns the chunks to save,
+    /// along with the ones whose last save failed.
+    fn queue_saves(
+        &mut self,
+        chunks: impl IntoIterator<Item = (IVec3, Arc<Chunk>)>,
+    ) -> Vec<(IVec3, u64, Arc<Chunk>)> {
+        let first = self.generation + 1;
+        for (coord, chunk) in chunks {
+            self.generation += 1;
+            self.known.insert(coord);
+            self.failed.remove(&coord);
+            self.unsaved.insert(coord, (self.generation, chunk));
+        }
+        let mut queued: Vec<(IVec3, u64, Arc<Chunk>)> = self
+            .unsaved
+            .iter()
+            .filter(|(coord, (generation, _))| *generation >= first || self.failed.contains(coord))
+            .map(|(coord, (generation, chunk))| (*coord, *generation, chunk.clone()))
+            .collect();
+        self.failed.clear();
+        queued.sort_by_key(|(coord, _, _)| (coord.z, coord.y, coord.x));
+        queued
+    }
+
+    fn finish_save(&mut self, chunks: Vec<(IVec3, u64)>, result: io::Result<()>) {
+        self.save_progress.finish(chunks.len());
+        let failed = result.is_err();
+        if let Err(error) = result {
+            self.errors.push(error);
+        }
+        for (coord, generation) in chunks {
+            // A newer unload of the chunk has its own save.
+            if self
+                .unsaved
+                .get(&coord)
+                .map_or(true, |(unsaved, _)| *unsaved != generation)
+            {
+                continue;
+            }
+            if failed {
+                self.failed.insert(coord);
+            } else {
+                self.unsaved.remove(&coord);
+            }
+        }
+    }
+}
+
+fn lock(backend: &Mutex<SharedBackend>) -> std::sync::MutexGuard<'_, SharedBackend> {
+    backend
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+fn poll_ready<T>(task: &mut Task<T>, context: &mut Context) -> Option<T> {
+    match Pin::new(task).poll(context) {
+        Poll::Ready(value) => Some(value),
+        Poll::Pending => None,
+    }
+}
+
+fn load_stored_chunks<L: MapLabel>(
+    mut requests: EventReader<ChunkLoadRequest<L>>,
+    streaming: Res<ChunkStreaming<L>>,
+    mut store: ResMut<ChunkStore<L>>,
+    io_pool: Option<Res<IoTaskPool>>,
+    mut writer: TileMapWriter<L>,
+) {
+    let store = &mut *store;
+    store.cancel_loads_where(|coord| !streaming.is_requested(coord));
+    for coord in requests.iter().map(|request| &request.0) {
+        if let Some((_, chunk)) = store.unsaved.get(coord) {
+            insert_loaded_chunk(&mut store.ready, &mut writer, coord, chunk.clone());
+            continue;
+        }
+        if !store.known.contains(coord)
+            || store.loads.contains_key(coord)
+            || store.ready.contains_key(coord)
+        {
+            continue;
+        }
+        let backend = store.backend.clone();
+        let coord = *coord;
+        store.load_progress.start(1);
+        match &io_pool {
+            Some(pool) => {
+                let task = pool.spawn(async move { lock(&backend).backend.load_chunk(&coord) });
+                store.loads.insert(coord, task);
+            }
+            None => {
+                store.load_progress.finish(1);
+                match lock(&backend).backend.load_chunk(&coord) {
+                    Ok(Some(chunk)) => {
+                        insert_loaded_chunk(&mut store.ready, &mut writer, &coord, Arc::new(chunk))
+                    }
+                    Ok(None) => {}
+                    Err(error) => store.errors.push(error),
+                }
+            }
+        }
+    }
+}
+
+/// Inserts a chunk loaded from the store. If the chunk was created while it was loading, e.g.
+/// by gameplay writing to it, the tiles written since are kept and the loaded ones only fill
+/// the empty spots. The generator never creates stored chunks, so there's none to replace.
+/// Chunks overlapping a locked box wait in `ready` until it is unlocked.
+fn insert_loaded_chunk<L: MapLabel>(
+    ready: &mut HashMap<IVec3, Arc<Chunk>>,
+    writer: &mut TileMapWriter<L>,
+    coord: &IVec3,
+    chunk: Arc<Chunk>,
+) {
+    if writer.is_chunk_locked(coord) {
+        ready.insert(*coord, chunk);
+        return;
+    }
+    let existing = match writer.chunks.get_chunk(coord) {
+        Some(existing) => existing,
+        None => {
+            writer.insert_chunk(coord, chunk);
+            return;
+        }
+    };
+    let chunk_coord = writer.chunks.normalize_chunk(coord);
+    let missing: Vec<(TileCoord, Option<Tile>)> = (0..=u8::MAX)
+        .filter(|index| existing.get_tile(*index).is_none())
+        .filter_map(|index| {
+            let tile = *chunk.get_tile(index)?;
+            Some((
+                TileCoord {
+                    index,
+                    chunk: chunk_coord,
+                },
+                Some(tile),
+            ))
+        })
+        .collect();
+    writer.set_tiles(missing);
+}
+
+fn save_unloaded_chunks<L: MapLabel>(
+    mut streaming: ResMut<ChunkStreaming<L>>,
+    mut store: ResMut<ChunkStore<L>>,
+    io_pool: Option<Res<IoTaskPool>>,
+) {
+    let chunks: Vec<(IVec3, Arc<Chunk>)> = streaming.drain_stored_chunks().collect();
+    if chunks.is_empty() {
+        return;
+    }
+    let store = &mut *store;
+    let queued = store.queue_saves(chunks);
+    store.save_progress.start(queued.len());
+    let backend = store.backend.clone();
+    let save = move || {
+        let chunks = queued
+            .iter()
+            .map(|(coord, generation, _)| (*coord, *generation))
+            .collect();
+        let result = lock(&backend)
+            .save(
+                queued
+                    .iter()
+                    .map(|(coord, generation, chunk)| (*coord, *generation, chunk.as_ref())),
+            )
+            .map(|_| ());
+        (chunks, result)
+    };
+    match &io_pool {
+        Some(pool) => store.saves.push(pool.spawn(async move { save() })),
+        None => {
+            let (chunks, result) = save();
+            store.finish_save(chunks, result);
+        }
+    }
+}
+
+fn finish_chunk_io<L: MapLabel>(mut store: ResMut<ChunkStore<L>>, mut writer: TileMapWriter<L>) {
+    let store = &mut *store;
+    if store.is_busy() {
+        poll_chunk_io(store, &mut writer);
+    }
+}
+
+fn report_chunk_io_progress(
+    mut store: ResMut<ChunkStore>,
+    mut load_progress: EventWriter<MapLoadProgress>,
+    mut save_progress: EventWriter<MapSaveProgress>,
+) {
+    if let Some((loaded, total)) = store.load_progress.report() {
+        load_progress.send(MapLoadProgress { loaded, total });
+    }
+    if let Some((saved, total)) = store.save_progress.report() {
+        save_progress.send(MapSaveProgress { saved, total });
+    }
+}
+
+fn poll_chunk_io<L: MapLabel>(store: &mut ChunkStore<L>, writer: &mut TileMapWriter<L>) {
+    let waker = noop_waker();
+    let mut context = Context::from_waker(&waker);
+    for (coord, chunk) in std::mem::take(&mut store.ready) {
+        insert_loaded_chunk(&mut store.ready, writer, &coord, chunk);
+    }
+    let mut errors = Vec::new();
+    let mut finished_loads = 0;
+    let ready = &mut store.ready;
+    store.loads.retain(|coord, task| {
+        let result = match poll_ready(task, &mut context) {
+            Some(result) => result,
+            None => return true,
+        };
+        match result {
+            Ok(Some(chunk)) => insert_loaded_chunk(ready, writer, coord, Arc::new(chunk)),
+            Ok(None) => {}
+            Err(error) => errors.push(error),
+        }
+        finished_loads += 1;
+        false
+    });
+    store.errors.extend(errors);
+    store.load_progress.finish(finished_loads);
+
+    let mut finished = Vec::new();
+    store
+        .saves
+        .retain_mut(|task| match poll_ready(task, &mut context) {
+            Some(done) => {
+                finished.push(done);
+                false
+            }
+            None => true,
+        });
+    for (chunks, result) in finished {
+        store.finish_save(chunks, result);
+    }
+}
@@ -0,0 +1,181 @@
+use bevy::{math::IVec3, utils::HashSet};
+
+use crate::{regions::RegionShape, wraps_into, Tile, TileCoord, TileMap, CHUNK_SIZE};
+
+type TileFilter = Box<dyn Fn(&Tile) -> bool + Send + Sync>;
+
+/// Index of a rule in [`PlacementRules`], in the order the rules were added.
+pub type PlacementRuleId = usize;
+
+enum PlacementRule {
+    Adjacent {
+        applies: TileFilter,
+        neighbour: TileFilter,
+    },
+    Support {
+        applies: TileFilter,
+        support: TileFilter,
+    },
+    MaxInRegion {
+        applies: TileFilter,
+        region: RegionShape,
+        max: usize,
+    },
+}
+
+/// Why a tile can't be placed, see [`PlacementRules::check`].
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum PlacementViolation {
+    /// The map bounds reject the coordinate.
+    OutOfBounds,
+    /// None of the four neighbours on the same layer is accepted by the rule.
+    MissingNeighbour(PlacementRuleId),
+    /// The tile on the layer below is missing or not accepted by the rule.
+    MissingSupport(PlacementRuleId),
+    /// The region of the rule already holds its maximum of matching tiles.
+    RegionFull(PlacementRuleId),
+}
+
+/// Requirements for placing tiles, e.g. in a building game, checked by
+/// [`crate::TileMapWriter::try_place`]. Other writes ignore the rules.
+///
+/// Every rule only applies to the tiles its `applies` filter accepts. The same checks can be made
+/// without placing anything through [`PlacementRules::check`], e.g. to tint preview tiles.
+#[derive(Default)]
+pub struct PlacementRules {
+    rules: Vec<PlacementRule>,
+}
+
+impl PlacementRules {
+    /// Requires one of the four neighbours on the same layer to be accepted by `neighbour`.
+    pub fn require_adjacent(
+        &mut self,
+        applies: impl Fn(&Tile) -> bool + Send + Sync + 'static,
+        neighbour: impl Fn(&Tile) -> bool + Send + Sync + 'static,
+    ) -> PlacementRuleId {
+        self.add(PlacementRule::Adjacent {
+            applies: Box::new(applies),
+            neighbour: Box::new(neighbour),
+        })
+    }
+
+    /// Requires the tile at the same position one layer below to be accepted by `support`.
+    pub fn require_support(
+        &mut self,
+        applies: impl Fn(&Tile) -> bool + Send + Sync + 'static,
+        support: impl Fn(&Tile) -> bool + Send + Sync + 'static,
+    ) -> PlacementRuleId {
+        self.add(PlacementRule::Support {
+            applies: Box::new(applies),
+            support: Box::new(support),
+        })
+    }
+
+    /// Allows at most `max` tiles accepted by `applies` inside `region`. On wrapping maps the
+    /// region may reach past the seam, each tile is counted once however often it repeats.
+    pub fn max_per_region(
+        &mut self,
+        applies: impl Fn(&Tile) -> bool + Send + Sync + 'static,
+        region: RegionShape,
+        max: usize,
+    ) -> PlacementRuleId {
+        self.add(PlacementRule::MaxInRegion {
+            applies: Box::new(applies),
+            region,
+            max,
+        })
+    }
+
+    fn add(&mut self, rule: PlacementRule) -> PlacementRuleId {
+        self.rules.push(rule);
+        self.rules.len() - 1
+    }
+
+    /// Every rule placing `tile` at `coord` would break, empty if it can be placed.
+    pub fn check<L>(
+        &self,
+        map: &TileMap<L>,
+        coord: &TileCoord,
+        tile: &Tile,
+    ) -> Vec<PlacementViolation> {
+        let coord = match map.resolve_coord(coord) {
+            Some(coord) => coord,
+            None => return vec![PlacementViolation::OutOfBounds],
+        };
+        let mut violations = Vec::new();
+        for (id, rule) in self.rules.iter().enumerate() {
+            match rule {
+                PlacementRule::Adjacent { applies, neighbour } if applies(tile) => {
+                    let found = map.neighbours(&coord).iter().any(|neighbour_coord| {
+                        map.get_tile(neighbour_coord).is_some_and(neighbour)
+                    });
+                    if !found {
+                        violations.push(PlacementViolation::MissingNeighbour(id));
+                    }
+                }
+                PlacementRule::Support { applies, support } if applies(tile) => {
+                    let below = map.offset_coord(&coord, IVec3::new(0, 0, -1));
+                    if !map.get_tile(&below).is_some_and(support) {
+                        violations.push(PlacementViolation::MissingSupport(id));
+                    }
+                }
+                PlacementRule::MaxInRegion {
+                    applies,
+                    region,
+                    max,
+                } if applies(tile) && region_covers(map, region, &coord) => {
+                    let others = count_in_region(map, region, |other, other_tile| {
+                        *other != coord && applies(other_tile)
+                    });
+                    if others >= *max {
+                        violations.push(PlacementViolation::RegionFull(id));
+                    }
+                }
+                _ => {}
+            }
+        }
+        violations
+    }
+}
+
+/// Whether the region holds the tile or, on wrapping maps, one of its repeats. `coord` must be
+/// resolved.
+fn region_covers<L>(map: &TileMap<L>, region: &RegionShape, coord: &TileCoord) -> bool {
+    let period = match map.wrap() {
+        Some(wrap) => wrap.period * CHUNK_SIZE,
+        None => return region.contains(coord),
+    };
+    match region {
+        RegionShape::Rect { min, max } => {
+            let position = coord.tile_position();
+            let period = period.extend(0);
+            (0..3).all(|axis| wraps_into(position[axis], min[axis], max[axis], period[axis]))
+        }
+        RegionShape::Tiles(positions) => positions.iter().any(|position| {
+            map.resolve_coord(&TileCoord::from_tile_position(*position)) == Some(*coord)
+        }),
+    }
+}
+
+/// Counts the tiles of the region accepted by `filter`, which gets resolved coordinates, so
+/// each tile of a wrapping map is counted once.
+fn count_in_region<L>(
+    map: &TileMap<L>,
+    region: &RegionShape,
+    filter: impl Fn(&TileCoord, &Tile) -> bool,
+) -> usize {
+    let coords: HashSet<TileCoord> = match region {
+        RegionShape::Rect { min, max } => map
+            .iter_region(*min, *max)
+            .filter_map(|(coord, _)| map.resolve_coord(&coord))
+            .collect(),
+        RegionShape::Tiles(positions) => positions
+            .iter()
+            .filter_map(|position| map.resolve_coord(&TileCoord::from_tile_position(*position)))
+            .collect(),
+    };
+    coords
+        .iter()
+        .filter(|coord| map.get_tile(coord).is_some_and(|tile| filter(coord, tile)))
+        .count()
+}
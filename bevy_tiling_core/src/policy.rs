@@ -0,0 +1,140 @@
+use bevy::{
+    ecs::system::SystemParam,
+    math::IVec3,
+    prelude::{Entity, Res},
+    utils::{hashbrown::hash_map::Keys, HashSet},
+};
+
+use crate::{
+    locks::TileLocks, Chunk, DefaultMap, IntoTileCoord, MapLabel, MapReader, Tile, TileCoord,
+    TileMapWriter,
+};
+
+type WriteRule =
+    Box<dyn Fn(Entity, &TileCoord, Option<&Tile>, Option<&Tile>) -> bool + Send + Sync>;
+
+/// Central place for deciding who may edit which tiles, e.g. protection zones in a building game.
+/// Only writes made through [`GuardedTileMapWriter`] are checked, a policy without rules allows
+/// everything.
+#[derive(Default)]
+pub struct TileWritePolicy {
+    rules: Vec<WriteRule>,
+}
+
+impl TileWritePolicy {
+    /// Adds a rule called with the acting entity, the coordinate, the current tile and the new
+    /// tile. A write is only allowed if every rule allows it.
+    pub fn add_rule(
+        &mut self,
+        rule: impl Fn(Entity, &TileCoord, Option<&Tile>, Option<&Tile>) -> bool + Send + Sync + 'static,
+    ) {
+        self.rules.push(Box::new(rule));
+    }
+
+    pub fn allow(
+        &self,
+        actor: Entity,
+        coord: &TileCoord,
+        old: Option<&Tile>,
+        new: Option<&Tile>,
+    ) -> bool {
+        self.rules.iter().all(|rule| rule(actor, coord, old, new))
+    }
+}
+
+/// Returned when [`TileWritePolicy`] refuses a write or the tile is locked.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub struct WriteDenied(pub TileCoord);
+
+/// A [`TileMapWriter`] that checks every write against the [`TileLocks`] and the
+/// [`TileWritePolicy`] on behalf of an actor. Denied writes leave the map untouched and cause
+/// no updates. Locks are checked separately from the policy, so replacing the policy resource
+/// keeps them in force.
+#[derive(SystemParam)]
+pub struct GuardedTileMapWriter<'w, 's, L: MapLabel = DefaultMap> {
+    writer: TileMapWriter<'w, 's, L>,
+    policy: Res<'w, TileWritePolicy>,
+    locks: Res<'w, TileLocks>,
+}
+
+impl<'w, 's, L: MapLabel> GuardedTileMapWriter<'w, 's, L> {
+    fn allows(&self, actor: Entity, coord: &TileCoord, tile: Option<&Tile>) -> bool {
+        !self.locks.is_locked(coord)
+            && self
+                .policy
+                .allow(actor, coord, self.writer.get_tile(coord), tile)
+    }
+
+    /// Sets or removes a tile if the policy allows `actor` to, returning the previous tile.
+    pub fn set_tile(
+        &mut self,
+        actor: Entity,
+        coord: impl IntoTileCoord,
+        tile: Option<Tile>,
+    ) -> Result<Option<Tile>, WriteDenied> {
+        let coord = coord.into_tile_coord();
+        if !self.allows(actor, &coord, tile.as_ref()) {
+            return Err(WriteDenied(coord));
+        }
+        Ok(self.writer.set_tile(coord, tile))
+    }
+
+    /// Applies every allowed write at once, see [`TileMapWriter::set_tiles`].
+    /// Returns the coordinates of the denied writes.
+    pub fn set_tiles(
+        &mut self,
+        actor: Entity,
+        tiles: impl IntoIterator<Item = (TileCoord, Option<Tile>)>,
+    ) -> Vec<WriteDenied> {
+        let mut denied = Vec::new();
+        let allowed: Vec<(TileCoord, Option<Tile>)> = tiles
+            .into_iter()
+            .filter(|(coord, tile)| {
+                let allowed = self.allows(actor, coord, tile.as_ref());
+                if !allowed {
+                    denied.push(WriteDenied(*coord));
+                }
+                allowed
+            })
+            .collect();
+        self.writer.set_tiles(allowed);
+        denied
+    }
+}
+
+impl<'w, 's, L: MapLabel> MapReader for GuardedTileMapWriter<'w, 's, L> {
+    #[inline]
+    fn get_tile(&self, coord: impl IntoTileCoord) -> Option<&Tile> {
+        self.writer.get_tile(coord)
+    }
+
+    #[inline]
+    fn get_chunk(&self, coord: &IVec3) -> Option<&Chunk> {
+        self.writer.get_chunk(coord)
+    }
+
+    #[inline]
+    fn get_chunk_updates(&self) -> Keys<'_, IVec3, HashSet<u8>> {
+        self.writer.get_chunk_updates()
+    }
+
+    #[inline]
+    fn get_chunk_removals(&self) -> impl Iterator<Item = &IVec3> {
+        self.writer.get_chunk_removals()
+    }
+
+    #[inline]
+    fn get_tile_updates(&self) -> impl Iterator<Item = (TileCoord, Option<&Tile>)> {
+        self.writer.get_tile_updates()
+    }
+
+    #[inline]
+    fn iter_region(&self, min: IVec3, max: IVec3) -> impl Iterator<Item = (TileCoord, &Tile)> {
+        self.writer.iter_region(min, max)
+    }
+
+    #[inline]
+    fn get_chunk_data<T: Send + Sync + 'static>(&self, chunk: &IVec3) -> Option<&T> {
+        self.writer.get_chunk_data(chunk)
+    }
+}
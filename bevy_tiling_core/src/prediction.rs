@@ -0,0 +1,138 @@
+use std::marker::PhantomData;
+
+use crate::{DefaultMap, MapLabel, Tile, TileCoord, TileMapWriter};
+
+/// Identifies a batch of predicted edits, e.g. to send along with the edit request to a server.
+pub type PredictionId = u32;
+
+struct PredictedTile {
+    coord: TileCoord,
+    old: Option<Tile>,
+}
+
+struct Prediction {
+    id: PredictionId,
+    confirmed: bool,
+    tiles: Vec<PredictedTile>,
+}
+
+/// Optimistic local tile edits awaiting confirmation from an authority, such as a game server.
+///
+/// Edits are applied right away through [`TileMapWriter`] so they cause updates like any other
+/// edit. Reverting a misprediction restores the previous tiles, which causes updates again so
+/// dependent systems rebuild the affected chunks.
+///
+/// Confirmed predictions are kept while an earlier one is still pending, so reverting the
+/// earlier one leaves the tiles a confirmed prediction set alone.
+pub struct TilePredictions<L = DefaultMap> {
+    predictions: Vec<Prediction>,
+    next_id: PredictionId,
+    label: PhantomData<fn() -> L>,
+}
+
+impl Default for TilePredictions {
+    fn default() -> Self {
+        Self::empty()
+    }
+}
+
+impl<L> TilePredictions<L> {
+    pub(crate) fn empty() -> Self {
+        Self {
+            predictions: Vec::new(),
+            next_id: 0,
+            label: PhantomData,
+        }
+    }
+
+    fn position(&self, id: PredictionId) -> Option<usize> {
+        self.predictions
+            .iter()
+            .position(|prediction| prediction.id == id && !prediction.confirmed)
+    }
+
+    /// Drops the confirmed predictions no pending prediction comes before.
+    fn drop_settled(&mut self) {
+        let settled = self
+            .predictions
+            .iter()
+            .take_while(|prediction| prediction.confirmed)
+            .count();
+        self.predictions.drain(..settled);
+    }
+
+    /// Accepts the edits of a prediction as they are, returning false if it was not pending.
+    pub fn confirm(&mut self, id: PredictionId) -> bool {
+        match self.position(id) {
+            Some(index) => {
+                self.predictions[index].confirmed = true;
+                self.drop_settled();
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn is_pending(&self, id: PredictionId) -> bool {
+        self.position(id).is_some()
+    }
+
+    /// Number of predictions neither confirmed nor reverted yet.
+    pub fn pending_count(&self) -> usize {
+        self.predictions
+            .iter()
+            .filter(|prediction| !prediction.confirmed)
+            .count()
+    }
+}
+
+impl<L: MapLabel> TilePredictions<L> {
+    /// Applies the edits locally and remembers the tiles they replaced.
+    /// Returns the id to confirm or revert the edits with later.
+    pub fn predict(
+        &mut self,
+        writer: &mut TileMapWriter<L>,
+        edits: impl IntoIterator<Item = (TileCoord, Option<Tile>)>,
+    ) -> PredictionId {
+        let id = self.next_id;
+        self.next_id = self.next_id.wrapping_add(1);
+        let mut tiles = Vec::new();
+        for (coord, tile) in edits {
+            if let Some(coord) = writer.chunks.resolve_coord(&coord) {
+                let old = writer.set_tile(coord, tile);
+                tiles.push(PredictedTile { coord, old });
+            }
+        }
+        self.predictions.push(Prediction {
+            id,
+            confirmed: false,
+            tiles,
+        });
+        id
+    }
+
+    /// Undoes the edits of a prediction, returning false if it was not pending.
+    /// Tiles also edited by a later prediction keep that prediction's value. If the later one
+    /// is still pending, reverting it as well restores the tiles from before both.
+    pub fn revert(&mut self, writer: &mut TileMapWriter<L>, id: PredictionId) -> bool {
+        let index = match self.position(id) {
+            Some(index) => index,
+            None => return false,
+        };
+        let prediction = self.predictions.remove(index);
+        for predicted in prediction.tiles.into_iter().rev() {
+            let later = self.predictions[index..]
+                .iter_mut()
+                .flat_map(|later| later.tiles.iter_mut())
+                .find(|later| later.coord == predicted.coord);
+            match later {
+                Some(later) => later.old = predicted.old,
+                None => {
+                    writer.set_tile(predicted.coord, predicted.old);
+                }
+            }
+        }
+        self.drop_settled();
+        true
+    }
+}
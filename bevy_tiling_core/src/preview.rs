@@ -0,0 +1,75 @@
+use bevy::{prelude::ResMut, utils::HashMap};
+
+use crate::{placement::PlacementRules, IntoTileCoord, Tile, TileCoord, TileMap};
+
+/// A ghost tile shown by [`TilePreview`].
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub struct PreviewTile {
+    pub tile: Tile,
+    /// Whether the tile could be placed, renderers tint valid tiles green and invalid ones red.
+    pub valid: bool,
+}
+
+/// Ghost tiles drawn above the map, e.g. a building the player is about to place. They never
+/// enter the [`TileMap`] and cause no updates.
+///
+/// Previews only last for the frame they were set in, UI systems set them again every frame
+/// for as long as they should stay visible.
+#[derive(Default, Debug)]
+pub struct TilePreview {
+    tiles: HashMap<TileCoord, (PreviewTile, bool)>,
+}
+
+impl TilePreview {
+    pub fn set(&mut self, coord: impl IntoTileCoord, tile: Tile, valid: bool) {
+        self.tiles
+            .insert(coord.into_tile_coord(), (PreviewTile { tile, valid }, true));
+    }
+
+    /// Previews a tile, checking the [`PlacementRules`] to tell whether it is valid.
+    /// Returns whether it is.
+    pub fn set_checked<L>(
+        &mut self,
+        coord: impl IntoTileCoord,
+        tile: Tile,
+        rules: &PlacementRules,
+        map: &TileMap<L>,
+    ) -> bool {
+        let coord = coord.into_tile_coord();
+        let valid = rules.check(map, &coord, &tile).is_empty();
+        self.set(coord, tile, valid);
+        valid
+    }
+
+    pub fn get(&self, coord: impl IntoTileCoord) -> Option<&PreviewTile> {
+        self.tiles
+            .get(&coord.into_tile_coord())
+            .map(|(preview, _)| preview)
+    }
+
+    pub fn remove(&mut self, coord: impl IntoTileCoord) -> Option<PreviewTile> {
+        self.tiles
+            .remove(&coord.into_tile_coord())
+            .map(|(preview, _)| preview)
+    }
+
+    pub fn clear(&mut self) {
+        self.tiles.clear();
+    }
+
+    /// Every previewed tile, in no particular order.
+    pub fn iter(&self) -> impl Iterator<Item = (&TileCoord, &PreviewTile)> {
+        self.tiles
+            .iter()
+            .map(|(coord, (preview, _))| (coord, preview))
+    }
+}
+
+/// Drops the previews that weren't set this frame.
+pub(crate) fn expire_tile_previews(mut preview: ResMut<TilePreview>) {
+    preview.tiles.retain(|_, (_, refreshed)| {
+        let keep = *refreshed;
+        *refreshed = false;
+        keep
+    });
+}
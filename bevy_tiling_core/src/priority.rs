@@ -0,0 +1,59 @@
+use std::{fmt, marker::PhantomData};
+
+use bevy::{math::IVec3, utils::HashMap};
+
+use crate::{DefaultMap, MapLabel};
+
+/// Importance hints for chunks set by gameplay, e.g. a combat area or the player base, so work
+/// on important chunks is done first when there is more to do than time.
+/// Chunks without a hint have priority 0, higher values are more important.
+///
+/// [`crate::streaming::ChunkStreamingPlugin`] loads hinted chunks first and never unloads them.
+/// The hints of the map labeled `L` are in `ChunkPriorities<L>`.
+pub struct ChunkPriorities<L: MapLabel = DefaultMap> {
+    priorities: HashMap<IVec3, u8>,
+    label: PhantomData<fn() -> L>,
+}
+
+impl<L: MapLabel> Default for ChunkPriorities<L> {
+    fn default() -> Self {
+        Self {
+            priorities: HashMap::default(),
+            label: PhantomData,
+        }
+    }
+}
+
+impl<L: MapLabel> fmt::Debug for ChunkPriorities<L> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ChunkPriorities")
+            .field("priorities", &self.priorities)
+            .finish()
+    }
+}
+
+impl<L: MapLabel> ChunkPriorities<L> {
+    pub fn get(&self, chunk: &IVec3) -> u8 {
+        self.priorities.get(chunk).copied().unwrap_or_default()
+    }
+
+    /// Sets the priority of a chunk, 0 removes the hint.
+    pub fn set(&mut self, chunk: IVec3, priority: u8) {
+        if priority == 0 {
+            self.priorities.remove(&chunk);
+        } else {
+            self.priorities.insert(chunk, priority);
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.priorities.clear();
+    }
+
+    /// Every chunk with a hint, in no particular order.
+    pub fn iter(&self) -> impl Iterator<Item = (&IVec3, u8)> {
+        self.priorities
+            .iter()
+            .map(|(chunk, priority)| (chunk, *priority))
+    }
+}
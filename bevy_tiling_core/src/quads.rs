@@ -0,0 +1,222 @@
+use bevy::{
+    math::{IVec3, UVec2},
+    prelude::{Plugin, Res, ResMut},
+    utils::{HashMap, HashSet},
+};
+
+use crate::{Chunk, Tile, TileMap, TileMapUpdates, TilingCoreStage, CHUNK_SIZE};
+
+const ROWS: usize = CHUNK_SIZE as usize;
+
+/// A rectangle of equal tiles in a chunk, in tiles from the chunk's corner closest to
+/// negative infinity.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub struct TileQuad {
+    pub min: UVec2,
+    pub size: UVec2,
+    pub tile: Tile,
+}
+
+/// A run of equal tiles in one row.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+struct Run {
+    start: u8,
+    len: u8,
+    tile: Tile,
+}
+
+/// The greedy merged quads of a chunk. Runs of equal tiles are kept per row, so an edit only
+/// re-merges the rows it touched before the runs are stacked into quads again.
+#[derive(Clone, Default, Debug)]
+pub struct ChunkQuads {
+    rows: [Vec<Run>; ROWS],
+    quads: Vec<TileQuad>,
+}
+
+impl ChunkQuads {
+    pub fn build(chunk: &Chunk) -> Self {
+        let mut quads = Self::default();
+        quads.update_rows(chunk, 0..ROWS);
+        quads
+    }
+
+    /// Re-merges the rows containing the `dirty` tile indices, returns how many rows that was.
+    pub fn update(&mut self, chunk: &Chunk, dirty: impl IntoIterator<Item = u8>) -> usize {
+        let mut rows = [false; ROWS];
+        for index in dirty {
+            rows[index as usize / ROWS] = true;
+        }
+        let dirty_rows: Vec<usize> = (0..ROWS).filter(|row| rows[*row]).collect();
+        if dirty_rows.is_empty() {
+            return 0;
+        }
+        // Uniform chunks refill every row, which is cheaper than reading the dirty ones.
+        let merged = match chunk.as_uniform() {
+            Some(_) => ROWS,
+            None => dirty_rows.len(),
+        };
+        self.update_rows(chunk, dirty_rows);
+        merged
+    }
+
+    pub fn quads(&self) -> &[TileQuad] {
+        &self.quads
+    }
+
+    fn update_rows(&mut self, chunk: &Chunk, rows: impl IntoIterator<Item = usize>) {
+        if let Some(tile) = chunk.as_uniform() {
+            let run = tile.map(|tile| Run {
+                start: 0,
+                len: ROWS as u8,
+                tile,
+            });
+            for row in self.rows.iter_mut() {
+                row.clear();
+                row.extend(run);
+            }
+        } else {
+            for row in rows {
+                self.rows[row] = row_runs(chunk, row);
+            }
+        }
+        self.stack_runs();
+    }
+
+    /// Grows a quad downwards from every run that doesn't continue a quad of the row above.
+    fn stack_runs(&mut self) {
+        self.quads.clear();
+        // Quads still open, by the start and length of their run in the previous row.
+        let mut open: HashMap<(u8, u8), usize> = HashMap::default();
+        for (y, row) in self.rows.iter().enumerate().rev() {
+            let mut next = HashMap::default();
+            for run in row {
+                let key = (run.start, run.len);
+                match open.get(&key) {
+                    Some(quad) if self.quads[*quad].tile == run.tile => {
+                        let quad_index = *quad;
+                        let quad = &mut self.quads[quad_index];
+                        quad.min.y = y as u32;
+                        quad.size.y += 1;
+                        next.insert(key, quad_index);
+                    }
+                    _ => {
+                        next.insert(key, self.quads.len());
+                        self.quads.push(TileQuad {
+                            min: UVec2::new(run.start as u32, y as u32),
+                            size: UVec2::new(run.len as u32, 1),
+                            tile: run.tile,
+                        });
+                    }
+                }
+            }
+            open = next;
+        }
+    }
+}
+
+fn row_runs(chunk: &Chunk, row: usize) -> Vec<Run> {
+    let mut runs: Vec<Run> = Vec::new();
+    for x in 0..ROWS {
+        let tile = match chunk.get_tile((row * ROWS + x) as u8) {
+            Some(tile) => *tile,
+            None => continue,
+        };
+        match runs.last_mut() {
+            Some(run) if run.tile == tile && (run.start + run.len) as usize == x => run.len += 1,
+            _ => runs.push(Run {
+                start: x as u8,
+                len: 1,
+                tile,
+            }),
+        }
+    }
+    runs
+}
+
+/// What [`TileQuadCache`] did during the last update.
+#[derive(Copy, Clone, PartialEq, Eq, Default, Debug)]
+pub struct QuadMergeStats {
+    /// Chunks merged from scratch because they weren't cached yet.
+    pub chunks_built: usize,
+    /// Cached chunks whose dirty rows were re-merged.
+    pub chunks_updated: usize,
+    /// Rows re-merged, sixteen per built chunk.
+    pub rows_merged: usize,
+    /// Rows of updated chunks kept from the cache.
+    pub rows_reused: usize,
+}
+
+/// Greedy merged [`TileQuad`]s of every chunk that was updated since [`TileQuadPlugin`] was
+/// added, for building meshes or colliders without one quad per tile.
+#[derive(Default)]
+pub struct TileQuadCache {
+    chunks: HashMap<IVec3, ChunkQuads>,
+    stats: QuadMergeStats,
+}
+
+impl TileQuadCache {
+    pub fn get(&self, chunk: &IVec3) -> Option<&ChunkQuads> {
+        self.chunks.get(chunk)
+    }
+
+    /// Merges a chunk from scratch, e.g. a chunk that was in the map before the plugin was added.
+    pub fn rebuild<L>(&mut self, map: &TileMap<L>, chunk: &IVec3) -> Option<&ChunkQuads> {
+        let quads = ChunkQuads::build(map.get_chunk(chunk)?);
+        self.chunks.insert(*chunk, quads);
+        self.chunks.get(chunk)
+    }
+
+    pub fn stats(&self) -> QuadMergeStats {
+        self.stats
+    }
+}
+
+/// Keeps the [`TileQuadCache`] in sync with the map, re-merging the rows touched each update.
+/// [`crate::TilingPlugin`] must be added too.
+pub struct TileQuadPlugin;
+
+impl Plugin for TileQuadPlugin {
+    fn build(&self, app: &mut bevy::prelude::App) {
+        app.init_resource::<TileQuadCache>()
+            .add_system_to_stage(TilingCoreStage::Update, update_tile_quads);
+    }
+}
+
+fn update_tile_quads(
+    mut cache: ResMut<TileQuadCache>,
+    map: Res<TileMap>,
+    updates: Res<TileMapUpdates>,
+) {
+    let cache = &mut *cache;
+    let mut stats = QuadMergeStats::default();
+    let removed: HashSet<IVec3> = updates.get_chunk_removals().copied().collect();
+    let chunks: HashSet<IVec3> = updates
+        .get_chunk_updates()
+        .chain(removed.iter())
+        .copied()
+        .collect();
+    for chunk_coord in chunks {
+        let chunk = match map.get_chunk(&chunk_coord) {
+            Some(chunk) => chunk,
+            None => {
+                cache.chunks.remove(&chunk_coord);
+                continue;
+            }
+        };
+        // A removed chunk that is back was replaced, its cached rows say nothing about it.
+        match cache.chunks.get_mut(&chunk_coord) {
+            Some(quads) if !removed.contains(&chunk_coord) => {
+                let merged = quads.update(chunk, updates.get_chunk_tile_updates(&chunk_coord));
+                stats.chunks_updated += 1;
+                stats.rows_merged += merged;
+                stats.rows_reused += ROWS - merged;
+            }
+            _ => {
+                cache.chunks.insert(chunk_coord, ChunkQuads::build(chunk));
+                stats.chunks_built += 1;
+                stats.rows_merged += ROWS;
+            }
+        }
+    }
+    cache.stats = stats;
+}
@@ -0,0 +1,174 @@
+use bevy::{
+    math::{IVec3, Vec2},
+    utils::HashSet,
+};
+
+use crate::TileCoord;
+
+/// How the two ends of a rasterized path are finished.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum LineCap {
+    /// The path stops exactly at its first and last point.
+    Butt,
+    /// The path is extended by half its width past both ends.
+    Square,
+    /// Both ends are rounded off with a half disc.
+    Round,
+}
+
+#[derive(Copy, Clone, Debug)]
+pub struct StrokeSettings {
+    /// Width of the path in tiles. Widths below one tile still produce a connected line.
+    pub width: f32,
+    pub cap: LineCap,
+}
+
+impl Default for StrokeSettings {
+    fn default() -> Self {
+        Self {
+            width: 1.0,
+            cap: LineCap::Round,
+        }
+    }
+}
+
+/// Returns every tile covered by a polyline whose points are given in tile units.
+/// Tiles are returned once each, in the order the path first reaches them.
+/// Joints between segments are always rounded so corners never leave gaps.
+pub fn rasterize_polyline(
+    points: &[Vec2],
+    layer: i32,
+    settings: &StrokeSettings,
+) -> Vec<TileCoord> {
+    let half_width = (settings.width * 0.5).max(0.5);
+    let mut seen = HashSet::default();
+    let mut tiles = Vec::new();
+
+    if points.len() == 1 {
+        let segment = Segment {
+            start: points[0],
+            end: points[0],
+            clip_start: false,
+            clip_end: false,
+        };
+        match settings.cap {
+            LineCap::Butt => {}
+            LineCap::Round => segment.rasterize(half_width, layer, &mut seen, &mut tiles),
+            LineCap::Square => {
+                let offset = Vec2::new(half_width, 0.0);
+                Segment {
+                    start: points[0] - offset,
+                    end: points[0] + offset,
+                    clip_start: true,
+                    clip_end: true,
+                }
+                .rasterize(half_width, layer, &mut seen, &mut tiles)
+            }
+        }
+        return tiles;
+    }
+
+    let last = points.len().saturating_sub(2);
+    for (i, pair) in points.windows(2).enumerate() {
+        let mut segment = Segment {
+            start: pair[0],
+            end: pair[1],
+            clip_start: false,
+            clip_end: false,
+        };
+        let direction = (segment.end - segment.start).normalize_or_zero();
+        if i == 0 {
+            match settings.cap {
+                LineCap::Butt => segment.clip_start = true,
+                LineCap::Square => {
+                    segment.start -= direction * half_width;
+                    segment.clip_start = true;
+                }
+                LineCap::Round => {}
+            }
+        }
+        if i == last {
+            match settings.cap {
+                LineCap::Butt => segment.clip_end = true,
+                LineCap::Square => {
+                    segment.end += direction * half_width;
+                    segment.clip_end = true;
+                }
+                LineCap::Round => {}
+            }
+        }
+        segment.rasterize(half_width, layer, &mut seen, &mut tiles);
+    }
+    tiles
+}
+
+/// Flattens a Catmull-Rom spline through `points` into a polyline suitable for [`rasterize_polyline`].
+/// The curve passes through every control point, `samples_per_segment` controls how finely it is sampled.
+pub fn catmull_rom(points: &[Vec2], samples_per_segment: usize) -> Vec<Vec2> {
+    if points.len() < 3 || samples_per_segment == 0 {
+        return points.to_vec();
+    }
+    let mut result = Vec::with_capacity((points.len() - 1) * samples_per_segment + 1);
+    for i in 0..points.len() - 1 {
+        let p0 = points[i.saturating_sub(1)];
+        let p1 = points[i];
+        let p2 = points[i + 1];
+        let p3 = points[(i + 2).min(points.len() - 1)];
+        for step in 0..samples_per_segment {
+            let t = step as f32 / samples_per_segment as f32;
+            let t2 = t * t;
+            let t3 = t2 * t;
+            result.push(
+                0.5 * ((2.0 * p1)
+                    + (p2 - p0) * t
+                    + (2.0 * p0 - 5.0 * p1 + 4.0 * p2 - p3) * t2
+                    + (3.0 * p1 - p0 - 3.0 * p2 + p3) * t3),
+            );
+        }
+    }
+    result.push(points[points.len() - 1]);
+    result
+}
+
+struct Segment {
+    start: Vec2,
+    end: Vec2,
+    clip_start: bool,
+    clip_end: bool,
+}
+
+impl Segment {
+    fn rasterize(
+        &self,
+        half_width: f32,
+        layer: i32,
+        seen: &mut HashSet<IVec3>,
+        tiles: &mut Vec<TileCoord>,
+    ) {
+        let min = self.start.min(self.end) - half_width;
+        let max = self.start.max(self.end) + half_width;
+        let delta = self.end - self.start;
+        let length_squared = delta.length_squared();
+        for y in min.y.floor() as i32..=max.y.ceil() as i32 {
+            for x in min.x.floor() as i32..=max.x.ceil() as i32 {
+                let center = Vec2::new(x as f32 + 0.5, y as f32 + 0.5);
+                let t = if length_squared > 0.0 {
+                    (center - self.start).dot(delta) / length_squared
+                } else {
+                    0.0
+                };
+                if (self.clip_start && t < 0.0) || (self.clip_end && t > 1.0) {
+                    continue;
+                }
+                let closest = self.start + delta * t.clamp(0.0, 1.0);
+                if closest.distance_squared(center) > half_width * half_width {
+                    continue;
+                }
+                let position = IVec3::new(x, y, layer);
+                if seen.insert(position) {
+                    tiles.push(TileCoord::from_tile_position(position));
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,377 @@
+use bevy::{
+    math::IVec3,
+    utils::{HashMap, HashSet},
+};
+
+use crate::TileCoord;
+
+pub type RegionId = u32;
+
+/// The tiles a region covers, positions are in tile units with z as the layer.
+pub enum RegionShape {
+    /// Every tile between `min` and `max`, inclusive.
+    Rect {
+        min: IVec3,
+        max: IVec3,
+    },
+    Tiles(HashSet<IVec3>),
+}
+
+impl RegionShape {
+    pub fn contains(&self, coord: &TileCoord) -> bool {
+        let position = coord.tile_position();
+        match self {
+            RegionShape::Rect { min, max } => {
+                position.cmpge(*min).all() && position.cmple(*max).all()
+            }
+            RegionShape::Tiles(tiles) => tiles.contains(&position),
+        }
+    }
+
+    /// Number of tiles covered, saturating for rects too large to count.
+    pub fn area(&self) -> u64 {
+        match self {
+            RegionShape::Rect { min, max } => {
+                let size = |min: i32, max: i32| (max as i64 - min as i64 + 1).max(0) as u64;
+                size(min.x, max.x)
+                    .saturating_mul(size(min.y, max.y))
+                    .saturating_mul(size(min.z, max.z))
+            }
+            RegionShape::Tiles(tiles) => tiles.len() as u64,
+        }
+    }
+
+    /// The chunks holding the tiles of a [`RegionShape::Tiles`], None for other shapes.
+    fn tile_chunks(&self) -> Option<HashSet<IVec3>> {
+        match self {
+            RegionShape::Rect { .. } => None,
+            RegionShape::Tiles(tiles) => Some(
+                tiles
+                    .iter()
+                    .map(|position| TileCoord::from_tile_position(*position).chunk)
+                    .collect(),
+            ),
+        }
+    }
+
+    /// The range of chunks a [`RegionShape::Rect`] touches, None for other shapes.
+    fn chunk_box(&self) -> Option<ChunkBox> {
+        match self {
+            RegionShape::Rect { min, max } => Some(ChunkBox {
+                min: TileCoord::from_tile_position(min.min(*max)).chunk,
+                max: TileCoord::from_tile_position(min.max(*max)).chunk,
+            }),
+            RegionShape::Tiles(_) => None,
+        }
+    }
+}
+
+pub struct RegionInfo {
+    pub name: String,
+    pub shape: RegionShape,
+}
+
+/// Named areas of the map, e.g. "Dark Forest", with lookups by tile.
+/// Rects are kept in an R-tree over the chunks they span and tile regions are bucketed by the
+/// chunks holding their tiles, so a lookup only tests regions near the tile and a huge rect costs
+/// no more to add than a small one.
+#[derive(Default)]
+pub struct TileRegions {
+    regions: HashMap<RegionId, RegionInfo>,
+    rects: RectTree,
+    index: HashMap<IVec3, Vec<RegionId>>,
+    next_id: RegionId,
+}
+
+impl TileRegions {
+    /// Adds a rectangular region covering the tiles between `min` and `max`, inclusive.
+    pub fn add_rect(&mut self, name: impl Into<String>, min: IVec3, max: IVec3) -> RegionId {
+        self.add(RegionInfo {
+            name: name.into(),
+            shape: RegionShape::Rect {
+                min: min.min(max),
+                max: min.max(max),
+            },
+        })
+    }
+
+    /// Adds a region made of arbitrary tiles.
+    pub fn add_tiles(
+        &mut self,
+        name: impl Into<String>,
+        tiles: impl IntoIterator<Item = TileCoord>,
+    ) -> RegionId {
+        self.add(RegionInfo {
+            name: name.into(),
+            shape: RegionShape::Tiles(tiles.into_iter().map(|t| t.tile_position()).collect()),
+        })
+    }
+
+    pub fn add(&mut self, region: RegionInfo) -> RegionId {
+        let id = self.next_id;
+        self.next_id += 1;
+        if let Some(chunks) = region.shape.chunk_box() {
+            self.rects.insert(chunks, id);
+        }
+        for chunk in region.shape.tile_chunks().into_iter().flatten() {
+            self.index.entry(chunk).or_default().push(id);
+        }
+        self.regions.insert(id, region);
+        id
+    }
+
+    pub fn remove(&mut self, id: RegionId) -> Option<RegionInfo> {
+        let region = self.regions.remove(&id)?;
+        if let Some(chunks) = region.shape.chunk_box() {
+            self.rects.remove(&chunks, id);
+        }
+        for chunk in region.shape.tile_chunks().into_iter().flatten() {
+            if let Some(ids) = self.index.get_mut(&chunk) {
+                ids.retain(|other| *other != id);
+                if ids.is_empty() {
+                    self.index.remove(&chunk);
+                }
+            }
+        }
+        Some(region)
+    }
+
+    pub fn get(&self, id: RegionId) -> Option<&RegionInfo> {
+        self.regions.get(&id)
+    }
+
+    /// Every region containing the tile.
+    pub fn regions_at(&self, coord: &TileCoord) -> impl Iterator<Item = (RegionId, &RegionInfo)> {
+        let coord = *coord;
+        let mut ids = Vec::new();
+        self.rects.find(&coord.chunk, &mut ids);
+        ids.extend(self.index.get(&coord.chunk).into_iter().flatten());
+        ids.into_iter()
+            .map(|id| (id, &self.regions[&id]))
+            .filter(move |(_, region)| region.shape.contains(&coord))
+    }
+
+    /// The most specific region containing the tile, that is the smallest one when regions overlap.
+    pub fn region_at(&self, coord: &TileCoord) -> Option<&RegionInfo> {
+        self.regions_at(coord)
+            .min_by_key(|(id, region)| (region.shape.area(), *id))
+            .map(|(_, region)| region)
+    }
+}
+
+/// Most entries a node of the [`RectTree`] holds before it is split.
+const MAX_ENTRIES: usize = 8;
+
+/// An inclusive box of chunk coordinates.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+struct ChunkBox {
+    min: IVec3,
+    max: IVec3,
+}
+
+impl ChunkBox {
+    fn contains(&self, chunk: &IVec3) -> bool {
+        chunk.cmpge(self.min).all() && chunk.cmple(self.max).all()
+    }
+
+    fn contains_box(&self, other: &ChunkBox) -> bool {
+        self.contains(&other.min) && self.contains(&other.max)
+    }
+
+    fn union(&self, other: &ChunkBox) -> ChunkBox {
+        ChunkBox {
+            min: self.min.min(other.min),
+            max: self.max.max(other.max),
+        }
+    }
+
+    /// Number of chunks covered, wide enough for boxes spanning the whole coordinate range.
+    fn volume(&self) -> i128 {
+        let size = |min: i32, max: i32| max as i128 - min as i128 + 1;
+        size(self.min.x, self.max.x) * size(self.min.y, self.max.y) * size(self.min.z, self.max.z)
+    }
+
+    fn enlargement(&self, other: &ChunkBox) -> i128 {
+        self.union(other).volume() - self.volume()
+    }
+}
+
+enum RectNode {
+    Leaf(Vec<(ChunkBox, RegionId)>),
+    Branch(Vec<(ChunkBox, RectNode)>),
+}
+
+impl RectNode {
+    fn bounds(&self) -> ChunkBox {
+        fn union<T>(entries: &[(ChunkBox, T)]) -> ChunkBox {
+            entries
+                .iter()
+                .map(|(bounds, _)| *bounds)
+                .reduce(|a, b| a.union(&b))
+                .unwrap()
+        }
+        match self {
+            RectNode::Leaf(entries) => union(entries),
+            RectNode::Branch(children) => union(children),
+        }
+    }
+
+    fn is_empty(&self) -> bool {
+        match self {
+            RectNode::Leaf(entries) => entries.is_empty(),
+            RectNode::Branch(children) => children.is_empty(),
+        }
+    }
+
+    fn find(&self, chunk: &IVec3, ids: &mut Vec<RegionId>) {
+        match self {
+            RectNode::Leaf(entries) => ids.extend(
+                entries
+                    .iter()
+                    .filter(|(bounds, _)| bounds.contains(chunk))
+                    .map(|(_, id)| *id),
+            ),
+            RectNode::Branch(children) => {
+                for (bounds, child) in children {
+                    if bounds.contains(chunk) {
+                        child.find(chunk, ids);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Inserts below this node, returning the node split off if it overflowed.
+    fn insert(&mut self, rect: ChunkBox, id: RegionId) -> Option<RectNode> {
+        match self {
+            RectNode::Leaf(entries) => {
+                entries.push((rect, id));
+                (entries.len() > MAX_ENTRIES).then(|| RectNode::Leaf(split(entries)))
+            }
+            RectNode::Branch(children) => {
+                let best = children
+                    .iter()
+                    .enumerate()
+                    .min_by_key(|(_, (bounds, _))| (bounds.enlargement(&rect), bounds.volume()))
+                    .map(|(index, _)| index)
+                    .unwrap();
+                let (bounds, child) = &mut children[best];
+                let sibling = child.insert(rect, id);
+                *bounds = child.bounds();
+                if let Some(sibling) = sibling {
+                    children.push((sibling.bounds(), sibling));
+                }
+                (children.len() > MAX_ENTRIES).then(|| RectNode::Branch(split(children)))
+            }
+        }
+    }
+
+    /// Removes the entry of `id`, returning whether it was found.
+    /// Children left empty are dropped, so every leaf stays at the same depth.
+    fn remove(&mut self, rect: &ChunkBox, id: RegionId) -> bool {
+        match self {
+            RectNode::Leaf(entries) => {
+                let len = entries.len();
+                entries.retain(|(bounds, other)| !(bounds == rect && *other == id));
+                entries.len() != len
+            }
+            RectNode::Branch(children) => {
+                for index in 0..children.len() {
+                    let (bounds, child) = &mut children[index];
+                    if bounds.contains_box(rect) && child.remove(rect, id) {
+                        if child.is_empty() {
+                            children.swap_remove(index);
+                        } else {
+                            *bounds = child.bounds();
+                        }
+                        return true;
+                    }
+                }
+                false
+            }
+        }
+    }
+}
+
+/// Quadratic split: seeds the two groups with the pair of entries wasting the most space
+/// together, then hands out the rest by least enlargement. Returns the second group.
+fn split<T>(entries: &mut Vec<(ChunkBox, T)>) -> Vec<(ChunkBox, T)> {
+    let mut seeds = (0, 1);
+    let mut worst = i128::MIN;
+    for a in 0..entries.len() {
+        for b in a + 1..entries.len() {
+            let waste = entries[a].0.union(&entries[b].0).volume()
+                - entries[a].0.volume()
+                - entries[b].0.volume();
+            if waste > worst {
+                worst = waste;
+                seeds = (a, b);
+            }
+        }
+    }
+    let mut rest: Vec<(ChunkBox, T)> = std::mem::take(entries);
+    let second_seed = rest.swap_remove(seeds.1);
+    let first_seed = rest.swap_remove(seeds.0);
+    let (mut first_bounds, mut second_bounds) = (first_seed.0, second_seed.0);
+    let mut first = vec![first_seed];
+    let mut second = vec![second_seed];
+    let min_entries = MAX_ENTRIES / 2;
+    while let Some(entry) = rest.pop() {
+        let remaining = rest.len() + 1;
+        let to_first = if first.len() + remaining <= min_entries {
+            true
+        } else if second.len() + remaining <= min_entries {
+            false
+        } else {
+            (first_bounds.enlargement(&entry.0), first.len())
+                <= (second_bounds.enlargement(&entry.0), second.len())
+        };
+        if to_first {
+            first_bounds = first_bounds.union(&entry.0);
+            first.push(entry);
+        } else {
+            second_bounds = second_bounds.union(&entry.0);
+            second.push(entry);
+        }
+    }
+    *entries = first;
+    second
+}
+
+/// R-tree of the chunk ranges of rect regions.
+struct RectTree {
+    root: RectNode,
+}
+
+impl Default for RectTree {
+    fn default() -> Self {
+        Self {
+            root: RectNode::Leaf(Vec::new()),
+        }
+    }
+}
+
+impl RectTree {
+    fn insert(&mut self, rect: ChunkBox, id: RegionId) {
+        if let Some(sibling) = self.root.insert(rect, id) {
+            let root = std::mem::replace(&mut self.root, RectNode::Leaf(Vec::new()));
+            self.root = RectNode::Branch(vec![(root.bounds(), root), (sibling.bounds(), sibling)]);
+        }
+    }
+
+    fn remove(&mut self, rect: &ChunkBox, id: RegionId) {
+        self.root.remove(rect, id);
+        if let RectNode::Branch(children) = &mut self.root {
+            match children.len() {
+                0 => self.root = RectNode::Leaf(Vec::new()),
+                1 => self.root = children.pop().unwrap().1,
+                _ => {}
+            }
+        }
+    }
+
+    /// Pushes the id of every rect touching `chunk`.
+    fn find(&self, chunk: &IVec3, ids: &mut Vec<RegionId>) {
+        self.root.find(chunk, ids);
+    }
+}
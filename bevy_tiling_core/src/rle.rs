@@ -0,0 +1,252 @@
+use std::{
+    collections::VecDeque,
+    sync::{Arc, OnceLock},
+};
+
+use bevy::{
+    math::IVec3,
+    prelude::{Res, ResMut},
+    utils::HashMap,
+};
+
+use crate::{Chunk, ChunkStorage, Tile, TileMap, TileMapUpdates, CHUNK_SIZE};
+
+const TILES: usize = (CHUNK_SIZE * CHUNK_SIZE) as usize;
+
+/// A run of equal positions in an [`RleChunk`].
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TileRun {
+    /// Number of positions in the run, from 1 to 256.
+    pub len: u16,
+    pub tile: Option<Tile>,
+}
+
+/// The positions of a chunk as runs of equal tiles in index order, e.g. to send chunks that
+/// are mostly one tile over the network. Chunks can also be kept in this form in memory, see
+/// [`Chunk::compress`].
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct RleChunk {
+    /// Last index of every run along with its tile.
+    runs: Vec<(u8, Option<Tile>)>,
+}
+
+impl RleChunk {
+    pub fn from_chunk(chunk: &Chunk) -> Self {
+        let mut runs: Vec<(u8, Option<Tile>)> = Vec::new();
+        for index in 0..=u8::MAX {
+            let tile = chunk.get_tile(index).copied();
+            match runs.last_mut() {
+                Some((last, run_tile)) if *run_tile == tile => *last = index,
+                _ => runs.push((index, tile)),
+            }
+        }
+        Self { runs }
+    }
+
+    /// Builds the runs back up, None unless the lengths are at least 1 and add up to 256.
+    pub fn from_runs(runs: impl IntoIterator<Item = TileRun>) -> Option<Self> {
+        let mut end = 0;
+        let mut merged: Vec<(u8, Option<Tile>)> = Vec::new();
+        for run in runs {
+            if run.len == 0 || end + run.len as usize > TILES {
+                return None;
+            }
+            end += run.len as usize;
+            let last = (end - 1) as u8;
+            match merged.last_mut() {
+                Some((previous, tile)) if *tile == run.tile => *previous = last,
+                _ => merged.push((last, run.tile)),
+            }
+        }
+        (end == TILES).then_some(Self { runs: merged })
+    }
+
+    /// A chunk reading from a copy of the runs, it stays compressed until written to.
+    pub fn to_chunk(&self) -> Chunk {
+        let mut chunk = Chunk {
+            storage: ChunkStorage::Compressed(self.clone()),
+            histogram: OnceLock::new(),
+        };
+        chunk.compact();
+        chunk
+    }
+
+    pub fn get_tile(&self, index: u8) -> Option<&Tile> {
+        let run = self.runs.partition_point(|(last, _)| *last < index);
+        self.runs[run].1.as_ref()
+    }
+
+    /// The runs in index order, neighbouring runs never hold the same tile.
+    pub fn runs(&self) -> impl Iterator<Item = TileRun> + '_ {
+        let mut start = 0;
+        self.runs.iter().map(move |(last, tile)| {
+            let len = *last as u16 + 1 - start;
+            start = *last as u16 + 1;
+            TileRun { len, tile: *tile }
+        })
+    }
+
+    /// Number of runs, 1 for uniform chunks.
+    pub fn len(&self) -> usize {
+        self.runs.len()
+    }
+
+    /// Always false, every chunk has at least one run.
+    pub fn is_empty(&self) -> bool {
+        self.runs.is_empty()
+    }
+
+    /// Appends the compact byte form: per run the length minus one, whether a tile is set,
+    /// then the tile's sheet and index as little endian `u16`s and its flags.
+    pub fn encode(&self, bytes: &mut Vec<u8>) {
+        for run in self.runs() {
+            bytes.push((run.len - 1) as u8);
+            match run.tile {
+                Some(tile) => {
+                    bytes.push(1);
+                    bytes.extend_from_slice(&tile.sheet.to_le_bytes());
+                    bytes.extend_from_slice(&tile.index.to_le_bytes());
+                    bytes.push(tile.flags);
+                }
+                None => bytes.push(0),
+            }
+        }
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        self.encode(&mut bytes);
+        bytes
+    }
+
+    /// Reads the form written by [`RleChunk::encode`], None if the bytes are malformed or
+    /// continue past the chunk.
+    pub fn from_bytes(mut bytes: &[u8]) -> Option<Self> {
+        let mut runs = Vec::new();
+        while !bytes.is_empty() {
+            let len = bytes[0] as u16 + 1;
+            let tile = match bytes.get(1)? {
+                0 => {
+                    bytes = &bytes[2..];
+                    None
+                }
+                1 => {
+                    let tile = bytes.get(2..7)?;
+                    bytes = &bytes[7..];
+                    Some(Tile {
+                        sheet: u16::from_le_bytes([tile[0], tile[1]]),
+                        index: u16::from_le_bytes([tile[2], tile[3]]),
+                        flags: tile[4],
+                    })
+                }
+                _ => return None,
+            };
+            runs.push(TileRun { len, tile });
+        }
+        Self::from_runs(runs)
+    }
+}
+
+impl From<&Chunk> for RleChunk {
+    fn from(chunk: &Chunk) -> Self {
+        Self::from_chunk(chunk)
+    }
+}
+
+impl From<&RleChunk> for Chunk {
+    fn from(rle: &RleChunk) -> Self {
+        rle.to_chunk()
+    }
+}
+
+/// Compresses chunks of the [`TileMap`] that weren't updated for a while, see
+/// [`Chunk::compress`]. Off by default, set `idle_frames` to turn it on.
+///
+/// Only chunks with updates are looked at, they are queued by the frame of their last update
+/// and compressed once `idle_frames` passed. Every chunk of the map is queued once when
+/// compression is turned on. Tiles written without updates, e.g. through
+/// [`TileMap::get_chunk_mut`], don't delay compression.
+///
+/// [`crate::history::TileHistory`] only keeps deltas, so recording edits doesn't keep chunks
+/// from being compressed, and rolling back expands the chunks it writes to like any other
+/// edit. Chunks still shared with another owner, like a clone of the map, are left alone since
+/// compressing them would copy them, they are tried again after another `idle_frames`.
+#[derive(Default, Debug)]
+pub struct ChunkCompression {
+    /// Frames a chunk must go without updates before it is compressed.
+    pub idle_frames: Option<u32>,
+    frame: u64,
+    tracking: bool,
+    /// Frame of the last update of every chunk waiting to be compressed.
+    last_update: HashMap<IVec3, u64>,
+    /// Chunks by the frame they were queued in, oldest first. Entries whose chunk was updated
+    /// again since are skipped.
+    queue: VecDeque<(u64, IVec3)>,
+}
+
+impl ChunkCompression {
+    fn queue(&mut self, chunk: IVec3) {
+        self.last_update.insert(chunk, self.frame);
+        self.queue.push_back((self.frame, chunk));
+    }
+}
+
+pub(crate) fn compress_idle_chunks(
+    mut compression: ResMut<ChunkCompression>,
+    mut map: ResMut<TileMap>,
+    updates: Res<TileMapUpdates>,
+) {
+    let compression = &mut *compression;
+    let idle_frames = match compression.idle_frames {
+        Some(idle_frames) => idle_frames as u64,
+        None => {
+            if compression.tracking {
+                compression.tracking = false;
+                compression.last_update.clear();
+                compression.queue.clear();
+            }
+            return;
+        }
+    };
+    compression.frame += 1;
+    if !compression.tracking {
+        compression.tracking = true;
+        for chunk in map.chunks.keys() {
+            compression.queue(*chunk);
+        }
+    }
+    for chunk in updates
+        .get_chunk_updates()
+        .chain(updates.get_chunk_insertions())
+    {
+        compression.queue(*chunk);
+    }
+    for chunk in updates.get_chunk_removals() {
+        if !map.chunks.contains_key(chunk) {
+            compression.last_update.remove(chunk);
+        }
+    }
+
+    let frame = compression.frame;
+    let mut retry = Vec::new();
+    while let Some(&(queued, coord)) = compression.queue.front() {
+        if frame - queued < idle_frames {
+            break;
+        }
+        compression.queue.pop_front();
+        if compression.last_update.get(&coord) != Some(&queued) {
+            continue;
+        }
+        compression.last_update.remove(&coord);
+        if let Some(chunk) = map.chunks.get_mut(&coord) {
+            match Arc::get_mut(chunk) {
+                Some(chunk) => chunk.compress(),
+                None => retry.push(coord),
+            }
+        }
+    }
+    for coord in retry {
+        compression.queue(coord);
+    }
+}
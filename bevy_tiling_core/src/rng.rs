@@ -0,0 +1,38 @@
+use bevy::math::IVec3;
+
+/// Small deterministic generator (splitmix64) used by the procedural helpers.
+/// Seeding it with a chunk coordinate gives every chunk its own reproducible stream.
+#[derive(Clone, Debug)]
+pub(crate) struct ChunkRng {
+    state: u64,
+}
+
+impl ChunkRng {
+    pub(crate) fn new(seed: u64, chunk: IVec3) -> Self {
+        let mut rng = Self { state: seed };
+        for component in [chunk.x, chunk.y, chunk.z] {
+            rng.state ^= component as u32 as u64;
+            rng.next_u64();
+        }
+        rng
+    }
+
+    pub(crate) fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    /// Returns a value in `0..bound`, `bound` must not be zero.
+    #[cfg(any(
+        feature = "autotile",
+        feature = "passes",
+        feature = "structures",
+        feature = "wfc"
+    ))]
+    pub(crate) fn below(&mut self, bound: u64) -> u64 {
+        self.next_u64() % bound
+    }
+}
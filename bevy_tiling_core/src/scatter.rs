@@ -0,0 +1,88 @@
+use bevy::math::{IVec2, IVec3};
+
+use crate::{rng::ChunkRng, Chunk, Tile, TileCoord, CHUNK_SIZE};
+
+/// Controls how [`scatter_chunk`] picks tiles.
+#[derive(Copy, Clone, Debug)]
+pub struct ScatterSettings {
+    /// World seed, mixed with the tile positions so every chunk gets its own placements.
+    pub seed: u64,
+    /// Minimum distance, in tiles, between any two picked tiles of a layer, also across chunks.
+    pub min_spacing: f32,
+    /// Upper bound on the number of tiles picked in a single chunk.
+    pub max_per_chunk: usize,
+}
+
+impl Default for ScatterSettings {
+    fn default() -> Self {
+        Self {
+            seed: 0,
+            min_spacing: 2.0,
+            max_per_chunk: usize::MAX,
+        }
+    }
+}
+
+/// Picks tiles matching `predicate` out of a chunk, keeping at least `min_spacing` tiles between picks.
+///
+/// Every tile position gets a random priority from the seed, and a matching tile is picked if
+/// its priority is the highest of all positions closer than `min_spacing`, whatever their tiles.
+/// Since that only depends on the seed, the spacing holds between picks of neighbouring chunks
+/// without looking at them, and regenerating the same chunk always yields the same placements.
+/// Positions without a matching tile still claim their surroundings, so sparse candidates give
+/// fewer picks than the spacing would allow. With `max_per_chunk` the picks with the highest
+/// priorities are kept.
+pub fn scatter_chunk<F>(
+    chunk: &Chunk,
+    chunk_coord: IVec3,
+    settings: &ScatterSettings,
+    predicate: F,
+) -> Vec<TileCoord>
+where
+    F: Fn(&Tile) -> bool,
+{
+    let min_distance_squared = settings.min_spacing * settings.min_spacing;
+    let reach = settings.min_spacing.ceil().max(0.0) as i32;
+    let offsets: Vec<IVec2> = (-reach..=reach)
+        .flat_map(|y| (-reach..=reach).map(move |x| IVec2::new(x, y)))
+        .filter(|offset| {
+            *offset != IVec2::ZERO && offset.as_vec2().length_squared() < min_distance_squared
+        })
+        .collect();
+    // Ties between priorities are broken by position so the order is total.
+    let priority = |position: IVec2| {
+        let rng_position = IVec3::new(position.x, position.y, chunk_coord.z);
+        (
+            ChunkRng::new(settings.seed, rng_position).next_u64(),
+            position.y,
+            position.x,
+        )
+    };
+
+    let origin = chunk_coord.truncate() * CHUNK_SIZE;
+    let mut picked: Vec<_> = (0..=u8::MAX)
+        .filter(|index| chunk.get_tile(*index).is_some_and(&predicate))
+        .filter_map(|index| {
+            let position = origin + local_position(index);
+            let own = priority(position);
+            offsets
+                .iter()
+                .all(|offset| priority(position + *offset) < own)
+                .then_some((own, index))
+        })
+        .collect();
+    picked.sort_unstable_by(|(a, _), (b, _)| b.cmp(a));
+    picked.truncate(settings.max_per_chunk);
+
+    picked
+        .into_iter()
+        .map(|(_, index)| TileCoord {
+            index,
+            chunk: chunk_coord,
+        })
+        .collect()
+}
+
+fn local_position(index: u8) -> IVec2 {
+    IVec2::new((index % 16) as i32, (index / 16) as i32)
+}
@@ -0,0 +1,98 @@
+use std::collections::BTreeMap;
+
+use bevy::prelude::ResMut;
+
+use crate::{IntoTileCoord, Tile, TileCoord, TileMapWriter};
+
+/// Tile changes queued for later ticks, e.g. crops regrowing, bomb fuses or finished buildings.
+///
+/// One tick passes per frame. Due changes are applied through [`TileMapWriter`] before
+/// `CoreStage::Update`, so they cause updates like any other edit. Changes due on the same tick
+/// are applied in the order they were scheduled. Due changes of tiles locked with
+/// [`crate::locks::TileLocks`] wait for the next tick, ahead of the changes due then.
+#[derive(Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TileSchedule {
+    tick: u64,
+    queue: BTreeMap<u64, Vec<(TileCoord, Option<Tile>)>>,
+}
+
+impl TileSchedule {
+    /// The tick the next due changes are applied for.
+    pub fn tick(&self) -> u64 {
+        self.tick
+    }
+
+    /// Moves the schedule to `tick`, e.g. to continue the timeline of a loaded map.
+    /// Changes due before `tick` are applied on the next run.
+    pub fn set_tick(&mut self, tick: u64) {
+        self.tick = tick;
+    }
+
+    /// Sets or removes a tile once `at_tick` is reached, changes due in the past are applied
+    /// on the next run.
+    pub fn schedule_set(&mut self, coord: impl IntoTileCoord, tile: Option<Tile>, at_tick: u64) {
+        self.queue
+            .entry(at_tick)
+            .or_default()
+            .push((coord.into_tile_coord(), tile));
+    }
+
+    /// Sets or removes a tile `ticks` ticks from now.
+    pub fn schedule_in(&mut self, coord: impl IntoTileCoord, tile: Option<Tile>, ticks: u64) {
+        self.schedule_set(coord, tile, self.tick.saturating_add(ticks));
+    }
+
+    /// Drops every pending change of a tile, returning how many there were.
+    pub fn cancel(&mut self, coord: impl IntoTileCoord) -> usize {
+        let coord = coord.into_tile_coord();
+        let mut cancelled = 0;
+        self.queue.retain(|_, changes| {
+            let len = changes.len();
+            changes.retain(|(pending, _)| *pending != coord);
+            cancelled += len - changes.len();
+            !changes.is_empty()
+        });
+        cancelled
+    }
+
+    /// Every pending change in the order it will be applied, e.g. to save it along with the map.
+    pub fn iter(&self) -> impl Iterator<Item = (u64, &TileCoord, Option<&Tile>)> {
+        self.queue.iter().flat_map(|(tick, changes)| {
+            changes
+                .iter()
+                .map(move |(coord, tile)| (*tick, coord, tile.as_ref()))
+        })
+    }
+
+    /// Number of pending changes.
+    pub fn len(&self) -> usize {
+        self.queue.values().map(Vec::len).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.queue.is_empty()
+    }
+
+    /// Applies the changes due at the current tick or earlier and moves on to the next tick.
+    pub fn run(&mut self, writer: &mut TileMapWriter) {
+        let next = self.tick.saturating_add(1);
+        let later = self.queue.split_off(&next);
+        let due = std::mem::replace(&mut self.queue, later);
+        let (mut locked, unlocked): (Vec<_>, Vec<_>) = due
+            .into_values()
+            .flatten()
+            .partition(|(coord, _)| writer.is_locked(coord));
+        writer.set_tiles(unlocked);
+        if !locked.is_empty() {
+            let queued = self.queue.entry(next).or_default();
+            locked.append(queued);
+            *queued = locked;
+        }
+        self.tick = next;
+    }
+}
+
+pub(crate) fn run_tile_schedule(mut schedule: ResMut<TileSchedule>, mut writer: TileMapWriter) {
+    schedule.run(&mut writer);
+}
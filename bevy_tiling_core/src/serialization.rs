@@ -0,0 +1,217 @@
+//! Serde support for [`Chunk`], [`TileMap`], [`BiomeMap`] and [`ChunkData`], enabled by the
+//! `serde` feature. [`crate::Tile`], [`crate::TileCoord`], [`crate::schedule::TileSchedule`] and
+//! the bounds types derive the traits directly.
+
+use std::{
+    marker::PhantomData,
+    sync::{Arc, OnceLock},
+};
+
+use bevy::math::IVec3;
+use serde::{de::Error, Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::{
+    biome::{BiomeId, BiomeMap},
+    bounds::{MapBounds, MapWrap},
+    chunk_data::ChunkData,
+    rle::{RleChunk, TileRun},
+    Chunk, ChunkStorage, DenseTiles, Tile, TileMap, CHUNK_SIZE,
+};
+
+/// Other chunks either store their set tiles in index order along with a bit per position,
+/// or their runs of equal tiles, whichever is smaller.
+#[derive(Serialize, Deserialize)]
+enum ChunkFormat {
+    Uniform(Option<Tile>),
+    Sparse { mask: [u64; 4], tiles: Vec<Tile> },
+    Runs(Vec<TileRun>),
+}
+
+impl Serialize for Chunk {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let data = match &self.storage {
+            ChunkStorage::Uniform(tile) => ChunkFormat::Uniform(*tile),
+            ChunkStorage::Dense(_) | ChunkStorage::Compressed(_) => {
+                let mut mask = [0u64; 4];
+                let mut tiles = Vec::new();
+                for index in 0..=u8::MAX {
+                    if let Some(tile) = self.get_tile(index) {
+                        mask[index as usize / 64] |= 1 << (index % 64);
+                        tiles.push(*tile);
+                    }
+                }
+                let runs: Vec<TileRun> = RleChunk::from_chunk(self).runs().collect();
+                if runs.len() * 2 < tiles.len() {
+                    ChunkFormat::Runs(runs)
+                } else {
+                    ChunkFormat::Sparse { mask, tiles }
+                }
+            }
+        };
+        data.serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for Chunk {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let (mask, tiles) = match ChunkFormat::deserialize(deserializer)? {
+            ChunkFormat::Uniform(tile) => return Ok(Chunk::uniform(tile)),
+            ChunkFormat::Sparse { mask, tiles } => (mask, tiles),
+            ChunkFormat::Runs(runs) => {
+                return RleChunk::from_runs(runs)
+                    .map(|runs| runs.to_chunk())
+                    .ok_or_else(|| D::Error::custom("chunk runs don't add up to 256 tiles"))
+            }
+        };
+        let set: u32 = mask.iter().map(|bits| bits.count_ones()).sum();
+        if set as usize != tiles.len() {
+            return Err(D::Error::custom(format!(
+                "chunk mask has {} tiles set but {} tiles are stored",
+                set,
+                tiles.len()
+            )));
+        }
+        let mut dense = Box::new(DenseTiles {
+            tiles: [Tile::new(0, 0); 256],
+            valid: [false; 256],
+        });
+        let mut tiles = tiles.into_iter();
+        for index in 0..256 {
+            if mask[index / 64] & (1 << (index % 64)) != 0 {
+                dense.valid[index] = true;
+                dense.tiles[index] = tiles.next().unwrap();
+            }
+        }
+        Ok(Chunk {
+            storage: ChunkStorage::Dense(dense),
+            histogram: OnceLock::new(),
+        })
+    }
+}
+
+#[derive(Serialize)]
+struct TileMapDataRef<'a> {
+    chunks: Vec<(IVec3, &'a Chunk)>,
+    bounds: Option<MapBounds>,
+    wrap: Option<MapWrap>,
+}
+
+#[derive(Deserialize)]
+struct TileMapData {
+    chunks: Vec<(IVec3, Chunk)>,
+    bounds: Option<MapBounds>,
+    wrap: Option<MapWrap>,
+}
+
+/// Chunks are written sorted by layer, then row, so the same map always serializes the same way.
+/// Chunks shared between coordinates are written once per coordinate and no longer shared after
+/// loading.
+impl<L> Serialize for TileMap<L> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut chunks: Vec<(IVec3, &Chunk)> = self
+            .chunks
+            .iter()
+            .map(|(coord, chunk)| (*coord, chunk.as_ref()))
+            .collect();
+        chunks.sort_by_key(|(coord, _)| (coord.z, coord.y, coord.x));
+        TileMapDataRef {
+            chunks,
+            bounds: self.bounds,
+            wrap: self.wrap,
+        }
+        .serialize(serializer)
+    }
+}
+
+impl<'de, L> Deserialize<'de> for TileMap<L> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let data = TileMapData::deserialize(deserializer)?;
+        Ok(TileMap {
+            chunks: data
+                .chunks
+                .into_iter()
+                .map(|(coord, chunk)| (coord, Arc::new(chunk)))
+                .collect(),
+            bounds: data.bounds,
+            wrap: data.wrap,
+            label: PhantomData,
+        })
+    }
+}
+
+#[derive(Serialize)]
+struct BiomeMapDataRef<'a> {
+    cell_size: u8,
+    chunks: Vec<(IVec3, &'a [Option<BiomeId>])>,
+}
+
+#[derive(Deserialize)]
+struct BiomeMapData {
+    cell_size: u8,
+    chunks: Vec<(IVec3, Vec<Option<BiomeId>>)>,
+}
+
+/// Chunks are written sorted like the chunks of [`TileMap`].
+impl Serialize for BiomeMap {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut chunks: Vec<(IVec3, &[Option<BiomeId>])> = self
+            .chunks
+            .iter()
+            .map(|(coord, cells)| (*coord, cells.as_slice()))
+            .collect();
+        chunks.sort_by_key(|(coord, _)| (coord.z, coord.y, coord.x));
+        BiomeMapDataRef {
+            cell_size: self.cell_size(),
+            chunks,
+        }
+        .serialize(serializer)
+    }
+}
+
+/// Rejects cell sizes that don't divide the chunk size and chunks with the wrong number of cells.
+impl<'de> Deserialize<'de> for BiomeMap {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let data = BiomeMapData::deserialize(deserializer)?;
+        let cell_size = data.cell_size as i32;
+        if cell_size == 0 || CHUNK_SIZE % cell_size != 0 {
+            return Err(D::Error::custom(format!(
+                "biome cell size {} does not divide the chunk size",
+                cell_size
+            )));
+        }
+        let cells = ((CHUNK_SIZE / cell_size) * (CHUNK_SIZE / cell_size)) as usize;
+        let mut map = BiomeMap::new(data.cell_size);
+        for (coord, chunk) in data.chunks {
+            if chunk.len() != cells {
+                return Err(D::Error::custom(format!(
+                    "biome chunk {} has {} cells instead of {}",
+                    coord,
+                    chunk.len(),
+                    cells
+                )));
+            }
+            map.chunks.insert(coord, chunk);
+        }
+        Ok(map)
+    }
+}
+
+/// Written as a list of chunk coordinates and their data, sorted like the chunks of [`TileMap`].
+impl<T: Serialize> Serialize for ChunkData<T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut chunks: Vec<(IVec3, &T)> =
+            self.iter().map(|(coord, data)| (*coord, data)).collect();
+        chunks.sort_by_key(|(coord, _)| (coord.z, coord.y, coord.x));
+        chunks.serialize(serializer)
+    }
+}
+
+impl<'de, T: Deserialize<'de>> Deserialize<'de> for ChunkData<T> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let mut data = ChunkData::default();
+        for (coord, value) in Vec::<(IVec3, T)>::deserialize(deserializer)? {
+            data.insert(coord, value);
+        }
+        Ok(data)
+    }
+}
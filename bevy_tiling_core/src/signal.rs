@@ -0,0 +1,386 @@
+use bevy::{
+    math::IVec3,
+    prelude::{Plugin, Res, ResMut},
+    utils::{HashMap, HashSet},
+};
+
+use crate::{Chunk, MapReader, Tile, TileCoord, TileMap, TileMapReader, TilingCoreStage};
+
+/// Identifies a connected network of conductive tiles.
+///
+/// A network keeps its id while it doesn't change and through edits that neither join nor
+/// split it. Joined networks keep the smallest of their ids, and when a network splits, the
+/// part holding its lowest tile, by chunk and then by label, keeps the id. Other networks get
+/// ids never used before.
+pub type NetworkId = u32;
+
+/// Power balance of a single network.
+#[derive(Copy, Clone, Default, PartialEq)]
+pub struct NetworkState {
+    pub supply: f32,
+    pub demand: f32,
+}
+
+impl NetworkState {
+    pub fn is_powered(&self) -> bool {
+        self.supply > 0.0 && self.supply >= self.demand
+    }
+}
+
+/// A component of a chunk, identified by its label.
+type Node = (IVec3, u16);
+
+fn node_order(node: &Node) -> (i32, i32, i32, u16) {
+    (node.0.z, node.0.y, node.0.x, node.1)
+}
+
+/// Right, top, left and bottom neighbours, with the first index of the own border, the first
+/// index of the neighbour's border and the stride along both.
+const BORDERS: [((i32, i32), usize, usize, usize); 4] = [
+    ((1, 0), 15, 0, 16),
+    ((0, 1), 240, 0, 1),
+    ((-1, 0), 0, 15, 16),
+    ((0, -1), 0, 240, 1),
+];
+
+/// Tracks networks of conductive tiles, like wires, and the sources and sinks attached to them.
+///
+/// Each chunk is labeled on its own and only relabeled when its tiles change. Networks are
+/// formed by merging labels that touch across chunk borders, and only the networks reaching
+/// into or bordering relabeled chunks are formed again. Updates without relabeled chunks or
+/// changed sources and sinks do nothing.
+pub struct SignalNetworks {
+    conductive: Box<dyn Fn(&Tile) -> bool + Send + Sync>,
+    chunks: HashMap<IVec3, ChunkLabels>,
+    dirty: HashSet<IVec3>,
+    /// Whether sources or sinks changed since the last balance.
+    unbalanced: bool,
+    network_ids: HashMap<Node, NetworkId>,
+    networks: HashMap<NetworkId, Network>,
+    next_id: NetworkId,
+    sources: HashMap<TileCoord, f32>,
+    sinks: HashMap<TileCoord, f32>,
+}
+
+struct ChunkLabels {
+    /// 0 means not conductive, otherwise a component label local to the chunk.
+    labels: Box<[u16; 256]>,
+}
+
+#[derive(Default)]
+struct Network {
+    nodes: Vec<Node>,
+    state: NetworkState,
+}
+
+impl SignalNetworks {
+    pub fn new(conductive: impl Fn(&Tile) -> bool + Send + Sync + 'static) -> Self {
+        Self {
+            conductive: Box::new(conductive),
+            chunks: HashMap::default(),
+            dirty: HashSet::default(),
+            unbalanced: false,
+            network_ids: HashMap::default(),
+            networks: HashMap::default(),
+            next_id: 0,
+            sources: HashMap::default(),
+            sinks: HashMap::default(),
+        }
+    }
+
+    /// Forces a chunk to be relabeled on the next update,
+    /// needed after edits that bypass update tracking.
+    pub fn mark_dirty(&mut self, chunk: IVec3) {
+        self.dirty.insert(chunk);
+    }
+
+    /// Registers a power source at a tile, replacing any previous output there.
+    pub fn set_source(&mut self, coord: TileCoord, output: f32) {
+        self.sources.insert(coord, output);
+        self.unbalanced = true;
+    }
+
+    pub fn remove_source(&mut self, coord: &TileCoord) -> Option<f32> {
+        self.unbalanced = true;
+        self.sources.remove(coord)
+    }
+
+    /// Registers a power sink at a tile, replacing any previous demand there.
+    pub fn set_sink(&mut self, coord: TileCoord, demand: f32) {
+        self.sinks.insert(coord, demand);
+        self.unbalanced = true;
+    }
+
+    pub fn remove_sink(&mut self, coord: &TileCoord) -> Option<f32> {
+        self.unbalanced = true;
+        self.sinks.remove(coord)
+    }
+
+    /// The network the tile belongs to, if the tile is conductive.
+    pub fn network_at(&self, coord: &TileCoord) -> Option<NetworkId> {
+        let label = self.chunks.get(&coord.chunk)?.labels[coord.index as usize];
+        if label == 0 {
+            return None;
+        }
+        self.network_ids.get(&(coord.chunk, label)).copied()
+    }
+
+    pub fn network_state(&self, network: NetworkId) -> Option<&NetworkState> {
+        self.networks.get(&network).map(|network| &network.state)
+    }
+
+    pub fn network_count(&self) -> usize {
+        self.networks.len()
+    }
+
+    /// Relabels dirty chunks, forms the networks they touch again and recomputes power
+    /// balances.
+    pub fn update(&mut self, map: &TileMap) {
+        if !self.dirty.is_empty() {
+            self.relabel(map);
+            self.unbalanced = true;
+        }
+        if std::mem::take(&mut self.unbalanced) {
+            self.balance();
+        }
+    }
+
+    fn relabel(&mut self, map: &TileMap) {
+        let mut dirty: Vec<IVec3> = self.dirty.drain().collect();
+        dirty.sort_unstable_by_key(|chunk| (chunk.z, chunk.y, chunk.x));
+        let dirty_set: HashSet<IVec3> = dirty.iter().copied().collect();
+
+        // The networks of the dirty chunks and of the labels bordering them are formed again.
+        // Nodes of the dirty chunks remember the networks their tiles belonged to.
+        let mut affected: HashSet<NetworkId> = HashSet::default();
+        let mut previous: HashMap<Node, NetworkId> = HashMap::default();
+        for chunk in dirty.iter() {
+            let old = self.chunks.remove(chunk);
+            let new = map
+                .get_chunk(chunk)
+                .and_then(|tiles| label_chunk(tiles, &self.conductive));
+            if let Some(old) = &old {
+                for index in 0..256 {
+                    let id = match self.network_ids.get(&(*chunk, old.labels[index])) {
+                        Some(id) => *id,
+                        None => continue,
+                    };
+                    affected.insert(id);
+                    let label = new.as_ref().map_or(0, |new| new.labels[index]);
+                    if label != 0 {
+                        let previous = previous.entry((*chunk, label)).or_insert(id);
+                        *previous = (*previous).min(id);
+                    }
+                }
+                for label in old.labels.iter() {
+                    self.network_ids.remove(&(*chunk, *label));
+                }
+            }
+            if let Some(new) = new {
+                self.chunks.insert(*chunk, new);
+            }
+        }
+        for chunk in dirty.iter() {
+            for ((x, y), _, other_start, stride) in BORDERS {
+                let neighbour = *chunk + IVec3::new(x, y, 0);
+                if dirty_set.contains(&neighbour) {
+                    continue;
+                }
+                let labels = match self.chunks.get(&neighbour) {
+                    Some(labels) => labels,
+                    None => continue,
+                };
+                for i in 0..16 {
+                    let label = labels.labels[other_start + i * stride];
+                    if let Some(id) = self.network_ids.get(&(neighbour, label)) {
+                        affected.insert(*id);
+                    }
+                }
+            }
+        }
+
+        let mut nodes: Vec<Node> = Vec::new();
+        for id in affected.iter() {
+            if let Some(network) = self.networks.remove(id) {
+                for node in network.nodes {
+                    if !dirty_set.contains(&node.0) {
+                        previous.insert(node, *id);
+                        nodes.push(node);
+                    }
+                }
+            }
+        }
+        for chunk in dirty.iter() {
+            if let Some(labels) = self.chunks.get(chunk) {
+                let mut seen = [false; 256];
+                for label in labels.labels.iter().copied().filter(|label| *label != 0) {
+                    if !std::mem::replace(&mut seen[label as usize], true) {
+                        nodes.push((*chunk, label));
+                    }
+                }
+            }
+        }
+        nodes.sort_unstable_by_key(node_order);
+        self.form_networks(nodes, &previous);
+    }
+
+    /// Merges the nodes into networks, giving them ids as described by [`NetworkId`].
+    fn form_networks(&mut self, nodes: Vec<Node>, previous: &HashMap<Node, NetworkId>) {
+        let indices: HashMap<Node, usize> = nodes
+            .iter()
+            .enumerate()
+            .map(|(index, node)| (*node, index))
+            .collect();
+        let mut parents: Vec<usize> = (0..nodes.len()).collect();
+        let mut chunks: Vec<IVec3> = nodes.iter().map(|(chunk, _)| *chunk).collect();
+        chunks.dedup();
+        for chunk in chunks {
+            let labels = &self.chunks[&chunk];
+            for ((x, y), own_start, other_start, stride) in BORDERS {
+                let neighbour_chunk = chunk + IVec3::new(x, y, 0);
+                let neighbour = match self.chunks.get(&neighbour_chunk) {
+                    Some(neighbour) => neighbour,
+                    None => continue,
+                };
+                for i in 0..16 {
+                    let a = labels.labels[own_start + i * stride];
+                    let b = neighbour.labels[other_start + i * stride];
+                    if a == 0 || b == 0 {
+                        continue;
+                    }
+                    if let (Some(a), Some(b)) =
+                        (indices.get(&(chunk, a)), indices.get(&(neighbour_chunk, b)))
+                    {
+                        union(&mut parents, *a, *b);
+                    }
+                }
+            }
+        }
+
+        // Components in the order of their lowest node, each with its nodes in order.
+        let mut components: Vec<Vec<Node>> = Vec::new();
+        let mut component_of: HashMap<usize, usize> = HashMap::default();
+        for (index, node) in nodes.iter().enumerate() {
+            let root = find(&mut parents, index);
+            let next = components.len();
+            let component = *component_of.entry(root).or_insert(next);
+            if component == next {
+                components.push(Vec::new());
+            }
+            components[component].push(*node);
+        }
+
+        let mut claimed: HashSet<NetworkId> = HashSet::default();
+        for nodes in components {
+            let mut candidates: Vec<NetworkId> = nodes
+                .iter()
+                .filter_map(|node| previous.get(node).copied())
+                .collect();
+            candidates.sort_unstable();
+            let id = match candidates.into_iter().find(|id| !claimed.contains(id)) {
+                Some(id) => id,
+                None => {
+                    self.next_id += 1;
+                    self.next_id - 1
+                }
+            };
+            claimed.insert(id);
+            for node in nodes.iter() {
+                self.network_ids.insert(*node, id);
+            }
+            self.networks.insert(
+                id,
+                Network {
+                    nodes,
+                    state: NetworkState::default(),
+                },
+            );
+        }
+    }
+
+    fn balance(&mut self) {
+        for network in self.networks.values_mut() {
+            network.state = NetworkState::default();
+        }
+        for (coord, output) in self.sources.iter() {
+            if let Some(network) = self.network_at(coord) {
+                self.networks.get_mut(&network).unwrap().state.supply += output;
+            }
+        }
+        for (coord, demand) in self.sinks.iter() {
+            if let Some(network) = self.network_at(coord) {
+                self.networks.get_mut(&network).unwrap().state.demand += demand;
+            }
+        }
+    }
+}
+
+fn label_chunk(
+    chunk: &Chunk,
+    conductive: &(dyn Fn(&Tile) -> bool + Send + Sync),
+) -> Option<ChunkLabels> {
+    let mut labels = Box::new([0u16; 256]);
+    let mut next_label = 0;
+    let mut stack = Vec::new();
+    for start in 0..256usize {
+        if labels[start] != 0 || !chunk.get_tile(start as u8).is_some_and(conductive) {
+            continue;
+        }
+        next_label += 1;
+        labels[start] = next_label;
+        stack.push(start);
+        while let Some(index) = stack.pop() {
+            let (x, y) = (index % 16, index / 16);
+            let neighbours = [
+                (x > 0).then(|| index - 1),
+                (x < 15).then(|| index + 1),
+                (y > 0).then(|| index - 16),
+                (y < 15).then(|| index + 16),
+            ];
+            for neighbour in neighbours.into_iter().flatten() {
+                if labels[neighbour] == 0 && chunk.get_tile(neighbour as u8).is_some_and(conductive)
+                {
+                    labels[neighbour] = next_label;
+                    stack.push(neighbour);
+                }
+            }
+        }
+    }
+    (next_label > 0).then_some(ChunkLabels { labels })
+}
+
+fn find(parents: &mut [usize], mut node: usize) -> usize {
+    while parents[node] != node {
+        parents[node] = parents[parents[node]];
+        node = parents[node];
+    }
+    node
+}
+
+fn union(parents: &mut [usize], a: usize, b: usize) {
+    let a = find(parents, a);
+    let b = find(parents, b);
+    if a != b {
+        parents[b] = a;
+    }
+}
+
+/// Keeps a user inserted [`SignalNetworks`] resource in sync with tile updates.
+pub struct SignalNetworkPlugin;
+
+impl Plugin for SignalNetworkPlugin {
+    fn build(&self, app: &mut bevy::prelude::App) {
+        app.add_system_to_stage(TilingCoreStage::Update, update_signal_networks);
+    }
+}
+
+fn update_signal_networks(
+    mut networks: ResMut<SignalNetworks>,
+    tile_map_reader: TileMapReader,
+    map: Res<TileMap>,
+) {
+    for chunk in tile_map_reader.get_chunk_updates() {
+        networks.mark_dirty(*chunk);
+    }
+    networks.update(&map);
+}
@@ -0,0 +1,178 @@
+use bevy::{
+    math::IVec3,
+    prelude::{Res, ResMut, SystemLabel},
+    utils::HashSet,
+};
+
+use crate::{Tile, TileCoord, TileMapUpdates, TileMapWriter, CHUNK_SIZE};
+
+type Stitcher = Box<dyn Fn(&ChunkSeam, &mut TileMapWriter) + Send + Sync>;
+
+/// Which way the second chunk of a [`ChunkSeam`] lies from the first.
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
+pub enum SeamAxis {
+    /// The second chunk is the right neighbour, the borders are columns indexed by y.
+    X,
+    /// The second chunk is the upper neighbour, the borders are rows indexed by x.
+    Y,
+}
+
+/// The border between two neighbouring chunks of a layer, passed to the callbacks of
+/// [`ChunkStitching`]. The borders are the tiles of each chunk right at the seam, the tile
+/// `i` of one border touches the tile `i` of the other.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct ChunkSeam {
+    pub first: IVec3,
+    pub second: IVec3,
+    pub axis: SeamAxis,
+    pub first_border: [Option<Tile>; 16],
+    pub second_border: [Option<Tile>; 16],
+}
+
+impl ChunkSeam {
+    /// The coordinate of tile `i` of the first chunk's border.
+    pub fn first_coord(&self, i: usize) -> TileCoord {
+        border_coord(self.first, self.axis, i, CHUNK_SIZE - 1)
+    }
+
+    /// The coordinate of tile `i` of the second chunk's border.
+    pub fn second_coord(&self, i: usize) -> TileCoord {
+        border_coord(self.second, self.axis, i, 0)
+    }
+}
+
+fn border_coord(chunk: IVec3, axis: SeamAxis, i: usize, depth: i32) -> TileCoord {
+    let (x, y) = match axis {
+        SeamAxis::X => (depth, i as i32),
+        SeamAxis::Y => (i as i32, depth),
+    };
+    TileCoord {
+        index: (y * CHUNK_SIZE + x) as u8,
+        chunk,
+    }
+}
+
+/// Callbacks fixing up the seams between chunks of the default map, e.g. to match cliff edges
+/// or continue roads between chunks generated on their own.
+///
+/// Every callback runs once for each seam between a chunk placed with
+/// [`TileMapWriter::insert_chunk`], like a loaded or generated one, and a neighbour the map
+/// has, in [`crate::TilingCoreStage::Schedule`] after the streamed chunks are inserted. Chunks
+/// inserted later in the frame are stitched in the next one. Writes through the writer cause
+/// updates like any other edit, and the borders are read again for each callback so later
+/// ones see what earlier ones wrote.
+#[derive(Default)]
+pub struct ChunkStitching {
+    stitchers: Vec<Stitcher>,
+    /// Insertions seen by this frame's stitching.
+    stitched: HashSet<IVec3>,
+    /// Insertions made after this frame's stitching, stitched in the next frame.
+    pending: HashSet<IVec3>,
+}
+
+impl ChunkStitching {
+    pub fn add(
+        &mut self,
+        stitcher: impl Fn(&ChunkSeam, &mut TileMapWriter) + Send + Sync + 'static,
+    ) {
+        self.stitchers.push(Box::new(stitcher));
+    }
+
+    pub fn len(&self) -> usize {
+        self.stitchers.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.stitchers.is_empty()
+    }
+}
+
+/// Runs the [`ChunkStitching`] callbacks, order systems inserting chunks in
+/// [`crate::TilingCoreStage::Schedule`] before it to stitch them in the same frame.
+#[derive(SystemLabel, PartialEq, Eq, Clone, Hash, Debug)]
+pub struct StitchSystem;
+
+pub(crate) fn stitch_inserted_chunks(
+    mut stitching: ResMut<ChunkStitching>,
+    mut writer: TileMapWriter,
+) {
+    let stitching = &mut *stitching;
+    stitching
+        .stitched
+        .extend(writer.updates.get_chunk_insertions().copied());
+    if stitching.stitchers.is_empty() {
+        stitching.pending.clear();
+        return;
+    }
+    let mut inserted: Vec<IVec3> = stitching
+        .pending
+        .drain()
+        .chain(stitching.stitched.iter().copied())
+        .collect();
+    inserted.sort_unstable_by_key(|chunk| (chunk.z, chunk.y, chunk.x));
+    inserted.dedup();
+
+    let mut seams: Vec<(IVec3, IVec3, SeamAxis)> = Vec::new();
+    for chunk in inserted {
+        for (offset, axis, after) in [
+            ((1, 0), SeamAxis::X, true),
+            ((-1, 0), SeamAxis::X, false),
+            ((0, 1), SeamAxis::Y, true),
+            ((0, -1), SeamAxis::Y, false),
+        ] {
+            let neighbour = writer
+                .chunks
+                .normalize_chunk(&(chunk + IVec3::new(offset.0, offset.1, 0)));
+            // A chunk wrapping onto itself has no seam with another chunk.
+            if neighbour == chunk || writer.chunks.get_chunk(&neighbour).is_none() {
+                continue;
+            }
+            let seam = if after {
+                (chunk, neighbour, axis)
+            } else {
+                (neighbour, chunk, axis)
+            };
+            if !seams.contains(&seam) {
+                seams.push(seam);
+            }
+        }
+    }
+
+    for (first, second, axis) in seams {
+        for stitcher in stitching.stitchers.iter() {
+            let (first_chunk, second_chunk) = match (
+                writer.chunks.get_chunk(&first),
+                writer.chunks.get_chunk(&second),
+            ) {
+                (Some(first_chunk), Some(second_chunk)) => (first_chunk, second_chunk),
+                _ => break,
+            };
+            let mut seam = ChunkSeam {
+                first,
+                second,
+                axis,
+                first_border: [None; 16],
+                second_border: [None; 16],
+            };
+            for i in 0..16 {
+                seam.first_border[i] = first_chunk.get_tile(seam.first_coord(i).index).copied();
+                seam.second_border[i] = second_chunk.get_tile(seam.second_coord(i).index).copied();
+            }
+            stitcher(&seam, &mut writer);
+        }
+    }
+}
+
+/// Keeps the chunks inserted after [`StitchSystem`] for the next frame.
+pub(crate) fn defer_late_insertions(
+    mut stitching: ResMut<ChunkStitching>,
+    updates: Res<TileMapUpdates>,
+) {
+    let stitching = &mut *stitching;
+    for chunk in updates.get_chunk_insertions() {
+        if !stitching.stitched.contains(chunk) {
+            stitching.pending.insert(*chunk);
+        }
+    }
+    stitching.stitched.clear();
+}
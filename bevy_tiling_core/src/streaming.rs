@@ -0,0 +1,275 @@
+use std::{any::TypeId, cmp::Reverse, fmt, marker::PhantomData, ops::RangeInclusive, sync::Arc};
+
+use bevy::{
+    math::{IVec2, IVec3},
+    prelude::{
+        App, Component, EventWriter, GlobalTransform, ParallelSystemDescriptorCoercion, Plugin,
+        Query, Res, ResMut, SystemLabel, With,
+    },
+    utils::{HashMap, HashSet},
+};
+
+use crate::{
+    bounds::MapWrap,
+    dimension::InDimension,
+    generator::{generate_requested_chunks, insert_generated_chunks, TileMapGenerator},
+    grid::TileGrid,
+    priority::ChunkPriorities,
+    Chunk, DefaultMap, MapLabel, TileMapWriter, TilingCoreStage,
+};
+
+/// Keeps the chunks around [`StreamingAnchor`]s resident and unloads the rest, so huge maps
+/// don't need to be loaded completely. Configure it through the [`ChunkStreaming`] resource,
+/// [`crate::TilingPlugin`] must be added too.
+///
+/// Missing chunks entering the load radius are requested with a [`ChunkLoadRequest`],
+/// whoever loads or generates the chunk inserts it with [`TileMapWriter::insert_chunk`].
+/// A [`crate::generator::ChunkGenerator`] set in [`TileMapGenerator`] answers the requests
+/// in the background. Unloaded chunks are removed with [`TileMapWriter::remove_chunk`], which also despawns
+/// their entities when the chunk ECS plugin is used. [`crate::persist::ChunkPersistPlugin`]
+/// saves them to disk and loads them back.
+///
+/// On wrapping maps chunks are requested and stored by their canonical coordinate, see
+/// [`crate::TileMap::normalize_chunk`], and distances are measured around the wrap. Chunks
+/// outside the [`crate::bounds::MapBounds`] are never requested.
+///
+/// This streams the default map, [`crate::dimension::DimensionPlugin`] streams other maps.
+pub struct ChunkStreamingPlugin;
+
+impl Plugin for ChunkStreamingPlugin {
+    fn build(&self, app: &mut App) {
+        add_streaming::<DefaultMap>(app);
+    }
+}
+
+/// Streams the map labeled `L`, its resources and events are the ones labeled `L`.
+pub(crate) fn add_streaming<L: MapLabel>(app: &mut App) {
+    app.init_resource::<ChunkStreaming<L>>()
+        .init_resource::<TileMapGenerator<L>>()
+        .init_resource::<ChunkPriorities<L>>()
+        .add_event::<ChunkLoadRequest<L>>()
+        .add_system_to_stage(
+            TilingCoreStage::Schedule,
+            stream_chunks::<L>.label(StreamingSystem::Stream),
+        )
+        .add_system_to_stage(
+            TilingCoreStage::Schedule,
+            generate_requested_chunks::<L>
+                .label(StreamingSystem::Generate)
+                .after(StreamingSystem::Stream),
+        )
+        .add_system_to_stage(
+            TilingCoreStage::Schedule,
+            insert_generated_chunks::<L>
+                .label(StreamingSystem::Insert)
+                .after(StreamingSystem::Generate),
+        );
+}
+
+#[derive(SystemLabel, PartialEq, Eq, Clone, Hash, Debug)]
+pub enum StreamingSystem {
+    /// Unloads far chunks and requests missing ones.
+    Stream,
+    /// Starts generating requested chunks with the [`TileMapGenerator`].
+    Generate,
+    /// Inserts the chunks that finished generating, or loading with
+    /// [`crate::persist::ChunkPersistPlugin`].
+    Insert,
+}
+
+/// Marks an entity, like a camera or a player, that chunks are streamed around. Anchors stream
+/// the default map unless they have an [`InDimension`].
+#[derive(Component, Default)]
+pub struct StreamingAnchor;
+
+/// Sent when a chunk the map labeled `L` doesn't have entered the load radius, with the
+/// canonical coordinate of the chunk on wrapping maps.
+pub struct ChunkLoadRequest<L: MapLabel = DefaultMap>(pub IVec3, PhantomData<fn() -> L>);
+
+impl<L: MapLabel> ChunkLoadRequest<L> {
+    pub fn new(chunk: IVec3) -> Self {
+        Self(chunk, PhantomData)
+    }
+}
+
+impl<L: MapLabel> Clone for ChunkLoadRequest<L> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<L: MapLabel> Copy for ChunkLoadRequest<L> {}
+
+impl<L: MapLabel> PartialEq for ChunkLoadRequest<L> {
+    fn eq(&self, other: &Self) -> bool {
+        self.0 == other.0
+    }
+}
+
+impl<L: MapLabel> Eq for ChunkLoadRequest<L> {}
+
+impl<L: MapLabel> fmt::Debug for ChunkLoadRequest<L> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("ChunkLoadRequest").field(&self.0).finish()
+    }
+}
+
+/// Settings and state of [`ChunkStreamingPlugin`] for the map labeled `L`. Radii are in chunks
+/// and measured along x and y from the chunk of each anchor, chunks on every layer are unloaded
+/// unless they have a [`ChunkPriorities`] hint.
+pub struct ChunkStreaming<L: MapLabel = DefaultMap> {
+    /// Chunks this close to an anchor are loaded.
+    pub load_radius: i32,
+    /// Chunks further than this from every anchor are unloaded, keep it at or above
+    /// `load_radius` so chunks at the edge don't load and unload as an anchor moves back and forth.
+    pub unload_radius: i32,
+    /// Layers chunks are loaded on.
+    pub layers: RangeInclusive<i32>,
+    /// Keeps unloaded chunks in memory and puts them back instead of requesting them again.
+    pub persist: bool,
+    /// Most chunks loaded or requested per frame, chunks with a higher [`ChunkPriorities`]
+    /// hint go first, then the ones closest to an anchor.
+    pub max_loads_per_frame: usize,
+    requested: HashSet<IVec3>,
+    stored: HashMap<IVec3, Arc<Chunk>>,
+    label: PhantomData<fn() -> L>,
+}
+
+impl<L: MapLabel> Default for ChunkStreaming<L> {
+    fn default() -> Self {
+        Self {
+            load_radius: 2,
+            unload_radius: 3,
+            layers: 0..=0,
+            persist: false,
+            max_loads_per_frame: usize::MAX,
+            requested: HashSet::default(),
+            stored: HashMap::default(),
+            label: PhantomData,
+        }
+    }
+}
+
+impl<L: MapLabel> ChunkStreaming<L> {
+    /// Whether a load was requested for the chunk and it hasn't left the unload radius since.
+    pub fn is_requested(&self, chunk: &IVec3) -> bool {
+        self.requested.contains(chunk)
+    }
+
+    /// Chunks unloaded while `persist` was set, e.g. to save them when the game exits.
+    pub fn stored_chunks(&self) -> impl Iterator<Item = (&IVec3, &Arc<Chunk>)> {
+        self.stored.iter()
+    }
+
+    /// Takes the stored chunks out, e.g. to write them to disk and free the memory.
+    pub fn drain_stored_chunks(&mut self) -> impl Iterator<Item = (IVec3, Arc<Chunk>)> + '_ {
+        self.stored.drain()
+    }
+}
+
+/// Chunks between `a` and `b` along the longer of x and y, going around the wrap if shorter.
+fn chunk_distance(a: IVec2, b: IVec2, wrap: Option<&MapWrap>) -> i32 {
+    let mut offset = (a - b).abs();
+    if let Some(wrap) = wrap {
+        for axis in 0..2 {
+            let period = wrap.period[axis];
+            if period > 0 {
+                let around = offset[axis].rem_euclid(period);
+                offset[axis] = around.min(period - around);
+            }
+        }
+    }
+    offset.max_element()
+}
+
+fn stream_chunks<L: MapLabel>(
+    anchors: Query<(&GlobalTransform, Option<&InDimension>), With<StreamingAnchor>>,
+    grid: Res<TileGrid>,
+    priorities: Res<ChunkPriorities<L>>,
+    mut streaming: ResMut<ChunkStreaming<L>>,
+    mut generator: ResMut<TileMapGenerator<L>>,
+    mut writer: TileMapWriter<L>,
+    mut requests: EventWriter<ChunkLoadRequest<L>>,
+) {
+    let default_map = TypeId::of::<L>() == TypeId::of::<DefaultMap>();
+    let centers: Vec<IVec2> = anchors
+        .iter()
+        .filter(|(_, dimension)| dimension.map_or(default_map, InDimension::is::<L>))
+        .map(|(transform, _)| grid.world_to_tile(transform.translation).chunk.truncate())
+        .collect();
+    // Without anchors there is nothing to tell which chunks are needed, keep everything.
+    if centers.is_empty() {
+        return;
+    }
+    let wrap = writer.chunks.wrap().copied();
+    let bounds = writer.chunks.bounds().copied();
+    let distance = |chunk: &IVec3| {
+        centers
+            .iter()
+            .map(|center| chunk_distance(chunk.truncate(), *center, wrap.as_ref()))
+            .min()
+            .unwrap_or(i32::MAX)
+    };
+    let in_range = |chunk: &IVec3, radius: i32| distance(chunk) <= radius;
+
+    let streaming = &mut *streaming;
+    let unload_radius = streaming.unload_radius.max(streaming.load_radius);
+    let far: Vec<IVec3> = writer
+        .chunks
+        .chunks
+        .keys()
+        .filter(|chunk| {
+            !in_range(chunk, unload_radius)
+                && priorities.get(chunk) == 0
+                && !writer.is_chunk_locked(chunk)
+        })
+        .copied()
+        .collect();
+    for chunk in far {
+        if let Some(data) = writer.remove_chunk(&chunk) {
+            if streaming.persist {
+                streaming.stored.insert(chunk, data);
+            }
+        }
+    }
+    streaming
+        .requested
+        .retain(|chunk| in_range(chunk, unload_radius));
+    // Chunks that left the radius before they finished generating would only be unloaded again.
+    generator.cancel_where(|chunk| !in_range(chunk, unload_radius));
+
+    let radius = streaming.load_radius;
+    let mut missing: HashSet<IVec3> = HashSet::default();
+    for center in centers.iter() {
+        for layer in streaming.layers.clone() {
+            for y in center.y - radius..=center.y + radius {
+                for x in center.x - radius..=center.x + radius {
+                    let chunk = writer.chunks.normalize_chunk(&IVec3::new(x, y, layer));
+                    if bounds.map_or(true, |bounds| bounds.contains_chunk(&chunk))
+                        && writer.chunks.get_chunk(&chunk).is_none()
+                        && !streaming.requested.contains(&chunk)
+                    {
+                        missing.insert(chunk);
+                    }
+                }
+            }
+        }
+    }
+    let mut missing: Vec<IVec3> = missing.into_iter().collect();
+    missing.sort_by_key(|chunk| (Reverse(priorities.get(chunk)), distance(chunk)));
+    for chunk in missing.into_iter().take(streaming.max_loads_per_frame) {
+        // Stored chunks wait until they are unlocked, like generated and loaded ones.
+        if streaming.stored.contains_key(&chunk) && writer.is_chunk_locked(&chunk) {
+            continue;
+        }
+        match streaming.stored.remove(&chunk) {
+            Some(data) => {
+                writer.insert_chunk(&chunk, data);
+            }
+            None => {
+                streaming.requested.insert(chunk);
+                requests.send(ChunkLoadRequest::new(chunk));
+            }
+        }
+    }
+}
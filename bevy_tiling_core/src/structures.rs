@@ -0,0 +1,347 @@
+//! Structures spanning several chunks, like villages or dungeons, placed into generated chunks.
+//!
+//! Structures are planned per region of chunks from a seed, so every chunk can work out which
+//! structures reach into it without the rest of the world existing. Wrapping a generator in a
+//! [`StructureGenerator`] stamps the part of each structure that falls into a chunk when the
+//! chunk is generated, and [`Structures`] keeps track of which parts have been placed so far.
+
+use std::sync::{Arc, Mutex, MutexGuard};
+
+use bevy::{
+    math::IVec3,
+    utils::{HashMap, HashSet},
+};
+
+use crate::{
+    blueprint::TilePatch, generator::ChunkGenerator, rng::ChunkRng, Chunk, TileCoord, CHUNK_SIZE,
+};
+
+/// A structure that can be placed, see [`StructureRegistry::register`].
+#[derive(Clone, Debug)]
+pub struct StructureKind {
+    pub name: String,
+    pub patch: TilePatch,
+    /// Relative chance of picking this kind over the others.
+    pub weight: u32,
+    min: IVec3,
+    max: IVec3,
+}
+
+impl StructureKind {
+    /// The smallest box holding the patch, as offsets from a structure's origin.
+    pub fn extent(&self) -> (IVec3, IVec3) {
+        (self.min, self.max)
+    }
+}
+
+/// Identifies a planned structure by the region it was planned in and its place in the plan.
+/// The z of a region is the layer its structures are placed on.
+#[derive(Copy, Clone, Hash, PartialEq, Eq, Debug)]
+pub struct StructureId {
+    pub region: IVec3,
+    pub index: u32,
+}
+
+impl StructureId {
+    fn placement_order(&self) -> (i32, i32, i32, u32) {
+        (self.region.z, self.region.y, self.region.x, self.index)
+    }
+}
+
+/// A structure picked by the plan of a region, see [`StructureRegistry::plan_region`].
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub struct PlannedStructure {
+    pub id: StructureId,
+    /// Index of the [`StructureKind`] in the registry.
+    pub kind: usize,
+    /// Tile position the patch offsets are relative to.
+    pub origin: IVec3,
+    pub min: IVec3,
+    pub max: IVec3,
+}
+
+impl PlannedStructure {
+    fn overlaps(&self, other: &PlannedStructure) -> bool {
+        self.min.cmple(other.max).all() && self.max.cmpge(other.min).all()
+    }
+}
+
+/// The kinds of structures and how they are spread over the world.
+///
+/// The world is split into square regions of `region_size` chunks per layer. Each region tries
+/// to place `per_region` structures with their origin inside of it, skipping the ones that
+/// would overlap a structure already planned in the same region. Structures of neighbouring
+/// regions may overlap, the ones of the lower region are placed first.
+#[derive(Clone, Debug)]
+pub struct StructureRegistry {
+    seed: u64,
+    region_size: i32,
+    per_region: u32,
+    kinds: Vec<StructureKind>,
+    min: IVec3,
+    max: IVec3,
+}
+
+impl StructureRegistry {
+    /// `region_size` is in chunks and at least 1.
+    pub fn new(seed: u64, region_size: i32) -> Self {
+        Self {
+            seed,
+            region_size: region_size.max(1),
+            per_region: 1,
+            kinds: Vec::new(),
+            min: IVec3::ZERO,
+            max: IVec3::ZERO,
+        }
+    }
+
+    /// Sets how many structures each region tries to place, 1 by default.
+    pub fn with_per_region(mut self, per_region: u32) -> Self {
+        self.per_region = per_region;
+        self
+    }
+
+    /// Adds a kind of structure, returning its index. Kinds with an empty patch or a weight of
+    /// 0 are never picked.
+    pub fn register(&mut self, name: impl Into<String>, patch: TilePatch, weight: u32) -> usize {
+        let (min, max) = {
+            let mut offsets = patch.tiles().map(|(offset, _)| offset);
+            match offsets.next() {
+                Some(first) => offsets.fold((first, first), |(min, max), offset| {
+                    (min.min(offset), max.max(offset))
+                }),
+                None => (IVec3::ZERO, IVec3::ZERO),
+            }
+        };
+        self.min = self.min.min(min);
+        self.max = self.max.max(max);
+        self.kinds.push(StructureKind {
+            name: name.into(),
+            weight: if patch.is_empty() { 0 } else { weight },
+            patch,
+            min,
+            max,
+        });
+        self.kinds.len() - 1
+    }
+
+    pub fn kind(&self, kind: usize) -> Option<&StructureKind> {
+        self.kinds.get(kind)
+    }
+
+    pub fn kinds(&self) -> impl Iterator<Item = &StructureKind> {
+        self.kinds.iter()
+    }
+
+    /// The region holding a tile position.
+    pub fn region_of(&self, position: IVec3) -> IVec3 {
+        let size = self.region_size * CHUNK_SIZE;
+        IVec3::new(
+            position.x.div_euclid(size),
+            position.y.div_euclid(size),
+            position.z,
+        )
+    }
+
+    /// The structures planned in a region, the same every time for the same seed and kinds.
+    pub fn plan_region(&self, region: IVec3) -> Vec<PlannedStructure> {
+        let total: u64 = self.kinds.iter().map(|kind| kind.weight as u64).sum();
+        if total == 0 {
+            return Vec::new();
+        }
+        let size = self.region_size * CHUNK_SIZE;
+        let mut rng = ChunkRng::new(self.seed, region);
+        let mut planned: Vec<PlannedStructure> = Vec::new();
+        for index in 0..self.per_region {
+            let mut pick = rng.below(total);
+            let kind = self
+                .kinds
+                .iter()
+                .position(|kind| match pick.checked_sub(kind.weight as u64) {
+                    Some(rest) => {
+                        pick = rest;
+                        false
+                    }
+                    None => true,
+                })
+                .unwrap_or_default();
+            let origin = IVec3::new(
+                region.x * size + rng.below(size as u64) as i32,
+                region.y * size + rng.below(size as u64) as i32,
+                region.z,
+            );
+            let structure = PlannedStructure {
+                id: StructureId { region, index },
+                kind,
+                origin,
+                min: origin + self.kinds[kind].min,
+                max: origin + self.kinds[kind].max,
+            };
+            if planned.iter().all(|other| !other.overlaps(&structure)) {
+                planned.push(structure);
+            }
+        }
+        planned
+    }
+
+    /// Every planned structure with tiles in the box of a chunk, in the order they are placed.
+    pub fn structures_in_chunk(&self, chunk: IVec3) -> Vec<PlannedStructure> {
+        let chunk_min = IVec3::new(chunk.x * CHUNK_SIZE, chunk.y * CHUNK_SIZE, chunk.z);
+        let chunk_max = chunk_min + IVec3::new(CHUNK_SIZE - 1, CHUNK_SIZE - 1, 0);
+        // Origins of structures reaching into the chunk lie in this box.
+        let low = self.region_of(chunk_min - self.max);
+        let high = self.region_of(chunk_max - self.min);
+        let mut structures = Vec::new();
+        for z in low.z..=high.z {
+            for y in low.y..=high.y {
+                for x in low.x..=high.x {
+                    structures.extend(self.plan_region(IVec3::new(x, y, z)).into_iter().filter(
+                        |structure| {
+                            structure.min.cmple(chunk_max).all()
+                                && structure.max.cmpge(chunk_min).all()
+                        },
+                    ));
+                }
+            }
+        }
+        structures.sort_by_key(|structure| structure.id.placement_order());
+        structures
+    }
+
+    /// The chunks a structure has tiles in.
+    pub fn chunks_of(&self, structure: &PlannedStructure) -> HashSet<IVec3> {
+        self.kinds[structure.kind]
+            .patch
+            .placed_at(structure.origin)
+            .map(|(coord, _)| coord.chunk)
+            .collect()
+    }
+}
+
+#[derive(Default)]
+struct StructureProgress {
+    placed: HashMap<StructureId, HashSet<IVec3>>,
+}
+
+/// A [`StructureRegistry`] along with which parts of the planned structures were placed.
+///
+/// Cloning gives another handle to the same structures, so the one given to a
+/// [`StructureGenerator`] can also be kept as a resource to look up progress.
+#[derive(Clone)]
+pub struct Structures {
+    registry: Arc<StructureRegistry>,
+    progress: Arc<Mutex<StructureProgress>>,
+}
+
+impl Structures {
+    pub fn new(registry: StructureRegistry) -> Self {
+        Self {
+            registry: Arc::new(registry),
+            progress: Default::default(),
+        }
+    }
+
+    fn progress(&self) -> MutexGuard<'_, StructureProgress> {
+        // The lock is never held while calling user code, so it can't be poisoned in practice.
+        self.progress
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    pub fn registry(&self) -> &StructureRegistry {
+        &self.registry
+    }
+
+    /// Wraps a generator so generated chunks get their part of the planned structures.
+    pub fn generator<G: ChunkGenerator>(&self, generator: G) -> StructureGenerator<G> {
+        StructureGenerator {
+            generator,
+            structures: self.clone(),
+        }
+    }
+
+    /// Stamps the parts of the structures reaching into a chunk over its tiles, returning the
+    /// structures that were placed.
+    pub fn place_in_chunk(&self, chunk_coord: IVec3, chunk: &mut Chunk) -> Vec<PlannedStructure> {
+        let structures = self.registry.structures_in_chunk(chunk_coord);
+        for structure in &structures {
+            for (coord, tile) in self.registry.kinds[structure.kind]
+                .patch
+                .placed_at(structure.origin)
+            {
+                if coord.chunk == chunk_coord {
+                    chunk.set_tile(coord.index, Some(*tile));
+                }
+            }
+        }
+        let mut progress = self.progress();
+        for structure in &structures {
+            progress
+                .placed
+                .entry(structure.id)
+                .or_default()
+                .insert(chunk_coord);
+        }
+        structures
+    }
+
+    /// How many of the chunks holding a structure were placed so far, out of how many. None if
+    /// no part of the structure was placed.
+    pub fn placed_chunks(&self, id: StructureId) -> Option<(usize, usize)> {
+        let placed = self.progress().placed.get(&id)?.len();
+        let structure = self
+            .registry
+            .plan_region(id.region)
+            .into_iter()
+            .find(|structure| structure.id == id)?;
+        Some((placed, self.registry.chunks_of(&structure).len()))
+    }
+
+    /// Whether every chunk holding the structure was placed.
+    pub fn is_complete(&self, id: StructureId) -> bool {
+        self.placed_chunks(id)
+            .is_some_and(|(placed, total)| placed == total)
+    }
+
+    /// The structures with at least one placed part, in the order they are placed.
+    pub fn started(&self) -> Vec<StructureId> {
+        let mut ids: Vec<StructureId> = self.progress().placed.keys().copied().collect();
+        ids.sort_unstable_by_key(StructureId::placement_order);
+        ids
+    }
+
+    /// Forgets which parts of a structure were placed, e.g. when its chunks are regenerated.
+    pub fn forget(&self, id: StructureId) -> bool {
+        self.progress().placed.remove(&id).is_some()
+    }
+
+    /// The planned structure with a tile at the coordinate, the one placed last if several
+    /// overlap there.
+    pub fn structure_at(&self, coord: &TileCoord) -> Option<PlannedStructure> {
+        let position = coord.tile_position();
+        self.registry
+            .structures_in_chunk(coord.chunk)
+            .into_iter()
+            .rev()
+            .find(|structure| {
+                self.registry.kinds[structure.kind]
+                    .patch
+                    .tiles()
+                    .any(|(offset, _)| structure.origin + offset == position)
+            })
+    }
+}
+
+/// A [`ChunkGenerator`] stamping [`Structures`] over the chunks another generator produces.
+pub struct StructureGenerator<G> {
+    generator: G,
+    structures: Structures,
+}
+
+impl<G: ChunkGenerator> ChunkGenerator for StructureGenerator<G> {
+    fn generate(&self, chunk_coord: IVec3) -> Chunk {
+        let mut chunk = self.generator.generate(chunk_coord);
+        self.structures.place_in_chunk(chunk_coord, &mut chunk);
+        chunk
+    }
+}
@@ -0,0 +1,82 @@
+use bevy::{math::IVec3, utils::HashMap};
+
+use crate::TileCoord;
+
+/// Typed user data attached to individual tiles, such as moisture or ownership, stored in
+/// chunks laid out like [`crate::TileMap`] so it can be kept alongside the rendered tiles.
+/// Each data type is its own resource, register it with `app.init_resource::<TileDataMap<T>>()`.
+pub struct TileDataMap<T> {
+    chunks: HashMap<IVec3, Box<[Option<T>; 256]>>,
+}
+
+impl<T> Default for TileDataMap<T> {
+    fn default() -> Self {
+        Self {
+            chunks: HashMap::default(),
+        }
+    }
+}
+
+impl<T> TileDataMap<T> {
+    pub fn get(&self, coord: &TileCoord) -> Option<&T> {
+        self.chunks.get(&coord.chunk)?[coord.index as usize].as_ref()
+    }
+
+    pub fn get_mut(&mut self, coord: &TileCoord) -> Option<&mut T> {
+        self.chunks.get_mut(&coord.chunk)?[coord.index as usize].as_mut()
+    }
+
+    /// Attaches data to a tile, or removes it with None, returning the data it replaced.
+    /// A chunk is dropped once none of its tiles hold data.
+    pub fn set(&mut self, coord: &TileCoord, data: Option<T>) -> Option<T> {
+        let chunk = match self.chunks.get_mut(&coord.chunk) {
+            Some(chunk) => chunk,
+            None if data.is_none() => return None,
+            None => self
+                .chunks
+                .entry(coord.chunk)
+                .or_insert_with(|| Box::new(std::array::from_fn(|_| None))),
+        };
+        let clearing = data.is_none();
+        let old = std::mem::replace(&mut chunk[coord.index as usize], data);
+        if clearing && chunk.iter().all(Option::is_none) {
+            self.chunks.remove(&coord.chunk);
+        }
+        old
+    }
+
+    /// Gets the data of a tile, creating it with `f` if the tile has none yet.
+    pub fn get_or_insert_with(&mut self, coord: &TileCoord, f: impl FnOnce() -> T) -> &mut T {
+        self.chunks
+            .entry(coord.chunk)
+            .or_insert_with(|| Box::new(std::array::from_fn(|_| None)))[coord.index as usize]
+            .get_or_insert_with(f)
+    }
+
+    /// The data of every tile in a chunk, indexed like the chunk's tiles.
+    pub fn get_chunk(&self, chunk: &IVec3) -> Option<&[Option<T>; 256]> {
+        self.chunks.get(chunk).map(|data| data.as_ref())
+    }
+
+    /// Removes the data of every tile in a chunk, e.g. when the chunk is unloaded.
+    pub fn remove_chunk(&mut self, chunk: &IVec3) -> bool {
+        self.chunks.remove(chunk).is_some()
+    }
+
+    /// Every tile holding data, in no particular order.
+    pub fn iter(&self) -> impl Iterator<Item = (TileCoord, &T)> {
+        self.chunks.iter().flat_map(|(chunk, data)| {
+            data.iter().enumerate().filter_map(move |(index, data)| {
+                data.as_ref().map(|data| {
+                    (
+                        TileCoord {
+                            index: index as u8,
+                            chunk: *chunk,
+                        },
+                        data,
+                    )
+                })
+            })
+        })
+    }
+}
@@ -0,0 +1,301 @@
+//! Conversion of [Tiled](https://www.mapeditor.org) tile layers into a [`TileMap`], enabled by
+//! the `tiled` feature.
+//!
+//! Tiled numbers tiles with global ids (GIDs) counting across every tileset of a map, each
+//! tileset starting at its `firstgid`. [`TiledTilesets`] maps those ranges to tile sheets,
+//! [`import_tile_layer`] writes a layer's GIDs into the map and [`export_tmx`] writes a part of
+//! the map back into a TMX file. Objects of object layers are
+//! converted with [`TiledObject::to_map_object`] and spawned by the
+//! [`ObjectSpawners`](crate::objects::ObjectSpawners). Loading TMX files through the
+//! `AssetServer` is up to [`crate::tiled_asset`].
+
+use std::{fmt, fmt::Write, num::ParseIntError};
+
+use bevy::{
+    math::{IVec2, IVec3, UVec2, Vec2},
+    utils::HashMap,
+};
+
+use crate::{
+    objects::{MapObject, PropertyValue},
+    Tile, TileCoord, TileMap,
+};
+
+const FLIPPED_HORIZONTALLY: u32 = 0x8000_0000;
+const FLIPPED_VERTICALLY: u32 = 0x4000_0000;
+const FLIPPED_DIAGONALLY: u32 = 0x2000_0000;
+/// Only used by hexagonal maps, ignored.
+const ROTATED_HEXAGONAL: u32 = 0x1000_0000;
+const FLAGS: u32 =
+    FLIPPED_HORIZONTALLY | FLIPPED_VERTICALLY | FLIPPED_DIAGONALLY | ROTATED_HEXAGONAL;
+
+/// The tile sheet used for every tileset of a Tiled map, keyed by the tileset's `firstgid`.
+#[derive(Clone, Default, Debug)]
+pub struct TiledTilesets {
+    /// Sorted by first GID.
+    tilesets: Vec<(u32, u16)>,
+    /// `.tsx` files by first GID, needed for exporting.
+    sources: HashMap<u32, String>,
+}
+
+impl TiledTilesets {
+    /// Maps the tileset starting at `first_gid` to `sheet`, replacing an earlier mapping of
+    /// the same tileset.
+    pub fn add(&mut self, first_gid: u32, sheet: u16) -> &mut Self {
+        match self
+            .tilesets
+            .binary_search_by_key(&first_gid, |(first, _)| *first)
+        {
+            Ok(found) => self.tilesets[found].1 = sheet,
+            Err(at) => self.tilesets.insert(at, (first_gid, sheet)),
+        }
+        self
+    }
+
+    /// Sets the `.tsx` file the tileset starting at `first_gid` is saved in, which
+    /// [`export_tmx`] references.
+    pub fn set_source(&mut self, first_gid: u32, source: impl Into<String>) -> &mut Self {
+        self.sources.insert(first_gid, source.into());
+        self
+    }
+
+    /// The tile a GID stands for, None for GID 0 (no tile), GIDs below every tileset and
+    /// indices that don't fit a [`Tile`].
+    ///
+    /// The flip bits become the tile's flips and rotation, assuming y points up in the map as
+    /// it does in a [`TileMap`]. A diagonal flip is a quarter turn followed by a flip.
+    pub fn tile(&self, gid: u32) -> Option<Tile> {
+        let id = gid & !FLAGS;
+        if id == 0 {
+            return None;
+        }
+        let at = self.tilesets.partition_point(|(first, _)| *first <= id);
+        let (first_gid, sheet) = *self.tilesets.get(at.checked_sub(1)?)?;
+        let index = u16::try_from(id - first_gid).ok()?;
+        let horizontal = gid & FLIPPED_HORIZONTALLY != 0;
+        let vertical = gid & FLIPPED_VERTICALLY != 0;
+        let tile = Tile::new(sheet, index);
+        Some(if gid & FLIPPED_DIAGONALLY != 0 {
+            tile.with_rotation(1).with_flip(horizontal, !vertical)
+        } else {
+            tile.with_flip(horizontal, vertical)
+        })
+    }
+
+    /// The GID of a tile, the inverse of [`TiledTilesets::tile`] up to half turns, which come
+    /// back as flips along both axes. Tiles of sheets with several tilesets use the first of
+    /// them. None if no tileset uses the tile's sheet or the index runs into the flag bits.
+    pub fn gid(&self, tile: &Tile) -> Option<u32> {
+        let (first_gid, _) = self
+            .tilesets
+            .iter()
+            .find(|(_, sheet)| *sheet == tile.sheet())?;
+        let id = first_gid + tile.index() as u32;
+        if id & FLAGS != 0 {
+            return None;
+        }
+        // Half a turn is a flip along both axes.
+        let (mut horizontal, mut vertical) = (tile.flip_x(), tile.flip_y());
+        if tile.rotation() >= 2 {
+            horizontal = !horizontal;
+            vertical = !vertical;
+        }
+        let mut gid = id;
+        if tile.rotation() % 2 == 1 {
+            gid |= FLIPPED_DIAGONALLY;
+            vertical = !vertical;
+        }
+        if horizontal {
+            gid |= FLIPPED_HORIZONTALLY;
+        }
+        if vertical {
+            gid |= FLIPPED_VERTICALLY;
+        }
+        Some(gid)
+    }
+}
+
+/// Reads the contents of a layer's `<data encoding="csv">` element.
+pub fn parse_csv_data(data: &str) -> Result<Vec<u32>, ParseIntError> {
+    data.split(',')
+        .map(str::trim)
+        .filter(|gid| !gid.is_empty())
+        .map(str::parse)
+        .collect()
+}
+
+/// Writes a Tiled tile layer `width` tiles wide into the map, returns how many tiles were set.
+///
+/// Tiled lists rows from the top, the top row lands on the highest y so the map looks the same
+/// as in Tiled. `origin` is the tile position of the bottom left tile, its z the layer.
+/// Empty cells and GIDs [`TiledTilesets`] doesn't know leave the map alone.
+pub fn import_tile_layer<L>(
+    map: &mut TileMap<L>,
+    tilesets: &TiledTilesets,
+    data: &[u32],
+    width: u32,
+    origin: IVec3,
+) -> usize {
+    let tiles = layer_tiles(tilesets, data, width, origin);
+    for (coord, tile) in tiles.iter() {
+        map.set_tile(coord, Some(*tile));
+    }
+    tiles.len()
+}
+
+/// The tiles [`import_tile_layer`] writes.
+pub(crate) fn layer_tiles(
+    tilesets: &TiledTilesets,
+    data: &[u32],
+    width: u32,
+    origin: IVec3,
+) -> Vec<(TileCoord, Tile)> {
+    if width == 0 {
+        return Vec::new();
+    }
+    let width = width as usize;
+    let height = data.len().div_ceil(width);
+    let mut tiles = Vec::new();
+    for (row, gids) in data.chunks(width).enumerate() {
+        let y = (height - 1 - row) as i32;
+        for (x, gid) in gids.iter().enumerate() {
+            if let Some(tile) = tilesets.tile(*gid) {
+                let position = origin + IVec3::new(x as i32, y, 0);
+                tiles.push((TileCoord::from_tile_position(position), tile));
+            }
+        }
+    }
+    tiles
+}
+
+/// Errors returned by [`export_tmx`].
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum TmxExportError {
+    /// No tileset uses the tile's sheet, or the tile's GID doesn't fit, see [`TiledTilesets::gid`].
+    UnknownTile(TileCoord, Tile),
+    /// A tileset has no `.tsx` file, see [`TiledTilesets::set_source`].
+    MissingSource(u32),
+}
+
+impl fmt::Display for TmxExportError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TmxExportError::UnknownTile(coord, tile) => {
+                write!(f, "tile {} at {} has no tileset", tile, coord)
+            }
+            TmxExportError::MissingSource(first_gid) => {
+                write!(f, "tileset with first GID {} has no source", first_gid)
+            }
+        }
+    }
+}
+
+impl std::error::Error for TmxExportError {}
+
+/// Writes the box from `min` to `max` (inclusive, in tiles) of the given map layers as a TMX
+/// map, the counterpart of [`import_tile_layer`]: `min` becomes the bottom left tile and each
+/// layer a tile layer named after its z, in the order given. Every tileset is referenced by its
+/// `.tsx` file. Save the result with `std::fs::write`.
+pub fn export_tmx<L>(
+    map: &TileMap<L>,
+    tilesets: &TiledTilesets,
+    min: IVec2,
+    max: IVec2,
+    layers: &[i32],
+    tile_size: UVec2,
+) -> Result<String, TmxExportError> {
+    let (min, max) = (min.min(max), min.max(max));
+    let size = (max - min + IVec2::ONE).as_uvec2();
+    let mut tmx = String::new();
+    // Writing to a String doesn't fail.
+    let _ = writeln!(tmx, r#"<?xml version="1.0" encoding="UTF-8"?>"#);
+    let _ = writeln!(
+        tmx,
+        r#"<map version="1.10" orientation="orthogonal" renderorder="right-down" width="{}" height="{}" tilewidth="{}" tileheight="{}" infinite="0" nextlayerid="{}" nextobjectid="1">"#,
+        size.x,
+        size.y,
+        tile_size.x,
+        tile_size.y,
+        layers.len() + 1
+    );
+    for (first_gid, _) in tilesets.tilesets.iter() {
+        let source = tilesets
+            .sources
+            .get(first_gid)
+            .ok_or(TmxExportError::MissingSource(*first_gid))?;
+        let _ = writeln!(
+            tmx,
+            r#" <tileset firstgid="{}" source="{}"/>"#,
+            first_gid,
+            escape_xml(source)
+        );
+    }
+    for (id, layer) in layers.iter().enumerate() {
+        let _ = writeln!(
+            tmx,
+            r#" <layer id="{}" name="Layer {}" width="{}" height="{}">"#,
+            id + 1,
+            layer,
+            size.x,
+            size.y
+        );
+        let _ = writeln!(tmx, r#"  <data encoding="csv">"#);
+        for y in (min.y..=max.y).rev() {
+            for x in min.x..=max.x {
+                let coord = TileCoord::from_tile_position(IVec3::new(x, y, *layer));
+                let gid = match map.get_tile(&coord) {
+                    Some(tile) => tilesets
+                        .gid(tile)
+                        .ok_or(TmxExportError::UnknownTile(coord, *tile))?,
+                    None => 0,
+                };
+                let last = x == max.x && y == min.y;
+                let _ = write!(tmx, "{}{}", gid, if last { "" } else { "," });
+            }
+            tmx.push('\n');
+        }
+        let _ = writeln!(tmx, "</data>\n </layer>");
+    }
+    tmx.push_str("</map>\n");
+    Ok(tmx)
+}
+
+fn escape_xml(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('"', "&quot;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+/// An object of a Tiled object layer, with the pixel values of its attributes.
+#[derive(Clone, PartialEq, Debug, Default)]
+pub struct TiledObject {
+    pub name: String,
+    /// The object's `type`, or `class` since Tiled 1.9.
+    pub object_type: String,
+    /// Top left corner in pixels, y pointing down from the top of the map.
+    pub x: f32,
+    pub y: f32,
+    pub width: f32,
+    pub height: f32,
+    pub properties: HashMap<String, PropertyValue>,
+}
+
+impl TiledObject {
+    /// Converts the object into tiles, placed like the tile layers by [`import_tile_layer`]:
+    /// `map_height` is the height of the map in tiles and `origin` the tile position of its
+    /// bottom left tile, its z the layer.
+    pub fn to_map_object(&self, tile_size: Vec2, map_height: u32, origin: IVec3) -> MapObject {
+        let size = Vec2::new(self.width, self.height) / tile_size;
+        let top_left = Vec2::new(self.x, self.y) / tile_size;
+        MapObject {
+            name: self.name.clone(),
+            object_type: self.object_type.clone(),
+            position: origin.truncate().as_vec2()
+                + Vec2::new(top_left.x, map_height as f32 - top_left.y - size.y),
+            layer: origin.z,
+            size,
+            properties: self.properties.clone(),
+        }
+    }
+}
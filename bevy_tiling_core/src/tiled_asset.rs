@@ -0,0 +1,555 @@
+//! Tiled maps loaded through the `AssetServer`, enabled by the `tiled` feature.
+//!
+//! Files ending in `.tmx` load as a [`TiledMap`], along with the `.tsx` files of their external
+//! tilesets. Tile layers need the CSV or XML layer format, which is Tiled's default; infinite
+//! maps work too. Push a loaded or loading map to [`TiledImports`] and [`TiledAssetPlugin`]
+//! writes its tile layers into the [`TileMap`](crate::TileMap) and spawns its objects through the
+//! [`ObjectSpawners`] once it is loaded.
+//!
+//! Every tileset image becomes a tile sheet, numbered by [`TiledSheets`] in the order the
+//! images are first seen, so maps sharing a tileset image share the sheet. Load the images
+//! from [`TiledSheets::image`] to draw the sheets. Tilesets without a single image, like image
+//! collections, get no sheet and their tiles are skipped.
+
+use std::{
+    fmt,
+    path::{Component, Path, PathBuf},
+};
+
+use bevy::{
+    asset::{AddAsset, AssetLoader, Assets, BoxedFuture, Handle, LoadContext, LoadedAsset},
+    math::{IVec3, UVec2},
+    prelude::{Commands, Plugin, Res, ResMut},
+    reflect::TypeUuid,
+    utils::HashMap,
+};
+use roxmltree::{Document, Node};
+
+use crate::{
+    grid::TileGrid,
+    objects::{MapObject, ObjectSpawners, PropertyValue},
+    tiled::{layer_tiles, parse_csv_data, TiledObject, TiledTilesets},
+    Tile, TileCoord, TileMapWriter, TilingCoreStage,
+};
+
+/// Errors of reading TMX and TSX files.
+#[derive(Debug)]
+pub enum TiledError {
+    Xml(roxmltree::Error),
+    /// An element lacks an attribute it needs, or the attribute can't be read.
+    InvalidAttribute {
+        element: String,
+        attribute: String,
+    },
+    /// Tile layer data in an encoding other than CSV or XML, like base64.
+    UnsupportedEncoding(String),
+}
+
+impl fmt::Display for TiledError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TiledError::Xml(error) => write!(f, "invalid XML: {}", error),
+            TiledError::InvalidAttribute { element, attribute } => {
+                write!(f, "<{}> has no valid {} attribute", element, attribute)
+            }
+            TiledError::UnsupportedEncoding(encoding) => write!(
+                f,
+                "tile layer encoding {} is not supported, save the map as CSV",
+                encoding
+            ),
+        }
+    }
+}
+
+impl std::error::Error for TiledError {}
+
+impl From<roxmltree::Error> for TiledError {
+    fn from(error: roxmltree::Error) -> Self {
+        TiledError::Xml(error)
+    }
+}
+
+/// A tileset of a [`TiledMap`].
+#[derive(Clone, PartialEq, Eq, Debug, Default)]
+pub struct TiledTileset {
+    pub first_gid: u32,
+    /// The `.tsx` file of an external tileset, as written in the map.
+    pub source: Option<String>,
+    pub name: String,
+    pub tile_size: UVec2,
+    pub columns: u32,
+    pub tile_count: u32,
+    /// The tileset image, relative to the asset folder once loaded through the `AssetServer`,
+    /// otherwise as written in the file. None for image collections and external tilesets that
+    /// weren't read yet, see [`TiledTileset::read_tsx`].
+    pub image: Option<PathBuf>,
+}
+
+impl TiledTileset {
+    /// Fills in the tileset from the contents of its `.tsx` file.
+    pub fn read_tsx(&mut self, tsx: &str) -> Result<(), TiledError> {
+        let document = Document::parse(tsx)?;
+        self.read_element(document.root_element())
+    }
+
+    fn read_element(&mut self, tileset: Node) -> Result<(), TiledError> {
+        self.name = tileset.attribute("name").unwrap_or_default().to_string();
+        self.tile_size = UVec2::new(
+            attribute(tileset, "tilewidth")?,
+            attribute(tileset, "tileheight")?,
+        );
+        self.columns = optional_attribute(tileset, "columns")?.unwrap_or_default();
+        self.tile_count = optional_attribute(tileset, "tilecount")?.unwrap_or_default();
+        self.image = elements(tileset, "image")
+            .next()
+            .and_then(|image| image.attribute("source"))
+            .map(PathBuf::from);
+        Ok(())
+    }
+}
+
+/// A tile layer of a [`TiledMap`], with the box its GIDs cover in tiles, y pointing down from
+/// the top of the map like in Tiled.
+#[derive(Clone, PartialEq, Eq, Debug, Default)]
+pub struct TiledTileLayer {
+    pub name: String,
+    /// Index of the layer among every tile and object layer of the map.
+    pub layer: i32,
+    pub x: i32,
+    pub y: i32,
+    pub width: u32,
+    pub height: u32,
+    /// Rows from the top, 0 for no tile.
+    pub gids: Vec<u32>,
+}
+
+/// An object layer of a [`TiledMap`].
+#[derive(Clone, PartialEq, Debug, Default)]
+pub struct TiledObjectLayer {
+    pub name: String,
+    /// Index of the layer among every tile and object layer of the map.
+    pub layer: i32,
+    pub objects: Vec<TiledObject>,
+}
+
+/// A Tiled map read from a TMX file, see the [module docs](self). Layers of groups are listed
+/// along with the others.
+#[derive(TypeUuid, Clone, PartialEq, Debug, Default)]
+#[uuid = "0c6f6c1e-3a39-4ad7-9d0a-5f3e28f0b1a4"]
+pub struct TiledMap {
+    /// Size in tiles, the size of the first chunks for infinite maps.
+    pub width: u32,
+    pub height: u32,
+    pub tile_size: UVec2,
+    pub tilesets: Vec<TiledTileset>,
+    pub tile_layers: Vec<TiledTileLayer>,
+    pub object_layers: Vec<TiledObjectLayer>,
+}
+
+impl TiledMap {
+    /// Reads the contents of a TMX file. External tilesets only get their first GID and
+    /// source, read their `.tsx` files with [`TiledTileset::read_tsx`].
+    pub fn from_tmx(tmx: &str) -> Result<Self, TiledError> {
+        let document = Document::parse(tmx)?;
+        let root = document.root_element();
+        let mut map = TiledMap {
+            width: attribute(root, "width")?,
+            height: attribute(root, "height")?,
+            tile_size: UVec2::new(
+                attribute(root, "tilewidth")?,
+                attribute(root, "tileheight")?,
+            ),
+            ..Default::default()
+        };
+        for element in elements(root, "tileset") {
+            let mut tileset = TiledTileset {
+                first_gid: attribute(element, "firstgid")?,
+                ..Default::default()
+            };
+            match element.attribute("source") {
+                Some(source) => tileset.source = Some(source.to_string()),
+                None => tileset.read_element(element)?,
+            }
+            map.tilesets.push(tileset);
+        }
+        map.read_layers(root)?;
+        Ok(map)
+    }
+
+    fn read_layers(&mut self, parent: Node) -> Result<(), TiledError> {
+        for element in parent.children().filter(Node::is_element) {
+            let layer = (self.tile_layers.len() + self.object_layers.len()) as i32;
+            let name = element.attribute("name").unwrap_or_default().to_string();
+            match element.tag_name().name() {
+                "layer" => {
+                    if let Some(data) = elements(element, "data").next() {
+                        self.tile_layers.push(read_tile_layer(data, name, layer)?);
+                    }
+                }
+                "objectgroup" => {
+                    let objects = elements(element, "object")
+                        .map(read_object)
+                        .collect::<Result<_, _>>()?;
+                    self.object_layers.push(TiledObjectLayer {
+                        name,
+                        layer,
+                        objects,
+                    });
+                }
+                "group" => self.read_layers(element)?,
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+
+    /// Maps the tilesets to the sheets of their images, registering images seen for the first
+    /// time.
+    pub fn tilesets(&self, sheets: &mut TiledSheets) -> TiledTilesets {
+        let mut tilesets = TiledTilesets::default();
+        for tileset in self.tilesets.iter() {
+            if let Some(image) = &tileset.image {
+                tilesets.add(tileset.first_gid, sheets.register(image));
+            }
+            if let Some(source) = &tileset.source {
+                tilesets.set_source(tileset.first_gid, source.clone());
+            }
+        }
+        tilesets
+    }
+
+    /// The tiles of every tile layer, placed like [`crate::tiled::import_tile_layer`] places a
+    /// layer: `origin` is the tile position of the bottom left tile of the map and each layer
+    /// goes `layer` layers above it.
+    pub fn tiles(&self, tilesets: &TiledTilesets, origin: IVec3) -> Vec<(TileCoord, Tile)> {
+        let mut tiles = Vec::new();
+        for layer in self.tile_layers.iter() {
+            let bottom = self.height as i32 - layer.y - layer.height as i32;
+            let layer_origin = origin + IVec3::new(layer.x, bottom, layer.layer);
+            tiles.extend(layer_tiles(
+                tilesets,
+                &layer.gids,
+                layer.width,
+                layer_origin,
+            ));
+        }
+        tiles
+    }
+
+    /// The objects of every object layer, placed like [`TiledMap::tiles`].
+    pub fn objects(&self, origin: IVec3) -> Vec<MapObject> {
+        let tile_size = self.tile_size.as_vec2();
+        self.object_layers
+            .iter()
+            .flat_map(|layer| {
+                let origin = origin + IVec3::Z * layer.layer;
+                layer
+                    .objects
+                    .iter()
+                    .map(move |object| object.to_map_object(tile_size, self.height, origin))
+            })
+            .collect()
+    }
+}
+
+fn elements<'a, 'input>(
+    parent: Node<'a, 'input>,
+    name: &'static str,
+) -> impl Iterator<Item = Node<'a, 'input>> {
+    parent
+        .children()
+        .filter(move |child| child.is_element() && child.tag_name().name() == name)
+}
+
+fn optional_attribute<T: std::str::FromStr>(
+    element: Node,
+    name: &str,
+) -> Result<Option<T>, TiledError> {
+    element
+        .attribute(name)
+        .map(|value| {
+            value.parse().map_err(|_| TiledError::InvalidAttribute {
+                element: element.tag_name().name().to_string(),
+                attribute: name.to_string(),
+            })
+        })
+        .transpose()
+}
+
+fn attribute<T: std::str::FromStr>(element: Node, name: &str) -> Result<T, TiledError> {
+    optional_attribute(element, name)?.ok_or_else(|| TiledError::InvalidAttribute {
+        element: element.tag_name().name().to_string(),
+        attribute: name.to_string(),
+    })
+}
+
+/// Reads the GIDs of a `<data>` or `<chunk>` element, the encoding is set on the `<data>`.
+fn read_gids(data: Node, encoding: Option<&str>) -> Result<Vec<u32>, TiledError> {
+    match encoding {
+        Some("csv") => parse_csv_data(data.text().unwrap_or_default()).map_err(|_| {
+            TiledError::InvalidAttribute {
+                element: "data".to_string(),
+                attribute: "csv".to_string(),
+            }
+        }),
+        Some(encoding) => Err(TiledError::UnsupportedEncoding(encoding.to_string())),
+        None => elements(data, "tile")
+            .map(|tile| Ok(optional_attribute(tile, "gid")?.unwrap_or_default()))
+            .collect(),
+    }
+}
+
+/// Reads a layer's `<data>`, merging the chunks of infinite maps into one box.
+fn read_tile_layer(data: Node, name: String, layer: i32) -> Result<TiledTileLayer, TiledError> {
+    let chunks: Vec<Node> = elements(data, "chunk").collect();
+    if chunks.is_empty() {
+        let parent = data.parent_element().unwrap_or(data);
+        return Ok(TiledTileLayer {
+            name,
+            layer,
+            x: 0,
+            y: 0,
+            width: attribute(parent, "width")?,
+            height: attribute(parent, "height")?,
+            gids: read_gids(data, data.attribute("encoding"))?,
+        });
+    }
+    let mut read = Vec::with_capacity(chunks.len());
+    for chunk in chunks {
+        let (x, y): (i32, i32) = (attribute(chunk, "x")?, attribute(chunk, "y")?);
+        let (width, height): (u32, u32) = (attribute(chunk, "width")?, attribute(chunk, "height")?);
+        read.push((
+            x,
+            y,
+            width,
+            height,
+            read_gids(chunk, data.attribute("encoding"))?,
+        ));
+    }
+    let min_x = read.iter().map(|(x, ..)| *x).min().unwrap_or_default();
+    let min_y = read.iter().map(|(_, y, ..)| *y).min().unwrap_or_default();
+    let max_x = read
+        .iter()
+        .map(|(x, _, width, ..)| x + *width as i32)
+        .max()
+        .unwrap_or_default();
+    let max_y = read
+        .iter()
+        .map(|(_, y, _, height, _)| y + *height as i32)
+        .max()
+        .unwrap_or_default();
+    let (width, height) = ((max_x - min_x) as u32, (max_y - min_y) as u32);
+    let mut gids = vec![0; (width * height) as usize];
+    for (x, y, chunk_width, _, chunk_gids) in read {
+        if chunk_width == 0 {
+            continue;
+        }
+        for (row, chunk_row) in chunk_gids.chunks(chunk_width as usize).enumerate() {
+            let start = (y - min_y + row as i32) as usize * width as usize + (x - min_x) as usize;
+            gids[start..start + chunk_row.len()].copy_from_slice(chunk_row);
+        }
+    }
+    Ok(TiledTileLayer {
+        name,
+        layer,
+        x: min_x,
+        y: min_y,
+        width,
+        height,
+        gids,
+    })
+}
+
+fn read_object(object: Node) -> Result<TiledObject, TiledError> {
+    let mut properties = HashMap::default();
+    for property in elements(object, "properties").flat_map(|list| elements(list, "property")) {
+        let name = property.attribute("name").unwrap_or_default().to_string();
+        let text = property
+            .attribute("value")
+            .or_else(|| property.text())
+            .unwrap_or_default();
+        let invalid = || TiledError::InvalidAttribute {
+            element: "property".to_string(),
+            attribute: "value".to_string(),
+        };
+        let value = match property.attribute("type") {
+            Some("bool") => PropertyValue::Bool(text == "true"),
+            Some("int") | Some("object") => {
+                PropertyValue::Int(text.parse().map_err(|_| invalid())?)
+            }
+            Some("float") => PropertyValue::Float(text.parse().map_err(|_| invalid())?),
+            _ => PropertyValue::String(text.to_string()),
+        };
+        properties.insert(name, value);
+    }
+    Ok(TiledObject {
+        name: object.attribute("name").unwrap_or_default().to_string(),
+        object_type: object
+            .attribute("type")
+            .or_else(|| object.attribute("class"))
+            .unwrap_or_default()
+            .to_string(),
+        x: attribute(object, "x")?,
+        y: attribute(object, "y")?,
+        width: optional_attribute(object, "width")?.unwrap_or_default(),
+        height: optional_attribute(object, "height")?.unwrap_or_default(),
+        properties,
+    })
+}
+
+/// Joins a path written in a file to the folder of the file, resolving `..` so the result can
+/// be loaded by the `AssetServer`.
+fn relative_to(file: &Path, path: &str) -> PathBuf {
+    let mut joined = PathBuf::new();
+    for component in file.parent().unwrap_or(file).join(path).components() {
+        match component {
+            Component::ParentDir => {
+                joined.pop();
+            }
+            Component::CurDir => {}
+            component => joined.push(component),
+        }
+    }
+    joined
+}
+
+#[derive(Default)]
+pub struct TiledMapLoader;
+
+impl AssetLoader for TiledMapLoader {
+    fn load<'a>(
+        &'a self,
+        bytes: &'a [u8],
+        load_context: &'a mut LoadContext,
+    ) -> BoxedFuture<'a, Result<(), anyhow::Error>> {
+        Box::pin(async move {
+            let path = load_context.path().to_path_buf();
+            let mut map = TiledMap::from_tmx(std::str::from_utf8(bytes)?)?;
+            for tileset in map.tilesets.iter_mut() {
+                let file = match &tileset.source {
+                    Some(source) => relative_to(&path, source),
+                    None => path.clone(),
+                };
+                if tileset.source.is_some() {
+                    let tsx = load_context.read_asset_bytes(&file).await?;
+                    tileset.read_tsx(std::str::from_utf8(&tsx)?)?;
+                }
+                tileset.image = tileset
+                    .image
+                    .as_ref()
+                    .map(|image| relative_to(&file, &image.to_string_lossy()));
+            }
+            load_context.set_default_asset(LoadedAsset::new(map));
+            Ok(())
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["tmx"]
+    }
+}
+
+/// The tile sheets of the tileset images of imported Tiled maps, see the [module docs](self).
+#[derive(Default, Debug)]
+pub struct TiledSheets {
+    images: Vec<PathBuf>,
+}
+
+impl TiledSheets {
+    /// The sheet of an image, registering the image as the next sheet if it's new.
+    pub fn register(&mut self, image: &Path) -> u16 {
+        let sheet = match self.images.iter().position(|known| known == image) {
+            Some(sheet) => sheet,
+            None => {
+                self.images.push(image.to_path_buf());
+                self.images.len() - 1
+            }
+        };
+        sheet as u16
+    }
+
+    pub fn sheet(&self, image: &Path) -> Option<u16> {
+        self.images
+            .iter()
+            .position(|known| known == image)
+            .map(|sheet| sheet as u16)
+    }
+
+    /// The image of a sheet, relative to the asset folder.
+    pub fn image(&self, sheet: u16) -> Option<&Path> {
+        self.images.get(sheet as usize).map(PathBuf::as_path)
+    }
+
+    /// Every sheet along with its image.
+    pub fn iter(&self) -> impl Iterator<Item = (u16, &Path)> {
+        self.images
+            .iter()
+            .enumerate()
+            .map(|(sheet, image)| (sheet as u16, image.as_path()))
+    }
+}
+
+/// Maps waiting to be imported by [`TiledAssetPlugin`]. Maps are imported once loaded and once
+/// none of their tiles is in a box locked with [`crate::locks::TileLocks`].
+#[derive(Default)]
+pub struct TiledImports {
+    maps: Vec<(Handle<TiledMap>, IVec3)>,
+}
+
+impl TiledImports {
+    /// Imports the map once it is loaded, `origin` being the tile position of its bottom left
+    /// tile and the layer of its first layer.
+    pub fn push(&mut self, map: Handle<TiledMap>, origin: IVec3) {
+        self.maps.push((map, origin));
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.maps.is_empty()
+    }
+}
+
+/// Loads [`TiledMap`]s and imports the ones queued in [`TiledImports`], see the
+/// [module docs](self). [`crate::TilingPlugin`] and the `AssetPlugin` must be added too.
+///
+/// Tiles are written through [`TileMapWriter`] before `CoreStage::Update`, so they cause
+/// updates like any other edit.
+pub struct TiledAssetPlugin;
+
+impl Plugin for TiledAssetPlugin {
+    fn build(&self, app: &mut bevy::prelude::App) {
+        app.add_asset::<TiledMap>()
+            .init_asset_loader::<TiledMapLoader>()
+            .init_resource::<TiledSheets>()
+            .init_resource::<TiledImports>()
+            .add_system_to_stage(TilingCoreStage::Schedule, import_tiled_maps);
+    }
+}
+
+fn import_tiled_maps(
+    mut imports: ResMut<TiledImports>,
+    assets: Res<Assets<TiledMap>>,
+    mut sheets: ResMut<TiledSheets>,
+    (spawners, grid): (Res<ObjectSpawners>, Res<TileGrid>),
+    mut commands: Commands,
+    mut writer: TileMapWriter,
+) {
+    if imports.maps.is_empty() {
+        return;
+    }
+    imports.maps.retain(|(handle, origin)| {
+        let map = match assets.get(handle) {
+            Some(map) => map,
+            None => return true,
+        };
+        let tilesets = map.tilesets(&mut sheets);
+        let tiles = map.tiles(&tilesets, *origin);
+        if tiles.iter().any(|(coord, _)| writer.is_locked(coord)) {
+            return true;
+        }
+        writer.set_tiles(tiles.into_iter().map(|(coord, tile)| (coord, Some(tile))));
+        for object in map.objects(*origin) {
+            spawners.spawn(&mut commands, &grid, &object);
+        }
+        false
+    });
+}
@@ -0,0 +1,160 @@
+//! A [`ChunkBackend`] for browser builds keeping chunks in the page's local storage, enabled by
+//! the `web_storage` feature.
+//!
+//! Every chunk is stored under its own key, the prefix followed by the chunk coordinate like
+//! `map/1,-2,0`, holding the chunk encoded like in a [`crate::persist::ChunkFile`] with one
+//! character per byte. Where local storage can't be used, like outside the browser, in private
+//! modes that disable it or when the page may not access it, [`WebChunkStorage`] keeps the
+//! chunks in memory instead, so streaming keeps working for the session.
+
+use std::io;
+
+use bevy::{
+    math::IVec3,
+    utils::{HashMap, HashSet},
+};
+use web_sys::Storage;
+
+use crate::{
+    persist::{decode_chunk, encode_chunk, ChunkBackend},
+    Chunk,
+};
+
+/// Chunks in local storage or in memory, see the [module docs](self). Create a store with
+/// `ChunkStore::with_backend(WebChunkStorage::new("map/"))`.
+pub struct WebChunkStorage {
+    prefix: String,
+    known: HashSet<IVec3>,
+    /// The chunks of a storage without local storage, None while local storage is used.
+    memory: Option<HashMap<IVec3, Vec<u8>>>,
+}
+
+impl WebChunkStorage {
+    /// Chunks stored under keys starting with `prefix`, picking up the ones saved by earlier
+    /// sessions. Falls back to memory if local storage can't be used.
+    pub fn new(prefix: impl Into<String>) -> Self {
+        let prefix = prefix.into();
+        let storage = match local_storage() {
+            Some(storage) => storage,
+            None => return Self::in_memory(prefix),
+        };
+        let mut known = HashSet::default();
+        for i in 0..storage.length().unwrap_or_default() {
+            if let Some(coord) = storage
+                .key(i)
+                .ok()
+                .flatten()
+                .and_then(|key| parse_key(&prefix, &key))
+            {
+                known.insert(coord);
+            }
+        }
+        Self {
+            prefix,
+            known,
+            memory: None,
+        }
+    }
+
+    /// Chunks kept in memory for this session only.
+    pub fn in_memory(prefix: impl Into<String>) -> Self {
+        Self {
+            prefix: prefix.into(),
+            known: HashSet::default(),
+            memory: Some(HashMap::default()),
+        }
+    }
+
+    /// Whether chunks go to local storage and outlive the page.
+    pub fn is_persistent(&self) -> bool {
+        self.memory.is_none()
+    }
+
+    fn key(&self, coord: &IVec3) -> String {
+        format!("{}{},{},{}", self.prefix, coord.x, coord.y, coord.z)
+    }
+}
+
+impl ChunkBackend for WebChunkStorage {
+    fn chunks(&self) -> Vec<IVec3> {
+        self.known.iter().copied().collect()
+    }
+
+    fn load_chunk(&mut self, coord: &IVec3) -> io::Result<Option<Chunk>> {
+        if !self.known.contains(coord) {
+            return Ok(None);
+        }
+        let bytes = match &self.memory {
+            Some(memory) => memory.get(coord).cloned(),
+            None => storage()?
+                .get_item(&self.key(coord))
+                .map_err(|_| storage_error("reading a chunk failed"))?
+                .map(|text| text_to_bytes(&text))
+                .transpose()?,
+        };
+        bytes.map(|bytes| decode_chunk(&bytes)).transpose()
+    }
+
+    fn save_chunks(&mut self, chunks: &[(IVec3, &Chunk)]) -> io::Result<usize> {
+        for (coord, chunk) in chunks {
+            let mut bytes = Vec::new();
+            encode_chunk(chunk, &mut bytes);
+            match &mut self.memory {
+                Some(memory) => {
+                    memory.insert(*coord, bytes);
+                }
+                // Setting an item fails when the storage quota is used up.
+                None => storage()?
+                    .set_item(&self.key(coord), &bytes_to_text(&bytes))
+                    .map_err(|_| storage_error("local storage is full"))?,
+            }
+            self.known.insert(*coord);
+        }
+        Ok(chunks.len())
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+fn local_storage() -> Option<Storage> {
+    web_sys::window()?.local_storage().ok()?
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn local_storage() -> Option<Storage> {
+    None
+}
+
+/// Local storage for a storage that found it when created, looked up on every use since the
+/// handle can't be sent to IO tasks.
+fn storage() -> io::Result<Storage> {
+    local_storage().ok_or_else(|| storage_error("local storage is no longer available"))
+}
+
+fn storage_error(message: &str) -> io::Error {
+    io::Error::other(message)
+}
+
+fn parse_key(prefix: &str, key: &str) -> Option<IVec3> {
+    let mut values = key.strip_prefix(prefix)?.split(',').map(str::parse::<i32>);
+    let coord = IVec3::new(
+        values.next()?.ok()?,
+        values.next()?.ok()?,
+        values.next()?.ok()?,
+    );
+    values.next().is_none().then_some(coord)
+}
+
+/// Local storage only holds strings, every byte becomes the character with the same code.
+fn bytes_to_text(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| *byte as char).collect()
+}
+
+fn text_to_bytes(text: &str) -> io::Result<Vec<u8>> {
+    text.chars()
+        .map(|c| {
+            u8::try_from(c).map_err(|_| {
+                io::Error::new(io::ErrorKind::InvalidData, "stored chunk isn't a chunk")
+            })
+        })
+        .collect()
+}
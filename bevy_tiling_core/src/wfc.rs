@@ -0,0 +1,500 @@
+//! A wave function collapse [`ChunkGenerator`], filling chunks with tiles that only touch
+//! neighbours their [`WfcRules`] allow.
+//!
+//! Chunks are generated on their own, in any order, yet still fit together: the left column
+//! and bottom row of every chunk are derived from the seed and their position alone, so both
+//! chunks along a seam agree on it, and each chunk collapses its interior between its own seams
+//! and the seams of its right and top neighbours.
+
+use bevy::{
+    math::IVec3,
+    utils::{HashMap, HashSet},
+};
+
+use crate::{
+    generator::ChunkGenerator, rng::ChunkRng, Chunk, Tile, TileCoord, TileMap, CHUNK_SIZE,
+};
+
+const SIZE: usize = CHUNK_SIZE as usize;
+/// Cells per side of a chunk with the seams of its right and top neighbours.
+const SPAN: usize = SIZE + 1;
+
+/// Directions in the order of [`CompiledRules::adjacent`].
+const OFFSETS: [(i32, i32); 4] = [(1, 0), (-1, 0), (0, 1), (0, -1)];
+const RIGHT: usize = 0;
+const UP: usize = 2;
+
+/// Salts keeping the random streams of the parts of a chunk apart.
+const CORNER_SALT: u64 = 0x5EA5_0001;
+const COLUMN_SALT: u64 = 0x5EA5_0002;
+const ROW_SALT: u64 = 0x5EA5_0003;
+const INTERIOR_SALT: u64 = 0x5EA5_0004;
+
+/// The tiles a [`WfcGenerator`] places, with their weights and which tiles may sit next to
+/// each other. Empty cells are a tile like any other, written as None.
+#[derive(Clone, Default, Debug)]
+pub struct WfcRules {
+    tiles: Vec<Option<Tile>>,
+    ids: HashMap<Option<Tile>, usize>,
+    weights: Vec<u32>,
+    /// Pairs of tile ids, the first left of the second.
+    horizontal: HashSet<(usize, usize)>,
+    /// Pairs of tile ids, the first below the second.
+    vertical: HashSet<(usize, usize)>,
+}
+
+impl WfcRules {
+    /// Adds a tile or changes its weight, tiles with a higher weight are picked more often.
+    pub fn add_tile(&mut self, tile: Option<Tile>, weight: u32) -> &mut Self {
+        let id = self.id(tile);
+        self.weights[id] = weight;
+        self
+    }
+
+    /// Allows `right` right of `left`, adding missing tiles with a weight of one.
+    pub fn allow_horizontal(&mut self, left: Option<Tile>, right: Option<Tile>) -> &mut Self {
+        let pair = (self.id(left), self.id(right));
+        self.horizontal.insert(pair);
+        self
+    }
+
+    /// Allows `above` above `below`, adding missing tiles with a weight of one.
+    pub fn allow_vertical(&mut self, below: Option<Tile>, above: Option<Tile>) -> &mut Self {
+        let pair = (self.id(below), self.id(above));
+        self.vertical.insert(pair);
+        self
+    }
+
+    /// Learns the rules from the box from `min` to `max` (inclusive, in tiles) of a sample map:
+    /// every tile in it, weighted by how often it appears, and every pair of neighbours on the
+    /// same layer.
+    pub fn learn<L>(map: &TileMap<L>, min: IVec3, max: IVec3) -> Self {
+        let (min, max) = (min.min(max), min.max(max));
+        let mut rules = Self::default();
+        let tile_at = |x, y, z| {
+            map.get_tile(&TileCoord::from_tile_position(IVec3::new(x, y, z)))
+                .copied()
+        };
+        for z in min.z..=max.z {
+            for y in min.y..=max.y {
+                for x in min.x..=max.x {
+                    let tile = tile_at(x, y, z);
+                    let id = rules.id(tile);
+                    rules.weights[id] = rules.weights[id].saturating_add(1);
+                    if x < max.x {
+                        rules.allow_horizontal(tile, tile_at(x + 1, y, z));
+                    }
+                    if y < max.y {
+                        rules.allow_vertical(tile, tile_at(x, y + 1, z));
+                    }
+                }
+            }
+        }
+        // Every tile started out with a weight of one before being counted.
+        for weight in rules.weights.iter_mut() {
+            *weight = weight.saturating_sub(1);
+        }
+        rules
+    }
+
+    /// Number of tiles.
+    pub fn len(&self) -> usize {
+        self.tiles.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.tiles.is_empty()
+    }
+
+    fn id(&mut self, tile: Option<Tile>) -> usize {
+        match self.ids.get(&tile) {
+            Some(id) => *id,
+            None => {
+                let id = self.tiles.len();
+                self.tiles.push(tile);
+                self.weights.push(1);
+                self.ids.insert(tile, id);
+                id
+            }
+        }
+    }
+}
+
+/// A set of tile ids.
+#[derive(Clone, PartialEq, Eq, Debug)]
+struct TileSet {
+    words: Vec<u64>,
+}
+
+impl TileSet {
+    fn empty(len: usize) -> Self {
+        Self {
+            words: vec![0; len.div_ceil(64)],
+        }
+    }
+
+    fn full(len: usize) -> Self {
+        let mut set = Self::empty(len);
+        for id in 0..len {
+            set.insert(id);
+        }
+        set
+    }
+
+    fn insert(&mut self, id: usize) {
+        self.words[id / 64] |= 1 << (id % 64);
+    }
+
+    fn contains(&self, id: usize) -> bool {
+        self.words[id / 64] & 1 << (id % 64) != 0
+    }
+
+    fn count(&self) -> u32 {
+        self.words.iter().map(|word| word.count_ones()).sum()
+    }
+
+    fn union_with(&mut self, other: &TileSet) {
+        for (word, other) in self.words.iter_mut().zip(other.words.iter()) {
+            *word |= other;
+        }
+    }
+
+    /// Returns whether the set changed.
+    fn intersect_with(&mut self, other: &TileSet) -> bool {
+        let mut changed = false;
+        for (word, other) in self.words.iter_mut().zip(other.words.iter()) {
+            changed |= *word & other != *word;
+            *word &= other;
+        }
+        changed
+    }
+
+    fn iter(&self) -> impl Iterator<Item = usize> + '_ {
+        self.words.iter().enumerate().flat_map(|(index, word)| {
+            (0..64)
+                .filter(move |bit| word & 1 << bit != 0)
+                .map(move |bit| index * 64 + bit)
+        })
+    }
+}
+
+/// [`WfcRules`] with the neighbours of every tile looked up.
+#[derive(Clone, Debug)]
+struct CompiledRules {
+    tiles: Vec<Option<Tile>>,
+    weights: Vec<u32>,
+    /// The tiles allowed next to each tile, by direction: right, left, up, down.
+    adjacent: [Vec<TileSet>; 4],
+    /// The tiles used at chunk corners, see [`CompiledRules::corner_tiles`].
+    corners: Vec<usize>,
+}
+
+impl CompiledRules {
+    fn new(rules: &WfcRules) -> Self {
+        let len = rules.len();
+        let mut adjacent: [Vec<TileSet>; 4] = Default::default();
+        for sets in adjacent.iter_mut() {
+            *sets = vec![TileSet::empty(len); len];
+        }
+        for (left, right) in rules.horizontal.iter() {
+            adjacent[0][*left].insert(*right);
+            adjacent[1][*right].insert(*left);
+        }
+        for (below, above) in rules.vertical.iter() {
+            adjacent[2][*below].insert(*above);
+            adjacent[3][*above].insert(*below);
+        }
+        let mut compiled = Self {
+            tiles: rules.tiles.clone(),
+            weights: rules.weights.clone(),
+            adjacent,
+            corners: Vec::new(),
+        };
+        compiled.corners = compiled.corner_tiles();
+        compiled
+    }
+
+    /// Tiles that can reach each other over a whole seam in both directions, so any two
+    /// corners can be joined. Picked greedily by weight, all tiles if none qualifies.
+    fn corner_tiles(&self) -> Vec<usize> {
+        let len = self.len();
+        // The tiles a seam starting at each tile can end on, per direction.
+        let reach = |direction: usize| -> Vec<TileSet> {
+            (0..len)
+                .map(|start| {
+                    let mut current = TileSet::empty(len);
+                    current.insert(start);
+                    for _ in 0..SIZE {
+                        let mut next = TileSet::empty(len);
+                        for id in current.iter() {
+                            next.union_with(&self.adjacent[direction][id]);
+                        }
+                        current = next;
+                    }
+                    current
+                })
+                .collect()
+        };
+        let (right, up) = (reach(RIGHT), reach(UP));
+        let joins = |a: usize, b: usize| right[a].contains(b) && up[a].contains(b);
+        let mut by_weight: Vec<usize> = (0..len).collect();
+        by_weight.sort_by_key(|id| std::cmp::Reverse(self.weights[*id]));
+        let mut corners: Vec<usize> = Vec::new();
+        for id in by_weight {
+            if joins(id, id)
+                && corners
+                    .iter()
+                    .all(|other| joins(id, *other) && joins(*other, id))
+            {
+                corners.push(id);
+            }
+        }
+        if corners.is_empty() {
+            corners = (0..len).collect();
+        }
+        corners.sort_unstable();
+        corners
+    }
+
+    fn len(&self) -> usize {
+        self.tiles.len()
+    }
+
+    fn allows(&self, direction: usize, from: usize, to: usize) -> bool {
+        self.adjacent[direction][from].contains(to)
+    }
+
+    /// Picks one of `candidates` by weight, None if there are none.
+    fn pick(&self, rng: &mut ChunkRng, candidates: impl Iterator<Item = usize>) -> Option<usize> {
+        let candidates: Vec<usize> = candidates.collect();
+        let total: u64 = candidates.iter().map(|id| self.weights[*id] as u64).sum();
+        if total == 0 {
+            let at = rng.below(candidates.len().max(1) as u64) as usize;
+            return candidates.get(at).copied();
+        }
+        let mut roll = rng.below(total);
+        candidates.into_iter().find(|id| {
+            let weight = self.weights[*id] as u64;
+            match roll.checked_sub(weight) {
+                Some(rest) => {
+                    roll = rest;
+                    false
+                }
+                None => true,
+            }
+        })
+    }
+
+    /// The tile at the corner of a chunk, shared by the four chunks around it.
+    fn corner(&self, seed: u64, corner: IVec3) -> usize {
+        let mut rng = ChunkRng::new(seed ^ CORNER_SALT, corner);
+        self.pick(&mut rng, self.corners.iter().copied())
+            .unwrap_or(0)
+    }
+
+    /// The cells between two corners going in `direction`, picked so the whole line fits
+    /// between them when it can.
+    fn seam(&self, rng: &mut ChunkRng, start: usize, end: usize, direction: usize) -> Vec<usize> {
+        let len = self.len();
+        let cells = SIZE - 1;
+        // reaches[i] holds the tiles of cell i that can still reach `end`.
+        let mut reaches = vec![TileSet::empty(len); cells];
+        let mut next = TileSet::empty(len);
+        next.insert(end);
+        for cell in (0..cells).rev() {
+            for id in 0..len {
+                if next.iter().any(|to| self.allows(direction, id, to)) {
+                    reaches[cell].insert(id);
+                }
+            }
+            next = reaches[cell].clone();
+        }
+        let mut line = Vec::with_capacity(cells);
+        let mut previous = start;
+        for reach in reaches.iter() {
+            let fits = |id: &usize| self.allows(direction, previous, *id);
+            let id = self
+                .pick(rng, reach.iter().filter(fits))
+                // The end can't be reached, keep the line itself whole.
+                .or_else(|| self.pick(rng, (0..len).filter(fits)))
+                .or_else(|| self.pick(rng, 0..len))
+                .unwrap_or(0);
+            line.push(id);
+            previous = id;
+        }
+        line
+    }
+
+    /// The left column of a chunk above its corner.
+    fn column(&self, seed: u64, chunk: IVec3) -> Vec<usize> {
+        let start = self.corner(seed, chunk);
+        let end = self.corner(seed, chunk + IVec3::Y);
+        let mut rng = ChunkRng::new(seed ^ COLUMN_SALT, chunk);
+        self.seam(&mut rng, start, end, UP)
+    }
+
+    /// The bottom row of a chunk right of its corner.
+    fn row(&self, seed: u64, chunk: IVec3) -> Vec<usize> {
+        let start = self.corner(seed, chunk);
+        let end = self.corner(seed, chunk + IVec3::X);
+        let mut rng = ChunkRng::new(seed ^ ROW_SALT, chunk);
+        self.seam(&mut rng, start, end, RIGHT)
+    }
+}
+
+/// The cells of a chunk with the seams of its right and top neighbours, indexed by `y * SPAN + x`.
+struct Frame {
+    cells: Vec<usize>,
+}
+
+impl Frame {
+    fn new(rules: &CompiledRules, seed: u64, chunk: IVec3) -> Self {
+        let mut cells = vec![0; SPAN * SPAN];
+        let mut set = |x: usize, y: usize, id: usize| cells[y * SPAN + x] = id;
+        for (dx, dy) in [(0, 0), (1, 0), (0, 1), (1, 1)] {
+            let corner = rules.corner(seed, chunk + IVec3::new(dx, dy, 0));
+            set(dx as usize * SIZE, dy as usize * SIZE, corner);
+        }
+        for (x, chunk) in [(0, chunk), (SIZE, chunk + IVec3::X)] {
+            for (y, id) in rules.column(seed, chunk).into_iter().enumerate() {
+                set(x, y + 1, id);
+            }
+        }
+        for (y, chunk) in [(0, chunk), (SIZE, chunk + IVec3::Y)] {
+            for (x, id) in rules.row(seed, chunk).into_iter().enumerate() {
+                set(x + 1, y, id);
+            }
+        }
+        Self { cells }
+    }
+
+    fn is_interior(x: i32, y: i32) -> bool {
+        (1..SIZE as i32).contains(&x) && (1..SIZE as i32).contains(&y)
+    }
+
+    /// Collapses the interior, None on a contradiction.
+    fn collapse(&self, rules: &CompiledRules, rng: &mut ChunkRng) -> Option<Vec<usize>> {
+        let mut domains = vec![TileSet::full(rules.len()); SPAN * SPAN];
+        for (cell, id) in self.cells.iter().enumerate() {
+            let (x, y) = ((cell % SPAN) as i32, (cell / SPAN) as i32);
+            if !Frame::is_interior(x, y) {
+                domains[cell] = TileSet::empty(rules.len());
+                domains[cell].insert(*id);
+            }
+        }
+        let mut dirty: Vec<usize> = (0..SPAN * SPAN)
+            .filter(|cell| !Frame::is_interior((cell % SPAN) as i32, (cell / SPAN) as i32))
+            .collect();
+        loop {
+            while let Some(cell) = dirty.pop() {
+                let (x, y) = ((cell % SPAN) as i32, (cell / SPAN) as i32);
+                for (direction, (dx, dy)) in OFFSETS.iter().enumerate() {
+                    let (nx, ny) = (x + dx, y + dy);
+                    if !Frame::is_interior(nx, ny) {
+                        continue;
+                    }
+                    let mut allowed = TileSet::empty(rules.len());
+                    for id in domains[cell].iter() {
+                        allowed.union_with(&rules.adjacent[direction][id]);
+                    }
+                    let neighbour = ny as usize * SPAN + nx as usize;
+                    if domains[neighbour].intersect_with(&allowed) {
+                        if domains[neighbour].count() == 0 {
+                            return None;
+                        }
+                        dirty.push(neighbour);
+                    }
+                }
+            }
+            // Collapse the most constrained cell left.
+            let open: Vec<(usize, u32)> = (0..SPAN * SPAN)
+                .map(|cell| (cell, domains[cell].count()))
+                .filter(|(_, count)| *count > 1)
+                .collect();
+            let fewest = match open.iter().map(|(_, count)| *count).min() {
+                Some(fewest) => fewest,
+                None => break,
+            };
+            let candidates: Vec<usize> = open
+                .iter()
+                .filter(|(_, count)| *count == fewest)
+                .map(|(cell, _)| *cell)
+                .collect();
+            let cell = candidates[rng.below(candidates.len() as u64) as usize];
+            let id = rules.pick(rng, domains[cell].iter())?;
+            domains[cell] = TileSet::empty(rules.len());
+            domains[cell].insert(id);
+            dirty.push(cell);
+        }
+        domains.iter().map(|domain| domain.iter().next()).collect()
+    }
+
+    /// Fills the interior row by row, fitting each cell to its left and bottom neighbours
+    /// when possible. Used when collapsing keeps running into contradictions.
+    fn fill(&self, rules: &CompiledRules, rng: &mut ChunkRng) -> Vec<usize> {
+        let mut cells = self.cells.clone();
+        for y in 1..SIZE {
+            for x in 1..SIZE {
+                let left = cells[y * SPAN + x - 1];
+                let below = cells[(y - 1) * SPAN + x];
+                let fits =
+                    |id: &usize| rules.allows(RIGHT, left, *id) && rules.allows(UP, below, *id);
+                cells[y * SPAN + x] = rules
+                    .pick(rng, (0..rules.len()).filter(fits))
+                    .or_else(|| rules.pick(rng, 0..rules.len()))
+                    .unwrap_or(0);
+            }
+        }
+        cells
+    }
+}
+
+/// Generates chunks by wave function collapse, the same seed always giving the same chunks.
+/// Set it with [`crate::generator::TileMapGenerator::set`].
+///
+/// A chunk whose interior keeps running into contradictions after `attempts` tries is filled
+/// row by row instead, which can break the rules inside that chunk but never along its seams.
+#[derive(Clone, Debug)]
+pub struct WfcGenerator {
+    rules: CompiledRules,
+    seed: u64,
+    /// Tries at collapsing a chunk before falling back to filling it.
+    pub attempts: u32,
+}
+
+impl WfcGenerator {
+    pub fn new(rules: &WfcRules, seed: u64) -> Self {
+        Self {
+            rules: CompiledRules::new(rules),
+            seed,
+            attempts: 8,
+        }
+    }
+
+    pub fn with_attempts(mut self, attempts: u32) -> Self {
+        self.attempts = attempts;
+        self
+    }
+}
+
+impl ChunkGenerator for WfcGenerator {
+    fn generate(&self, chunk_coord: IVec3) -> Chunk {
+        let mut chunk = Chunk::default();
+        if self.rules.len() == 0 {
+            return chunk;
+        }
+        let frame = Frame::new(&self.rules, self.seed, chunk_coord);
+        let mut rng = ChunkRng::new(self.seed ^ INTERIOR_SALT, chunk_coord);
+        let cells = (0..self.attempts)
+            .find_map(|_| frame.collapse(&self.rules, &mut rng))
+            .unwrap_or_else(|| frame.fill(&self.rules, &mut rng));
+        for y in 0..SIZE {
+            for x in 0..SIZE {
+                let tile = self.rules.tiles[cells[y * SPAN + x]];
+                chunk.set_tile((y * SIZE + x) as u8, tile);
+            }
+        }
+        chunk.compact();
+        chunk
+    }
+}
@@ -0,0 +1,100 @@
+use bevy::{
+    math::{IVec2, IVec3},
+    prelude::{Res, ResMut},
+    utils::HashMap,
+};
+
+use crate::{
+    biome::{BiomeId, BiomeMap},
+    Tile, TileMap, TileMapUpdates,
+};
+
+/// Summary of a single chunk kept by [`WorldMap`].
+#[derive(Copy, Clone, PartialEq, Eq, Default, Debug)]
+pub struct WorldMapCell {
+    /// The most common tile of the chunk when it was last updated.
+    pub dominant_tile: Option<Tile>,
+    /// The most common biome of the chunk when it was last updated.
+    pub biome: Option<BiomeId>,
+    /// Set by the game, e.g. once the player has seen the chunk.
+    pub explored: bool,
+}
+
+/// One [`WorldMapCell`] per chunk, for strategic views and world map screens that show the
+/// whole map zoomed out. Cells are refreshed from the chunk updates of the default map and
+/// kept after the chunk is unloaded, so the chunk data doesn't have to stay resident.
+#[derive(Default, Debug)]
+pub struct WorldMap {
+    cells: HashMap<IVec3, WorldMapCell>,
+}
+
+impl WorldMap {
+    pub fn get(&self, chunk: &IVec3) -> Option<&WorldMapCell> {
+        self.cells.get(chunk)
+    }
+
+    pub fn is_explored(&self, chunk: &IVec3) -> bool {
+        self.cells.get(chunk).is_some_and(|cell| cell.explored)
+    }
+
+    pub fn set_explored(&mut self, chunk: IVec3, explored: bool) {
+        self.cells.entry(chunk).or_default().explored = explored;
+    }
+
+    /// Recomputes the cell of a chunk from the map and biomes, leaving it as it is if the chunk
+    /// isn't loaded.
+    pub fn refresh_chunk<L>(&mut self, chunk: &IVec3, map: &TileMap<L>, biomes: &BiomeMap) {
+        let tiles = match map.get_chunk(chunk) {
+            Some(tiles) => tiles,
+            None => return,
+        };
+        let cell = self.cells.entry(*chunk).or_default();
+        cell.dominant_tile = tiles.dominant_tile();
+        cell.biome = biomes.get_chunk_biomes(chunk).and_then(dominant_biome);
+    }
+
+    /// Every cell, in no particular order.
+    pub fn iter(&self) -> impl Iterator<Item = (&IVec3, &WorldMapCell)> {
+        self.cells.iter()
+    }
+
+    /// Pixels of the chunks from `min` to `max` (inclusive) on a layer, one per chunk in
+    /// row-major order with the top row first, e.g. to fill a texture. `color` also gets None
+    /// for chunks without a cell.
+    pub fn to_pixels<P>(
+        &self,
+        layer: i32,
+        min: IVec2,
+        max: IVec2,
+        color: impl Fn(Option<&WorldMapCell>) -> P,
+    ) -> Vec<P> {
+        let (min, max) = (min.min(max), min.max(max));
+        (min.y..=max.y)
+            .rev()
+            .flat_map(|y| (min.x..=max.x).map(move |x| IVec3::new(x, y, layer)))
+            .map(|chunk| color(self.cells.get(&chunk)))
+            .collect()
+    }
+}
+
+fn dominant_biome(cells: &[Option<BiomeId>]) -> Option<BiomeId> {
+    let mut counts: HashMap<BiomeId, usize> = HashMap::default();
+    for biome in cells.iter().flatten() {
+        *counts.entry(*biome).or_default() += 1;
+    }
+    counts
+        .into_iter()
+        .max_by_key(|(biome, count)| (*count, std::cmp::Reverse(*biome)))
+        .map(|(biome, _)| biome)
+}
+
+pub(crate) fn update_world_map(
+    mut world_map: ResMut<WorldMap>,
+    updates: Res<TileMapUpdates>,
+    map: Res<TileMap>,
+    biomes: Res<BiomeMap>,
+) {
+    for chunk in updates.get_chunk_updates() {
+        world_map.refresh_chunk(chunk, &map, &biomes);
+    }
+}
@@ -0,0 +1,4 @@
+<?xml version="1.0" encoding="UTF-8"?>
+<tileset version="1.10" tiledversion="1.10.2" name="terrain" tilewidth="16" tileheight="16" tilecount="64" columns="8">
+ <image source="../images/terrain.png" width="128" height="128"/>
+</tileset>
@@ -0,0 +1,118 @@
+#![cfg(feature = "autotile")]
+
+use bevy::{
+    math::IVec3,
+    prelude::{App, Local},
+};
+use bevy_tiling_core::{
+    autotile::{corner_mask, neighbour_mask, BitmaskMode, Terrain, TerrainRules, TileAutotiler},
+    Tile, TileCoord, TileMap, TileMapWriter, TilingPlugin,
+};
+
+/// A 16 tile wall terrain on sheet 1, the index of each mask is 100 plus the mask.
+fn walls() -> TerrainRules {
+    let mut indices = [0; 16];
+    for (mask, index) in indices.iter_mut().enumerate() {
+        *index = 100 + mask as u16;
+    }
+    let mut rules = TerrainRules::default();
+    rules.add(Terrain::edges(1, indices));
+    rules
+}
+
+fn coord(x: i32, y: i32) -> TileCoord {
+    TileCoord::from_tile_position(IVec3::new(x, y, 0))
+}
+
+/// Paints a wall tile at every position during the next update, like a game system would.
+fn paint(app: &mut App, positions: impl IntoIterator<Item = (i32, i32)>) {
+    let tiles: Vec<(TileCoord, Option<Tile>)> = positions
+        .into_iter()
+        .map(|(x, y)| (coord(x, y), Some(Tile::new(1, 100))))
+        .collect();
+    app.add_system(move |mut writer: TileMapWriter, mut done: Local<bool>| {
+        if !std::mem::replace(&mut *done, true) {
+            writer.set_tiles(tiles.clone());
+        }
+    });
+}
+
+fn index_at(app: &App, x: i32, y: i32) -> Option<u16> {
+    app.world
+        .resource::<TileMap>()
+        .get_tile(&coord(x, y))
+        .map(|tile| tile.index())
+}
+
+#[test]
+fn masks_follow_the_neighbours() {
+    let mut map = TileMap::default();
+    for (x, y) in [(0, 0), (1, 0), (0, 1), (1, 1), (-1, 0)] {
+        map.set_tile(&coord(x, y), Some(Tile::new(0, 0)));
+    }
+    let any = |_: &Tile| true;
+    // Up, right and left are set, down is not.
+    assert_eq!(neighbour_mask(&map, &coord(0, 0), any), 1 | 2 | 8);
+    // Only the up right corner has both of its edges and the corner itself set.
+    assert_eq!(corner_mask(&map, &coord(0, 0), any), 1 | 2 | 8 | 16);
+    assert_eq!(neighbour_mask(&map, &coord(5, 5), any), 0);
+}
+
+#[test]
+fn painted_terrain_resolves_to_fitting_tiles() {
+    let mut app = App::new();
+    app.add_plugin(TilingPlugin);
+    app.world
+        .resource_mut::<TileAutotiler>()
+        .set_terrains(walls());
+
+    // A 3x3 block crossing the chunk border at x = 0.
+    paint(
+        &mut app,
+        (-1..=1).flat_map(|y| (-1..=1).map(move |x| (x, y))),
+    );
+    for _ in 0..4 {
+        app.update();
+    }
+
+    assert!(app.world.resource::<TileAutotiler>().is_settled());
+    assert_eq!(index_at(&app, 0, 0), Some(115));
+    assert_eq!(index_at(&app, -1, -1), Some(100 + (1 | 2)));
+    assert_eq!(index_at(&app, 0, 1), Some(100 + (2 | 4 | 8)));
+    assert_eq!(index_at(&app, 1, 0), Some(100 + (1 | 4 | 8)));
+}
+
+#[test]
+fn budget_spreads_resolving_over_frames() {
+    let mut app = App::new();
+    app.add_plugin(TilingPlugin);
+    {
+        let mut autotiler = app.world.resource_mut::<TileAutotiler>();
+        autotiler.set_terrains(walls());
+        autotiler.budget_per_frame = 4;
+    }
+
+    paint(&mut app, (0..10).map(|x| (x, 0)));
+    app.update();
+    let pending = app.world.resource::<TileAutotiler>().pending();
+    assert!(pending > 0);
+
+    for _ in 0..20 {
+        app.update();
+    }
+    assert!(app.world.resource::<TileAutotiler>().is_settled());
+    assert_eq!(index_at(&app, 0, 0), Some(100 + 2));
+    assert_eq!(index_at(&app, 5, 0), Some(100 + (2 | 8)));
+    assert_eq!(index_at(&app, 9, 0), Some(100 + 8));
+}
+
+#[test]
+fn corner_terrain_falls_back_to_edge_tiles() {
+    let terrain = Terrain::new(2, BitmaskMode::Corners)
+        .with_index(0b1111, 7)
+        .with_index(0xff, 8);
+    assert_eq!(terrain.index(0xff), Some(8));
+    assert_eq!(terrain.index(0b1111 | 16), Some(7));
+    assert_eq!(terrain.index(1), None);
+    assert_eq!(terrain.first_index(), Some(7));
+}
@@ -0,0 +1,86 @@
+#![cfg(feature = "autotile_assets")]
+
+use bevy::{
+    asset::{AssetPlugin, Assets, Handle},
+    core::CorePlugin,
+    math::IVec3,
+    prelude::{App, Local},
+};
+use bevy_tiling_core::{
+    autotile::TileAutotiler,
+    autotile_asset::{ActiveAutotileRules, AutotileAssetPlugin, AutotileRules},
+    Tile, TileCoord, TileMap, TileMapWriter, TilingPlugin,
+};
+
+/// An edge terrain on sheet 1 using `base` plus the mask as the index of the masks of a row.
+fn rules_file(base: u16) -> String {
+    format!(
+        "(terrains: [(sheet: 1, mode: Edges, tiles: {{ 0: {}, 2: {}, 8: {}, 10: [({}, 1)] }})])",
+        base,
+        base + 2,
+        base + 8,
+        base + 10
+    )
+}
+
+fn coord(x: i32) -> TileCoord {
+    TileCoord::from_tile_position(IVec3::new(x, 0, 0))
+}
+
+fn row_indices(app: &App) -> Vec<Option<u16>> {
+    let map = app.world.resource::<TileMap>();
+    (0..4)
+        .map(|x| map.get_tile(&coord(x)).map(|tile| tile.index()))
+        .collect()
+}
+
+fn app() -> (App, Handle<AutotileRules>) {
+    let mut app = App::new();
+    app.add_plugin(CorePlugin)
+        .add_plugin(AssetPlugin)
+        .add_plugin(TilingPlugin)
+        .add_plugin(AutotileAssetPlugin);
+    let rules: AutotileRules = ron::de::from_str(&rules_file(100)).unwrap();
+    let handle = app.world.resource_mut::<Assets<AutotileRules>>().add(rules);
+    app.world.resource_mut::<ActiveAutotileRules>().0 = Some(handle.clone());
+    app.add_system(|mut writer: TileMapWriter, mut done: Local<bool>| {
+        if !std::mem::replace(&mut *done, true) {
+            writer.set_tiles((0..4).map(|x| (coord(x), Some(Tile::new(1, 100)))));
+        }
+    });
+    (app, handle)
+}
+
+fn settle(app: &mut App) {
+    for _ in 0..5 {
+        app.update();
+    }
+    assert!(app.world.resource::<TileAutotiler>().is_settled());
+}
+
+#[test]
+fn loaded_rules_resolve_painted_tiles() {
+    let (mut app, _) = app();
+    settle(&mut app);
+    assert_eq!(
+        row_indices(&app),
+        vec![Some(102), Some(110), Some(110), Some(108)]
+    );
+}
+
+#[test]
+fn changed_rules_move_tiles_to_the_new_indices() {
+    let (mut app, handle) = app();
+    settle(&mut app);
+
+    let changed: AutotileRules = ron::de::from_str(&rules_file(200)).unwrap();
+    *app.world
+        .resource_mut::<Assets<AutotileRules>>()
+        .get_mut(&handle)
+        .unwrap() = changed;
+    settle(&mut app);
+    assert_eq!(
+        row_indices(&app),
+        vec![Some(202), Some(210), Some(210), Some(208)]
+    );
+}
@@ -0,0 +1,92 @@
+use bevy::{
+    ecs::system::SystemState,
+    math::{IVec2, IVec3},
+    prelude::{App, World},
+};
+use bevy_tiling_core::{
+    biome::BiomeMap, bounds::MapWrap, Tile, TileCoord, TileMap, TileMapWriter, TilingPlugin,
+};
+
+fn coord(x: i32, y: i32) -> TileCoord {
+    TileCoord::from_tile_position(IVec3::new(x, y, 0))
+}
+
+fn write(world: &mut World, f: impl FnOnce(&mut TileMapWriter)) {
+    let mut state: SystemState<TileMapWriter> = SystemState::new(world);
+    f(&mut state.get_mut(world));
+    state.apply(world);
+}
+
+#[test]
+fn biomes_cover_their_whole_cell() {
+    let mut biomes = BiomeMap::new(4);
+    assert_eq!(biomes.set_biome(&coord(-3, 5), Some(1)), None);
+    assert_eq!(biomes.biome_at(&coord(-4, 4)), Some(1));
+    assert_eq!(biomes.biome_at(&coord(-1, 7)), Some(1));
+    assert_eq!(biomes.biome_at(&coord(0, 4)), None);
+    assert_eq!(biomes.set_biome(&coord(-2, 6), Some(2)), Some(1));
+    assert!(biomes.get_chunk_biomes(&IVec3::new(-1, 0, 0)).is_some());
+}
+
+#[test]
+fn blending_weighs_the_surrounding_cells() {
+    let mut biomes = BiomeMap::new(4);
+    biomes.set_biome(&coord(0, 0), Some(1));
+    biomes.set_biome(&coord(4, 0), Some(2));
+    biomes.set_biome(&coord(0, 4), Some(1));
+    biomes.set_biome(&coord(4, 4), Some(2));
+
+    // Cells without a biome to the left are left out.
+    assert_eq!(biomes.blend_at(&coord(1, 2)), vec![(1, 1.0)]);
+    // The center of tile 3 sits 3/8 of the way from the center of cell 0 to that of cell 1.
+    let blend = biomes.blend_at(&coord(3, 2));
+    assert_eq!(blend.len(), 2);
+    assert_eq!(blend[0].0, 1);
+    assert!((blend[0].1 - 0.625).abs() < 1e-6);
+    assert!((blend[1].1 - 0.375).abs() < 1e-6);
+    assert!(biomes.blend_at(&coord(40, 40)).is_empty());
+}
+
+#[test]
+fn lookups_wrap_around_like_the_map() {
+    let mut biomes = BiomeMap::new(4);
+    biomes.set_wrap(Some(MapWrap::new(IVec2::new(2, 2))));
+    biomes.set_biome(&coord(0, 0), Some(1));
+    biomes.set_biome(&coord(-1, 0), Some(2));
+
+    assert_eq!(biomes.biome_at(&coord(32, 0)), Some(1));
+    assert_eq!(biomes.biome_at(&coord(31, 0)), Some(2));
+    assert!(biomes.get_chunk_biomes(&IVec3::new(-1, 0, 0)).is_some());
+    // Blending across the seam sees the cells on both sides.
+    let blend = biomes.blend_at(&coord(32, 2));
+    assert_eq!(
+        blend.iter().map(|(biome, _)| *biome).collect::<Vec<_>>(),
+        vec![1, 2]
+    );
+}
+
+fn remove_second_chunk(mut writer: TileMapWriter) {
+    writer.remove_chunk(&IVec3::new(1, 0, 0));
+}
+
+#[test]
+fn biomes_follow_the_default_map() {
+    let mut app = App::new();
+    app.add_plugin(TilingPlugin).add_system(remove_second_chunk);
+    app.world
+        .resource_mut::<TileMap>()
+        .set_wrap(Some(MapWrap::new(IVec2::new(2, 2))));
+    write(&mut app.world, |writer| {
+        writer.set_tile(coord(0, 0), Some(Tile::new(0, 1)));
+        writer.set_tile(coord(16, 0), Some(Tile::new(0, 1)));
+    });
+    {
+        let mut biomes = app.world.resource_mut::<BiomeMap>();
+        biomes.set_biome(&coord(0, 0), Some(1));
+        biomes.set_biome(&coord(16, 0), Some(2));
+    }
+    app.update();
+    let biomes = app.world.resource::<BiomeMap>();
+    assert_eq!(biomes.biome_at(&coord(32, 0)), Some(1));
+    assert_eq!(biomes.biome_at(&coord(16, 0)), None);
+}
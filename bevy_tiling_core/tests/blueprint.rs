@@ -0,0 +1,120 @@
+use bevy::{
+    ecs::system::SystemState,
+    math::IVec3,
+    prelude::{App, Mut, World},
+};
+use bevy_tiling_core::{
+    blueprint::{Blueprint, TileConstruction, TilePatch},
+    preview::TilePreview,
+    Tile, TileCoord, TileMap, TileMapWriter, TilingPlugin,
+};
+
+fn coord(x: i32, y: i32) -> TileCoord {
+    TileCoord::from_tile_position(IVec3::new(x, y, 0))
+}
+
+fn write(world: &mut World, f: impl FnOnce(&mut TileMapWriter)) {
+    let mut state: SystemState<TileMapWriter> = SystemState::new(world);
+    f(&mut state.get_mut(world));
+    state.apply(world);
+}
+
+/// A map with a wall at (10, 5) and (11, 5) and a door at (10, 6).
+fn map() -> TileMap {
+    let mut map = TileMap::default();
+    map.set_tile(&coord(11, 5), Some(Tile::new(0, 1)));
+    map.set_tile(&coord(10, 5), Some(Tile::new(0, 1)));
+    map.set_tile(&coord(10, 6), Some(Tile::new(0, 2)));
+    map
+}
+
+#[test]
+fn patches_are_relative_to_the_minimum_corner() {
+    let patch = TilePatch::capture(&map(), IVec3::new(12, 7, 0), IVec3::new(10, 5, 0));
+    assert_eq!(
+        patch.tiles().collect::<Vec<_>>(),
+        vec![
+            (IVec3::new(0, 0, 0), &Tile::new(0, 1)),
+            (IVec3::new(1, 0, 0), &Tile::new(0, 1)),
+            (IVec3::new(0, 1, 0), &Tile::new(0, 2)),
+        ]
+    );
+
+    let mut patch = patch;
+    patch.insert(IVec3::new(1, 0, 0), Tile::new(0, 3));
+    patch.insert(IVec3::new(0, 0, 1), Tile::new(0, 4));
+    assert_eq!(patch.len(), 4);
+
+    let mut app = App::new();
+    app.add_plugin(TilingPlugin);
+    write(&mut app.world, |writer| {
+        patch.stamp(writer, IVec3::new(-1, -1, 0))
+    });
+    let map = app.world.resource::<TileMap>();
+    assert_eq!(map.get_tile(&coord(0, -1)), Some(&Tile::new(0, 3)));
+    assert_eq!(
+        map.get_tile(&TileCoord::from_tile_position(IVec3::new(-1, -1, 1))),
+        Some(&Tile::new(0, 4))
+    );
+}
+
+#[test]
+fn blueprints_sum_the_cost_of_their_tiles() {
+    let blueprint = Blueprint::capture(
+        &map(),
+        IVec3::new(10, 5, 0),
+        IVec3::new(11, 6, 0),
+        |tile| match tile.index() {
+            1 => vec![("stone".to_string(), 2)],
+            _ => vec![("wood".to_string(), 1), ("stone".to_string(), 1)],
+        },
+    );
+    assert_eq!(blueprint.patch.len(), 3);
+    assert_eq!(blueprint.requirements["stone"], 5);
+    assert_eq!(blueprint.requirements["wood"], 1);
+}
+
+#[test]
+fn confirmed_cells_are_built_and_the_rest_shown_as_ghosts() {
+    let mut app = App::new();
+    app.add_plugin(TilingPlugin);
+    app.world
+        .resource_mut::<TileMap>()
+        .set_tile(&coord(0, 0), Some(Tile::new(0, 1)));
+    let mut patch = TilePatch::default();
+    patch.insert(IVec3::ZERO, Tile::new(0, 1));
+    patch.insert(IVec3::X, Tile::new(0, 1));
+    patch.insert(IVec3::Y, Tile::new(0, 2));
+
+    app.world
+        .resource_scope(|world, mut construction: Mut<TileConstruction>| {
+            // The map already has the tile at the origin.
+            assert_eq!(
+                construction.place(&patch, IVec3::ZERO, world.resource::<TileMap>()),
+                2
+            );
+            assert!(construction.confirm(coord(1, 0)));
+            assert!(!construction.confirm(coord(0, 0)));
+        });
+    app.update();
+
+    let map = app.world.resource::<TileMap>();
+    assert_eq!(map.get_tile(&coord(1, 0)), Some(&Tile::new(0, 1)));
+    assert!(map.get_tile(&coord(0, 1)).is_none());
+    let construction = app.world.resource::<TileConstruction>();
+    assert_eq!(construction.len(), 1);
+    assert_eq!(
+        construction.pending().collect::<Vec<_>>(),
+        vec![(&coord(0, 1), &Tile::new(0, 2))]
+    );
+    let preview = app.world.resource::<TilePreview>();
+    assert_eq!(
+        preview.get(coord(0, 1)).map(|ghost| ghost.tile),
+        Some(Tile::new(0, 2))
+    );
+    assert!(preview.get(coord(1, 0)).is_none());
+
+    let mut construction = app.world.resource_mut::<TileConstruction>();
+    assert_eq!(construction.cancel(coord(0, 1)), Some(Tile::new(0, 2)));
+    assert!(construction.is_empty());
+}
@@ -0,0 +1,74 @@
+use bevy::{
+    ecs::system::SystemState,
+    math::{IVec2, IVec3},
+    prelude::{App, World},
+};
+use bevy_tiling_core::{
+    bounds::MapWrap, chunk_data::ChunkDataStore, MapReader, Tile, TileMap, TileMapReader,
+    TileMapWriter, TilingPlugin,
+};
+
+struct Pollution(u32);
+
+struct Discovered;
+
+fn app() -> App {
+    let mut app = App::new();
+    app.add_plugin(TilingPlugin);
+    app
+}
+
+fn write(world: &mut World, f: impl FnOnce(&mut TileMapWriter)) {
+    let mut state: SystemState<TileMapWriter> = SystemState::new(world);
+    f(&mut state.get_mut(world));
+    state.apply(world);
+}
+
+fn read<R>(world: &mut World, f: impl FnOnce(&TileMapReader) -> R) -> R {
+    let mut state: SystemState<TileMapReader> = SystemState::new(world);
+    f(&state.get_mut(world))
+}
+
+#[test]
+fn data_is_shared_by_wrapped_coordinates() {
+    let mut app = app();
+    app.world
+        .resource_mut::<TileMap>()
+        .set_wrap(Some(MapWrap::new(IVec2::new(4, 0))));
+    write(&mut app.world, |writer| {
+        assert!(writer
+            .insert_chunk_data(&IVec3::new(5, 0, 0), Pollution(3))
+            .is_none());
+        writer
+            .get_chunk_data_mut::<Pollution>(&IVec3::new(1, 0, 0))
+            .unwrap()
+            .0 += 1;
+    });
+    let pollution = read(&mut app.world, |reader| {
+        reader
+            .get_chunk_data::<Pollution>(&IVec3::new(-3, 0, 0))
+            .map(|pollution| pollution.0)
+    });
+    assert_eq!(pollution, Some(4));
+    let stored = app.world.resource::<ChunkDataStore>();
+    assert!(stored.get::<Discovered>().is_none());
+    assert_eq!(stored.get::<Pollution>().unwrap().iter().count(), 1);
+}
+
+#[test]
+fn removing_a_chunk_drops_its_data() {
+    let mut app = app();
+    let kept = IVec3::new(1, 0, 0);
+    write(&mut app.world, |writer| {
+        writer.set_tile_xy(0, 0, 0, Some(Tile::new(0, 1)));
+        writer.insert_chunk_data(&IVec3::ZERO, Pollution(7));
+        writer.insert_chunk_data(&IVec3::ZERO, Discovered);
+        writer.insert_chunk_data(&kept, Discovered);
+        writer.remove_chunk(&IVec3::ZERO);
+    });
+    read(&mut app.world, |reader| {
+        assert!(reader.get_chunk_data::<Pollution>(&IVec3::ZERO).is_none());
+        assert!(reader.get_chunk_data::<Discovered>(&IVec3::ZERO).is_none());
+        assert!(reader.get_chunk_data::<Discovered>(&kept).is_some());
+    });
+}
@@ -0,0 +1,87 @@
+use bevy::{
+    ecs::system::SystemState,
+    math::IVec3,
+    prelude::{App, World},
+};
+use bevy_tiling_core::{
+    csv::{parse_csv, CsvError, EMPTY_CELL},
+    Tile, TileCoord, TileMap, TileMapUpdates, TileMapWriter, TilingPlugin,
+};
+
+fn coord(x: i32, y: i32, z: i32) -> TileCoord {
+    TileCoord::from_tile_position(IVec3::new(x, y, z))
+}
+
+fn write(world: &mut World, f: impl FnOnce(&mut TileMapWriter)) {
+    let mut state: SystemState<TileMapWriter> = SystemState::new(world);
+    f(&mut state.get_mut(world));
+    state.apply(world);
+}
+
+#[test]
+fn csv_maps_read_like_the_text() {
+    let map = TileMap::from_csv("1, 2,\n\n-1,3,4\n", 2, 7).unwrap();
+    assert_eq!(map.get_tile(&coord(0, 1, 2)), Some(&Tile::new(7, 1)));
+    assert_eq!(map.get_tile(&coord(1, 1, 2)), Some(&Tile::new(7, 2)));
+    assert!(map.get_tile(&coord(2, 1, 2)).is_none());
+    assert!(map.get_tile(&coord(0, 0, 2)).is_none());
+    assert_eq!(map.get_tile(&coord(2, 0, 2)), Some(&Tile::new(7, 4)));
+    assert_eq!(map.iter_region(IVec3::ZERO, IVec3::new(2, 1, 2)).count(), 4);
+}
+
+#[test]
+fn bad_cells_and_ragged_rows_are_reported() {
+    assert_eq!(
+        parse_csv("1,2\n3,x"),
+        Err(CsvError::InvalidCell {
+            line: 2,
+            column: 2,
+            value: "x".to_string(),
+        })
+    );
+    // The empty cell value can't be written as an index.
+    assert!(matches!(
+        parse_csv(&EMPTY_CELL.to_string()),
+        Err(CsvError::InvalidCell { line: 1, .. })
+    ));
+    assert_eq!(
+        parse_csv("1,2\n\n3"),
+        Err(CsvError::RaggedRow {
+            line: 3,
+            expected: 2,
+            found: 1,
+        })
+    );
+    assert_eq!(parse_csv(" \n"), Ok((Vec::new(), 0)));
+}
+
+#[test]
+fn short_last_rows_land_on_the_bottom_row() {
+    let map = TileMap::from_grid(&[1, 2, 3, 4, 5], 2, 0, 0);
+    assert_eq!(map.get_tile(&coord(0, 2, 0)), Some(&Tile::new(0, 1)));
+    assert_eq!(map.get_tile(&coord(0, 0, 0)), Some(&Tile::new(0, 5)));
+    assert!(map.get_tile(&coord(1, 0, 0)).is_none());
+}
+
+#[test]
+fn set_grid_clears_empty_cells_and_causes_updates() {
+    let mut app = App::new();
+    app.add_plugin(TilingPlugin);
+    app.world
+        .resource_mut::<TileMap>()
+        .set_tile(&coord(10, 10, 0), Some(Tile::new(0, 9)));
+    write(&mut app.world, |writer| {
+        writer.set_grid(&[EMPTY_CELL, 3], 2, IVec3::new(10, 10, 0), 1)
+    });
+
+    let map = app.world.resource::<TileMap>();
+    assert!(map.get_tile(&coord(10, 10, 0)).is_none());
+    assert_eq!(map.get_tile(&coord(11, 10, 0)), Some(&Tile::new(1, 3)));
+    assert_eq!(
+        app.world
+            .resource::<TileMapUpdates>()
+            .get_chunk_updates()
+            .count(),
+        1
+    );
+}
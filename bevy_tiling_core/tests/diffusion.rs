@@ -0,0 +1,65 @@
+#![cfg(feature = "diffusion")]
+
+use bevy::math::{IVec2, IVec3};
+use bevy_tiling_core::{bounds::MapWrap, diffusion::DiffusionLayer, TileCoord, TileMap};
+
+struct Heat;
+
+fn coord(x: i32, y: i32) -> TileCoord {
+    TileCoord::from_tile_position(IVec3::new(x, y, 0))
+}
+
+fn layer() -> DiffusionLayer<Heat> {
+    DiffusionLayer::new(0.0, 0.1, |_| 1.0)
+}
+
+#[test]
+fn values_spread_into_neighbouring_chunks() {
+    let map = TileMap::default();
+    let mut heat = layer();
+    heat.set_value(&coord(15, 0), 100.0);
+    for _ in 0..4 {
+        heat.step(&map);
+    }
+    assert!(heat.value_at(&coord(16, 0)) > 0.0);
+    assert!(heat.value_at(&coord(15, 0)) < 100.0);
+}
+
+#[test]
+fn values_spread_across_the_seam() {
+    let mut map = TileMap::default();
+    map.set_wrap(Some(MapWrap::new(IVec2::new(2, 2))));
+    let mut heat = layer();
+    heat.set_value(&coord(0, 0), 100.0);
+    for _ in 0..4 {
+        heat.step(&map);
+    }
+    // x = -1 wraps around to x = 31, in chunk (1, 0).
+    assert!(heat.value_at(&coord(31, 0)) > 0.0);
+    assert!(heat
+        .active_chunks()
+        .all(|chunk| chunk.x >= 0 && chunk.x < 2 && chunk.y >= 0 && chunk.y < 2));
+}
+
+#[test]
+fn small_writes_add_up_before_settling() {
+    let map = TileMap::default();
+    let mut heat = DiffusionLayer::<Heat>::new(0.0, 0.1, |_| 0.0);
+    for _ in 0..4 {
+        heat.add_value(&coord(0, 0), 0.0005);
+        heat.step(&map);
+    }
+    assert!((heat.value_at(&coord(0, 0)) - 0.002).abs() < 1e-6);
+}
+
+#[test]
+fn settled_chunks_are_dropped_once_left_alone() {
+    let map = TileMap::default();
+    let mut heat = layer();
+    heat.set_value(&coord(0, 0), 0.0005);
+    heat.step(&map);
+    assert_eq!(heat.active_chunks().count(), 1);
+    heat.step(&map);
+    assert_eq!(heat.active_chunks().count(), 0);
+    assert_eq!(heat.value_at(&coord(0, 0)), 0.0);
+}
@@ -0,0 +1,175 @@
+#![cfg(feature = "streaming")]
+
+use bevy::{
+    math::{IVec3, Vec2, Vec3},
+    prelude::{App, GlobalTransform},
+};
+use bevy_tiling_core::{
+    dimension::{DimensionMapping, DimensionPlugin, Dimensions, InDimension},
+    generator::TileMapGenerator,
+    grid::TileGrid,
+    streaming::{ChunkStreaming, ChunkStreamingPlugin, StreamingAnchor},
+    Chunk, DefaultMap, MapLabel, Tile, TileCoord, TileMap, TilingPlugin,
+};
+
+struct Cave;
+
+impl MapLabel for Cave {}
+
+struct Nether;
+
+impl MapLabel for Nether {}
+
+fn nether_dimensions() -> Dimensions {
+    let mut dimensions = Dimensions::default();
+    dimensions.set_mapping::<Nether>(DimensionMapping {
+        scale: 8,
+        offset: IVec3::new(0, 0, 1),
+    });
+    dimensions
+}
+
+/// Streams the default map and a cave dimension with one chunk radius 0 around each anchor,
+/// generating stone in the default map and dirt in the cave.
+fn app() -> App {
+    let mut app = App::new();
+    app.add_plugin(TilingPlugin)
+        .add_plugin(ChunkStreamingPlugin)
+        .add_plugin(DimensionPlugin::<Cave>::default());
+    app.world
+        .resource_mut::<TileMapGenerator>()
+        .set(|_| Chunk::uniform(Some(Tile::new(0, 1))));
+    app.world
+        .resource_mut::<TileMapGenerator<Cave>>()
+        .set(|_| Chunk::uniform(Some(Tile::new(0, 2))));
+    app.world.resource_mut::<ChunkStreaming>().load_radius = 0;
+    app.world.resource_mut::<ChunkStreaming<Cave>>().load_radius = 0;
+    app
+}
+
+#[test]
+fn anchors_stream_the_map_of_their_dimension() {
+    let mut app = app();
+    let anchor = app
+        .world
+        .spawn()
+        .insert(StreamingAnchor)
+        .insert(GlobalTransform::from_translation(Vec3::ZERO))
+        .id();
+
+    app.update();
+    assert!(app
+        .world
+        .resource::<TileMap>()
+        .get_chunk(&IVec3::ZERO)
+        .is_some());
+    assert!(app
+        .world
+        .resource::<TileMap<Cave>>()
+        .get_chunk(&IVec3::ZERO)
+        .is_none());
+
+    app.world
+        .entity_mut(anchor)
+        .insert(InDimension::of::<Cave>())
+        .insert(GlobalTransform::from_translation(Vec3::new(40.0, 0.0, 0.0)));
+    app.update();
+    let coord = TileCoord::from_tile_position(IVec3::new(40, 0, 0));
+    assert_eq!(
+        app.world.resource::<TileMap<Cave>>().get_tile(&coord),
+        Some(&Tile::new(0, 2))
+    );
+    assert!(app.world.resource::<TileMap>().get_tile(&coord).is_none());
+}
+
+#[test]
+fn positions_correspond_through_the_scale() {
+    let dimensions = nether_dimensions();
+    assert_eq!(
+        dimensions.corresponding::<DefaultMap, Nether>(IVec3::new(17, -1, 1)),
+        IVec3::new(2, -1, 0)
+    );
+    assert_eq!(
+        dimensions.corresponding::<Nether, DefaultMap>(IVec3::new(2, -1, 0)),
+        IVec3::new(16, -8, 1)
+    );
+    assert_eq!(
+        dimensions.corresponding::<Cave, DefaultMap>(IVec3::new(5, 6, 0)),
+        IVec3::new(5, 6, 0)
+    );
+}
+
+#[test]
+fn teleporting_keeps_the_offset_inside_the_tile() {
+    let dimensions = nether_dimensions();
+    let grid = TileGrid::new(Vec2::splat(16.0));
+    let mut translation = Vec3::new(17.0 * 16.0 + 3.0, 5.0, 1.0);
+
+    let dimension = dimensions.teleport::<Nether>(&grid, None, &mut translation);
+    assert!(dimension.is::<Nether>());
+    assert_eq!(translation, Vec3::new(2.0 * 16.0 + 3.0, 5.0, 0.0));
+
+    let dimension = dimensions.teleport::<DefaultMap>(&grid, Some(&dimension), &mut translation);
+    assert!(dimension.is::<DefaultMap>());
+    assert_eq!(translation, Vec3::new(16.0 * 16.0 + 3.0, 5.0, 1.0));
+}
+
+#[cfg(feature = "persist")]
+#[test]
+fn dimensions_persist_into_their_own_store() {
+    use bevy_tiling_core::{
+        dimension::DimensionPersistPlugin,
+        persist::{ChunkFile, ChunkStore},
+    };
+
+    let path = std::env::temp_dir().join(format!(
+        "bevy_tiling_dimension_{}.chunks",
+        std::process::id()
+    ));
+    let mut app = App::new();
+    app.add_plugin(TilingPlugin)
+        .add_plugin(DimensionPlugin::<Cave>::default())
+        .add_plugin(DimensionPersistPlugin::<Cave>::default())
+        .insert_resource(ChunkStore::<Cave>::for_map(
+            ChunkFile::create(&path).unwrap(),
+        ));
+    {
+        let mut streaming = app.world.resource_mut::<ChunkStreaming<Cave>>();
+        streaming.load_radius = 0;
+        streaming.unload_radius = 0;
+    }
+    let coord = TileCoord::from_tile_position(IVec3::new(3, 4, 0));
+    app.world
+        .resource_mut::<TileMap<Cave>>()
+        .set_tile(&coord, Some(Tile::new(0, 9)));
+    let anchor = app
+        .world
+        .spawn()
+        .insert(StreamingAnchor)
+        .insert(InDimension::of::<Cave>())
+        .insert(GlobalTransform::from_translation(Vec3::new(
+            100.0, 0.0, 0.0,
+        )))
+        .id();
+
+    app.update();
+    assert!(app
+        .world
+        .resource::<TileMap<Cave>>()
+        .get_tile(&coord)
+        .is_none());
+    assert!(app
+        .world
+        .resource::<ChunkStore<Cave>>()
+        .has_chunk(&IVec3::ZERO));
+
+    app.world
+        .entity_mut(anchor)
+        .insert(GlobalTransform::from_translation(Vec3::new(1.0, 1.0, 0.0)));
+    app.update();
+    assert_eq!(
+        app.world.resource::<TileMap<Cave>>().get_tile(&coord),
+        Some(&Tile::new(0, 9))
+    );
+    let _ = std::fs::remove_file(&path);
+}
@@ -0,0 +1,29 @@
+use bevy_tiling_core::{Chunk, Tile};
+
+#[test]
+fn ties_are_ordered_by_flags_too() {
+    let plain = Tile::new(0, 1);
+    let variants = [
+        plain.with_rotation(3),
+        plain.with_flip(true, false),
+        plain,
+        plain.with_flip(false, true).with_rotation(1),
+    ];
+    for first in 0..variants.len() {
+        // The same counts set in a different order each time.
+        let mut chunk = Chunk::uniform(None);
+        for (offset, index) in (0..=u8::MAX).enumerate() {
+            chunk.set_tile(index, Some(variants[(first + offset) % variants.len()]));
+        }
+        let order: Vec<Tile> = chunk.histogram().iter().map(|(tile, _)| *tile).collect();
+        assert_eq!(order[0], plain);
+        assert_eq!(chunk.dominant_tile(), Some(plain));
+
+        let mut expected = Chunk::uniform(None);
+        for (offset, index) in (0..=u8::MAX).rev().enumerate() {
+            expected.set_tile(index, Some(variants[offset % variants.len()]));
+        }
+        let expected: Vec<Tile> = expected.histogram().iter().map(|(tile, _)| *tile).collect();
+        assert_eq!(order, expected);
+    }
+}
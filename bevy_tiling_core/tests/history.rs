@@ -0,0 +1,119 @@
+use bevy::math::IVec3;
+use bevy_tiling_core::{
+    history::{HistoryWriter, TileDelta, TileHistory},
+    DefaultMap, Tile, TileCoord, TileMap, TileMapUpdates,
+};
+
+fn coord(x: i32) -> TileCoord {
+    TileCoord::from_tile_position(IVec3::new(x, 0, 0))
+}
+
+fn index_at(map: &TileMap, x: i32) -> Option<u16> {
+    map.get_tile(&coord(x)).map(|tile| tile.index())
+}
+
+/// Frame `frame` places tile `index` at `x = frame`.
+fn simulate(writer: &mut HistoryWriter<DefaultMap>, frame: i32, index: u16) {
+    writer.set_tile(coord(frame), Some(Tile::new(0, index)));
+}
+
+#[test]
+fn resimulated_frames_replace_the_undone_ones() {
+    let mut map = TileMap::default();
+    let mut updates = TileMapUpdates::default();
+    let mut history = TileHistory::new(8);
+    for frame in 0..4 {
+        simulate(&mut history.writer(&mut map, &mut updates), frame, 1);
+        history.record();
+    }
+
+    let mut updates = TileMapUpdates::default();
+    let mut resimulated = Vec::new();
+    let frames =
+        history.rollback_and_resimulate(&mut map, &mut updates, 2, |frames_ago, writer| {
+            resimulated.push(frames_ago);
+            simulate(writer, 3 - frames_ago as i32, 2);
+        });
+
+    assert_eq!(frames, 2);
+    assert_eq!(resimulated, vec![1, 0]);
+    assert_eq!(
+        (0..4).map(|x| index_at(&map, x)).collect::<Vec<_>>(),
+        vec![Some(1), Some(1), Some(2), Some(2)]
+    );
+    assert_eq!(history.len(), 4);
+    let newest = history.frame(0).unwrap();
+    assert_eq!(newest.len(), 1);
+    assert_eq!(newest[0].coord, coord(3));
+    assert_eq!(newest[0].old, None);
+    assert_eq!(newest[0].new, Some(Tile::new(0, 2)));
+    assert_eq!(updates.get_tile_updates().count(), 2);
+
+    // Rolling back everything again restores the map from before the first frame.
+    history.rollback(&mut map, &mut updates, 8);
+    assert!((0..4).all(|x| index_at(&map, x).is_none()));
+}
+
+#[test]
+fn recording_keeps_no_part_of_the_map() {
+    let mut map = TileMap::default();
+    let mut updates = TileMapUpdates::default();
+    map.set_tile(&coord(0), Some(Tile::new(0, 1)));
+    let chunk = map.get_chunk(&coord(0).chunk()).unwrap() as *const _;
+
+    let mut history = TileHistory::new(4);
+    history
+        .writer(&mut map, &mut updates)
+        .set_tile(coord(1), Some(Tile::new(0, 2)));
+    history.record();
+
+    assert!(!map.is_chunk_shared(&coord(0).chunk()));
+    // Editing after recording writes the chunk in place instead of copying it.
+    map.set_tile(&coord(2), Some(Tile::new(0, 3)));
+    assert!(std::ptr::eq(
+        map.get_chunk(&coord(0).chunk()).unwrap(),
+        chunk
+    ));
+}
+
+#[test]
+fn tracked_edits_are_merged_per_tile() {
+    let mut history = TileHistory::new(4);
+    let delta = |old: Option<u16>, new: Option<u16>| TileDelta {
+        coord: coord(0),
+        old: old.map(|index| Tile::new(0, index)),
+        new: new.map(|index| Tile::new(0, index)),
+    };
+    history.track([delta(None, Some(1)), delta(Some(1), Some(2))]);
+    history.record();
+    // Setting a tile and putting it back makes no edit.
+    history.track([delta(Some(2), Some(3)), delta(Some(3), Some(2))]);
+    history.record();
+
+    assert_eq!(history.frame(1).unwrap(), &[delta(None, Some(2))]);
+    assert!(history.frame(0).unwrap().is_empty());
+}
+
+#[test]
+fn rollback_removes_chunks_it_empties() {
+    let mut map = TileMap::default();
+    let mut updates = TileMapUpdates::default();
+    let far = TileCoord::from_tile_position(IVec3::new(100, 100, 0));
+    let mut history = TileHistory::new(4);
+    {
+        let mut writer = history.writer(&mut map, &mut updates);
+        writer.set_tile(coord(0), Some(Tile::new(0, 1)));
+        writer.set_tile(far, Some(Tile::new(0, 1)));
+    }
+    history.record();
+    // The far chunk is dropped by something else before the rollback.
+    map.remove_chunk(&far.chunk());
+
+    let mut updates = TileMapUpdates::default();
+    assert_eq!(history.rollback(&mut map, &mut updates, 1), 1);
+    assert!(map.get_chunk(&coord(0).chunk()).is_none());
+    assert!(map.get_chunk(&far.chunk()).is_none());
+    assert!(updates
+        .get_chunk_removals()
+        .any(|chunk| *chunk == coord(0).chunk()));
+}
@@ -0,0 +1,33 @@
+use bevy::math::IVec3;
+use bevy_tiling_core::{
+    internal::{tile_coord, tile_flags, tile_from_raw},
+    Tile, TileCoord, CHUNK_SIZE,
+};
+
+#[test]
+fn raw_coordinates_match_tile_positions() {
+    let position = IVec3::new(-1, CHUNK_SIZE + 2, 3);
+    let coord = TileCoord::from_tile_position(position);
+    assert_eq!(coord.chunk(), IVec3::new(-1, 1, 3));
+    assert_eq!(coord.index(), (2 * CHUNK_SIZE + CHUNK_SIZE - 1) as u8);
+    assert_eq!(tile_coord(coord.chunk(), coord.index()), coord);
+    assert_eq!(tile_coord(IVec3::ZERO, 0).tile_position(), IVec3::ZERO);
+}
+
+#[test]
+fn raw_flags_round_trip() {
+    for tile in [
+        Tile::new(1, 2),
+        Tile::new(1, 2).with_flip(true, false),
+        Tile::new(1, 2).with_flip(false, true).with_rotation(3),
+    ] {
+        assert_eq!(
+            tile_from_raw(tile.sheet(), tile.index(), tile_flags(&tile)),
+            tile
+        );
+    }
+    let tile = tile_from_raw(0, 5, tile_flags(&Tile::new(0, 0).with_rotation(1)));
+    assert_eq!(tile.rotation(), 1);
+    assert!(!tile.flip_x() && !tile.flip_y());
+    assert_eq!(tile_flags(&Tile::new(7, 7)), 0);
+}
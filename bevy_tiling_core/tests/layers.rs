@@ -0,0 +1,52 @@
+use bevy::{math::Vec2, prelude::App};
+use bevy_tiling_core::{
+    grid::TileGrid,
+    layers::{LayerSettings, TileLayers},
+    TilingPlugin,
+};
+
+#[test]
+fn unconfigured_layers_use_the_defaults() {
+    let mut app = App::new();
+    app.add_plugin(TilingPlugin);
+    let layers = app.world.resource::<TileLayers>();
+    assert_eq!(layers.get(-3), LayerSettings::default());
+    assert!(layers.is_visible(7));
+    assert_eq!(layers.iter().count(), 0);
+}
+
+#[test]
+fn settings_change_one_layer_only() {
+    let mut layers = TileLayers::default();
+    layers.set_visible(1, false);
+    layers.set_z_offset(1, 0.25);
+    layers.set_z_offset(2, -0.5);
+
+    assert_eq!(
+        layers.get(1),
+        LayerSettings {
+            visible: false,
+            z_offset: 0.25,
+        }
+    );
+    assert!(layers.is_visible(2));
+    assert!(layers.is_visible(0));
+
+    layers.set(1, LayerSettings::default());
+    assert!(layers.is_visible(1));
+    let mut configured: Vec<i32> = layers.iter().map(|(layer, _)| layer).collect();
+    configured.sort_unstable();
+    assert_eq!(configured, vec![1, 2]);
+}
+
+#[test]
+fn world_z_adds_the_offset_to_the_layer_height() {
+    let mut layers = TileLayers::default();
+    let mut grid = TileGrid::new(Vec2::splat(16.0));
+    grid.layer_height = 10.0;
+    layers.set_z_offset(2, 5.0);
+
+    assert_eq!(layers.world_z(0, &grid), 0.0);
+    assert_eq!(layers.world_z(2, &grid), 25.0);
+    assert_eq!(layers.world_z(-1, &grid), -10.0);
+}
@@ -0,0 +1,179 @@
+#![cfg(feature = "ldtk")]
+
+use bevy::{
+    ecs::system::EntityCommands,
+    math::{IVec3, Vec2},
+    prelude::{App, Component},
+};
+use bevy_tiling_core::{
+    ldtk::{IntGridValue, LdtkImportPlugin, LdtkImports, LdtkProject, LdtkTilesets},
+    locks::TileLocks,
+    objects::{MapObject, ObjectSpawners, PropertyValue},
+    regions::TileRegions,
+    tile_data::TileDataMap,
+    Tile, TileCoord, TileMap, TilingPlugin,
+};
+
+/// A 2 by 2 cell level at cell (2, 1) of the world with a chest on its top layer and an
+/// IntGrid layer with auto tiles below.
+const PROJECT: &str = r#"{
+    "jsonVersion": "1.1.3",
+    "levels": [{
+        "identifier": "Start",
+        "worldX": 32,
+        "worldY": 16,
+        "pxWid": 32,
+        "pxHei": 32,
+        "layerInstances": [
+            {
+                "__identifier": "Entities",
+                "__type": "Entities",
+                "__cWid": 2,
+                "__cHei": 2,
+                "__gridSize": 16,
+                "__tilesetDefUid": null,
+                "entityInstances": [{
+                    "__identifier": "Chest",
+                    "__grid": [1, 1],
+                    "__pivot": [0, 0],
+                    "px": [16, 16],
+                    "width": 16,
+                    "height": 16,
+                    "fieldInstances": [
+                        {"__identifier": "loot", "__value": "gold"},
+                        {"__identifier": "count", "__value": 3},
+                        {"__identifier": "target", "__value": {"cx": 1, "cy": 0}},
+                        {"__identifier": "key", "__value": null}
+                    ]
+                }]
+            },
+            {
+                "__identifier": "Ground",
+                "__type": "IntGrid",
+                "__cWid": 2,
+                "__cHei": 2,
+                "__gridSize": 16,
+                "__tilesetDefUid": 7,
+                "intGridCsv": [1, 0, 0, 2],
+                "autoLayerTiles": [{"px": [0, 0], "src": [0, 0], "t": 5, "f": 1}]
+            }
+        ]
+    }]
+}"#;
+
+fn project() -> LdtkProject {
+    serde_json::from_str(PROJECT).unwrap()
+}
+
+fn coord(x: i32, y: i32, z: i32) -> TileCoord {
+    TileCoord::from_tile_position(IVec3::new(x, y, z))
+}
+
+#[derive(Component)]
+struct Chest;
+
+fn app() -> App {
+    let mut app = App::new();
+    app.add_plugin(TilingPlugin).add_plugin(LdtkImportPlugin);
+    app.world.resource_mut::<LdtkTilesets>().add(7, 3);
+    app.world.resource_mut::<ObjectSpawners>().register(
+        "Chest",
+        |entity: &mut EntityCommands, _: &MapObject| {
+            entity.insert(Chest);
+        },
+    );
+    app
+}
+
+#[test]
+fn levels_keep_their_layout_with_y_pointing_up() {
+    let level = &project().levels[0];
+    let ground = Tile::new(3, 5).with_flip(true, false);
+    let mut tilesets = LdtkTilesets::default();
+    tilesets.add(7, 3);
+    assert_eq!(level.tiles(&tilesets), vec![(coord(2, -1, 0), ground)]);
+    assert_eq!(
+        level.int_grid(),
+        vec![
+            (coord(2, -1, 0), IntGridValue(1)),
+            (coord(3, -2, 0), IntGridValue(2)),
+        ]
+    );
+    assert_eq!(
+        level.tile_bounds(),
+        Some((IVec3::new(2, -2, 0), IVec3::new(3, -1, 1)))
+    );
+
+    let objects = level.objects();
+    assert_eq!(objects.len(), 1);
+    let chest = &objects[0];
+    assert_eq!(chest.position, Vec2::new(3.0, -2.0));
+    assert_eq!(chest.size, Vec2::ONE);
+    assert_eq!(chest.tile(), coord(3, -2, 1));
+    assert_eq!(
+        chest.property("loot"),
+        Some(&PropertyValue::String("gold".to_string()))
+    );
+    assert_eq!(
+        chest.property("count").and_then(PropertyValue::as_int),
+        Some(3)
+    );
+    // Points and nulls have no property value.
+    assert_eq!(chest.properties.len(), 2);
+}
+
+#[test]
+fn imports_write_tiles_regions_int_grid_and_objects() {
+    let mut app = app();
+    app.world.resource_mut::<LdtkImports>().push(project());
+    app.update();
+
+    assert!(app.world.resource::<LdtkImports>().is_empty());
+    assert_eq!(
+        app.world.resource::<TileMap>().get_tile(&coord(2, -1, 0)),
+        Some(&Tile::new(3, 5).with_flip(true, false))
+    );
+    assert_eq!(
+        app.world
+            .resource::<TileDataMap<IntGridValue>>()
+            .get(&coord(3, -2, 0)),
+        Some(&IntGridValue(2))
+    );
+    let regions = app.world.resource::<TileRegions>();
+    assert_eq!(
+        regions
+            .region_at(&coord(3, -2, 1))
+            .map(|region| region.name.as_str()),
+        Some("Start")
+    );
+    assert!(regions.region_at(&coord(4, -2, 0)).is_none());
+    let mut chests = app.world.query::<(&Chest, &MapObject)>();
+    assert_eq!(chests.iter(&app.world).count(), 1);
+}
+
+#[test]
+fn imports_wait_for_locked_tiles() {
+    let mut app = app();
+    let guard = app
+        .world
+        .resource::<TileLocks>()
+        .lock_region(IVec3::new(2, -1, 0), IVec3::new(2, -1, 0))
+        .unwrap();
+    app.world.resource_mut::<LdtkImports>().push(project());
+    app.update();
+    assert!(!app.world.resource::<LdtkImports>().is_empty());
+    assert!(app
+        .world
+        .resource::<TileMap>()
+        .get_tile(&coord(2, -1, 0))
+        .is_none());
+
+    drop(guard);
+    app.update();
+    assert!(app.world.resource::<LdtkImports>().is_empty());
+    assert!(app
+        .world
+        .resource::<TileMap>()
+        .get_tile(&coord(2, -1, 0))
+        .is_some());
+}
@@ -0,0 +1,133 @@
+use bevy::{
+    ecs::system::SystemState,
+    math::IVec3,
+    prelude::{App, Entity, Mut, World},
+};
+use bevy_tiling_core::{
+    blueprint::{TileConstruction, TilePatch},
+    locks::TileLocks,
+    policy::{GuardedTileMapWriter, TileWritePolicy, WriteDenied},
+    schedule::TileSchedule,
+    Tile, TileCoord, TileMap, TilingPlugin,
+};
+
+fn coord(x: i32) -> TileCoord {
+    TileCoord::from_tile_position(IVec3::new(x, 0, 0))
+}
+
+fn guarded_write(world: &mut World, x: i32) -> Result<Option<Tile>, WriteDenied> {
+    let mut state: SystemState<GuardedTileMapWriter> = SystemState::new(world);
+    let result =
+        state
+            .get_mut(world)
+            .set_tile(Entity::from_raw(0), coord(x), Some(Tile::new(0, 1)));
+    state.apply(world);
+    result
+}
+
+/// A policy that only forbids writes at x = 3, replacing the default one.
+fn policy() -> TileWritePolicy {
+    let mut policy = TileWritePolicy::default();
+    policy.add_rule(|_, coord, _, _| coord.tile_position().x != 3);
+    policy
+}
+
+#[test]
+fn locks_hold_with_a_policy_inserted_before_the_plugin() {
+    let mut app = App::new();
+    app.insert_resource(policy()).add_plugin(TilingPlugin);
+    let guard = app
+        .world
+        .resource::<TileLocks>()
+        .lock_region(IVec3::new(0, 0, 0), IVec3::new(1, 0, 0))
+        .unwrap();
+
+    assert_eq!(guarded_write(&mut app.world, 1), Err(WriteDenied(coord(1))));
+    assert_eq!(guarded_write(&mut app.world, 3), Err(WriteDenied(coord(3))));
+    assert_eq!(guarded_write(&mut app.world, 2), Ok(None));
+
+    drop(guard);
+    assert_eq!(guarded_write(&mut app.world, 1), Ok(None));
+}
+
+#[test]
+fn locks_hold_with_a_policy_replaced_after_the_plugin() {
+    let mut app = App::new();
+    app.add_plugin(TilingPlugin).insert_resource(policy());
+    let _guard = app
+        .world
+        .resource::<TileLocks>()
+        .lock_region(IVec3::new(0, 0, 0), IVec3::new(1, 0, 0))
+        .unwrap();
+
+    assert_eq!(guarded_write(&mut app.world, 0), Err(WriteDenied(coord(0))));
+    assert_eq!(guarded_write(&mut app.world, 3), Err(WriteDenied(coord(3))));
+    assert_eq!(guarded_write(&mut app.world, 4), Ok(None));
+}
+
+#[test]
+fn scheduled_changes_wait_for_locked_tiles() {
+    let mut app = App::new();
+    app.add_plugin(TilingPlugin);
+    let guard = app
+        .world
+        .resource::<TileLocks>()
+        .lock_region(IVec3::new(0, 0, 0), IVec3::new(0, 0, 0))
+        .unwrap();
+    {
+        let mut schedule = app.world.resource_mut::<TileSchedule>();
+        schedule.schedule_in(coord(0), Some(Tile::new(0, 1)), 0);
+        schedule.schedule_in(coord(1), Some(Tile::new(0, 1)), 0);
+        schedule.schedule_in(coord(0), Some(Tile::new(0, 2)), 1);
+    }
+    app.update();
+    app.update();
+    let map = app.world.resource::<TileMap>();
+    assert_eq!(map.get_tile(&coord(0)), None);
+    assert_eq!(map.get_tile(&coord(1)), Some(&Tile::new(0, 1)));
+
+    drop(guard);
+    app.update();
+    // The held back change is applied before the one scheduled after it.
+    let map = app.world.resource::<TileMap>();
+    assert_eq!(map.get_tile(&coord(0)), Some(&Tile::new(0, 2)));
+}
+
+#[test]
+fn confirmed_construction_waits_for_locked_tiles() {
+    let mut app = App::new();
+    app.add_plugin(TilingPlugin);
+    let guard = app
+        .world
+        .resource::<TileLocks>()
+        .lock_region(IVec3::new(0, 0, 0), IVec3::new(0, 0, 0))
+        .unwrap();
+    let mut patch = TilePatch::default();
+    patch.insert(IVec3::new(0, 0, 0), Tile::new(0, 1));
+    patch.insert(IVec3::new(1, 0, 0), Tile::new(0, 1));
+    app.world
+        .resource_scope(|world, mut construction: Mut<TileConstruction>| {
+            construction.place(&patch, IVec3::ZERO, world.resource::<TileMap>());
+            assert!(construction.confirm(coord(0)));
+            assert!(construction.confirm(coord(1)));
+        });
+    app.update();
+    assert_eq!(app.world.resource::<TileMap>().get_tile(&coord(0)), None);
+    assert!(app
+        .world
+        .resource::<TileConstruction>()
+        .get(coord(0))
+        .is_some());
+    assert!(app
+        .world
+        .resource::<TileConstruction>()
+        .get(coord(1))
+        .is_none());
+
+    drop(guard);
+    app.update();
+    assert_eq!(
+        app.world.resource::<TileMap>().get_tile(&coord(0)),
+        Some(&Tile::new(0, 1))
+    );
+}
@@ -0,0 +1,83 @@
+use bevy::{
+    math::IVec3,
+    prelude::{App, Local},
+};
+use bevy_tiling_core::{
+    markers::TileMarkers, Tile, TileCoord, TileMap, TileMapWriter, TilingPlugin,
+};
+
+fn coord(x: i32) -> TileCoord {
+    TileCoord::from_tile_position(IVec3::new(x, 0, 0))
+}
+
+fn shallow() -> Tile {
+    Tile::new(0, 1)
+}
+
+fn deep() -> Tile {
+    Tile::new(0, 2)
+}
+
+fn water_markers() -> TileMarkers {
+    let mut markers = TileMarkers::default();
+    markers.add_rule("water", |tile| *tile == shallow());
+    markers.add_rule("water", |tile| *tile == deep());
+    markers.add_rule("deep", |tile| *tile == deep());
+    markers
+}
+
+fn sorted(mut coords: Vec<TileCoord>) -> Vec<TileCoord> {
+    coords.sort_by_key(|coord| coord.tile_position().x);
+    coords
+}
+
+#[test]
+fn a_name_matches_if_any_of_its_rules_does() {
+    let mut app = App::new();
+    app.add_plugin(TilingPlugin)
+        .insert_resource(water_markers());
+    app.add_system(move |mut writer: TileMapWriter, mut frame: Local<u32>| {
+        *frame += 1;
+        match *frame {
+            1 => {
+                writer.set_tile(coord(0), Some(shallow()));
+                writer.set_tile(coord(1), Some(deep()));
+                writer.set_tile(coord(2), Some(Tile::new(0, 3)));
+            }
+            2 => {
+                writer.set_tile(coord(1), Some(shallow()));
+                writer.set_tile(coord(0), None);
+            }
+            _ => {}
+        }
+    });
+
+    app.update();
+    let markers = app.world.resource::<TileMarkers>();
+    assert_eq!(sorted(markers.markers("water")), vec![coord(0), coord(1)]);
+    assert_eq!(markers.markers("deep"), vec![coord(1)]);
+
+    app.update();
+    let markers = app.world.resource::<TileMarkers>();
+    assert_eq!(markers.markers("water"), vec![coord(1)]);
+    assert!(markers.markers("deep").is_empty());
+}
+
+#[test]
+fn rescans_and_tags_use_every_rule() {
+    let mut map = TileMap::default();
+    map.set_tile(&coord(0), Some(shallow()));
+    map.set_tile(&coord(5), Some(deep()));
+    let mut markers = water_markers();
+    markers.tag("water", coord(9));
+    markers.rescan(&map);
+
+    assert_eq!(
+        sorted(markers.markers("water")),
+        vec![coord(0), coord(5), coord(9)]
+    );
+    assert!(markers.is_marked("deep", &coord(5)));
+    assert!(markers.untag("water", &coord(9)));
+    assert!(!markers.is_marked("water", &coord(9)));
+    assert!(!markers.untag("water", &coord(9)));
+}
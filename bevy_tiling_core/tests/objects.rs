@@ -0,0 +1,93 @@
+use bevy::{
+    ecs::system::{CommandQueue, EntityCommands},
+    math::{IVec3, Vec2, Vec3},
+    prelude::{Commands, Component, Entity, Transform, World},
+};
+use bevy_tiling_core::{
+    grid::TileGrid,
+    objects::{MapObject, ObjectSpawners, PropertyValue},
+    TileCoord,
+};
+
+#[derive(Component, PartialEq, Debug)]
+struct Spawn(i64);
+
+fn spawn_point() -> MapObject {
+    let mut object = MapObject {
+        name: "start".to_string(),
+        object_type: "spawn".to_string(),
+        position: Vec2::new(-0.5, 2.25),
+        layer: 1,
+        ..Default::default()
+    };
+    object
+        .properties
+        .insert("team".to_string(), PropertyValue::Int(2));
+    object
+}
+
+fn spawn(world: &mut World, spawners: &ObjectSpawners, object: &MapObject) -> Option<Entity> {
+    let mut queue = CommandQueue::default();
+    let mut commands = Commands::new(&mut queue, world);
+    let mut grid = TileGrid::new(Vec2::splat(16.0));
+    grid.layer_height = 10.0;
+    let entity = spawners.spawn(&mut commands, &grid, object);
+    queue.apply(world);
+    entity
+}
+
+#[test]
+fn spawners_are_picked_by_the_object_type() {
+    let mut world = World::new();
+    let mut spawners = ObjectSpawners::default();
+    spawners.register("spawn", |entity: &mut EntityCommands, _: &MapObject| {
+        entity.insert(Spawn(0));
+    });
+    // Registering a type again replaces its spawner.
+    spawners.register(
+        "spawn",
+        |entity: &mut EntityCommands, object: &MapObject| {
+            let team = object.property("team").and_then(PropertyValue::as_int);
+            entity.insert(Spawn(team.unwrap_or_default()));
+        },
+    );
+    assert!(spawners.is_registered("spawn"));
+    assert!(!spawners.is_registered("chest"));
+
+    let object = spawn_point();
+    let entity = spawn(&mut world, &spawners, &object).unwrap();
+    let entity = world.entity(entity);
+    assert_eq!(entity.get::<Spawn>(), Some(&Spawn(2)));
+    assert_eq!(entity.get::<MapObject>(), Some(&object));
+    assert_eq!(
+        entity.get::<Transform>().unwrap().translation,
+        Vec3::new(-8.0, 36.0, 10.0)
+    );
+
+    let chest = MapObject {
+        object_type: "chest".to_string(),
+        ..spawn_point()
+    };
+    assert!(spawn(&mut world, &spawners, &chest).is_none());
+    assert_eq!(world.entities().len(), 1);
+}
+
+#[test]
+fn objects_lie_in_the_tile_containing_their_position() {
+    assert_eq!(
+        spawn_point().tile(),
+        TileCoord::from_tile_position(IVec3::new(-1, 2, 1))
+    );
+}
+
+#[test]
+fn property_values_convert_where_lossless() {
+    assert_eq!(PropertyValue::Int(3).as_float(), Some(3.0));
+    assert_eq!(PropertyValue::Float(3.0).as_int(), None);
+    assert_eq!(PropertyValue::Bool(true).as_bool(), Some(true));
+    assert_eq!(PropertyValue::Bool(true).as_str(), None);
+    assert_eq!(
+        PropertyValue::String("gold".to_string()).as_str(),
+        Some("gold")
+    );
+}
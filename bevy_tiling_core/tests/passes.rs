@@ -0,0 +1,255 @@
+#![cfg(feature = "passes")]
+
+use std::sync::{Arc, Mutex};
+
+use bevy::{
+    ecs::system::SystemState,
+    math::IVec3,
+    prelude::{App, World},
+};
+use bevy_tiling_core::{
+    generator::ChunkGenerator,
+    passes::{MapPasses, OreVeins, PassContext, PassGenerator, Scatter, Smooth},
+    scatter::ScatterSettings,
+    Chunk, Tile, TileCoord, TileMap, TileMapUpdates, TileMapWriter, TilingPlugin, CHUNK_SIZE,
+};
+
+fn stone() -> Tile {
+    Tile::new(0, 1)
+}
+
+fn ore() -> Tile {
+    Tile::new(0, 2)
+}
+
+fn flower() -> Tile {
+    Tile::new(0, 3)
+}
+
+fn coord(x: i32, y: i32) -> TileCoord {
+    TileCoord::from_tile_position(IVec3::new(x, y, 0))
+}
+
+fn stone_chunk(_: IVec3) -> Chunk {
+    Chunk::uniform(Some(stone()))
+}
+
+fn passes(seed: u64) -> MapPasses {
+    let mut passes = MapPasses::new(seed);
+    passes
+        .add(
+            0,
+            Scatter::new(flower(), |tile| *tile == stone()).with_settings(ScatterSettings {
+                max_per_chunk: 6,
+                ..ScatterSettings::default()
+            }),
+        )
+        .add(1, {
+            let mut veins = OreVeins::new(ore(), |tile| *tile == stone());
+            veins.veins_per_chunk = 3;
+            veins
+        });
+    passes
+}
+
+fn stone_map(chunks: i32) -> TileMap {
+    let mut map = TileMap::default();
+    for x in 0..chunks {
+        map.insert_shared_chunk(IVec3::new(x, 0, 0), Arc::new(stone_chunk(IVec3::ZERO)));
+    }
+    map
+}
+
+fn tiles(map: &TileMap, chunks: i32) -> Vec<Option<Tile>> {
+    (0..chunks * CHUNK_SIZE)
+        .flat_map(|x| (0..CHUNK_SIZE).map(move |y| (x, y)))
+        .map(|(x, y)| map.get_tile(&coord(x, y)).copied())
+        .collect()
+}
+
+#[test]
+fn passes_run_by_order_then_insertion() {
+    let ran = Arc::new(Mutex::new(Vec::new()));
+    let mut passes = MapPasses::new(0);
+    for (order, name) in [(5, "late"), (0, "first"), (5, "later"), (-1, "early")] {
+        let ran = ran.clone();
+        passes.add(order, move |_: &mut PassContext| {
+            ran.lock().unwrap().push(name)
+        });
+    }
+    passes.run(&mut TileMap::default(), IVec3::ZERO, IVec3::ZERO);
+    assert_eq!(
+        *ran.lock().unwrap(),
+        vec!["early", "first", "late", "later"]
+    );
+}
+
+#[test]
+fn passes_only_write_inside_the_region() {
+    let mut passes = MapPasses::new(0);
+    passes.add(0, |context: &mut PassContext| {
+        for x in -2..6 {
+            context.set_tile(IVec3::new(x, 0, 0), Some(ore()));
+        }
+    });
+    let mut map = TileMap::default();
+    passes.run(&mut map, IVec3::new(3, 0, 0), IVec3::new(0, 0, 0));
+    let written: Vec<i32> = (-2..6)
+        .filter(|x| map.get_tile(&coord(*x, 0)).is_some())
+        .collect();
+    assert_eq!(written, vec![0, 1, 2, 3]);
+}
+
+#[test]
+fn generated_chunks_match_a_run_over_each_chunk() {
+    let generator = PassGenerator::new(stone_chunk, passes(42));
+    let mut generated = TileMap::default();
+    for x in 0..2 {
+        generated.insert_shared_chunk(
+            IVec3::new(x, 0, 0),
+            Arc::new(generator.generate(IVec3::new(x, 0, 0))),
+        );
+    }
+
+    let mut loaded = stone_map(2);
+    let runner = passes(42);
+    for x in 0..2 {
+        let min = IVec3::new(x * CHUNK_SIZE, 0, 0);
+        runner.run(
+            &mut loaded,
+            min,
+            min + IVec3::new(CHUNK_SIZE - 1, CHUNK_SIZE - 1, 0),
+        );
+    }
+
+    let generated = tiles(&generated, 2);
+    assert_eq!(generated, tiles(&loaded, 2));
+    assert!(generated.contains(&Some(ore())));
+    assert_eq!(
+        generated
+            .iter()
+            .filter(|tile| **tile == Some(flower()))
+            .count(),
+        12
+    );
+
+    let reseeded = PassGenerator::new(stone_chunk, passes(7));
+    let mut map = TileMap::default();
+    for x in 0..2 {
+        map.insert_shared_chunk(
+            IVec3::new(x, 0, 0),
+            Arc::new(reseeded.generate(IVec3::new(x, 0, 0))),
+        );
+    }
+    assert_ne!(generated, tiles(&map, 2));
+}
+
+#[test]
+fn smoothing_fills_holes_and_removes_specks() {
+    let mut map = stone_map(1);
+    map.set_tile(&coord(5, 5), None);
+    map.set_tile(&coord(9, 9), None);
+    map.set_tile(&coord(9, 10), None);
+    map.set_tile(&coord(10, 9), None);
+    map.set_tile(&coord(10, 10), None);
+    // A lone tile outside of the stone.
+    map.set_tile(&coord(-3, 3), Some(stone()));
+
+    let mut passes = MapPasses::new(0);
+    passes.add(0, Smooth::default());
+    passes.run(&mut map, IVec3::new(-4, 0, 0), IVec3::new(15, 15, 0));
+
+    assert_eq!(map.get_tile(&coord(5, 5)), Some(&stone()));
+    assert_eq!(map.get_tile(&coord(-3, 3)), None);
+    // A 2x2 hole has only three empty neighbours per tile, so it is filled too.
+    assert_eq!(map.get_tile(&coord(9, 9)), Some(&stone()));
+    // Edges of the stone next to empty space keep their majority.
+    assert_eq!(map.get_tile(&coord(0, 7)), Some(&stone()));
+}
+
+#[test]
+fn veins_only_replace_host_tiles() {
+    let mut map = stone_map(1);
+    for y in 0..CHUNK_SIZE {
+        map.set_tile(&coord(8, y), Some(flower()));
+    }
+    let mut passes = MapPasses::new(3);
+    passes.add(0, {
+        let mut veins = OreVeins::new(ore(), |tile| *tile == stone());
+        veins.veins_per_chunk = 8;
+        veins.length = 64;
+        veins
+    });
+    passes.run(
+        &mut map,
+        IVec3::ZERO,
+        IVec3::new(CHUNK_SIZE - 1, CHUNK_SIZE - 1, 0),
+    );
+
+    let tiles = tiles(&map, 1);
+    assert!(tiles.contains(&Some(ore())));
+    assert!((0..CHUNK_SIZE).all(|y| map.get_tile(&coord(8, y)) == Some(&flower())));
+    assert!((-1..=CHUNK_SIZE).all(|y| map.get_tile(&coord(CHUNK_SIZE, y)).is_none()));
+}
+
+fn write(world: &mut World, f: impl FnOnce(&mut TileMapWriter)) {
+    let mut state: SystemState<TileMapWriter> = SystemState::new(world);
+    f(&mut state.get_mut(world));
+    state.apply(world);
+}
+
+#[test]
+fn applying_passes_updates_the_changed_tiles() {
+    let mut app = App::new();
+    app.add_plugin(TilingPlugin);
+    let mut map = app.world.resource_mut::<TileMap>();
+    map.set_tile(&coord(0, 0), Some(stone()));
+    map.set_tile(&coord(20, 0), Some(stone()));
+
+    let mut passes = MapPasses::new(0);
+    passes.add(0, |context: &mut PassContext| {
+        for position in context.positions().collect::<Vec<_>>() {
+            if context.get_tile(position) == Some(&stone()) {
+                context.set_tile(position, Some(ore()));
+            }
+        }
+    });
+    write(&mut app.world, |writer| {
+        passes.apply(writer, IVec3::ZERO, IVec3::new(40, 0, 0));
+    });
+
+    let updates = app.world.resource::<TileMapUpdates>();
+    let mut updated: Vec<TileCoord> = updates.get_tile_updates().collect();
+    updated.sort_by_key(|coord| coord.tile_position().x);
+    assert_eq!(updated, vec![coord(0, 0), coord(20, 0)]);
+    assert_eq!(
+        app.world.resource::<TileMap>().get_tile(&coord(20, 0)),
+        Some(&ore())
+    );
+}
+
+#[test]
+fn tiles_a_pass_writes_back_are_not_updated() {
+    let mut app = App::new();
+    app.add_plugin(TilingPlugin);
+    let mut map = app.world.resource_mut::<TileMap>();
+    map.set_tile(&coord(0, 0), Some(stone()));
+    map.set_tile(&coord(1, 0), Some(stone()));
+
+    let mut passes = MapPasses::new(0);
+    passes
+        .add(0, |context: &mut PassContext| {
+            context.set_tile(IVec3::new(0, 0, 0), Some(ore()));
+            context.set_tile(IVec3::new(1, 0, 0), Some(ore()));
+        })
+        .add(1, |context: &mut PassContext| {
+            context.set_tile(IVec3::new(0, 0, 0), Some(stone()));
+        });
+    write(&mut app.world, |writer| {
+        passes.apply(writer, IVec3::ZERO, IVec3::new(1, 0, 0));
+    });
+
+    let updates = app.world.resource::<TileMapUpdates>();
+    let updated: Vec<TileCoord> = updates.get_tile_updates().collect();
+    assert_eq!(updated, vec![coord(1, 0)]);
+}
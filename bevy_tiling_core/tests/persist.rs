@@ -0,0 +1,292 @@
+#![cfg(feature = "persist")]
+
+use std::{
+    fs,
+    io::{self, Seek, SeekFrom, Write},
+    path::PathBuf,
+};
+
+use bevy::{
+    ecs::event::Events,
+    math::{IVec3, Vec3},
+    prelude::{App, GlobalTransform},
+};
+use bevy_tiling_core::{
+    persist::{ChunkFile, ChunkPersistPlugin, ChunkStore, MapLoadProgress, MapSaveProgress},
+    streaming::{ChunkLoadRequest, ChunkStreaming, ChunkStreamingPlugin, StreamingAnchor},
+    Chunk, Tile, TileCoord, TileMap, TilingPlugin,
+};
+
+/// A fresh path in the temporary directory, removed when dropped.
+struct TempPath(PathBuf);
+
+impl TempPath {
+    fn new(name: &str) -> Self {
+        let path = std::env::temp_dir().join(format!(
+            "bevy_tiling_{}_{}.chunks",
+            name,
+            std::process::id()
+        ));
+        let _ = fs::remove_file(&path);
+        Self(path)
+    }
+}
+
+impl Drop for TempPath {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.0);
+    }
+}
+
+/// One chunk for every encoding: empty, uniform, scattered tiles and long runs.
+fn chunks() -> Vec<(IVec3, Chunk)> {
+    let mut sparse = Chunk::uniform(None);
+    for index in (0..=u8::MAX).step_by(3) {
+        sparse.set_tile(
+            index,
+            Some(Tile::new(1, index as u16).with_flip(true, false)),
+        );
+    }
+    let mut runs = Chunk::uniform(Some(Tile::new(0, 1)));
+    for index in 100..140 {
+        runs.set_tile(index, Some(Tile::new(0, 2)));
+    }
+    vec![
+        (IVec3::new(0, 0, 0), Chunk::uniform(None)),
+        (IVec3::new(1, 0, 0), Chunk::uniform(Some(Tile::new(2, 3)))),
+        (IVec3::new(-1, 4, 0), sparse),
+        (IVec3::new(7, -2, 1), runs),
+    ]
+}
+
+fn assert_same_tiles(loaded: &Chunk, expected: &Chunk) {
+    for index in 0..=u8::MAX {
+        assert_eq!(
+            loaded.get_tile(index),
+            expected.get_tile(index),
+            "index {}",
+            index
+        );
+    }
+}
+
+#[test]
+fn chunks_survive_reopening_and_resaving() {
+    let path = TempPath::new("round_trip");
+    let chunks = chunks();
+    {
+        let mut file = ChunkFile::create(&path.0).unwrap();
+        let saved = file
+            .save_chunks(chunks.iter().map(|(coord, chunk)| (*coord, chunk)))
+            .unwrap();
+        assert_eq!(saved, chunks.len());
+    }
+
+    let mut file = ChunkFile::open(&path.0).unwrap();
+    assert_eq!(file.len(), chunks.len());
+    for (coord, chunk) in chunks.iter() {
+        assert_same_tiles(&file.load_chunk(coord).unwrap().unwrap(), chunk);
+    }
+    assert!(file.load_chunk(&IVec3::new(9, 9, 9)).unwrap().is_none());
+
+    let mut changed = chunks[3].1.clone();
+    changed.set_tile(0, Some(Tile::new(5, 5)));
+    file.save_chunk(chunks[3].0, &changed).unwrap();
+    drop(file);
+
+    let mut file = ChunkFile::open(&path.0).unwrap();
+    assert_eq!(file.len(), chunks.len());
+    assert_same_tiles(&file.load_chunk(&chunks[3].0).unwrap().unwrap(), &changed);
+    assert_same_tiles(
+        &file.load_chunk(&chunks[2].0).unwrap().unwrap(),
+        &chunks[2].1,
+    );
+}
+
+#[test]
+fn resaving_reuses_freed_space() {
+    let path = TempPath::new("reuse");
+    let chunks = chunks();
+    let mut file = ChunkFile::create(&path.0).unwrap();
+    file.save_chunks(chunks.iter().map(|(coord, chunk)| (*coord, chunk)))
+        .unwrap();
+    let size = fs::metadata(&path.0).unwrap().len();
+
+    for _ in 0..100 {
+        file.save_chunks(chunks.iter().map(|(coord, chunk)| (*coord, chunk)))
+            .unwrap();
+    }
+    // Every save needs its new data and index next to the live ones, never more.
+    assert!(fs::metadata(&path.0).unwrap().len() <= size * 3);
+
+    assert!(file.compact().unwrap() > 0);
+    assert_eq!(file.free_bytes(), 0);
+    assert_eq!(fs::metadata(&path.0).unwrap().len(), size);
+    drop(file);
+
+    let mut file = ChunkFile::open(&path.0).unwrap();
+    assert_eq!(file.free_bytes(), 0);
+    for (coord, chunk) in chunks.iter() {
+        assert_same_tiles(&file.load_chunk(coord).unwrap().unwrap(), chunk);
+    }
+}
+
+#[test]
+fn corrupt_index_is_invalid_data() {
+    let path = TempPath::new("corrupt");
+    let chunks = chunks();
+    let mut file = ChunkFile::create(&path.0).unwrap();
+    file.save_chunks(chunks.iter().map(|(coord, chunk)| (*coord, chunk)))
+        .unwrap();
+    drop(file);
+
+    // The index length in the header, claiming far more entries than the file holds.
+    let mut raw = fs::OpenOptions::new().write(true).open(&path.0).unwrap();
+    raw.seek(SeekFrom::Start(14)).unwrap();
+    raw.write_all(&u32::MAX.to_le_bytes()).unwrap();
+    drop(raw);
+
+    let error = ChunkFile::open(&path.0).err().unwrap();
+    assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+
+    fs::write(&path.0, b"BTCF").unwrap();
+    let error = ChunkFile::open(&path.0).err().unwrap();
+    assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+}
+
+#[test]
+fn unloaded_chunks_are_saved_and_loaded_back() {
+    let path = TempPath::new("streaming");
+    let mut app = App::new();
+    app.add_plugin(TilingPlugin)
+        .add_plugin(ChunkStreamingPlugin)
+        .add_plugin(ChunkPersistPlugin)
+        .insert_resource(ChunkStore::new(ChunkFile::create(&path.0).unwrap()));
+    {
+        let mut streaming = app.world.resource_mut::<ChunkStreaming>();
+        streaming.load_radius = 0;
+        streaming.unload_radius = 0;
+    }
+    let coord = TileCoord::from_tile_position(IVec3::new(3, 4, 0));
+    app.world
+        .resource_mut::<TileMap>()
+        .set_tile(&coord, Some(Tile::new(0, 9)));
+    let anchor = app
+        .world
+        .spawn()
+        .insert(StreamingAnchor)
+        .insert(GlobalTransform::from_translation(Vec3::new(
+            100.0, 0.0, 0.0,
+        )))
+        .id();
+
+    app.update();
+    assert!(app.world.resource::<TileMap>().get_tile(&coord).is_none());
+    let store = app.world.resource::<ChunkStore>();
+    assert!(store.has_chunk(&IVec3::ZERO));
+    assert_eq!(store.unsaved_count(), 0);
+
+    app.world
+        .entity_mut(anchor)
+        .insert(GlobalTransform::from_translation(Vec3::new(1.0, 1.0, 0.0)));
+    app.update();
+    assert_eq!(
+        app.world.resource::<TileMap>().get_tile(&coord),
+        Some(&Tile::new(0, 9))
+    );
+    assert!(app
+        .world
+        .resource_mut::<ChunkStore>()
+        .take_errors()
+        .is_empty());
+
+    let mut file = ChunkFile::open(&path.0).unwrap();
+    let saved = file.load_chunk(&IVec3::ZERO).unwrap().unwrap();
+    assert_eq!(saved.get_tile(coord.index()), Some(&Tile::new(0, 9)));
+}
+
+fn drain_progress<E: Copy + Send + Sync + 'static>(app: &mut App) -> Vec<E> {
+    app.world.resource_mut::<Events<E>>().drain().collect()
+}
+
+#[test]
+fn loads_and_saves_report_progress() {
+    let path = TempPath::new("progress");
+    let mut file = ChunkFile::create(&path.0).unwrap();
+    let stored: Vec<(IVec3, Chunk)> = (-1..=1)
+        .map(|x| (IVec3::new(x, 0, 0), Chunk::uniform(Some(Tile::new(0, 1)))))
+        .collect();
+    file.save_chunks(stored.iter().map(|(coord, chunk)| (*coord, chunk)))
+        .unwrap();
+
+    let mut app = App::new();
+    app.add_plugin(TilingPlugin)
+        .add_plugin(ChunkStreamingPlugin)
+        .add_plugin(ChunkPersistPlugin)
+        .insert_resource(ChunkStore::new(file));
+    {
+        let mut streaming = app.world.resource_mut::<ChunkStreaming>();
+        streaming.load_radius = 1;
+        streaming.unload_radius = 1;
+        streaming.layers = 0..=0;
+    }
+    let anchor = app
+        .world
+        .spawn()
+        .insert(StreamingAnchor)
+        .insert(GlobalTransform::default())
+        .id();
+
+    app.update();
+    assert_eq!(
+        drain_progress::<MapLoadProgress>(&mut app),
+        vec![MapLoadProgress {
+            loaded: 3,
+            total: 3
+        }]
+    );
+    app.update();
+    assert!(drain_progress::<MapLoadProgress>(&mut app).is_empty());
+
+    // Moving far away unloads the whole 3x3 area, the three stored chunks included.
+    app.world
+        .entity_mut(anchor)
+        .insert(GlobalTransform::from_translation(Vec3::new(
+            1000.0, 0.0, 0.0,
+        )));
+    app.update();
+    let saves = drain_progress::<MapSaveProgress>(&mut app);
+    assert_eq!(saves.len(), 1);
+    assert_eq!(saves[0].saved, saves[0].total);
+    assert!(saves[0].total >= 3);
+    assert_eq!(app.world.resource::<ChunkStore>().pending_loads(), 0);
+}
+
+#[test]
+fn loaded_chunks_keep_tiles_written_while_loading() {
+    let path = TempPath::new("merge");
+    let mut stored = Chunk::uniform(None);
+    stored.set_tile(0, Some(Tile::new(0, 1)));
+    stored.set_tile(1, Some(Tile::new(0, 1)));
+    let mut file = ChunkFile::create(&path.0).unwrap();
+    file.save_chunk(IVec3::ZERO, &stored).unwrap();
+
+    let mut app = App::new();
+    app.add_plugin(TilingPlugin)
+        .add_plugin(ChunkStreamingPlugin)
+        .add_plugin(ChunkPersistPlugin)
+        .insert_resource(ChunkStore::new(file));
+    let written = TileCoord::from_tile_position(IVec3::new(1, 0, 0));
+    app.world
+        .resource_mut::<TileMap>()
+        .set_tile(&written, Some(Tile::new(0, 2)));
+    app.world
+        .resource_mut::<Events<ChunkLoadRequest>>()
+        .send(ChunkLoadRequest::new(IVec3::ZERO));
+    app.update();
+
+    let map = app.world.resource::<TileMap>();
+    let chunk = map.get_chunk(&IVec3::ZERO).unwrap();
+    assert_eq!(chunk.get_tile(0), Some(&Tile::new(0, 1)));
+    assert_eq!(chunk.get_tile(1), Some(&Tile::new(0, 2)));
+}
@@ -0,0 +1,81 @@
+use bevy::math::{IVec2, IVec3};
+use bevy_tiling_core::{
+    bounds::MapWrap,
+    placement::{PlacementRules, PlacementViolation},
+    regions::RegionShape,
+    Tile, TileCoord, TileMap,
+};
+
+fn house() -> Tile {
+    Tile::new(0, 1)
+}
+
+fn coord(x: i32) -> TileCoord {
+    TileCoord::from_tile_position(IVec3::new(x, 0, 0))
+}
+
+/// A map wrapping every 32 tiles along x and y.
+fn wrapped_map() -> TileMap {
+    let mut map = TileMap::default();
+    map.set_wrap(Some(MapWrap::new(IVec2::new(2, 2))));
+    map
+}
+
+fn rules(min_x: i32, max_x: i32, max: usize) -> PlacementRules {
+    let mut rules = PlacementRules::default();
+    rules.max_per_region(
+        |tile| *tile == house(),
+        RegionShape::Rect {
+            min: IVec3::new(min_x, 0, 0),
+            max: IVec3::new(max_x, 0, 0),
+        },
+        max,
+    );
+    rules
+}
+
+#[test]
+fn regions_reach_across_the_seam() {
+    let mut map = wrapped_map();
+    map.set_tile(&coord(30), Some(house()));
+    let rules = rules(-4, 3, 1);
+
+    assert_eq!(
+        rules.check(&map, &coord(1), &house()),
+        vec![PlacementViolation::RegionFull(0)]
+    );
+    assert_eq!(
+        rules.check(&map, &coord(-1), &house()),
+        vec![PlacementViolation::RegionFull(0)]
+    );
+    assert!(rules.check(&map, &coord(10), &house()).is_empty());
+}
+
+#[test]
+fn replacing_a_tile_past_the_seam_does_not_count_it() {
+    let mut map = wrapped_map();
+    map.set_tile(&coord(33), Some(house()));
+    let rules = rules(28, 35, 1);
+
+    assert!(rules.check(&map, &coord(33), &house()).is_empty());
+    assert!(rules.check(&map, &coord(1), &house()).is_empty());
+    assert_eq!(
+        rules.check(&map, &coord(2), &house()),
+        vec![PlacementViolation::RegionFull(0)]
+    );
+}
+
+#[test]
+fn regions_wider_than_the_period_count_each_tile_once() {
+    let mut map = wrapped_map();
+    map.set_tile(&coord(0), Some(house()));
+    map.set_tile(&coord(5), Some(house()));
+
+    assert!(rules(-32, 63, 3)
+        .check(&map, &coord(10), &house())
+        .is_empty());
+    assert_eq!(
+        rules(-32, 63, 2).check(&map, &coord(10), &house()),
+        vec![PlacementViolation::RegionFull(0)]
+    );
+}
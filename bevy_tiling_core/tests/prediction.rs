@@ -0,0 +1,106 @@
+use bevy::{
+    ecs::system::SystemState,
+    math::IVec3,
+    prelude::{App, World},
+};
+use bevy_tiling_core::{
+    prediction::TilePredictions, DefaultMap, MapLabel, MapReader, Tile, TileCoord, TileMap,
+    TileMapPlugin, TileMapWriter, TilingPlugin,
+};
+
+struct Overworld;
+
+impl MapLabel for Overworld {}
+
+fn coord(x: i32) -> TileCoord {
+    TileCoord::from_tile_position(IVec3::new(x, 0, 0))
+}
+
+fn tile(index: u16) -> Option<Tile> {
+    Some(Tile::new(0, index))
+}
+
+fn app() -> App {
+    let mut app = App::new();
+    app.add_plugin(TilingPlugin)
+        .add_plugin(TileMapPlugin::<Overworld>::default());
+    app
+}
+
+/// Runs `f` with the predictions and a writer of the map labeled `L`.
+fn with_writer<L: MapLabel, R>(
+    world: &mut World,
+    f: impl FnOnce(&mut TilePredictions<L>, &mut TileMapWriter<L>) -> R,
+) -> R {
+    let mut predictions = world.remove_resource::<TilePredictions<L>>().unwrap();
+    let mut state: SystemState<TileMapWriter<L>> = SystemState::new(world);
+    let result = f(&mut predictions, &mut state.get_mut(world));
+    state.apply(world);
+    world.insert_resource(predictions);
+    result
+}
+
+fn tile_at<L: MapLabel>(world: &World, x: i32) -> Option<Tile> {
+    world.resource::<TileMap<L>>().get_tile(&coord(x)).copied()
+}
+
+#[test]
+fn reverting_restores_the_tiles_from_before() {
+    let mut app = app();
+    let world = &mut app.world;
+    let first = with_writer::<DefaultMap, _>(world, |predictions, writer| {
+        writer.set_tile(coord(0), tile(1));
+        predictions.predict(writer, [(coord(0), tile(2)), (coord(1), tile(3))])
+    });
+    assert_eq!(tile_at::<DefaultMap>(world, 0), tile(2));
+
+    assert!(with_writer::<DefaultMap, _>(
+        world,
+        |predictions, writer| predictions.revert(writer, first)
+    ));
+    assert_eq!(tile_at::<DefaultMap>(world, 0), tile(1));
+    assert_eq!(tile_at::<DefaultMap>(world, 1), None);
+    assert!(!with_writer::<DefaultMap, _>(
+        world,
+        |predictions, writer| predictions.revert(writer, first)
+    ));
+    assert_eq!(world.resource::<TilePredictions>().pending_count(), 0);
+}
+
+#[test]
+fn reverting_after_a_later_confirmed_prediction_keeps_its_tiles() {
+    let mut app = app();
+    let world = &mut app.world;
+    let (first, second) = with_writer::<DefaultMap, _>(world, |predictions, writer| {
+        let first = predictions.predict(writer, [(coord(0), tile(1)), (coord(1), tile(1))]);
+        let second = predictions.predict(writer, [(coord(0), tile(2))]);
+        (first, second)
+    });
+    let mut predictions = world.resource_mut::<TilePredictions>();
+    assert!(predictions.confirm(second));
+    assert!(!predictions.is_pending(second));
+    assert_eq!(predictions.pending_count(), 1);
+
+    with_writer::<DefaultMap, _>(world, |predictions, writer| {
+        assert!(predictions.revert(writer, first));
+    });
+    assert_eq!(tile_at::<DefaultMap>(world, 0), tile(2));
+    assert_eq!(tile_at::<DefaultMap>(world, 1), None);
+    assert_eq!(world.resource::<TilePredictions>().pending_count(), 0);
+}
+
+#[test]
+fn reverting_both_of_two_overlapping_predictions_restores_the_original() {
+    let mut app = app();
+    let world = &mut app.world;
+    with_writer::<Overworld, _>(world, |predictions, writer| {
+        writer.set_tile(coord(0), tile(9));
+        let first = predictions.predict(writer, [(coord(0), tile(1))]);
+        let second = predictions.predict(writer, [(coord(0), tile(2))]);
+        assert!(predictions.revert(writer, first));
+        assert_eq!(writer.get_tile(coord(0)).copied(), tile(2));
+        assert!(predictions.revert(writer, second));
+    });
+    assert_eq!(tile_at::<Overworld>(world, 0), tile(9));
+    assert_eq!(tile_at::<DefaultMap>(world, 0), None);
+}
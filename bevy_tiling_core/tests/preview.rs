@@ -0,0 +1,76 @@
+use bevy::{
+    math::IVec3,
+    prelude::{App, ResMut},
+};
+use bevy_tiling_core::{
+    placement::PlacementRules,
+    preview::{PreviewTile, TilePreview},
+    Tile, TileCoord, TileMap, TileMapUpdates, TilingPlugin,
+};
+
+fn coord(x: i32) -> TileCoord {
+    TileCoord::from_tile_position(IVec3::new(x, 0, 0))
+}
+
+fn ghost(mut preview: ResMut<TilePreview>) {
+    preview.set(coord(1), Tile::new(0, 2), true);
+}
+
+#[test]
+fn previews_last_for_the_frame_they_were_set_in() {
+    let mut app = App::new();
+    app.add_plugin(TilingPlugin).add_system(ghost);
+    app.world
+        .resource_mut::<TilePreview>()
+        .set(coord(0), Tile::new(0, 1), false);
+
+    app.update();
+    let preview = app.world.resource::<TilePreview>();
+    assert_eq!(
+        preview.get(coord(0)),
+        Some(&PreviewTile {
+            tile: Tile::new(0, 1),
+            valid: false,
+        })
+    );
+    assert!(preview.get(coord(1)).is_some());
+
+    app.update();
+    app.update();
+    let preview = app.world.resource::<TilePreview>();
+    assert!(preview.get(coord(0)).is_none());
+    assert!(preview.get(coord(1)).is_some());
+    assert_eq!(preview.iter().count(), 1);
+    assert!(app
+        .world
+        .resource::<TileMap>()
+        .get_tile(&coord(1))
+        .is_none());
+    assert_eq!(
+        app.world
+            .resource::<TileMapUpdates>()
+            .get_chunk_updates()
+            .count(),
+        0
+    );
+}
+
+#[test]
+fn checked_previews_follow_the_placement_rules() {
+    let mut rules = PlacementRules::default();
+    rules.require_adjacent(|tile| *tile == Tile::new(0, 1), |_| true);
+    let mut map = TileMap::default();
+    map.set_tile(&coord(0), Some(Tile::new(0, 5)));
+
+    let mut preview = TilePreview::default();
+    assert!(preview.set_checked(coord(1), Tile::new(0, 1), &rules, &map));
+    assert!(!preview.set_checked(coord(5), Tile::new(0, 1), &rules, &map));
+    assert!(!preview.get(coord(5)).unwrap().valid);
+
+    assert_eq!(
+        preview.remove(coord(1)).map(|preview| preview.valid),
+        Some(true)
+    );
+    preview.clear();
+    assert!(preview.get(coord(5)).is_none());
+}
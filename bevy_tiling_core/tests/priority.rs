@@ -0,0 +1,107 @@
+#![cfg(feature = "streaming")]
+
+use bevy::{
+    math::{IVec3, Vec3},
+    prelude::{App, Entity, GlobalTransform},
+};
+use bevy_tiling_core::{
+    generator::TileMapGenerator,
+    priority::ChunkPriorities,
+    streaming::{ChunkStreaming, ChunkStreamingPlugin, StreamingAnchor},
+    Chunk, Tile, TileMap, TilingPlugin,
+};
+
+/// An app loading the chunks next to an anchor in chunk (0, 0).
+fn app() -> (App, Entity) {
+    let mut app = App::new();
+    app.add_plugin(TilingPlugin)
+        .add_plugin(ChunkStreamingPlugin);
+    {
+        let mut streaming = app.world.resource_mut::<ChunkStreaming>();
+        streaming.load_radius = 1;
+        streaming.unload_radius = 1;
+    }
+    app.world
+        .resource_mut::<TileMapGenerator>()
+        .set(|_: IVec3| Chunk::uniform(Some(Tile::new(0, 1))));
+    let anchor = app
+        .world
+        .spawn()
+        .insert(StreamingAnchor)
+        .insert(GlobalTransform::from_translation(Vec3::new(8.0, 8.0, 0.0)))
+        .id();
+    (app, anchor)
+}
+
+fn loaded_chunks(app: &App) -> Vec<IVec3> {
+    app.world
+        .resource::<TileMap>()
+        .chunk_coords()
+        .copied()
+        .collect()
+}
+
+#[test]
+fn hinted_chunks_load_first() {
+    let (mut app, _) = app();
+    app.world
+        .resource_mut::<ChunkStreaming>()
+        .max_loads_per_frame = 1;
+    app.update();
+    assert_eq!(loaded_chunks(&app), vec![IVec3::ZERO]);
+
+    app.world
+        .resource_mut::<ChunkPriorities>()
+        .set(IVec3::new(1, -1, 0), 2);
+    app.world
+        .resource_mut::<ChunkPriorities>()
+        .set(IVec3::new(-1, 1, 0), 1);
+    app.update();
+    assert!(loaded_chunks(&app).contains(&IVec3::new(1, -1, 0)));
+    assert!(!loaded_chunks(&app).contains(&IVec3::new(-1, 1, 0)));
+    app.update();
+    assert!(loaded_chunks(&app).contains(&IVec3::new(-1, 1, 0)));
+    assert_eq!(loaded_chunks(&app).len(), 3);
+}
+
+#[test]
+fn hinted_chunks_are_never_unloaded() {
+    let (mut app, anchor) = app();
+    app.update();
+    app.world
+        .resource_mut::<ChunkPriorities>()
+        .set(IVec3::new(1, 0, 0), 5);
+
+    app.world
+        .entity_mut(anchor)
+        .insert(GlobalTransform::from_translation(Vec3::new(
+            100.0, 8.0, 0.0,
+        )));
+    app.update();
+    assert!(loaded_chunks(&app).contains(&IVec3::new(1, 0, 0)));
+    assert!(!loaded_chunks(&app).contains(&IVec3::new(0, 0, 0)));
+
+    // Clearing the hint lets the chunk go like any other.
+    app.world
+        .resource_mut::<ChunkPriorities>()
+        .set(IVec3::new(1, 0, 0), 0);
+    app.update();
+    assert!(!loaded_chunks(&app).contains(&IVec3::new(1, 0, 0)));
+}
+
+#[test]
+fn zero_removes_the_hint() {
+    let mut priorities: ChunkPriorities = ChunkPriorities::default();
+    priorities.set(IVec3::ZERO, 3);
+    priorities.set(IVec3::ONE, 1);
+    assert_eq!(priorities.get(&IVec3::ZERO), 3);
+    assert_eq!(priorities.get(&IVec3::X), 0);
+
+    priorities.set(IVec3::ZERO, 0);
+    assert_eq!(
+        priorities.iter().collect::<Vec<_>>(),
+        vec![(&IVec3::ONE, 1)]
+    );
+    priorities.clear();
+    assert_eq!(priorities.iter().count(), 0);
+}
@@ -0,0 +1,125 @@
+use bevy::{
+    math::{IVec3, UVec2},
+    prelude::App,
+};
+use bevy_tiling_core::{
+    quads::{ChunkQuads, QuadMergeStats, TileQuad, TileQuadCache, TileQuadPlugin},
+    schedule::TileSchedule,
+    Chunk, Tile, TileCoord, TilingPlugin,
+};
+
+fn index(x: u8, y: u8) -> u8 {
+    y * 16 + x
+}
+
+/// A 3 by 2 block of stone with a step of dirt on its top row and a lone dirt tile.
+fn chunk() -> Chunk {
+    let mut chunk = Chunk::uniform(None);
+    for y in 5..=6 {
+        for x in 2..=4 {
+            chunk.set_tile(index(x, y), Some(Tile::new(0, 1)));
+        }
+    }
+    chunk.set_tile(index(4, 6), Some(Tile::new(0, 2)));
+    chunk.set_tile(index(0, 0), Some(Tile::new(0, 2)));
+    chunk
+}
+
+/// Checks that the quads cover exactly the set tiles of the chunk.
+fn assert_covers(quads: &ChunkQuads, chunk: &Chunk) {
+    let mut covered = 0;
+    for quad in quads.quads() {
+        for y in quad.min.y..quad.min.y + quad.size.y {
+            for x in quad.min.x..quad.min.x + quad.size.x {
+                assert_eq!(chunk.get_tile(index(x as u8, y as u8)), Some(&quad.tile));
+                covered += 1;
+            }
+        }
+    }
+    let set = (0..=u8::MAX)
+        .filter(|index| chunk.get_tile(*index).is_some())
+        .count();
+    assert_eq!(covered, set);
+}
+
+#[test]
+fn equal_runs_stack_into_quads() {
+    let chunk = chunk();
+    let quads = ChunkQuads::build(&chunk);
+    assert_covers(&quads, &chunk);
+    assert_eq!(quads.quads().len(), 4);
+    assert!(quads.quads().contains(&TileQuad {
+        min: UVec2::new(2, 5),
+        size: UVec2::new(3, 1),
+        tile: Tile::new(0, 1),
+    }));
+
+    assert_eq!(
+        ChunkQuads::build(&Chunk::uniform(Some(Tile::new(0, 3)))).quads(),
+        &[TileQuad {
+            min: UVec2::ZERO,
+            size: UVec2::splat(16),
+            tile: Tile::new(0, 3),
+        }]
+    );
+}
+
+#[test]
+fn updates_only_merge_the_dirty_rows() {
+    let mut chunk = chunk();
+    let mut quads = ChunkQuads::build(&chunk);
+    chunk.set_tile(index(4, 6), Some(Tile::new(0, 1)));
+    chunk.set_tile(index(7, 6), Some(Tile::new(0, 1)));
+
+    assert_eq!(quads.update(&chunk, [index(4, 6), index(7, 6)]), 1);
+    assert_covers(&quads, &chunk);
+    assert!(quads.quads().contains(&TileQuad {
+        min: UVec2::new(2, 5),
+        size: UVec2::new(3, 2),
+        tile: Tile::new(0, 1),
+    }));
+    assert_eq!(quads.update(&chunk, []), 0);
+}
+
+#[test]
+fn the_cache_follows_the_map() {
+    let mut app = App::new();
+    app.add_plugin(TilingPlugin).add_plugin(TileQuadPlugin);
+    let coord = |x, y| TileCoord::from_tile_position(IVec3::new(x, y, 0));
+    {
+        let mut schedule = app.world.resource_mut::<TileSchedule>();
+        schedule.schedule_in(coord(0, 0), Some(Tile::new(0, 1)), 0);
+        schedule.schedule_in(coord(1, 0), Some(Tile::new(0, 1)), 1);
+    }
+
+    app.update();
+    let cache = app.world.resource::<TileQuadCache>();
+    assert_eq!(
+        cache.stats(),
+        QuadMergeStats {
+            chunks_built: 1,
+            rows_merged: 16,
+            ..Default::default()
+        }
+    );
+
+    app.update();
+    let cache = app.world.resource::<TileQuadCache>();
+    assert_eq!(
+        cache.stats(),
+        QuadMergeStats {
+            chunks_updated: 1,
+            rows_merged: 1,
+            rows_reused: 15,
+            ..Default::default()
+        }
+    );
+    assert_eq!(
+        cache.get(&IVec3::ZERO).unwrap().quads(),
+        &[TileQuad {
+            min: UVec2::ZERO,
+            size: UVec2::new(2, 1),
+            tile: Tile::new(0, 1),
+        }]
+    );
+}
@@ -0,0 +1,81 @@
+use bevy::math::{IVec3, Vec2};
+use bevy_tiling_core::{
+    raster::{catmull_rom, rasterize_polyline, LineCap, StrokeSettings},
+    TileCoord,
+};
+
+fn positions(tiles: &[TileCoord]) -> Vec<IVec3> {
+    tiles.iter().map(TileCoord::tile_position).collect()
+}
+
+#[test]
+fn caps_extend_the_ends_differently() {
+    let points = [Vec2::new(0.5, 0.5), Vec2::new(4.5, 0.5)];
+    let count = |cap| rasterize_polyline(&points, 0, &StrokeSettings { width: 2.5, cap }).len();
+    assert_eq!(count(LineCap::Butt), 15);
+    assert_eq!(count(LineCap::Round), 17);
+    assert_eq!(count(LineCap::Square), 21);
+}
+
+#[test]
+fn corners_are_visited_once_in_path_order() {
+    let points = [
+        Vec2::new(0.5, 0.5),
+        Vec2::new(3.5, 0.5),
+        Vec2::new(3.5, 3.5),
+    ];
+    let tiles = rasterize_polyline(&points, 2, &StrokeSettings::default());
+    assert_eq!(
+        positions(&tiles),
+        vec![
+            IVec3::new(0, 0, 2),
+            IVec3::new(1, 0, 2),
+            IVec3::new(2, 0, 2),
+            IVec3::new(3, 0, 2),
+            IVec3::new(3, 1, 2),
+            IVec3::new(3, 2, 2),
+            IVec3::new(3, 3, 2),
+        ]
+    );
+}
+
+#[test]
+fn thin_lines_stay_connected() {
+    let points = [Vec2::new(0.5, 0.5), Vec2::new(3.5, 3.5)];
+    let tiles = rasterize_polyline(
+        &points,
+        0,
+        &StrokeSettings {
+            width: 0.1,
+            cap: LineCap::Butt,
+        },
+    );
+    assert_eq!(
+        positions(&tiles),
+        (0..4).map(|i| IVec3::new(i, i, 0)).collect::<Vec<_>>()
+    );
+}
+
+#[test]
+fn single_points_depend_on_the_cap() {
+    let point = [Vec2::new(0.5, 0.5)];
+    let tiles = |cap| rasterize_polyline(&point, 0, &StrokeSettings { width: 1.0, cap });
+    assert!(tiles(LineCap::Butt).is_empty());
+    assert_eq!(positions(&tiles(LineCap::Round)), vec![IVec3::ZERO]);
+}
+
+#[test]
+fn splines_pass_through_their_control_points() {
+    let points = [
+        Vec2::new(0.0, 0.0),
+        Vec2::new(4.0, 2.0),
+        Vec2::new(8.0, -1.0),
+        Vec2::new(12.0, 3.0),
+    ];
+    let curve = catmull_rom(&points, 5);
+    assert_eq!(curve.len(), 3 * 5 + 1);
+    for (i, point) in points.iter().enumerate() {
+        assert!(curve[i * 5].distance(*point) < 1e-4);
+    }
+    assert_eq!(catmull_rom(&points[..2], 5), points[..2].to_vec());
+}
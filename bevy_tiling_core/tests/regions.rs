@@ -0,0 +1,90 @@
+use bevy::math::IVec3;
+use bevy_tiling_core::{
+    regions::{RegionId, TileRegions},
+    TileCoord,
+};
+
+/// A small xorshift generator so the layout is the same on every run.
+struct Random(u64);
+
+impl Random {
+    fn next(&mut self, below: i32) -> i32 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        (self.0 % below as u64) as i32
+    }
+
+    fn position(&mut self) -> IVec3 {
+        IVec3::new(self.next(200) - 100, self.next(200) - 100, self.next(3))
+    }
+}
+
+fn contains(min: IVec3, max: IVec3, position: IVec3) -> bool {
+    position.cmpge(min.min(max)).all() && position.cmple(min.max(max)).all()
+}
+
+fn found(regions: &TileRegions, position: IVec3) -> Vec<RegionId> {
+    let mut ids: Vec<RegionId> = regions
+        .regions_at(&TileCoord::from_tile_position(position))
+        .map(|(id, _)| id)
+        .collect();
+    ids.sort_unstable();
+    ids
+}
+
+#[test]
+fn lookups_match_every_rect_containing_the_tile() {
+    let mut random = Random(0x2545_f491_4f6c_dd1d);
+    let mut regions = TileRegions::default();
+    let mut rects = Vec::new();
+    for index in 0..300 {
+        let (a, b) = (random.position(), random.position());
+        rects.push((regions.add_rect(format!("rect {}", index), a, b), a, b));
+    }
+    // Remove every third rect, so lookups also run on a tree that has shrunk.
+    for (id, _, _) in rects.iter().step_by(3) {
+        assert!(regions.remove(*id).is_some());
+    }
+    rects = rects
+        .into_iter()
+        .enumerate()
+        .filter(|(index, _)| index % 3 != 0)
+        .map(|(_, rect)| rect)
+        .collect();
+
+    for _ in 0..500 {
+        let position = random.position();
+        let expected: Vec<RegionId> = rects
+            .iter()
+            .filter(|(_, min, max)| contains(*min, *max, position))
+            .map(|(id, _, _)| *id)
+            .collect();
+        assert_eq!(found(&regions, position), expected, "at {}", position);
+    }
+}
+
+#[test]
+fn huge_rects_and_tile_regions_are_found() {
+    let mut regions = TileRegions::default();
+    let world = regions.add_rect("world", IVec3::splat(i32::MIN), IVec3::splat(i32::MAX));
+    let town = regions.add_rect("town", IVec3::new(10, 10, 0), IVec3::new(40, 30, 0));
+    let well = regions.add_tiles(
+        "well",
+        [IVec3::new(20, 20, 0), IVec3::new(21, 20, 0)].map(TileCoord::from_tile_position),
+    );
+
+    assert_eq!(
+        found(&regions, IVec3::new(20, 20, 0)),
+        vec![world, town, well]
+    );
+    assert_eq!(found(&regions, IVec3::new(22, 20, 0)), vec![world, town]);
+    assert_eq!(found(&regions, IVec3::new(-5_000_000, 7, 99)), vec![world]);
+    let position = TileCoord::from_tile_position(IVec3::new(20, 20, 0));
+    assert_eq!(regions.region_at(&position).unwrap().name, "well");
+
+    regions.remove(world);
+    regions.remove(well);
+    assert_eq!(found(&regions, IVec3::new(20, 20, 0)), vec![town]);
+    assert!(found(&regions, IVec3::new(-5_000_000, 7, 99)).is_empty());
+}
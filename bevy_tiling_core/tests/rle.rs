@@ -0,0 +1,181 @@
+use bevy::{
+    math::IVec3,
+    prelude::{App, ResMut},
+};
+use bevy_tiling_core::{
+    history::TileHistory,
+    rle::{ChunkCompression, RleChunk, TileRun},
+    Chunk, Tile, TileCoord, TileMap, TileMapUpdates, TilingCoreStage, TilingPlugin,
+};
+
+/// Chunks from uniform to one run per position, with flags and empty positions mixed in.
+fn chunks() -> Vec<Chunk> {
+    let mut mostly_one = Chunk::uniform(Some(Tile::new(0, 1)));
+    for index in (7..=u8::MAX).step_by(23) {
+        mostly_one.set_tile(index, Some(Tile::new(0, 2).with_rotation(1)));
+    }
+    for index in 128..140 {
+        mostly_one.set_tile(index, None);
+    }
+    let mut alternating = Chunk::uniform(None);
+    for index in (0..=u8::MAX).step_by(2) {
+        alternating.set_tile(index, Some(Tile::new(3, index as u16)));
+    }
+    let mut last_only = Chunk::uniform(None);
+    last_only.set_tile(u8::MAX, Some(Tile::new(1, 1).with_flip(false, true)));
+    vec![
+        Chunk::uniform(None),
+        Chunk::uniform(Some(Tile::new(4, 4))),
+        mostly_one,
+        alternating,
+        last_only,
+    ]
+}
+
+fn assert_same_tiles(chunk: &Chunk, expected: &Chunk) {
+    for index in 0..=u8::MAX {
+        assert_eq!(
+            chunk.get_tile(index),
+            expected.get_tile(index),
+            "index {}",
+            index
+        );
+    }
+}
+
+#[test]
+fn runs_read_like_the_chunk() {
+    for chunk in chunks() {
+        let rle = RleChunk::from_chunk(&chunk);
+        for index in 0..=u8::MAX {
+            assert_eq!(rle.get_tile(index), chunk.get_tile(index));
+        }
+        assert_same_tiles(&rle.to_chunk(), &chunk);
+        assert_eq!(rle.runs().map(|run| run.len).sum::<u16>(), 256);
+    }
+}
+
+#[test]
+fn bytes_decode_to_the_same_runs() {
+    for chunk in chunks() {
+        let rle = RleChunk::from_chunk(&chunk);
+        let decoded = RleChunk::from_bytes(&rle.to_bytes()).unwrap();
+        assert_eq!(decoded, rle);
+        assert_same_tiles(&decoded.to_chunk(), &chunk);
+    }
+}
+
+#[test]
+fn compressed_chunks_keep_their_tiles() {
+    for chunk in chunks() {
+        let mut compressed = chunk.clone();
+        compressed.compress();
+        assert_same_tiles(&compressed, &chunk);
+
+        compressed.set_tile(5, Some(Tile::new(9, 9)));
+        let mut expected = chunk.clone();
+        expected.set_tile(5, Some(Tile::new(9, 9)));
+        assert_same_tiles(&compressed, &expected);
+    }
+}
+
+#[test]
+fn malformed_runs_are_rejected() {
+    let run = |len, tile| TileRun { len, tile };
+    assert!(RleChunk::from_runs([run(255, None)]).is_none());
+    assert!(RleChunk::from_runs([run(200, None), run(57, None)]).is_none());
+    assert!(RleChunk::from_runs([run(0, None), run(256, None)]).is_none());
+    assert_eq!(
+        RleChunk::from_runs([run(100, None), run(156, None)]),
+        Some(RleChunk::from_chunk(&Chunk::uniform(None)))
+    );
+
+    let bytes = RleChunk::from_chunk(&chunks()[2]).to_bytes();
+    assert!(RleChunk::from_bytes(&bytes[..bytes.len() - 1]).is_none());
+    assert!(RleChunk::from_bytes(&[255, 2]).is_none());
+}
+
+fn coord(x: i32) -> TileCoord {
+    TileCoord::from_tile_position(IVec3::new(x, 0, 0))
+}
+
+/// What the next update does to the map through the history.
+#[derive(Default)]
+enum Step {
+    #[default]
+    Idle,
+    Set(i32, u16),
+    Rollback,
+}
+
+fn simulate(
+    mut step: ResMut<Step>,
+    mut history: ResMut<TileHistory>,
+    mut map: ResMut<TileMap>,
+    mut updates: ResMut<TileMapUpdates>,
+) {
+    match std::mem::take(&mut *step) {
+        Step::Idle => {}
+        Step::Set(x, index) => {
+            history
+                .writer(&mut map, &mut updates)
+                .set_tile((x, 0), Some(Tile::new(0, index)));
+            history.record();
+        }
+        Step::Rollback => {
+            history.rollback(&mut map, &mut updates, 1);
+        }
+    }
+}
+
+#[test]
+fn idle_chunks_compress_while_a_history_is_kept() {
+    let mut app = App::new();
+    app.add_plugin(TilingPlugin)
+        .insert_resource(TileHistory::new(8))
+        .init_resource::<Step>()
+        .add_system_to_stage(TilingCoreStage::Schedule, simulate);
+    app.world.resource_mut::<ChunkCompression>().idle_frames = Some(2);
+    let run = |app: &mut App, step: Step| {
+        app.insert_resource(step);
+        app.update();
+        let map = app.world.resource::<TileMap>();
+        map.get_chunk(&IVec3::ZERO).unwrap().is_compressed()
+    };
+
+    assert!(!run(&mut app, Step::Set(0, 1)));
+    assert!(!run(&mut app, Step::Set(1, 2)));
+    // The second edit restarted the idle frames.
+    assert!(!run(&mut app, Step::Idle));
+    assert!(run(&mut app, Step::Idle));
+
+    // Rolling back writes to the chunk, which expands it until it is idle again.
+    assert!(!run(&mut app, Step::Rollback));
+    assert!(!run(&mut app, Step::Idle));
+    assert!(run(&mut app, Step::Idle));
+    let map = app.world.resource::<TileMap>();
+    assert_eq!(map.get_tile(&coord(0)), Some(&Tile::new(0, 1)));
+    assert!(map.get_tile(&coord(1)).is_none());
+}
+
+#[test]
+fn chunks_from_before_compression_was_turned_on_are_compressed() {
+    let mut app = App::new();
+    app.add_plugin(TilingPlugin);
+    {
+        // Written without updates, so only turning compression on queues the chunk.
+        let mut map = app.world.resource_mut::<TileMap>();
+        map.set_tile(&coord(0), Some(Tile::new(0, 1)));
+        map.set_tile(&coord(1), Some(Tile::new(0, 2)));
+    }
+    app.update();
+    app.world.resource_mut::<ChunkCompression>().idle_frames = Some(1);
+    app.update();
+    let compressed = |app: &App| {
+        let map = app.world.resource::<TileMap>();
+        map.get_chunk(&IVec3::ZERO).unwrap().is_compressed()
+    };
+    assert!(!compressed(&app));
+    app.update();
+    assert!(compressed(&app));
+}
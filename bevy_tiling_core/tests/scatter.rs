@@ -0,0 +1,89 @@
+use bevy::math::IVec3;
+use bevy_tiling_core::{
+    scatter::{scatter_chunk, ScatterSettings},
+    Chunk, Tile, TileCoord,
+};
+
+fn grass() -> Tile {
+    Tile::new(0, 1)
+}
+
+fn settings(seed: u64, min_spacing: f32) -> ScatterSettings {
+    ScatterSettings {
+        seed,
+        min_spacing,
+        ..ScatterSettings::default()
+    }
+}
+
+/// The picks of a square of grass chunks, as tile positions.
+fn picks(settings: &ScatterSettings, chunks: i32) -> Vec<IVec3> {
+    let chunk = Chunk::uniform(Some(grass()));
+    (0..chunks)
+        .flat_map(|y| (0..chunks).map(move |x| IVec3::new(x, y, 0)))
+        .flat_map(|coord| scatter_chunk(&chunk, coord, settings, |tile| *tile == grass()))
+        .map(|coord| coord.tile_position())
+        .collect()
+}
+
+#[test]
+fn spacing_holds_across_chunk_borders() {
+    for seed in 0..4 {
+        let settings = settings(seed, 3.0);
+        let picks = picks(&settings, 3);
+        assert!(!picks.is_empty());
+        for (i, a) in picks.iter().enumerate() {
+            for b in picks[i + 1..].iter() {
+                let distance = (*a - *b).as_vec3().length();
+                assert!(
+                    distance >= 3.0,
+                    "{:?} and {:?} are {} apart",
+                    a,
+                    b,
+                    distance
+                );
+            }
+        }
+    }
+}
+
+#[test]
+fn picks_are_the_same_every_time() {
+    let settings = settings(7, 2.0);
+    assert_eq!(picks(&settings, 2), picks(&settings, 2));
+    assert_ne!(
+        picks(&settings, 2),
+        picks(
+            &ScatterSettings {
+                seed: 8,
+                ..settings
+            },
+            2
+        )
+    );
+}
+
+#[test]
+fn only_matching_tiles_are_picked() {
+    let mut chunk = Chunk::uniform(Some(grass()));
+    for index in 0..128 {
+        chunk.set_tile(index, Some(Tile::new(0, 2)));
+    }
+    let picked = scatter_chunk(&chunk, IVec3::ZERO, &settings(1, 0.0), |tile| {
+        *tile == grass()
+    });
+    assert_eq!(picked.len(), 128);
+    assert!(picked.iter().all(|coord| coord.index() >= 128));
+}
+
+#[test]
+fn max_per_chunk_caps_the_picks() {
+    let settings = ScatterSettings {
+        max_per_chunk: 3,
+        ..settings(2, 2.0)
+    };
+    let chunk = Chunk::uniform(Some(grass()));
+    let picked: Vec<TileCoord> =
+        scatter_chunk(&chunk, IVec3::ZERO, &settings, |tile| *tile == grass());
+    assert_eq!(picked.len(), 3);
+}
@@ -0,0 +1,77 @@
+use bevy::{math::IVec3, prelude::App};
+use bevy_tiling_core::{schedule::TileSchedule, Tile, TileCoord, TileMap, TilingPlugin};
+
+fn coord(x: i32) -> TileCoord {
+    TileCoord::from_tile_position(IVec3::new(x, 0, 0))
+}
+
+fn app() -> App {
+    let mut app = App::new();
+    app.add_plugin(TilingPlugin);
+    app
+}
+
+#[test]
+fn changes_apply_once_their_tick_is_reached() {
+    let mut app = app();
+    app.world
+        .resource_mut::<TileSchedule>()
+        .schedule_in(coord(0), Some(Tile::new(0, 1)), 2);
+
+    app.update();
+    app.update();
+    assert!(app
+        .world
+        .resource::<TileMap>()
+        .get_tile(&coord(0))
+        .is_none());
+    app.update();
+    assert_eq!(
+        app.world.resource::<TileMap>().get_tile(&coord(0)),
+        Some(&Tile::new(0, 1))
+    );
+    let schedule = app.world.resource::<TileSchedule>();
+    assert_eq!(schedule.tick(), 3);
+    assert!(schedule.is_empty());
+}
+
+#[test]
+fn changes_due_together_apply_in_scheduling_order() {
+    let mut app = app();
+    {
+        let mut schedule = app.world.resource_mut::<TileSchedule>();
+        schedule.set_tick(10);
+        // Due in the past, so applied on the next run.
+        schedule.schedule_set(coord(0), Some(Tile::new(0, 1)), 4);
+        schedule.schedule_set(coord(0), Some(Tile::new(0, 2)), 10);
+        schedule.schedule_set(coord(1), Some(Tile::new(0, 3)), 10);
+        schedule.schedule_set(coord(1), None, 10);
+    }
+    app.update();
+    let map = app.world.resource::<TileMap>();
+    assert_eq!(map.get_tile(&coord(0)), Some(&Tile::new(0, 2)));
+    assert!(map.get_tile(&coord(1)).is_none());
+}
+
+#[test]
+fn cancel_drops_every_pending_change_of_a_tile() {
+    let mut schedule = TileSchedule::default();
+    schedule.schedule_in(coord(0), Some(Tile::new(0, 1)), 5);
+    schedule.schedule_in(coord(1), Some(Tile::new(0, 2)), 3);
+    schedule.schedule_in(coord(0), None, 1);
+    assert_eq!(schedule.len(), 3);
+
+    let pending: Vec<(u64, TileCoord)> = schedule
+        .iter()
+        .map(|(tick, coord, _)| (tick, *coord))
+        .collect();
+    assert_eq!(pending, vec![(1, coord(0)), (3, coord(1)), (5, coord(0))]);
+
+    assert_eq!(schedule.cancel(coord(0)), 2);
+    assert_eq!(schedule.cancel(coord(0)), 0);
+    assert_eq!(schedule.len(), 1);
+    assert_eq!(
+        schedule.iter().next().map(|(_, _, tile)| tile.copied()),
+        Some(Some(Tile::new(0, 2)))
+    );
+}
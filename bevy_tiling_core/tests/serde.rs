@@ -0,0 +1,129 @@
+#![cfg(feature = "serde")]
+
+use bevy::math::{IVec2, IVec3};
+use bevy_tiling_core::{
+    biome::BiomeMap,
+    bounds::{BoundsMode, MapBounds, MapWrap},
+    chunk_data::ChunkData,
+    schedule::TileSchedule,
+    Chunk, Tile, TileCoord, TileMap,
+};
+
+fn coord(x: i32, y: i32, z: i32) -> TileCoord {
+    TileCoord::from_tile_position(IVec3::new(x, y, z))
+}
+
+fn round_trip<T: serde::Serialize + serde::de::DeserializeOwned>(value: &T) -> T {
+    let text = ron::ser::to_string(value).unwrap();
+    ron::de::from_str(&text).unwrap()
+}
+
+/// A dense chunk, a chunk of long runs, a uniform chunk and flipped and rotated tiles.
+fn map() -> TileMap {
+    let mut map = TileMap::default();
+    map.set_bounds(Some(MapBounds::new(
+        IVec3::new(-64, -64, 0),
+        IVec3::new(63, 63, 1),
+        BoundsMode::Clamp,
+    )));
+    map.set_wrap(Some(MapWrap::new(IVec2::new(8, 0))));
+    for index in (0..256).step_by(5) {
+        map.set_tile(
+            &coord(index % 16, index / 16, 0),
+            Some(Tile::new(1, index as u16).with_flip(index % 2 == 0, false)),
+        );
+    }
+    for x in 16..32 {
+        for y in 0..16 {
+            let index = if y < 8 { 1 } else { 2 };
+            map.set_tile(&coord(x, y, 0), Some(Tile::new(0, index).with_rotation(1)));
+        }
+    }
+    map.insert_shared_chunk(
+        IVec3::new(-1, -1, 1),
+        Chunk::uniform(Some(Tile::new(3, 7))).into(),
+    );
+    map
+}
+
+#[test]
+fn tile_maps_keep_their_tiles_and_settings() {
+    let map = map();
+    let loaded: TileMap = round_trip(&map);
+    assert_eq!(loaded.bounds(), map.bounds());
+    assert_eq!(loaded.wrap(), map.wrap());
+    for (z, chunks) in [(0, 0..2), (1, -1..0)] {
+        for x in chunks.start * 16..chunks.end * 16 {
+            for y in -16..16 {
+                assert_eq!(
+                    loaded.get_tile(&coord(x, y, z)),
+                    map.get_tile(&coord(x, y, z)),
+                    "at {}, {}, {}",
+                    x,
+                    y,
+                    z
+                );
+            }
+        }
+    }
+    assert_eq!(
+        ron::ser::to_string(&loaded).unwrap(),
+        ron::ser::to_string(&map).unwrap()
+    );
+}
+
+#[test]
+fn malformed_chunks_are_rejected() {
+    let mask_mismatch = "Sparse(mask: (1, 0, 0, 0), tiles: [])";
+    let error = ron::de::from_str::<Chunk>(mask_mismatch).unwrap_err();
+    assert!(error.to_string().contains("mask"), "{}", error);
+    let short_runs = "Runs([(len: 255, tile: None)])";
+    assert!(ron::de::from_str::<Chunk>(short_runs).is_err());
+}
+
+#[test]
+fn biome_maps_keep_their_cells() {
+    let mut biomes = BiomeMap::new(8);
+    biomes.set_biome(&coord(0, 0, 0), Some(1));
+    biomes.set_biome(&coord(9, 0, 0), Some(2));
+    biomes.set_biome(&coord(-20, 40, 1), Some(3));
+    let loaded: BiomeMap = round_trip(&biomes);
+    assert_eq!(loaded.cell_size(), 8);
+    for chunk in [IVec3::new(0, 0, 0), IVec3::new(-2, 2, 1)] {
+        assert_eq!(
+            loaded.get_chunk_biomes(&chunk),
+            biomes.get_chunk_biomes(&chunk)
+        );
+    }
+    assert_eq!(loaded.biome_at(&coord(9, 0, 0)), Some(2));
+
+    let wrong_cell_size = "(cell_size: 3, chunks: [])";
+    assert!(ron::de::from_str::<BiomeMap>(wrong_cell_size).is_err());
+    let wrong_cell_count = "(cell_size: 8, chunks: [((0, 0, 0), [Some(1)])])";
+    assert!(ron::de::from_str::<BiomeMap>(wrong_cell_count).is_err());
+}
+
+#[test]
+fn chunk_data_and_schedules_keep_their_entries() {
+    let mut data = ChunkData::<(u32, String)>::default();
+    data.insert(IVec3::new(2, 0, 0), (5, "ore".to_string()));
+    data.insert(IVec3::new(-1, 3, 1), (0, String::new()));
+    let loaded: ChunkData<(u32, String)> = round_trip(&data);
+    let mut entries: Vec<_> = loaded.iter().collect();
+    entries.sort_by_key(|(coord, _)| coord.to_array());
+    let mut expected: Vec<_> = data.iter().collect();
+    expected.sort_by_key(|(coord, _)| coord.to_array());
+    assert_eq!(entries, expected);
+
+    let mut schedule = TileSchedule::default();
+    schedule.set_tick(10);
+    schedule.schedule_in(coord(1, 2, 0), Some(Tile::new(0, 4)), 5);
+    schedule.schedule_in(coord(-3, 0, 0), None, 5);
+    schedule.schedule_in(coord(7, 7, 1), Some(Tile::new(2, 1)), 1);
+    let loaded: TileSchedule = round_trip(&schedule);
+    assert_eq!(loaded.tick(), 10);
+    assert_eq!(
+        loaded.iter().collect::<Vec<_>>(),
+        schedule.iter().collect::<Vec<_>>()
+    );
+}
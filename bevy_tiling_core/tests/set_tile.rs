@@ -0,0 +1,242 @@
+use bevy::{
+    ecs::event::Events,
+    ecs::system::SystemState,
+    math::{IVec2, IVec3},
+    prelude::{App, World},
+};
+use bevy_tiling_core::{
+    bounds::{BoundsMode, MapBounds, MapWrap},
+    internal, MapReader, Tile, TileChanged, TileCoord, TileMap, TileMapUpdates, TileMapWriter,
+    TilingPlugin,
+};
+
+fn app() -> App {
+    let mut app = App::new();
+    app.add_plugin(TilingPlugin);
+    app
+}
+
+fn write(world: &mut World, f: impl FnOnce(&mut TileMapWriter)) {
+    let mut state: SystemState<TileMapWriter> = SystemState::new(world);
+    f(&mut state.get_mut(world));
+    state.apply(world);
+}
+
+fn updated_chunks(world: &World) -> Vec<IVec3> {
+    world
+        .resource::<TileMapUpdates>()
+        .get_chunk_updates()
+        .copied()
+        .collect()
+}
+
+#[test]
+fn set_tile_writes_into_new_chunk() {
+    let mut map = TileMap::default();
+    let coord = internal::tile_coord(IVec3::new(2, -3, 0), 17);
+    let tile = Tile::new(1, 5);
+
+    assert_eq!(map.set_tile(&coord, Some(tile)), None);
+    assert_eq!(map.get_tile(&coord), Some(&tile));
+}
+
+#[test]
+fn clearing_missing_tile_does_not_create_chunk() {
+    let mut map = TileMap::default();
+    let coord = internal::tile_coord(IVec3::new(4, 4, 0), 0);
+
+    assert_eq!(map.set_tile(&coord, None), None);
+    assert!(map.get_chunk(&coord.chunk()).is_none());
+}
+
+#[test]
+fn get_or_create_chunk_reuses_existing_chunk() {
+    let mut map = TileMap::default();
+    let coord = internal::tile_coord(IVec3::ZERO, 3);
+    map.set_tile(&coord, Some(Tile::new(0, 1)));
+
+    let chunk = map.get_or_create_chunk(&IVec3::ZERO);
+    assert_eq!(chunk.get_tile(3), Some(&Tile::new(0, 1)));
+}
+
+#[test]
+fn writer_set_tile_lands_and_marks_update() {
+    let mut app = app();
+    let coord = internal::tile_coord(IVec3::new(-1, 0, 0), 255);
+    let tile = Tile::new(2, 7);
+
+    write(&mut app.world, |writer| {
+        assert_eq!(writer.set_tile(coord, Some(tile)), None);
+        assert_eq!(writer.get_tile(coord), Some(&tile));
+    });
+
+    assert_eq!(
+        app.world.resource::<TileMap>().get_tile(&coord),
+        Some(&tile)
+    );
+    assert_eq!(updated_chunks(&app.world), vec![coord.chunk()]);
+}
+
+#[test]
+fn writer_set_tiles_lands_across_chunks() {
+    let mut app = app();
+    let tiles: Vec<(TileCoord, Option<Tile>)> = (-20..20)
+        .map(|x| {
+            (
+                TileCoord::from_tile_position(IVec3::new(x, 3, 0)),
+                Some(Tile::new(0, x.unsigned_abs() as u16)),
+            )
+        })
+        .collect();
+
+    write(&mut app.world, |writer| writer.set_tiles(tiles.clone()));
+
+    let map = app.world.resource::<TileMap>();
+    for (coord, tile) in tiles.iter() {
+        assert_eq!(map.get_tile(coord), tile.as_ref());
+    }
+    let mut chunks = updated_chunks(&app.world);
+    chunks.sort_by_key(|chunk| chunk.x);
+    assert_eq!(
+        chunks,
+        vec![
+            IVec3::new(-2, 0, 0),
+            IVec3::new(-1, 0, 0),
+            IVec3::new(0, 0, 0),
+            IVec3::new(1, 0, 0)
+        ]
+    );
+}
+
+#[test]
+fn writer_set_tile_without_change_does_not_mark_update() {
+    let mut app = app();
+    let coord = internal::tile_coord(IVec3::ZERO, 0);
+
+    write(&mut app.world, |writer| {
+        writer.set_tile(coord, None);
+    });
+
+    assert!(updated_chunks(&app.world).is_empty());
+}
+
+#[test]
+fn replace_where_accepts_corners_in_any_order() {
+    let mut app = app();
+    write(&mut app.world, |writer| {
+        writer.fill_rect(
+            IVec3::new(-20, -20, 0),
+            IVec3::new(20, 20, 0),
+            Some(Tile::new(0, 1)),
+        );
+        writer.replace_where(
+            IVec3::new(5, 5, 0),
+            IVec3::new(-5, -5, 0),
+            |tile| tile.index() == 1,
+            |tile| Some(tile.with_index(2)),
+        );
+    });
+
+    let map = app.world.resource::<TileMap>();
+    let index_at = |x, y| {
+        map.get_tile(&TileCoord::from_tile_position(IVec3::new(x, y, 0)))
+            .unwrap()
+            .index()
+    };
+    assert_eq!(index_at(-5, -5), 2);
+    assert_eq!(index_at(5, 5), 2);
+    assert_eq!(index_at(6, 0), 1);
+    assert_eq!(index_at(0, -6), 1);
+}
+
+#[test]
+fn replace_where_follows_the_map_wrapping() {
+    let mut app = app();
+    app.world
+        .resource_mut::<TileMap>()
+        .set_wrap(Some(MapWrap::new(IVec2::new(2, 0))));
+    write(&mut app.world, |writer| {
+        writer.fill_rect(
+            IVec3::new(0, 0, 0),
+            IVec3::new(31, 0, 0),
+            Some(Tile::new(0, 1)),
+        );
+        // Tiles 40 to 44 are the wrapped copies of tiles 8 to 12.
+        writer.replace_where(
+            IVec3::new(40, 0, 0),
+            IVec3::new(44, 0, 0),
+            |_| true,
+            |tile| Some(tile.with_index(2)),
+        );
+    });
+
+    let map = app.world.resource::<TileMap>();
+    let changed: Vec<i32> = (0..32)
+        .filter(|x| {
+            map.get_tile(&TileCoord::from_tile_position(IVec3::new(*x, 0, 0)))
+                .unwrap()
+                .index()
+                == 2
+        })
+        .collect();
+    assert_eq!(changed, (8..=12).collect::<Vec<_>>());
+}
+
+#[test]
+fn area_effect_counts_only_landed_writes() {
+    let mut app = app();
+    app.world
+        .resource_mut::<TileMap>()
+        .set_bounds(Some(MapBounds::new(
+            IVec3::new(0, 0, 0),
+            IVec3::new(100, 100, 0),
+            BoundsMode::Reject,
+        )));
+    write(&mut app.world, |writer| {
+        // A 3x3 square, only the quarter with x and y >= 0 is inside the bounds.
+        let count =
+            writer.apply_area_effect(IVec3::ZERO, 1.5, |_| 1.0, |_, _, _| Some(Tile::new(0, 1)));
+        assert_eq!(count, 4);
+        let count =
+            writer.apply_area_effect(IVec3::ZERO, 1.5, |_| 1.0, |_, _, _| Some(Tile::new(0, 1)));
+        assert_eq!(count, 0);
+    });
+}
+
+#[test]
+fn writer_fill_rect_reports_old_tiles_without_copying_chunks() {
+    let mut app = app();
+    write(&mut app.world, |writer| {
+        writer.set_tile_xy(1, 1, 0, Some(Tile::new(0, 1)));
+        writer.set_tile_xy(20, 1, 0, Some(Tile::new(0, 2)));
+    });
+    app.world.resource_mut::<Events<TileChanged>>().clear();
+    let chunk =
+        |world: &World| world.resource::<TileMap>().get_chunk(&IVec3::ZERO).unwrap() as *const _;
+    let before = chunk(&app.world);
+
+    write(&mut app.world, |writer| {
+        writer.fill_rect(
+            IVec3::new(0, 1, 0),
+            IVec3::new(20, 2, 0),
+            Some(Tile::new(0, 2)),
+        );
+    });
+
+    // The only owner of the chunk is the map, so it is filled in place.
+    assert_eq!(chunk(&app.world), before);
+    let mut changes: Vec<(IVec3, Option<Tile>)> = app
+        .world
+        .resource_mut::<Events<TileChanged>>()
+        .drain()
+        .map(|change| (change.coord.tile_position(), change.old))
+        .collect();
+    changes.sort_by_key(|(position, _)| (position.y, position.x));
+    // 42 tiles in the box, minus the one that already held the tile.
+    assert_eq!(changes.len(), 41);
+    assert_eq!(changes[1], (IVec3::new(1, 1, 0), Some(Tile::new(0, 1))));
+    assert!(changes
+        .iter()
+        .filter(|(position, _)| *position != IVec3::new(1, 1, 0))
+        .all(|(_, old)| old.is_none()));
+}
@@ -0,0 +1,132 @@
+#![cfg(feature = "signal")]
+
+use bevy::math::IVec3;
+use bevy_tiling_core::{
+    signal::{NetworkId, SignalNetworks},
+    Tile, TileCoord, TileMap,
+};
+
+fn coord(x: i32, y: i32) -> TileCoord {
+    TileCoord::from_tile_position(IVec3::new(x, y, 0))
+}
+
+fn wire() -> Tile {
+    Tile::new(0, 1)
+}
+
+fn networks() -> SignalNetworks {
+    SignalNetworks::new(|tile| tile.index() == 1)
+}
+
+/// Sets the tiles and marks their chunks dirty.
+fn paint(
+    map: &mut TileMap,
+    networks: &mut SignalNetworks,
+    tiles: &[(i32, i32)],
+    tile: Option<Tile>,
+) {
+    for (x, y) in tiles {
+        map.set_tile(&coord(*x, *y), tile);
+        networks.mark_dirty(coord(*x, *y).chunk());
+    }
+}
+
+fn row(from: i32, to: i32, y: i32) -> Vec<(i32, i32)> {
+    (from..=to).map(|x| (x, y)).collect()
+}
+
+fn id_at(networks: &SignalNetworks, x: i32, y: i32) -> NetworkId {
+    networks.network_at(&coord(x, y)).unwrap()
+}
+
+#[test]
+fn wires_across_chunk_borders_form_one_powered_network() {
+    let mut map = TileMap::default();
+    let mut networks = networks();
+    paint(&mut map, &mut networks, &row(10, 40, 3), Some(wire()));
+    networks.set_source(coord(10, 3), 5.0);
+    networks.set_sink(coord(40, 3), 3.0);
+    networks.update(&map);
+
+    assert_eq!(networks.network_count(), 1);
+    let id = id_at(&networks, 10, 3);
+    assert_eq!(id_at(&networks, 40, 3), id);
+    let state = networks.network_state(id).unwrap();
+    assert_eq!((state.supply, state.demand), (5.0, 3.0));
+    assert!(state.is_powered());
+
+    // Only the demand changes, the network stays the same.
+    networks.set_sink(coord(40, 3), 8.0);
+    networks.update(&map);
+    assert_eq!(id_at(&networks, 10, 3), id);
+    assert!(!networks.network_state(id).unwrap().is_powered());
+}
+
+#[test]
+fn ids_stay_with_networks_through_edits_joins_and_splits() {
+    let mut map = TileMap::default();
+    let mut networks = networks();
+    paint(&mut map, &mut networks, &row(0, 20, 0), Some(wire()));
+    paint(&mut map, &mut networks, &row(0, 20, 40), Some(wire()));
+    networks.update(&map);
+    let low = id_at(&networks, 0, 0);
+    let high = id_at(&networks, 0, 40);
+    assert_ne!(low, high);
+
+    // Growing a network keeps both ids, even with the chunk of the other one relabeled.
+    paint(&mut map, &mut networks, &row(21, 30, 0), Some(wire()));
+    networks.mark_dirty(coord(0, 40).chunk());
+    networks.update(&map);
+    assert_eq!(id_at(&networks, 30, 0), low);
+    assert_eq!(id_at(&networks, 0, 40), high);
+
+    // Joining them keeps the smaller id.
+    let column: Vec<(i32, i32)> = (1..40).map(|y| (5, y)).collect();
+    paint(&mut map, &mut networks, &column, Some(wire()));
+    networks.update(&map);
+    assert_eq!(networks.network_count(), 1);
+    assert_eq!(id_at(&networks, 0, 40), low.min(high));
+
+    // Cutting the column gives the upper part a new id.
+    paint(&mut map, &mut networks, &[(5, 20)], None);
+    networks.update(&map);
+    assert_eq!(networks.network_count(), 2);
+    assert_eq!(id_at(&networks, 0, 0), low.min(high));
+    let upper = id_at(&networks, 0, 40);
+    assert!(upper != low && upper != high);
+}
+
+#[test]
+fn ids_do_not_depend_on_the_order_chunks_were_marked() {
+    let tiles: Vec<(i32, i32)> = [
+        row(0, 3, 0),
+        row(40, 43, 0),
+        row(80, 83, 0),
+        row(-40, -37, 0),
+    ]
+    .concat();
+    let ids = |tiles: &[(i32, i32)]| {
+        let mut map = TileMap::default();
+        let mut networks = networks();
+        for tile in tiles {
+            paint(&mut map, &mut networks, &[*tile], Some(wire()));
+        }
+        networks.update(&map);
+        [0, 40, 80, -40].map(|x| id_at(&networks, x, 0))
+    };
+    let mut reversed = tiles.clone();
+    reversed.reverse();
+    assert_eq!(ids(&tiles), ids(&reversed));
+}
+
+#[test]
+fn removed_wires_leave_no_network() {
+    let mut map = TileMap::default();
+    let mut networks = networks();
+    paint(&mut map, &mut networks, &row(0, 3, 0), Some(wire()));
+    networks.update(&map);
+    paint(&mut map, &mut networks, &row(0, 3, 0), None);
+    networks.update(&map);
+    assert_eq!(networks.network_count(), 0);
+    assert!(networks.network_at(&coord(0, 0)).is_none());
+}
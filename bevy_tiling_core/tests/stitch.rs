@@ -0,0 +1,134 @@
+#![cfg(all(feature = "stitch", feature = "streaming"))]
+
+use std::sync::{Arc, Mutex};
+
+use bevy::{
+    math::{IVec2, IVec3, Vec3},
+    prelude::{App, CoreStage, GlobalTransform, ResMut},
+};
+use bevy_tiling_core::{
+    bounds::MapWrap,
+    generator::TileMapGenerator,
+    stitch::{ChunkSeam, ChunkStitching, SeamAxis},
+    streaming::{ChunkStreaming, ChunkStreamingPlugin, StreamingAnchor},
+    Chunk, Tile, TileMap, TileMapWriter, TilingPlugin,
+};
+
+fn road() -> Tile {
+    Tile::new(9, 9)
+}
+
+/// Records every seam and continues a road over the middle of each.
+fn recording_app() -> (App, Arc<Mutex<Vec<ChunkSeam>>>) {
+    let mut app = App::new();
+    app.add_plugin(TilingPlugin);
+    let seams = Arc::new(Mutex::new(Vec::new()));
+    let recorded = seams.clone();
+    app.world.resource_mut::<ChunkStitching>().add(
+        move |seam: &ChunkSeam, writer: &mut TileMapWriter| {
+            recorded.lock().unwrap().push(seam.clone());
+            writer.set_tile(seam.second_coord(8), Some(road()));
+        },
+    );
+    (app, seams)
+}
+
+/// Inserts the chunks queued in the resource, after the stitching of the frame.
+struct Queued(Vec<IVec3>);
+
+fn insert_queued(mut queued: ResMut<Queued>, mut writer: TileMapWriter) {
+    for chunk in queued.0.drain(..) {
+        writer.insert_chunk(&chunk, Arc::new(Chunk::uniform(Some(Tile::new(0, 1)))));
+    }
+}
+
+#[test]
+fn generated_chunks_are_stitched_to_their_neighbours() {
+    let (mut app, seams) = recording_app();
+    app.add_plugin(ChunkStreamingPlugin);
+    {
+        let mut streaming = app.world.resource_mut::<ChunkStreaming>();
+        streaming.load_radius = 1;
+        streaming.unload_radius = 1;
+    }
+    app.world
+        .resource_mut::<TileMapGenerator>()
+        .set(|chunk: IVec3| Chunk::uniform(Some(Tile::new(0, (chunk.x + 2) as u16))));
+    app.world
+        .spawn()
+        .insert(StreamingAnchor)
+        .insert(GlobalTransform::from_translation(Vec3::new(8.0, 8.0, 0.0)));
+    app.update();
+
+    let seams = seams.lock().unwrap();
+    // A 3 by 3 square of chunks has 6 seams along each axis.
+    assert_eq!(seams.len(), 12);
+    for seam in seams.iter() {
+        let offset = match seam.axis {
+            SeamAxis::X => IVec3::new(1, 0, 0),
+            SeamAxis::Y => IVec3::new(0, 1, 0),
+        };
+        assert_eq!(seam.second, seam.first + offset);
+        assert_eq!(
+            seam.first_border,
+            [Some(Tile::new(0, (seam.first.x + 2) as u16)); 16]
+        );
+        assert_eq!(
+            seam.second_border,
+            [Some(Tile::new(0, (seam.second.x + 2) as u16)); 16]
+        );
+        let map = app.world.resource::<TileMap>();
+        assert_eq!(map.get_tile(&seam.second_coord(8)), Some(&road()));
+    }
+}
+
+#[test]
+fn chunks_inserted_late_are_stitched_in_the_next_frame() {
+    let (mut app, seams) = recording_app();
+    app.insert_resource(Queued(vec![IVec3::new(1, 0, 0)]))
+        .add_system_to_stage(CoreStage::Update, insert_queued);
+    app.world
+        .resource_mut::<TileMap>()
+        .get_or_create_chunk(&IVec3::ZERO)
+        .set_tile(15, Some(Tile::new(0, 1)));
+
+    app.update();
+    assert!(seams.lock().unwrap().is_empty());
+    app.update();
+    let seams = seams.lock().unwrap();
+    assert_eq!(seams.len(), 1);
+    assert_eq!(
+        (seams[0].first, seams[0].second, seams[0].axis),
+        (IVec3::ZERO, IVec3::new(1, 0, 0), SeamAxis::X)
+    );
+    assert_eq!(seams[0].first_border[0], Some(Tile::new(0, 1)));
+    assert_eq!(seams[0].first_border[1], None);
+}
+
+#[test]
+fn seams_follow_the_wrapping() {
+    let (mut app, seams) = recording_app();
+    app.world
+        .resource_mut::<TileMap>()
+        .set_wrap(Some(MapWrap::new(IVec2::new(2, 1))));
+    app.insert_resource(Queued(vec![IVec3::ZERO, IVec3::new(1, 0, 0)]))
+        .add_system_to_stage(CoreStage::Update, insert_queued);
+    app.update();
+    app.update();
+
+    let mut found: Vec<(IVec3, IVec3, SeamAxis)> = seams
+        .lock()
+        .unwrap()
+        .iter()
+        .map(|seam| (seam.first, seam.second, seam.axis))
+        .collect();
+    found.sort_by_key(|(first, _, _)| first.x);
+    // Both chunks meet twice along x, and the wrapping row has no seams along y.
+    assert_eq!(
+        found,
+        vec![
+            (IVec3::ZERO, IVec3::new(1, 0, 0), SeamAxis::X),
+            (IVec3::new(1, 0, 0), IVec3::ZERO, SeamAxis::X),
+        ]
+    );
+}
@@ -0,0 +1,236 @@
+#![cfg(feature = "streaming")]
+
+use std::sync::{
+    atomic::{AtomicUsize, Ordering},
+    mpsc, Arc, Mutex,
+};
+
+use bevy::{
+    math::{IVec2, IVec3, Vec3},
+    prelude::{App, Entity, GlobalTransform},
+    tasks::{AsyncComputeTaskPool, TaskPool},
+};
+use bevy_tiling_core::{
+    bounds::{BoundsMode, MapBounds, MapWrap},
+    generator::TileMapGenerator,
+    locks::TileLocks,
+    streaming::{ChunkStreaming, ChunkStreamingPlugin, StreamingAnchor},
+    Chunk, Tile, TileCoord, TileMap, TilingPlugin,
+};
+
+/// An app loading the chunks next to an anchor in `chunk`, counting the generated chunks.
+fn app(chunk: IVec3, wrap: Option<MapWrap>) -> (App, Entity, Arc<AtomicUsize>) {
+    let mut app = App::new();
+    app.add_plugin(TilingPlugin)
+        .add_plugin(ChunkStreamingPlugin);
+    {
+        let mut streaming = app.world.resource_mut::<ChunkStreaming>();
+        streaming.load_radius = 1;
+        streaming.unload_radius = 1;
+    }
+    app.world.resource_mut::<TileMap>().set_wrap(wrap);
+    let generated = Arc::new(AtomicUsize::new(0));
+    let counter = generated.clone();
+    app.world
+        .resource_mut::<TileMapGenerator>()
+        .set(move |_: IVec3| {
+            counter.fetch_add(1, Ordering::Relaxed);
+            Chunk::uniform(Some(Tile::new(0, 1)))
+        });
+    let anchor = app
+        .world
+        .spawn()
+        .insert(StreamingAnchor)
+        .insert(anchor_transform(chunk))
+        .id();
+    (app, anchor, generated)
+}
+
+fn anchor_transform(chunk: IVec3) -> GlobalTransform {
+    GlobalTransform::from_translation(chunk.as_vec3() * 16.0 + Vec3::new(8.0, 8.0, 0.0))
+}
+
+fn loaded_chunks(app: &App) -> Vec<IVec3> {
+    let mut chunks: Vec<IVec3> = app
+        .world
+        .resource::<TileMap>()
+        .chunk_coords()
+        .copied()
+        .collect();
+    chunks.sort_by_key(|chunk| (chunk.y, chunk.x));
+    chunks
+}
+
+fn square(min: IVec2, max: IVec2) -> Vec<IVec3> {
+    (min.y..=max.y)
+        .flat_map(|y| (min.x..=max.x).map(move |x| IVec3::new(x, y, 0)))
+        .collect()
+}
+
+#[test]
+fn chunks_load_and_unload_around_anchor() {
+    let (mut app, anchor, generated) = app(IVec3::ZERO, None);
+
+    app.update();
+    assert_eq!(loaded_chunks(&app), square(IVec2::splat(-1), IVec2::ONE));
+    assert_eq!(generated.load(Ordering::Relaxed), 9);
+
+    app.world
+        .entity_mut(anchor)
+        .insert(anchor_transform(IVec3::new(5, 0, 0)));
+    app.update();
+    assert_eq!(
+        loaded_chunks(&app),
+        square(IVec2::new(4, -1), IVec2::new(6, 1))
+    );
+    assert_eq!(generated.load(Ordering::Relaxed), 18);
+}
+
+#[test]
+fn locked_chunks_are_neither_unloaded_nor_generated_into() {
+    let (mut app, anchor, _) = app(IVec3::ZERO, None);
+    app.update();
+    let locks = app.world.resource::<TileLocks>().clone();
+    // Tile (16, 0) is in chunk (1, 0), which stays loaded after the anchor moves away.
+    let kept = locks
+        .lock_region(IVec3::new(16, 0, 0), IVec3::new(16, 0, 0))
+        .unwrap();
+    // Chunk (6, 0) comes into range but waits for its lock.
+    let pending = locks
+        .lock_region(IVec3::new(96, 0, 0), IVec3::new(96, 0, 0))
+        .unwrap();
+
+    app.world
+        .entity_mut(anchor)
+        .insert(anchor_transform(IVec3::new(5, 0, 0)));
+    app.update();
+    let mut expected = square(IVec2::new(4, -1), IVec2::new(6, 1));
+    expected.retain(|chunk| *chunk != IVec3::new(6, 0, 0));
+    expected.push(IVec3::new(1, 0, 0));
+    expected.sort_by_key(|chunk| (chunk.y, chunk.x));
+    assert_eq!(loaded_chunks(&app), expected);
+    assert!(app
+        .world
+        .resource::<TileMapGenerator>()
+        .is_pending(&IVec3::new(6, 0, 0)));
+
+    drop((kept, pending));
+    app.update();
+    assert_eq!(
+        loaded_chunks(&app),
+        square(IVec2::new(4, -1), IVec2::new(6, 1))
+    );
+}
+
+#[test]
+fn wrapped_chunks_across_the_seam_stay_loaded() {
+    let wrap = MapWrap::new(IVec2::new(4, 4));
+    let (mut app, _, generated) = app(IVec3::new(3, 3, 0), Some(wrap));
+
+    for _ in 0..3 {
+        app.update();
+        assert_eq!(
+            loaded_chunks(&app),
+            [0, 2, 3]
+                .into_iter()
+                .flat_map(|y| [0, 2, 3].into_iter().map(move |x| IVec3::new(x, y, 0)))
+                .collect::<Vec<_>>()
+        );
+    }
+    assert_eq!(generated.load(Ordering::Relaxed), 9);
+}
+
+#[test]
+fn persisted_wrapped_chunks_come_back_from_memory() {
+    let wrap = MapWrap::new(IVec2::new(8, 8));
+    let (mut app, anchor, generated) = app(IVec3::ZERO, Some(wrap));
+    app.world.resource_mut::<ChunkStreaming>().persist = true;
+
+    app.update();
+    app.world
+        .entity_mut(anchor)
+        .insert(anchor_transform(IVec3::new(4, 0, 0)));
+    app.update();
+    assert_eq!(generated.load(Ordering::Relaxed), 18);
+
+    // Back across the seam from the other side, every chunk was stored when it unloaded.
+    app.world
+        .entity_mut(anchor)
+        .insert(anchor_transform(IVec3::new(8, 0, 0)));
+    app.update();
+    assert_eq!(generated.load(Ordering::Relaxed), 18);
+    assert_eq!(loaded_chunks(&app).len(), 9);
+}
+
+#[test]
+fn chunks_outside_the_bounds_are_not_requested() {
+    let (mut app, _, generated) = app(IVec3::ZERO, None);
+    app.world
+        .resource_mut::<TileMap>()
+        .set_bounds(Some(MapBounds::new(
+            IVec3::ZERO,
+            IVec3::new(31, 31, 0),
+            BoundsMode::Reject,
+        )));
+
+    app.update();
+    assert_eq!(loaded_chunks(&app), square(IVec2::ZERO, IVec2::ONE));
+    assert_eq!(generated.load(Ordering::Relaxed), 4);
+}
+
+#[test]
+fn chunks_written_while_generating_keep_their_tiles() {
+    let mut app = App::new();
+    app.add_plugin(TilingPlugin)
+        .add_plugin(ChunkStreamingPlugin)
+        .insert_resource(AsyncComputeTaskPool(TaskPool::new()));
+    app.world.resource_mut::<ChunkStreaming>().load_radius = 0;
+    let (release, released) = mpsc::channel::<()>();
+    let released = Mutex::new(released);
+    app.world
+        .resource_mut::<TileMapGenerator>()
+        .set(move |_: IVec3| {
+            let _ = released.lock().unwrap().recv();
+            Chunk::uniform(Some(Tile::new(0, 1)))
+        });
+    app.world
+        .spawn()
+        .insert(StreamingAnchor)
+        .insert(anchor_transform(IVec3::ZERO));
+
+    app.update();
+    assert!(app
+        .world
+        .resource::<TileMapGenerator>()
+        .is_pending(&IVec3::ZERO));
+    let written = TileCoord::from_tile_position(IVec3::new(2, 3, 0));
+    app.world
+        .resource_mut::<TileMap>()
+        .set_tile(&written, Some(Tile::new(0, 2)));
+    release.send(()).unwrap();
+    for _ in 0..1000 {
+        if !app
+            .world
+            .resource::<TileMapGenerator>()
+            .is_pending(&IVec3::ZERO)
+        {
+            break;
+        }
+        std::thread::sleep(std::time::Duration::from_millis(1));
+        app.update();
+    }
+
+    let map = app.world.resource::<TileMap>();
+    assert!(!app
+        .world
+        .resource::<TileMapGenerator>()
+        .is_pending(&IVec3::ZERO));
+    assert_eq!(map.get_tile(&written), Some(&Tile::new(0, 2)));
+    let chunk = map.get_chunk(&IVec3::ZERO).unwrap();
+    assert_eq!(
+        (0..=u8::MAX)
+            .filter(|index| chunk.get_tile(*index).is_some())
+            .count(),
+        1
+    );
+}
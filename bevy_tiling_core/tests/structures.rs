@@ -0,0 +1,150 @@
+#![cfg(feature = "structures")]
+
+use std::sync::Arc;
+
+use bevy::math::IVec3;
+use bevy_tiling_core::{
+    blueprint::TilePatch,
+    generator::ChunkGenerator,
+    structures::{StructureRegistry, Structures},
+    Chunk, Tile, TileCoord, TileMap, CHUNK_SIZE,
+};
+
+/// A `width` by `height` box of tile `index`, with a different tile at its origin.
+fn patch(width: i32, height: i32, index: u16) -> TilePatch {
+    let mut patch = TilePatch::default();
+    for y in 0..height {
+        for x in 0..width {
+            patch.insert(IVec3::new(x, y, 0), Tile::new(0, index));
+        }
+    }
+    patch.insert(IVec3::ZERO, Tile::new(1, index));
+    patch
+}
+
+fn structures(seed: u64) -> Structures {
+    let mut registry = StructureRegistry::new(seed, 2).with_per_region(3);
+    registry.register("wall", patch(40, 3, 1), 2);
+    registry.register("tower", patch(3, 40, 2), 1);
+    Structures::new(registry)
+}
+
+fn grass(_: IVec3) -> Chunk {
+    Chunk::uniform(Some(Tile::new(0, 0)))
+}
+
+#[test]
+fn chunks_generated_in_any_order_show_whole_structures() {
+    let structures = structures(9);
+    let registry = structures.registry();
+
+    // Every structure stamped in one go, in placement order.
+    let mut expected = TileMap::default();
+    for chunk_y in -1..=4 {
+        for chunk_x in -1..=4 {
+            expected.insert_shared_chunk(
+                IVec3::new(chunk_x, chunk_y, 0),
+                Arc::new(grass(IVec3::ZERO)),
+            );
+        }
+    }
+    let mut planned = 0;
+    for y in -2..=2 {
+        for x in -2..=2 {
+            for structure in registry.plan_region(IVec3::new(x, y, 0)) {
+                planned += 1;
+                let kind = registry.kind(structure.kind).unwrap();
+                for (coord, tile) in kind.patch.placed_at(structure.origin) {
+                    if expected.get_chunk(&coord.chunk()).is_some() {
+                        expected.set_tile(&coord, Some(*tile));
+                    }
+                }
+            }
+        }
+    }
+    assert!(planned > 25);
+
+    let generator = structures.generator(grass);
+    let mut generated = TileMap::default();
+    let mut order: Vec<IVec3> = (-1..=4)
+        .flat_map(|y| (-1..=4).map(move |x| IVec3::new(x, y, 0)))
+        .collect();
+    order.reverse();
+    order.swap(3, 20);
+    for chunk in order {
+        generated.insert_shared_chunk(chunk, Arc::new(generator.generate(chunk)));
+    }
+
+    for y in 0..4 * CHUNK_SIZE {
+        for x in 0..4 * CHUNK_SIZE {
+            let coord = TileCoord::from_tile_position(IVec3::new(x, y, 0));
+            assert_eq!(
+                generated.get_tile(&coord),
+                expected.get_tile(&coord),
+                "at {}",
+                coord
+            );
+        }
+    }
+}
+
+#[test]
+fn placed_parts_are_tracked_until_complete() {
+    let structures = structures(3);
+    let registry = structures.registry();
+    let structure = registry
+        .plan_region(IVec3::ZERO)
+        .into_iter()
+        .next()
+        .unwrap();
+    let mut chunks: Vec<IVec3> = registry.chunks_of(&structure).into_iter().collect();
+    assert!(chunks.len() > 1);
+    chunks.sort_by_key(|chunk| (chunk.y, chunk.x));
+
+    assert_eq!(structures.placed_chunks(structure.id), None);
+    for (placed, chunk) in chunks.iter().enumerate() {
+        assert!(!structures.is_complete(structure.id));
+        let mut generated = grass(*chunk);
+        let found = structures.place_in_chunk(*chunk, &mut generated);
+        assert!(found.contains(&structure));
+        assert_eq!(
+            structures.placed_chunks(structure.id),
+            Some((placed + 1, chunks.len()))
+        );
+    }
+    assert!(structures.is_complete(structure.id));
+    assert!(structures.started().contains(&structure.id));
+
+    // Placing a chunk again, e.g. after it was unloaded, doesn't count twice.
+    structures.place_in_chunk(chunks[0], &mut grass(chunks[0]));
+    assert_eq!(
+        structures.placed_chunks(structure.id),
+        Some((chunks.len(), chunks.len()))
+    );
+
+    let origin = TileCoord::from_tile_position(structure.origin);
+    assert_eq!(
+        structures.structure_at(&origin).map(|found| found.id),
+        Some(structure.id)
+    );
+
+    assert!(structures.forget(structure.id));
+    assert_eq!(structures.placed_chunks(structure.id), None);
+}
+
+#[test]
+fn plans_depend_only_on_the_seed() {
+    let region = IVec3::new(-3, 5, 0);
+    assert_eq!(
+        structures(1).registry().plan_region(region),
+        structures(1).registry().plan_region(region)
+    );
+    assert_ne!(
+        structures(1).registry().plan_region(region),
+        structures(2).registry().plan_region(region)
+    );
+    for structure in structures(1).registry().plan_region(region) {
+        assert_eq!(structures(1).registry().region_of(structure.origin), region);
+    }
+    assert!(StructureRegistry::new(1, 2).plan_region(region).is_empty());
+}
@@ -0,0 +1,58 @@
+use bevy::math::IVec3;
+use bevy_tiling_core::{tile_data::TileDataMap, TileCoord};
+
+#[derive(Debug, PartialEq)]
+struct Moisture(u8);
+
+#[test]
+fn set_replaces_and_clearing_drops_empty_chunks() {
+    let mut data = TileDataMap::default();
+    let first = TileCoord::from_tile_position(IVec3::new(1, 2, 0));
+    let second = TileCoord::from_tile_position(IVec3::new(3, 2, 0));
+
+    assert_eq!(data.set(&first, Some(Moisture(1))), None);
+    assert_eq!(data.set(&first, Some(Moisture(2))), Some(Moisture(1)));
+    data.set(&second, Some(Moisture(5)));
+    assert_eq!(data.get(&first), Some(&Moisture(2)));
+
+    assert_eq!(data.set(&first, None), Some(Moisture(2)));
+    assert!(data.get_chunk(&first.chunk()).is_some());
+    data.set(&second, None);
+    assert!(data.get_chunk(&first.chunk()).is_none());
+    assert_eq!(data.set(&first, None), None);
+    assert!(data.get_chunk(&first.chunk()).is_none());
+}
+
+#[test]
+fn get_or_insert_with_only_creates_missing_data() {
+    let mut data = TileDataMap::default();
+    let coord = TileCoord::from_tile_position(IVec3::new(-4, 7, 1));
+
+    data.get_or_insert_with(&coord, || Moisture(3)).0 += 1;
+    data.get_or_insert_with(&coord, || Moisture(100)).0 += 1;
+    data.get_mut(&coord).unwrap().0 += 1;
+    assert_eq!(data.get(&coord), Some(&Moisture(6)));
+}
+
+#[test]
+fn iter_and_remove_chunk_cover_every_chunk() {
+    let mut data = TileDataMap::default();
+    let coords = [
+        TileCoord::from_tile_position(IVec3::new(0, 0, 0)),
+        TileCoord::from_tile_position(IVec3::new(15, 15, 0)),
+        TileCoord::from_tile_position(IVec3::new(-1, 0, 0)),
+    ];
+    for (i, coord) in coords.iter().enumerate() {
+        data.set(coord, Some(Moisture(i as u8)));
+    }
+
+    let mut stored: Vec<(TileCoord, u8)> =
+        data.iter().map(|(coord, data)| (coord, data.0)).collect();
+    stored.sort_by_key(|(_, moisture)| *moisture);
+    assert_eq!(stored, vec![(coords[0], 0), (coords[1], 1), (coords[2], 2)]);
+
+    assert!(data.remove_chunk(&coords[2].chunk()));
+    assert!(!data.remove_chunk(&coords[2].chunk()));
+    assert!(data.get(&coords[2]).is_none());
+    assert_eq!(data.iter().count(), 2);
+}
@@ -0,0 +1,132 @@
+#![cfg(feature = "tiled")]
+
+use bevy::math::{IVec2, IVec3, UVec2};
+use bevy_tiling_core::{
+    tiled::{export_tmx, TiledTilesets, TmxExportError},
+    tiled_asset::TiledMap,
+    Tile, TileCoord, TileMap,
+};
+
+/// Sheet 0 from GID 1 and sheet 4 from GID 100.
+fn tilesets() -> TiledTilesets {
+    let mut tilesets = TiledTilesets::default();
+    tilesets
+        .add(100, 4)
+        .add(1, 0)
+        .set_source(1, "terrain.tsx")
+        .set_source(100, "props & \"decor\".tsx");
+    tilesets
+}
+
+fn coord(x: i32, y: i32, z: i32) -> TileCoord {
+    TileCoord::from_tile_position(IVec3::new(x, y, z))
+}
+
+fn sorted(mut tiles: Vec<(TileCoord, Tile)>) -> Vec<(TileCoord, Tile)> {
+    tiles.sort_by_key(|(coord, _)| {
+        let position = coord.tile_position();
+        (position.z, position.y, position.x)
+    });
+    tiles
+}
+
+/// Every flip and quarter turn of a tile.
+fn orientations(tile: Tile) -> impl Iterator<Item = Tile> {
+    (0..4).flat_map(move |rotation| {
+        [(false, false), (true, false), (false, true), (true, true)]
+            .into_iter()
+            .map(move |(x, y)| tile.with_rotation(rotation).with_flip(x, y))
+    })
+}
+
+#[test]
+fn gids_map_back_to_their_tiles() {
+    let tilesets = tilesets();
+    assert_eq!(tilesets.tile(0), None);
+    assert_eq!(tilesets.tile(1), Some(Tile::new(0, 0)));
+    assert_eq!(tilesets.tile(99), Some(Tile::new(0, 98)));
+    assert_eq!(tilesets.tile(105), Some(Tile::new(4, 5)));
+    assert_eq!(tilesets.gid(&Tile::new(4, 5)), Some(105));
+    assert_eq!(tilesets.gid(&Tile::new(2, 0)), None);
+
+    for tile in orientations(Tile::new(4, 7)) {
+        let gid = tilesets.gid(&tile).unwrap();
+        let back = tilesets.tile(gid).unwrap();
+        // Half turns come back as flips along both axes.
+        if tile.rotation() < 2 {
+            assert_eq!(back, tile);
+        } else {
+            assert_eq!(back.rotation(), tile.rotation() - 2);
+            assert_eq!(back.flip_x(), !tile.flip_x());
+            assert_eq!(back.flip_y(), !tile.flip_y());
+        }
+        assert_eq!(tilesets.gid(&back), Some(gid));
+    }
+}
+
+#[test]
+fn exported_maps_import_into_the_same_tiles() {
+    let tilesets = tilesets();
+    let mut map = TileMap::default();
+    map.set_tile(&coord(-2, -1, 0), Some(Tile::new(0, 3)));
+    map.set_tile(
+        &coord(2, 1, 0),
+        Some(Tile::new(4, 0).with_flip(true, false)),
+    );
+    map.set_tile(&coord(0, 0, 1), Some(Tile::new(4, 2).with_rotation(1)));
+    map.set_tile(
+        &coord(1, -1, 1),
+        Some(Tile::new(0, 9).with_rotation(1).with_flip(false, true)),
+    );
+    // Outside the exported box.
+    map.set_tile(&coord(3, 0, 0), Some(Tile::new(0, 1)));
+
+    let tmx = export_tmx(
+        &map,
+        &tilesets,
+        IVec2::new(2, 1),
+        IVec2::new(-2, -1),
+        &[0, 1],
+        UVec2::splat(16),
+    )
+    .unwrap();
+    let exported = TiledMap::from_tmx(&tmx).unwrap();
+    assert_eq!((exported.width, exported.height), (5, 3));
+    assert_eq!(
+        exported.tilesets[1].source.as_deref(),
+        Some("props & \"decor\".tsx")
+    );
+
+    let tiles = sorted(exported.tiles(&tilesets, IVec3::new(-2, -1, 0)));
+    let expected = sorted(
+        map.iter_region(IVec3::new(-2, -1, 0), IVec3::new(2, 1, 1))
+            .map(|(coord, tile)| (coord, *tile))
+            .collect(),
+    );
+    assert_eq!(expected.len(), 4);
+    assert_eq!(tiles, expected);
+}
+
+#[test]
+fn exports_need_a_tileset_and_source_for_every_tile() {
+    let mut map = TileMap::default();
+    map.set_tile(&coord(0, 0, 0), Some(Tile::new(2, 0)));
+    assert_eq!(
+        export_tmx(
+            &map,
+            &tilesets(),
+            IVec2::ZERO,
+            IVec2::ZERO,
+            &[0],
+            UVec2::ONE
+        ),
+        Err(TmxExportError::UnknownTile(coord(0, 0, 0), Tile::new(2, 0)))
+    );
+
+    let mut tilesets = tilesets();
+    tilesets.add(50, 2);
+    assert_eq!(
+        export_tmx(&map, &tilesets, IVec2::ZERO, IVec2::ZERO, &[0], UVec2::ONE),
+        Err(TmxExportError::MissingSource(50))
+    );
+}
@@ -0,0 +1,133 @@
+#![cfg(feature = "tiled")]
+
+use std::{path::Path, thread, time::Duration};
+
+use bevy::{
+    asset::{AssetPlugin, AssetServer, AssetServerSettings, LoadState},
+    core::CorePlugin,
+    math::{IVec3, UVec2, Vec2},
+    prelude::App,
+};
+use bevy_tiling_core::{
+    objects::PropertyValue,
+    tiled_asset::{TiledAssetPlugin, TiledError, TiledImports, TiledMap, TiledSheets},
+    Tile, TileCoord, TileMap, TilingPlugin,
+};
+
+fn tile_at(map: &TileMap, x: i32, y: i32, z: i32) -> Option<Tile> {
+    map.get_tile(&TileCoord::from_tile_position(IVec3::new(x, y, z)))
+        .copied()
+}
+
+#[test]
+fn infinite_maps_merge_their_chunks() {
+    let tmx = r#"<map width="2" height="2" tilewidth="8" tileheight="8" infinite="1">
+ <tileset firstgid="1" name="a" tilewidth="8" tileheight="8" columns="4">
+  <image source="a.png"/>
+ </tileset>
+ <layer name="Chunks" width="2" height="2">
+  <data encoding="csv">
+   <chunk x="-2" y="0" width="2" height="1">1,2</chunk>
+   <chunk x="0" y="1" width="2" height="1">3,4</chunk>
+  </data>
+ </layer>
+ <layer name="Xml" width="2" height="1">
+  <data><tile gid="5"/><tile/></data>
+ </layer>
+</map>"#;
+    let map = TiledMap::from_tmx(tmx).unwrap();
+    assert_eq!(map.tile_size, UVec2::new(8, 8));
+    let chunks = &map.tile_layers[0];
+    assert_eq!(
+        (chunks.x, chunks.y, chunks.width, chunks.height),
+        (-2, 0, 4, 2)
+    );
+    assert_eq!(chunks.gids, vec![1, 2, 0, 0, 0, 0, 3, 4]);
+    assert_eq!(map.tile_layers[1].gids, vec![5, 0]);
+    assert_eq!(map.tile_layers[1].layer, 1);
+
+    let mut sheets = TiledSheets::default();
+    let tilesets = map.tilesets(&mut sheets);
+    let tiles = map.tiles(&tilesets, IVec3::ZERO);
+    // The top row of the map is y = 1, the chunk left of it starts at x = -2.
+    assert!(tiles.contains(&(
+        TileCoord::from_tile_position(IVec3::new(-2, 1, 0)),
+        Tile::new(0, 0)
+    )));
+    assert!(tiles.contains(&(
+        TileCoord::from_tile_position(IVec3::new(1, 0, 0)),
+        Tile::new(0, 3)
+    )));
+    assert_eq!(tiles.len(), 5);
+}
+
+#[test]
+fn base64_layers_are_rejected() {
+    let tmx = r#"<map width="1" height="1" tilewidth="8" tileheight="8">
+ <layer name="Packed" width="1" height="1"><data encoding="base64">AQAAAA==</data></layer>
+</map>"#;
+    assert!(matches!(
+        TiledMap::from_tmx(tmx),
+        Err(TiledError::UnsupportedEncoding(encoding)) if encoding == "base64"
+    ));
+}
+
+#[test]
+fn loaded_maps_are_imported_with_their_tileset_images() {
+    let mut app = App::new();
+    app.insert_resource(AssetServerSettings {
+        asset_folder: "tests/assets".to_string(),
+        watch_for_changes: false,
+    })
+    .add_plugin(CorePlugin)
+    .add_plugin(AssetPlugin)
+    .add_plugin(TilingPlugin)
+    .add_plugin(TiledAssetPlugin);
+    let handle = app
+        .world
+        .resource::<AssetServer>()
+        .load::<TiledMap, _>("tiled/maps/level.tmx");
+    app.world
+        .resource_mut::<TiledImports>()
+        .push(handle.clone(), IVec3::new(10, 0, 0));
+    for _ in 0..1000 {
+        app.update();
+        if app.world.resource::<TiledImports>().is_empty() {
+            break;
+        }
+        assert_ne!(
+            app.world.resource::<AssetServer>().get_load_state(&handle),
+            LoadState::Failed
+        );
+        thread::sleep(Duration::from_millis(1));
+    }
+    assert!(app.world.resource::<TiledImports>().is_empty());
+
+    let sheets = app.world.resource::<TiledSheets>();
+    assert_eq!(sheets.image(0), Some(Path::new("tiled/images/terrain.png")));
+    assert_eq!(sheets.image(1), Some(Path::new("tiled/maps/props.png")));
+
+    let map = app.world.resource::<TileMap>();
+    assert_eq!(tile_at(map, 10, 1, 0), Some(Tile::new(0, 0)));
+    assert_eq!(tile_at(map, 12, 1, 0), Some(Tile::new(0, 2)));
+    assert_eq!(tile_at(map, 10, 0, 0), Some(Tile::new(0, 3)));
+    assert_eq!(tile_at(map, 11, 0, 0), None);
+    assert_eq!(tile_at(map, 12, 0, 0), Some(Tile::new(1, 1)));
+
+    let level = app
+        .world
+        .resource::<bevy::asset::Assets<TiledMap>>()
+        .get(&handle)
+        .unwrap()
+        .clone();
+    let objects = level.objects(IVec3::new(10, 0, 0));
+    assert_eq!(objects.len(), 1);
+    assert_eq!(objects[0].object_type, "spawn");
+    assert_eq!(objects[0].layer, 1);
+    assert_eq!(objects[0].position, Vec2::new(11.0, 1.0));
+    assert_eq!(objects[0].property("team"), Some(&PropertyValue::Int(2)));
+    assert_eq!(
+        objects[0].property("note"),
+        Some(&PropertyValue::String("first".to_string()))
+    );
+}
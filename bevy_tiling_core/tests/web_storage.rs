@@ -0,0 +1,75 @@
+#![cfg(feature = "web_storage")]
+
+use bevy::{
+    math::{IVec3, Vec3},
+    prelude::{App, GlobalTransform},
+};
+use bevy_tiling_core::{
+    persist::{ChunkBackend, ChunkPersistPlugin, ChunkStore},
+    streaming::{ChunkStreaming, ChunkStreamingPlugin, StreamingAnchor},
+    web_storage::WebChunkStorage,
+    Chunk, Tile, TileCoord, TileMap, TilingPlugin,
+};
+
+#[test]
+fn falls_back_to_memory_outside_the_browser() {
+    let mut storage = WebChunkStorage::new("map/");
+    assert!(!storage.is_persistent());
+    assert!(storage.chunks().is_empty());
+
+    let mut chunk = Chunk::uniform(Some(Tile::new(0, 1)));
+    chunk.set_tile(7, Some(Tile::new(2, 3).with_flip(true, false)));
+    let coord = IVec3::new(-1, 2, 0);
+    assert_eq!(storage.save_chunks(&[(coord, &chunk)]).unwrap(), 1);
+
+    assert_eq!(storage.chunks(), vec![coord]);
+    let loaded = storage.load_chunk(&coord).unwrap().unwrap();
+    for index in 0..=u8::MAX {
+        assert_eq!(loaded.get_tile(index), chunk.get_tile(index));
+    }
+    assert!(storage.load_chunk(&IVec3::ZERO).unwrap().is_none());
+}
+
+#[test]
+fn unloaded_chunks_come_back_from_the_fallback() {
+    let mut app = App::new();
+    app.add_plugin(TilingPlugin)
+        .add_plugin(ChunkStreamingPlugin)
+        .add_plugin(ChunkPersistPlugin)
+        .insert_resource(ChunkStore::with_backend(WebChunkStorage::new("map/")));
+    {
+        let mut streaming = app.world.resource_mut::<ChunkStreaming>();
+        streaming.load_radius = 0;
+        streaming.unload_radius = 0;
+    }
+    let coord = TileCoord::from_tile_position(IVec3::new(3, 4, 0));
+    app.world
+        .resource_mut::<TileMap>()
+        .set_tile(&coord, Some(Tile::new(0, 9)));
+    let anchor = app
+        .world
+        .spawn()
+        .insert(StreamingAnchor)
+        .insert(GlobalTransform::from_translation(Vec3::new(
+            100.0, 0.0, 0.0,
+        )))
+        .id();
+
+    app.update();
+    assert!(app.world.resource::<TileMap>().get_tile(&coord).is_none());
+    assert!(app.world.resource::<ChunkStore>().has_chunk(&IVec3::ZERO));
+
+    app.world
+        .entity_mut(anchor)
+        .insert(GlobalTransform::from_translation(Vec3::new(1.0, 1.0, 0.0)));
+    app.update();
+    assert_eq!(
+        app.world.resource::<TileMap>().get_tile(&coord),
+        Some(&Tile::new(0, 9))
+    );
+    assert!(app
+        .world
+        .resource_mut::<ChunkStore>()
+        .take_errors()
+        .is_empty());
+}
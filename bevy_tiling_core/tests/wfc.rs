@@ -0,0 +1,137 @@
+#![cfg(feature = "wfc")]
+
+use bevy::math::IVec3;
+use bevy_tiling_core::{
+    generator::ChunkGenerator,
+    wfc::{WfcGenerator, WfcRules},
+    Chunk, Tile, TileCoord, TileMap,
+};
+
+const WATER: u16 = 0;
+const SAND: u16 = 1;
+const GRASS: u16 = 2;
+
+fn tile(index: u16) -> Option<Tile> {
+    Some(Tile::new(0, index))
+}
+
+/// Water, sand and grass, where sand has to sit between water and grass.
+fn rules() -> WfcRules {
+    let mut rules = WfcRules::default();
+    rules
+        .add_tile(tile(WATER), 3)
+        .add_tile(tile(SAND), 1)
+        .add_tile(tile(GRASS), 3);
+    for (a, b) in [
+        (WATER, WATER),
+        (SAND, SAND),
+        (GRASS, GRASS),
+        (WATER, SAND),
+        (SAND, GRASS),
+    ] {
+        rules
+            .allow_horizontal(tile(a), tile(b))
+            .allow_horizontal(tile(b), tile(a))
+            .allow_vertical(tile(a), tile(b))
+            .allow_vertical(tile(b), tile(a));
+    }
+    rules
+}
+
+fn allowed(a: Option<&Tile>, b: Option<&Tile>) -> bool {
+    let (a, b) = (a.unwrap().index(), b.unwrap().index());
+    !matches!((a, b), (WATER, GRASS) | (GRASS, WATER))
+}
+
+/// Chunks generated one by one in an order unrelated to their position.
+fn generate_map(generator: &WfcGenerator, chunks: &[IVec3]) -> TileMap {
+    let mut map = TileMap::default();
+    for chunk in chunks {
+        map.insert_shared_chunk(*chunk, generator.generate(*chunk).into());
+    }
+    map
+}
+
+fn same_tiles(a: &Chunk, b: &Chunk) -> bool {
+    (0..=u8::MAX).all(|index| a.get_tile(index) == b.get_tile(index))
+}
+
+#[test]
+fn independently_generated_chunks_agree_on_seams() {
+    let generator = WfcGenerator::new(&rules(), 7);
+    let chunks = [
+        IVec3::new(1, 1, 0),
+        IVec3::new(-1, 0, 0),
+        IVec3::new(0, 1, 0),
+        IVec3::new(1, -1, 0),
+        IVec3::new(0, 0, 0),
+        IVec3::new(-1, 1, 0),
+        IVec3::new(1, 0, 0),
+        IVec3::new(0, -1, 0),
+        IVec3::new(-1, -1, 0),
+    ];
+    let map = generate_map(&generator, &chunks);
+    let tile_at = |x, y| map.get_tile(&TileCoord::from_tile_position(IVec3::new(x, y, 0)));
+
+    for seam in [-1, 15] {
+        for along in -16..32 {
+            assert!(
+                allowed(tile_at(seam, along), tile_at(seam + 1, along)),
+                "vertical seam at x {}, y {}",
+                seam,
+                along
+            );
+            assert!(
+                allowed(tile_at(along, seam), tile_at(along, seam + 1)),
+                "horizontal seam at y {}, x {}",
+                seam,
+                along
+            );
+        }
+    }
+}
+
+#[test]
+fn generation_is_deterministic() {
+    let rules = rules();
+    let chunk = IVec3::new(3, -5, 0);
+    let first = WfcGenerator::new(&rules, 42).generate(chunk);
+    let again = WfcGenerator::new(&rules, 42).generate(chunk);
+    assert!(same_tiles(&first, &again));
+
+    let other_seeds = (0..4).map(|seed| WfcGenerator::new(&rules, seed).generate(chunk));
+    assert!(other_seeds
+        .into_iter()
+        .any(|other| !same_tiles(&first, &other)));
+}
+
+#[test]
+fn learned_rules_keep_the_sample_neighbours() {
+    let mut sample = TileMap::default();
+    for y in 0..8 {
+        for x in 0..8 {
+            let index = match x {
+                0..=2 => WATER,
+                3 => SAND,
+                _ => GRASS,
+            };
+            sample.set_tile(
+                &TileCoord::from_tile_position(IVec3::new(x, y, 0)),
+                tile(index),
+            );
+        }
+    }
+    let rules = WfcRules::learn(&sample, IVec3::ZERO, IVec3::new(7, 7, 0));
+    assert_eq!(rules.len(), 3);
+
+    let map = generate_map(
+        &WfcGenerator::new(&rules, 1),
+        &[IVec3::new(0, 0, 0), IVec3::new(1, 0, 0)],
+    );
+    let tile_at = |x, y| map.get_tile(&TileCoord::from_tile_position(IVec3::new(x, y, 0)));
+    for y in 0..16 {
+        for x in 0..31 {
+            assert!(allowed(tile_at(x, y), tile_at(x + 1, y)), "at {}, {}", x, y);
+        }
+    }
+}
@@ -0,0 +1,102 @@
+use bevy::{
+    math::{IVec2, IVec3},
+    prelude::{App, ResMut},
+};
+use bevy_tiling_core::{
+    biome::BiomeMap,
+    schedule::TileSchedule,
+    world_map::{WorldMap, WorldMapCell},
+    Tile, TileCoord, TileMap, TileMapWriter, TilingPlugin,
+};
+
+/// A chunk to unload during the next frame.
+#[derive(Default)]
+struct Unload(Option<IVec3>);
+
+fn unload_chunk(mut unload: ResMut<Unload>, mut writer: TileMapWriter) {
+    if let Some(chunk) = unload.0.take() {
+        writer.remove_chunk(&chunk);
+    }
+}
+
+fn coord(x: i32, y: i32) -> TileCoord {
+    TileCoord::from_tile_position(IVec3::new(x, y, 0))
+}
+
+#[test]
+fn cells_follow_edits_and_outlive_their_chunk() {
+    let mut app = App::new();
+    app.add_plugin(TilingPlugin)
+        .init_resource::<Unload>()
+        .add_system(unload_chunk);
+    {
+        let mut schedule = app.world.resource_mut::<TileSchedule>();
+        schedule.schedule_in(coord(0, 0), Some(Tile::new(0, 1)), 0);
+        schedule.schedule_in(coord(1, 0), Some(Tile::new(0, 1)), 0);
+        schedule.schedule_in(coord(2, 0), Some(Tile::new(0, 2)), 0);
+    }
+    app.update();
+    assert_eq!(
+        app.world
+            .resource::<WorldMap>()
+            .get(&IVec3::ZERO)
+            .and_then(|cell| cell.dominant_tile),
+        Some(Tile::new(0, 1))
+    );
+
+    app.world.resource_mut::<Unload>().0 = Some(IVec3::ZERO);
+    app.update();
+    assert!(app
+        .world
+        .resource::<TileMap>()
+        .get_chunk(&IVec3::ZERO)
+        .is_none());
+    let world_map = app.world.resource::<WorldMap>();
+    assert_eq!(
+        world_map
+            .get(&IVec3::ZERO)
+            .and_then(|cell| cell.dominant_tile),
+        Some(Tile::new(0, 1))
+    );
+    assert!(world_map.get(&IVec3::X).is_none());
+}
+
+#[test]
+fn refresh_picks_the_most_common_biome() {
+    let mut map = TileMap::default();
+    map.set_tile(&coord(0, 0), Some(Tile::new(0, 3)));
+    let mut biomes = BiomeMap::new(4);
+    biomes.set_biome(&coord(0, 0), Some(2));
+    biomes.set_biome(&coord(4, 0), Some(2));
+    biomes.set_biome(&coord(8, 0), Some(1));
+    biomes.set_biome(&coord(12, 0), Some(1));
+
+    let mut world_map = WorldMap::default();
+    // Ties go to the lower biome.
+    world_map.refresh_chunk(&IVec3::ZERO, &map, &biomes);
+    assert_eq!(world_map.get(&IVec3::ZERO).unwrap().biome, Some(1));
+
+    biomes.set_biome(&coord(0, 4), Some(2));
+    world_map.refresh_chunk(&IVec3::ZERO, &map, &biomes);
+    assert_eq!(world_map.get(&IVec3::ZERO).unwrap().biome, Some(2));
+
+    // Chunks that aren't loaded keep their cell, or don't get one.
+    world_map.refresh_chunk(&IVec3::Y, &map, &biomes);
+    assert!(world_map.get(&IVec3::Y).is_none());
+}
+
+#[test]
+fn pixels_start_at_the_top_row() {
+    let mut world_map = WorldMap::default();
+    world_map.set_explored(IVec3::new(0, 1, 0), true);
+    world_map.set_explored(IVec3::new(1, 0, 0), true);
+    world_map.set_explored(IVec3::new(1, 0, 1), true);
+    assert!(world_map.is_explored(&IVec3::new(0, 1, 0)));
+    assert!(!world_map.is_explored(&IVec3::ZERO));
+
+    let explored = |cell: Option<&WorldMapCell>| cell.is_some_and(|cell| cell.explored);
+    assert_eq!(
+        world_map.to_pixels(0, IVec2::ONE, IVec2::ZERO, explored),
+        vec![true, false, false, true]
+    );
+}
@@ -0,0 +1,427 @@
+//! Inspects, validates, converts, compresses and diffs map files, enabled by the `cli` feature.
+//!
+//! The format of a file follows its extension:
+//!
+//! - `.ron`: a [`TileMap`] serialized with serde, read and written.
+//! - `.btcf`: a [`ChunkFile`], read and written.
+//! - `.tmx`: a Tiled map, read and written. External tilesets are read from their `.tsx` files
+//!   next to the map and every tileset image becomes a sheet, see `tiled_asset`. Written maps
+//!   start at the lowest tile of the map and reference a `sheet<N>.tsx` file per sheet.
+//! - `.ldtk`: an LDtk project, read only. Tilesets use their uid as the sheet, see `ldtk`.
+
+use std::{
+    collections::BTreeMap,
+    error::Error,
+    fmt::Write as _,
+    fs,
+    path::{Path, PathBuf},
+    process::ExitCode,
+    sync::Arc,
+};
+
+use bevy::math::{IVec3, UVec2};
+use bevy_tiling::{
+    internal,
+    ldtk::{LdtkProject, LdtkTilesets},
+    persist::ChunkFile,
+    tiled::{export_tmx, TiledTilesets},
+    tiled_asset::{TiledMap, TiledSheets},
+    Chunk, Tile, TileMap,
+};
+
+const USAGE: &str = "\
+usage: bevy_tiling_cli <command> <files>
+
+commands:
+    inspect <map>             prints the chunks, layers, bounds and sheets of a map
+    validate <map>...         checks that maps load completely, exits with 1 if one doesn't
+    convert <input> <output>  writes a map in the format of the output's extension
+    compress <map>            drops empty chunks and rewrites a .ron or .btcf map compactly
+    diff <first> <second>     lists the tiles that differ, exits with 1 if any do
+
+formats: .ron, .btcf (chunk file), .tmx (Tiled), .ldtk (LDtk, read only)";
+
+type CliResult<T> = Result<T, Box<dyn Error>>;
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+enum Format {
+    Ron,
+    ChunkFile,
+    Tmx,
+    Ldtk,
+}
+
+impl Format {
+    fn of(path: &Path) -> CliResult<Self> {
+        match path.extension().and_then(|extension| extension.to_str()) {
+            Some("ron") => Ok(Format::Ron),
+            Some("btcf") => Ok(Format::ChunkFile),
+            Some("tmx") => Ok(Format::Tmx),
+            Some("ldtk") => Ok(Format::Ldtk),
+            _ => Err(format!(
+                "{}: unknown format, expected .ron, .btcf, .tmx or .ldtk",
+                path.display()
+            )
+            .into()),
+        }
+    }
+}
+
+/// A map read from a file, along with what couldn't be read from it.
+struct Loaded {
+    map: TileMap,
+    problems: Vec<String>,
+}
+
+fn load(path: &Path) -> CliResult<Loaded> {
+    let mut loaded = Loaded {
+        map: TileMap::default(),
+        problems: Vec::new(),
+    };
+    match Format::of(path)? {
+        Format::Ron => loaded.map = ron::de::from_bytes(&fs::read(path)?)?,
+        Format::ChunkFile => {
+            let mut file = ChunkFile::open(path)?;
+            let coords: Vec<IVec3> = file.chunks().copied().collect();
+            for coord in coords {
+                match file.load_chunk(&coord) {
+                    Ok(Some(chunk)) => {
+                        loaded.map.insert_shared_chunk(coord, Arc::new(chunk));
+                    }
+                    Ok(None) => {}
+                    Err(error) => loaded
+                        .problems
+                        .push(format!("chunk {} can't be read: {}", coord, error)),
+                }
+            }
+        }
+        Format::Tmx => load_tmx(path, &mut loaded)?,
+        Format::Ldtk => {
+            let project: LdtkProject = serde_json::from_slice(&fs::read(path)?)?;
+            let tilesets = LdtkTilesets::default();
+            for level in project.levels.iter() {
+                if level.layer_instances.is_none() {
+                    loaded.problems.push(format!(
+                        "level {} is saved in a separate file and was skipped",
+                        level.identifier
+                    ));
+                }
+                loaded.map.set_tiles(
+                    level
+                        .tiles(&tilesets)
+                        .into_iter()
+                        .map(|(coord, tile)| (coord, Some(tile))),
+                );
+            }
+        }
+    }
+    Ok(loaded)
+}
+
+fn load_tmx(path: &Path, loaded: &mut Loaded) -> CliResult<()> {
+    let mut tiled = TiledMap::from_tmx(&fs::read_to_string(path)?)?;
+    let folder = path.parent().unwrap_or_else(|| Path::new(""));
+    for tileset in tiled.tilesets.iter_mut() {
+        let source = match &tileset.source {
+            Some(source) => folder.join(source),
+            None => continue,
+        };
+        match fs::read_to_string(&source) {
+            Ok(tsx) => tileset.read_tsx(&tsx)?,
+            Err(error) => loaded.problems.push(format!(
+                "tileset {} can't be read: {}",
+                source.display(),
+                error
+            )),
+        }
+    }
+    let tilesets = tiled.tilesets(&mut TiledSheets::default());
+    for layer in tiled.tile_layers.iter() {
+        let missing = layer
+            .gids
+            .iter()
+            .filter(|gid| **gid != 0 && tilesets.tile(**gid).is_none())
+            .count();
+        if missing > 0 {
+            loaded.problems.push(format!(
+                "layer {} has {} tiles of tilesets without an image",
+                layer.name, missing
+            ));
+        }
+    }
+    loaded.map.set_tiles(
+        tiled
+            .tiles(&tilesets, IVec3::ZERO)
+            .into_iter()
+            .map(|(coord, tile)| (coord, Some(tile))),
+    );
+    Ok(())
+}
+
+fn save(map: &TileMap, path: &Path) -> CliResult<()> {
+    match Format::of(path)? {
+        Format::Ron => fs::write(
+            path,
+            ron::ser::to_string_pretty(map, ron::ser::PrettyConfig::default())?,
+        )?,
+        Format::ChunkFile => {
+            let mut file = ChunkFile::create(path)?;
+            file.save_chunks(
+                map.chunk_coords()
+                    .filter_map(|coord| Some((*coord, map.get_chunk(coord)?))),
+            )?;
+        }
+        Format::Tmx => fs::write(path, to_tmx(map)?)?,
+        Format::Ldtk => return Err("LDtk projects can only be read".into()),
+    }
+    Ok(())
+}
+
+/// Every sheet gets a tileset big enough for its highest index.
+fn to_tmx(map: &TileMap) -> CliResult<String> {
+    let tiles = tiles(map);
+    let (min, max) = match tile_bounds(&tiles) {
+        Some(bounds) => bounds,
+        None => (IVec3::ZERO, IVec3::ZERO),
+    };
+    let mut sheets: BTreeMap<u16, u32> = BTreeMap::new();
+    for (_, tile) in tiles.iter() {
+        let count = sheets.entry(tile.sheet()).or_default();
+        *count = (*count).max(tile.index() as u32 + 1);
+    }
+    let mut tilesets = TiledTilesets::default();
+    let mut first_gid = 1;
+    for (sheet, count) in sheets {
+        tilesets
+            .add(first_gid, sheet)
+            .set_source(first_gid, format!("sheet{}.tsx", sheet));
+        first_gid += count;
+    }
+    let layers: Vec<i32> = (min.z..=max.z).collect();
+    Ok(export_tmx(
+        map,
+        &tilesets,
+        min.truncate(),
+        max.truncate(),
+        &layers,
+        UVec2::splat(16),
+    )?)
+}
+
+/// Every tile of the map, sorted by layer, then row, then column.
+fn tiles(map: &TileMap) -> Vec<(IVec3, Tile)> {
+    let mut tiles: Vec<(IVec3, Tile)> = map
+        .chunk_coords()
+        .flat_map(|chunk| {
+            (0..=u8::MAX).filter_map(move |index| {
+                let coord = internal::tile_coord(*chunk, index);
+                Some((coord.tile_position(), *map.get_tile(&coord)?))
+            })
+        })
+        .collect();
+    tiles.sort_by_key(|(position, _)| (position.z, position.y, position.x));
+    tiles
+}
+
+fn tile_bounds(tiles: &[(IVec3, Tile)]) -> Option<(IVec3, IVec3)> {
+    let first = tiles.first()?.0;
+    Some(
+        tiles
+            .iter()
+            .fold((first, first), |(min, max), (position, _)| {
+                (min.min(*position), max.max(*position))
+            }),
+    )
+}
+
+fn inspect(path: &Path) -> CliResult<()> {
+    let loaded = load(path)?;
+    let map = &loaded.map;
+    let mut report = String::new();
+    writeln!(report, "{}: {:?}", path.display(), Format::of(path)?)?;
+    if Format::of(path)? == Format::ChunkFile {
+        let file = ChunkFile::open(path)?;
+        writeln!(
+            report,
+            "file: {} bytes, {} free",
+            fs::metadata(path)?.len(),
+            file.free_bytes()
+        )?;
+    }
+    let chunks: Vec<&Chunk> = map
+        .chunk_coords()
+        .filter_map(|coord| map.get_chunk(coord))
+        .collect();
+    writeln!(
+        report,
+        "chunks: {} ({} uniform, {} compressed)",
+        chunks.len(),
+        chunks
+            .iter()
+            .filter(|chunk| chunk.as_uniform().is_some())
+            .count(),
+        chunks.iter().filter(|chunk| chunk.is_compressed()).count()
+    )?;
+    let tiles = tiles(map);
+    writeln!(report, "tiles: {}", tiles.len())?;
+    if let Some((min, max)) = tile_bounds(&tiles) {
+        writeln!(report, "bounds: {} to {}", min.truncate(), max.truncate())?;
+    }
+    let mut layers: BTreeMap<i32, usize> = BTreeMap::new();
+    let mut sheets: BTreeMap<u16, usize> = BTreeMap::new();
+    for (position, tile) in tiles.iter() {
+        *layers.entry(position.z).or_default() += 1;
+        *sheets.entry(tile.sheet()).or_default() += 1;
+    }
+    writeln!(report, "layers: {}", counts(&layers))?;
+    writeln!(report, "sheets: {}", counts(&sheets))?;
+    if let Some(wrap) = map.wrap() {
+        writeln!(report, "wrap: {:?}", wrap)?;
+    }
+    if let Some(bounds) = map.bounds() {
+        writeln!(report, "limits: {:?}", bounds)?;
+    }
+    for problem in loaded.problems.iter() {
+        writeln!(report, "problem: {}", problem)?;
+    }
+    print!("{}", report);
+    Ok(())
+}
+
+fn counts<K: std::fmt::Display>(counts: &BTreeMap<K, usize>) -> String {
+    counts
+        .iter()
+        .map(|(key, count)| format!("{} ({} tiles)", key, count))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// Whether every map loaded without problems.
+fn validate(paths: &[PathBuf]) -> bool {
+    let mut valid = true;
+    for path in paths {
+        match load(path) {
+            Ok(loaded) if loaded.problems.is_empty() => println!("{}: ok", path.display()),
+            Ok(loaded) => {
+                valid = false;
+                for problem in loaded.problems {
+                    println!("{}: {}", path.display(), problem);
+                }
+            }
+            Err(error) => {
+                valid = false;
+                println!("{}: {}", path.display(), error);
+            }
+        }
+    }
+    valid
+}
+
+/// Loads a map, printing its problems as warnings.
+fn load_map(path: &Path) -> CliResult<TileMap> {
+    let loaded = load(path)?;
+    for problem in loaded.problems.iter() {
+        eprintln!("warning: {}: {}", path.display(), problem);
+    }
+    Ok(loaded.map)
+}
+
+fn convert(input: &Path, output: &Path) -> CliResult<()> {
+    save(&load_map(input)?, output)
+}
+
+fn compress(path: &Path) -> CliResult<()> {
+    let format = Format::of(path)?;
+    if !matches!(format, Format::Ron | Format::ChunkFile) {
+        return Err("only .ron and .btcf maps can be compressed".into());
+    }
+    let before = fs::metadata(path)?.len();
+    let mut loaded = load(path)?;
+    if !loaded.problems.is_empty() {
+        return Err(format!("{} doesn't load completely, validate it", path.display()).into());
+    }
+    let empty: Vec<IVec3> = loaded
+        .map
+        .chunk_coords()
+        .filter(|coord| {
+            loaded
+                .map
+                .get_chunk(coord)
+                .is_some_and(|chunk| chunk.as_uniform() == Some(None))
+        })
+        .copied()
+        .collect();
+    for coord in empty.iter() {
+        loaded.map.remove_chunk(coord);
+    }
+    // Chunk files without empty chunks keep their chunks' bytes, others are written anew.
+    if format == Format::ChunkFile && empty.is_empty() {
+        ChunkFile::open(path)?.compact()?;
+    } else {
+        save(&loaded.map, path)?;
+    }
+    println!(
+        "{}: {} empty chunks dropped, {} bytes to {}",
+        path.display(),
+        empty.len(),
+        before,
+        fs::metadata(path)?.len()
+    );
+    Ok(())
+}
+
+/// Whether the maps hold the same tiles.
+fn diff(first: &Path, second: &Path) -> CliResult<bool> {
+    let (first, second) = (load_map(first)?, load_map(second)?);
+    let mut chunks: Vec<IVec3> = first
+        .chunk_coords()
+        .chain(second.chunk_coords())
+        .copied()
+        .collect();
+    chunks.sort_unstable_by_key(|chunk| (chunk.z, chunk.y, chunk.x));
+    chunks.dedup();
+    let mut differences = 0;
+    for chunk in chunks {
+        for index in 0..=u8::MAX {
+            let coord = internal::tile_coord(chunk, index);
+            let (old, new) = (first.get_tile(&coord), second.get_tile(&coord));
+            if old != new {
+                differences += 1;
+                println!("{}: {} -> {}", coord, describe(old), describe(new));
+            }
+        }
+    }
+    println!("{} tiles differ", differences);
+    Ok(differences == 0)
+}
+
+fn describe(tile: Option<&Tile>) -> String {
+    match tile {
+        Some(tile) => tile.to_string(),
+        None => "none".to_string(),
+    }
+}
+
+fn run(args: &[String]) -> CliResult<bool> {
+    let paths: Vec<PathBuf> = args.iter().skip(1).map(PathBuf::from).collect();
+    match (args.first().map(String::as_str), paths.as_slice()) {
+        (Some("inspect"), [path]) => inspect(path).map(|_| true),
+        (Some("validate"), paths) if !paths.is_empty() => Ok(validate(paths)),
+        (Some("convert"), [input, output]) => convert(input, output).map(|_| true),
+        (Some("compress"), [path]) => compress(path).map(|_| true),
+        (Some("diff"), [first, second]) => diff(first, second),
+        _ => Err(USAGE.into()),
+    }
+}
+
+fn main() -> ExitCode {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    match run(&args) {
+        Ok(true) => ExitCode::SUCCESS,
+        Ok(false) => ExitCode::from(1),
+        Err(error) => {
+            eprintln!("{}", error);
+            ExitCode::from(2)
+        }
+    }
+}
@@ -1 +1,57 @@
+//! One dependency for the whole library, with a cargo feature per optional subsystem:
+//!
+//! - `chunk_ecs` (default): an entity per chunk following the map, re-exported as `chunk_ecs`.
+//! - `autotile` (default): terrain autotiling from neighbour bitmasks, see the `autotile`
+//!   module.
+//! - `streaming` (default): loading chunks around anchors and generating missing ones, see the
+//!   `streaming` module, also for labeled maps used as dimensions, see the `dimension` module.
+//! - `persist` (default): saving unloaded chunks to disk and loading them back, see the
+//!   `persist` module.
+//! - `wfc`, `passes` and `structures` (default): chunk generation by wave function collapse,
+//!   by passes over boxes of chunks and with structures spanning chunks.
+//! - `signal` and `diffusion` (default): networks of conductive tiles and values spreading
+//!   between tiles.
+//! - `stitch` (default): callbacks fixing the seams between inserted chunks and their
+//!   neighbours, see the `stitch` module.
+//! - `web_storage`: chunk persistence in the browser's local storage, falling back to memory,
+//!   see the `web_storage` module.
+//! - `serde`: serialization of tiles, coordinates and chunks.
+//! - `tiled`: Tiled maps loaded as assets and TMX export, see the `tiled` and `tiled_asset`
+//!   modules.
+//! - `ldtk`: LDtk project import, see the `ldtk` module.
+//! - `autotile_assets`: autotile rules loaded from RON assets, see the `autotile_asset` module.
+//! - `cli`: the `bevy_tiling_cli` binary inspecting, validating, converting, compressing and
+//!   diffing map files in the RON, chunk file, TMX and LDtk formats.
+//! - `testing`: helpers for integration tests, see `chunk_ecs::testing`.
+//!
+//! Everything of `bevy_tiling_core` is re-exported at the root, add [`TilingPlugins`] to get
+//! the map and every enabled subsystem running.
 
+use bevy::app::{PluginGroup, PluginGroupBuilder};
+
+pub use bevy_tiling_core::*;
+
+#[cfg(feature = "chunk_ecs")]
+pub use bevy_tiling_chunk_ecs as chunk_ecs;
+
+pub mod prelude {
+    pub use crate::TilingPlugins;
+    #[cfg(feature = "chunk_ecs")]
+    pub use bevy_tiling_chunk_ecs::{BevyTilingChunkEcs, ChunkMap, ChunkMarker};
+    pub use bevy_tiling_core::{
+        grid::TileGrid, IntoTileCoord, MapReader, Tile, TileCoord, TileMap, TileMapReader,
+        TileMapUpdates, TileMapWriter, TilingCoreStage, TilingPlugin,
+    };
+}
+
+/// [`TilingPlugin`] and the plugins of the enabled subsystems that run on their own, the
+/// importers and opt-in caches are added separately.
+pub struct TilingPlugins;
+
+impl PluginGroup for TilingPlugins {
+    fn build(&mut self, group: &mut PluginGroupBuilder) {
+        group.add(TilingPlugin);
+        #[cfg(feature = "chunk_ecs")]
+        group.add(bevy_tiling_chunk_ecs::BevyTilingChunkEcs);
+    }
+}